@@ -0,0 +1,187 @@
+//! Run with `cargo bench -p schema_analysis`.
+//!
+//! The inputs are generated deterministically so that runs can be compared.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::de::DeserializeSeed;
+use serde_json::{json, Value};
+
+use schema_analysis::{traits::coalesce_owned, InferredSchema, Schema};
+
+/// An array of flat records sharing the same fields, the most common shape.
+fn flat_struct_array(records: usize) -> String {
+    let records: Vec<Value> = (0..records)
+        .map(|i| {
+            json!({
+                "id": i,
+                "name": format!("user_{}", i),
+                "email": format!("user_{}@example.com", i),
+                "score": i as f64 / 7.0,
+                "active": i % 3 == 0,
+                "created": "2021-06-01T12:00:00Z",
+                "tags": ["a", "b", "c"],
+                "parent": if i % 5 == 0 { Value::Null } else { json!(i - 1) },
+            })
+        })
+        .collect();
+    serde_json::to_string(&records).unwrap()
+}
+
+/// Many documents nested close to the default depth limit, alternating maps and sequences.
+fn deeply_nested(documents: usize, depth: usize) -> String {
+    let documents: Vec<Value> = (0..documents)
+        .map(|i| {
+            (0..depth).fold(json!(i), |inner, level| {
+                if level % 2 == 0 {
+                    json!({ "level": level, "inner": inner })
+                } else {
+                    json!([inner])
+                }
+            })
+        })
+        .collect();
+    serde_json::to_string(&documents).unwrap()
+}
+
+/// Values of every kind mixed in the same places, so that most nodes become unions.
+fn wide_union(values: usize) -> String {
+    let values: Vec<Value> = (0..values)
+        .map(|i| match i % 7 {
+            0 => json!(i),
+            1 => json!(i as f64 + 0.5),
+            2 => json!(format!("value_{}", i)),
+            3 => json!(i % 2 == 0),
+            4 => Value::Null,
+            5 => json!([i, format!("{}", i), { "x": i }]),
+            _ => json!({ "x": format!("{}", i), "y": [i], "z": { "w": i % 2 == 0 } }),
+        })
+        .map(|value| json!({ "value": value, "other": value }))
+        .collect();
+    serde_json::to_string(&values).unwrap()
+}
+
+/// Many records with the same wide set of keys, so that key handling dominates.
+fn repeated_keys(records: usize, keys: usize) -> String {
+    let records: Vec<Value> = (0..records)
+        .map(|i| {
+            (0..keys)
+                .map(|k| (format!("field_{}", k), json!(i + k)))
+                .collect()
+        })
+        .collect();
+    serde_json::to_string(&records).unwrap()
+}
+
+/// Many tiny records, so that the per-object overhead dominates.
+fn small_objects(records: usize) -> String {
+    let records: Vec<Value> = (0..records)
+        .map(|i| json!({ "id": i % 10, "ok": i % 2 == 0, "inner": { "x": i % 3 } }))
+        .collect();
+    serde_json::to_string(&records).unwrap()
+}
+
+/// Records whose fields hold long and distinct strings, so that the samples are large.
+fn large_samples(records: usize, keys: usize) -> String {
+    let records: Vec<Value> = (0..records)
+        .map(|i| {
+            (0..keys)
+                .map(|k| {
+                    (
+                        format!("field_{}", k),
+                        json!(format!("{:0>1000}", i * keys + k)),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    serde_json::to_string(&records).unwrap()
+}
+
+fn inference(c: &mut Criterion) {
+    let inputs = [
+        ("flat_struct_array", flat_struct_array(10_000)),
+        ("deeply_nested", deeply_nested(100, 120)),
+        ("wide_union", wide_union(10_000)),
+        ("repeated_keys", repeated_keys(10_000, 50)),
+    ];
+
+    let mut group = c.benchmark_group("inference");
+    for (name, input) in &inputs {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), input, |b, input| {
+            b.iter(|| serde_json::from_str::<InferredSchema>(black_box(input)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Extends a schema that already holds the shape of the data, like when analyzing many files,
+/// where maps reuse their key buffers instead of allocating new ones.
+fn extending(c: &mut Criterion) {
+    let input = small_objects(100_000);
+    let initial: InferredSchema = serde_json::from_str(&input).unwrap();
+
+    let mut group = c.benchmark_group("extending");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("small_objects", |b| {
+        b.iter_batched(
+            || initial.clone(),
+            |mut inferred| {
+                let mut deserializer = serde_json::Deserializer::from_str(black_box(&input));
+                (&mut inferred).deserialize(&mut deserializer).unwrap();
+                inferred
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn coalescing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("coalescing");
+    for count in [10, 100, 1_000] {
+        let schemas: Vec<Schema> = (0..count)
+            .map(|i| {
+                let input = if i % 2 == 0 {
+                    flat_struct_array(10)
+                } else {
+                    wide_union(10)
+                };
+                serde_json::from_str::<InferredSchema>(&input)
+                    .unwrap()
+                    .schema
+            })
+            .collect();
+
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &schemas,
+            |b, schemas| {
+                b.iter_batched(
+                    || schemas.clone(),
+                    |schemas| schemas.into_iter().reduce(coalesce_owned),
+                    criterion::BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+/// A full clone against [Schema::clone_shape_only], which skips the contexts.
+fn cloning(c: &mut Criterion) {
+    let schema = serde_json::from_str::<InferredSchema>(&large_samples(100, 50))
+        .unwrap()
+        .schema;
+
+    let mut group = c.benchmark_group("cloning");
+    group.bench_function("clone", |b| b.iter(|| black_box(&schema).clone()));
+    group.bench_function("clone_shape_only", |b| {
+        b.iter(|| black_box(&schema).clone_shape_only())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, inference, extending, coalescing, cloning);
+criterion_main!(benches);
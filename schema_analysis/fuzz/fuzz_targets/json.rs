@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde::de::DeserializeSeed;
+
+use schema_analysis::InferredSchema;
+
+// Errors are expected on most inputs, only panics are failures.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mut inferred) = serde_json::from_slice::<InferredSchema>(data) {
+        // Run the same document again to also exercise the seeded visitors.
+        let mut deserializer = serde_json::Deserializer::from_slice(data);
+        inferred.deserialize(&mut deserializer).unwrap();
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use schema_analysis::{helpers, InferredSchema};
+
+// Errors are expected on most inputs, only panics are failures.
+fuzz_target!(|data: &[u8]| {
+    let data = match std::str::from_utf8(data) {
+        Ok(data) => data,
+        Err(_) => return,
+    };
+    if let Ok(mut inferred) = quick_xml::de::from_str::<InferredSchema>(data) {
+        helpers::xml::cleanup_xml_schema(&mut inferred.schema);
+    }
+});
@@ -0,0 +1,91 @@
+use serde::Deserializer;
+
+use crate::{Coalesce, InferredSchema, Schema};
+
+/**
+Grows a [Schema] one document at a time, for when the documents arrive over time (like the
+requests handled by a server) rather than all at once.
+
+```
+# use schema_analysis::{Schema, SchemaAccumulator};
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let mut accumulator = SchemaAccumulator::new();
+assert!(accumulator.snapshot().is_none());
+
+accumulator.observe(&mut serde_json::Deserializer::from_str(r#"{ "id": 1 }"#))?;
+accumulator.observe(&mut serde_json::Deserializer::from_str(r#"{ "id": 2 }"#))?;
+
+assert_eq!(accumulator.documents(), 2);
+assert!(matches!(accumulator.snapshot(), Some(Schema::Struct { .. })));
+# Ok(())
+# }
+```
+
+The analysis itself is synchronous, but the accumulator is [Send] and [Sync], so it can be
+kept behind a lock shared by async tasks. To keep the lock short, documents may also be
+analyzed into separate accumulators and merged in later with [Coalesce].
+*/
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SchemaAccumulator {
+    inferred: Option<InferredSchema>,
+    documents: usize,
+}
+
+impl SchemaAccumulator {
+    /// An accumulator that has not observed any document yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the analysis on a document and merges it into the schema.
+    ///
+    /// The document is analyzed on its own and only merged if the deserialization succeeds,
+    /// so a malformed or truncated document leaves the schema untouched.
+    /// Its [Field](crate::Field)s are marked as seen in the document with the index
+    /// [documents](SchemaAccumulator::documents) had before it was observed, see
+    /// [first_seen](crate::FieldStatus::first_seen).
+    pub fn observe<'de, D: Deserializer<'de>>(&mut self, deserializer: D) -> Result<(), D::Error> {
+        let observed = InferredSchema::deserialize_document(self.documents, deserializer)?;
+        match &mut self.inferred {
+            Some(inferred) => inferred.coalesce(observed),
+            None => self.inferred = Some(observed),
+        }
+        self.documents += 1;
+        Ok(())
+    }
+
+    /// A copy of the schema so far, [None] if no document has been observed.
+    pub fn snapshot(&self) -> Option<Schema> {
+        self.inferred
+            .as_ref()
+            .map(|inferred| inferred.schema.clone())
+    }
+
+    /// The number of documents observed successfully.
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// Returns the schema so far, [None] if no document has been observed.
+    pub fn into_inferred(self) -> Option<InferredSchema> {
+        self.inferred
+    }
+}
+
+/// The document indexes seen by the other accumulator are kept as they are, so they
+/// may overlap with the ones of this accumulator.
+impl Coalesce for SchemaAccumulator {
+    fn coalesce(&mut self, other: Self)
+    where
+        Self: Sized,
+    {
+        self.inferred = match (self.inferred.take(), other.inferred) {
+            (Some(mut inferred), Some(other)) => {
+                inferred.coalesce(other);
+                Some(inferred)
+            }
+            (inferred, other) => inferred.or(other),
+        };
+        self.documents += other.documents;
+    }
+}
@@ -0,0 +1,45 @@
+use serde::Deserializer;
+
+use crate::{
+    context::{holds_json, may_hold_json, StringContext},
+    Aggregate, Coalesce,
+};
+
+use super::{schema::SchemaVisitor, State};
+
+impl<'s> State<'s> {
+    /// Counts the strings holding JSON, and analyzes them as documents of their own if the
+    /// [embedded_json_depth](crate::AnalysisSettings::embedded_json_depth) allows it.
+    /// The schema is stored in the [StringContext] of the string.
+    ///
+    /// Each value is parsed once: if it is analyzed, the analysis itself tells whether it is
+    /// valid JSON. It is analyzed into a new schema, so that a value that turns out not to be
+    /// JSON leaves nothing behind.
+    pub fn analyze_embedded_json(&self, context: &mut StringContext, value: &str) {
+        if !may_hold_json(value) {
+            return;
+        }
+        if self.embedded_depth >= self.context.settings.embedded_json_depth {
+            if holds_json(value) {
+                context.embedded_json.aggregate(value);
+            }
+            return;
+        }
+        let state = State {
+            embedded_depth: self.embedded_depth + 1,
+            element_number: None,
+            ..*self
+        };
+
+        let mut deserializer = serde_json::Deserializer::from_str(value);
+        let schema = match deserializer.deserialize_any(SchemaVisitor { state }) {
+            Ok(schema) if deserializer.end().is_ok() => schema,
+            _ => return,
+        };
+        context.embedded_json.aggregate(value);
+        match &mut context.embedded_schema {
+            Some(embedded) => embedded.coalesce(schema),
+            None => context.embedded_schema = Some(Box::new(schema)),
+        }
+    }
+}
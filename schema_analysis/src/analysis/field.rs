@@ -1,11 +1,14 @@
-use serde::de::{DeserializeSeed, Error, Visitor};
+use serde::{
+    de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor},
+    Deserialize,
+};
 
 use crate::Field;
 
-use super::{schema::SchemaVisitor, schema_seed::SchemaVisitorSeed, Context};
+use super::{schema::SchemaVisitor, schema_seed::SchemaVisitorSeed, ContentSeed, State};
 
 pub struct FieldVisitor<'s> {
-    pub context: &'s Context,
+    pub state: State<'s>,
 }
 
 impl<'de, 's> DeserializeSeed<'de> for FieldVisitor<'s> {
@@ -16,17 +19,19 @@ impl<'de, 's> DeserializeSeed<'de> for FieldVisitor<'s> {
         D: serde::Deserializer<'de>,
     {
         let mut field = Field::default();
-        deserializer.deserialize_any(FieldVisitorSeed {
-            context: self.context,
+        field.status.first_seen = self.state.document;
+        FieldVisitorSeed {
+            state: self.state,
             field: &mut field,
-        })?;
+        }
+        .deserialize(deserializer)?;
 
         Ok(field)
     }
 }
 
 pub struct FieldVisitorSeed<'s> {
-    pub context: &'s Context,
+    pub state: State<'s>,
     pub field: &'s mut Field,
 }
 
@@ -37,6 +42,14 @@ impl<'de, 's> DeserializeSeed<'de> for FieldVisitorSeed<'s> {
     where
         D: serde::Deserializer<'de>,
     {
+        self.field.status.record_last_seen(self.state.document);
+
+        // Values under ignored paths are skipped, we only record that they were found.
+        if self.state.is_ignored() {
+            IgnoredAny::deserialize(deserializer)?;
+            self.field.status.is_ignored = true;
+            return Ok(());
+        }
         deserializer.deserialize_any(self)
     }
 }
@@ -49,17 +62,14 @@ macro_rules! method_impl {
                 // the schema side of things take care of the rest.
                 Some(schema) => {
                     let () = SchemaVisitorSeed {
-                        context: self.context,
+                        state: self.state,
                         schema,
                     }
                     .$method_name(value)?;
                 }
                 // Otherwise we need to generate a new schema.
                 None => {
-                    let schema = SchemaVisitor {
-                        context: self.context,
-                    }
-                    .$method_name(value)?;
+                    let schema = SchemaVisitor { state: self.state }.$method_name(value)?;
                     self.field.schema = Some(schema);
                 }
             }
@@ -160,23 +170,26 @@ impl<'de, 's> Visitor<'de> for FieldVisitorSeed<'s> {
     where
         A: serde::de::SeqAccess<'de>,
     {
+        let mut is_empty = true;
+        let seq = EmptyCheck {
+            access: seq,
+            is_empty: &mut is_empty,
+        };
         match &mut self.field.schema {
             Some(schema) => {
                 SchemaVisitorSeed {
-                    context: self.context,
+                    state: self.state,
                     schema,
                 }
                 .visit_seq(seq)?;
             }
             None => {
-                let schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_seq(seq)?;
+                let schema = SchemaVisitor { state: self.state }.visit_seq(seq)?;
                 self.field.schema = Some(schema);
             }
         }
         self.field.status.may_be_normal = true;
+        self.field.status.may_be_empty_collection |= is_empty;
         Ok(())
     }
 
@@ -184,37 +197,91 @@ impl<'de, 's> Visitor<'de> for FieldVisitorSeed<'s> {
     where
         A: serde::de::MapAccess<'de>,
     {
+        let mut is_empty = true;
+        let map = EmptyCheck {
+            access: map,
+            is_empty: &mut is_empty,
+        };
         match &mut self.field.schema {
             Some(schema) => {
                 SchemaVisitorSeed {
-                    context: self.context,
+                    state: self.state,
                     schema,
                 }
                 .visit_map(map)?;
             }
             None => {
-                let schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_map(map)?;
+                let schema = SchemaVisitor { state: self.state }.visit_map(map)?;
                 self.field.schema = Some(schema);
             }
         }
         self.field.status.may_be_normal = true;
+        self.field.status.may_be_empty_collection |= is_empty;
         Ok(())
     }
 
-    fn visit_newtype_struct<D>(self, _deserializer: D) -> Result<Self::Value, D::Error>
+    /// The inner value is analyzed in place of the newtype.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        unreachable!("newtype structs are a rust construct")
+        deserializer.deserialize_any(self)
     }
 
-    fn visit_enum<A>(self, _data: A) -> Result<Self::Value, A::Error>
+    /// Enums are a Rust construct, but some formats use them for tagged values (like the
+    /// `!Tag value` of YAML), so the tag is dropped and the content is analyzed in its place.
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
     where
         A: serde::de::EnumAccess<'de>,
     {
-        unreachable!("enum types are usually not available from the format's side")
+        let (IgnoredAny, content) = data.variant()?;
+        content.newtype_variant_seed(ContentSeed(self))
+    }
+}
+
+/// Wraps a [SeqAccess] or [MapAccess] to record whether it had any elements.
+struct EmptyCheck<'a, A> {
+    access: A,
+    is_empty: &'a mut bool,
+}
+
+impl<'de, 'a, A: SeqAccess<'de>> SeqAccess<'de> for EmptyCheck<'a, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let element = self.access.next_element_seed(seed)?;
+        *self.is_empty &= element.is_none();
+        Ok(element)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, 'a, A: MapAccess<'de>> MapAccess<'de> for EmptyCheck<'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let key = self.access.next_key_seed(seed)?;
+        *self.is_empty &= key.is_none();
+        Ok(key)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.access.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
     }
 }
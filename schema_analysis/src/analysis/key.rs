@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+
+use serde::{
+    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
+    Deserializer,
+};
+
+use crate::context::push_json_string;
+
+/// Deserializes a map key into a string.
+///
+/// Keys are borrowed from the input when the format allows it, so that the key of a field
+/// that already exists is never allocated.
+///
+/// Some formats (like cbor and yaml) allow non-string keys, so instead of failing we stringify
+/// any primitive key we are given. The returned flag is `true` if the key was a proper string.
+/// Keys are read with [Deserializer::deserialize_any], as formats that follow a `str` hint
+/// would otherwise turn non-string keys into strings or reject them.
+///
+/// Composite keys (sequences and maps, which cbor and yaml also allow) are written out like
+/// JSON, as `[1,"a"]` or `{"a":1}`, with their own keys and elements stringified the same way.
+///
+/// Float keys that are not finite (which adversarial cbor or msgpack inputs may contain) are
+/// stringified as `"NaN"`, `"Infinity"` and `"-Infinity"`, like JavaScript does, so that they
+/// end up under the same deterministic key regardless of their bits.
+pub struct KeySeed;
+
+impl<'de> DeserializeSeed<'de> for KeySeed {
+    type Value = (Cow<'de, str>, bool);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed {
+    type Value = (Cow<'de, str>, bool);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a map key")
+    }
+
+    fn visit_borrowed_str<E: Error>(self, value: &'de str) -> Result<Self::Value, E> {
+        Ok((value.into(), true))
+    }
+    fn visit_str<E: Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok((value.to_string().into(), true))
+    }
+    fn visit_string<E: Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok((value.into(), true))
+    }
+    fn visit_char<E: Error>(self, value: char) -> Result<Self::Value, E> {
+        Ok((value.to_string().into(), true))
+    }
+
+    fn visit_bool<E: Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok((value.to_string().into(), false))
+    }
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok((value.to_string().into(), false))
+    }
+    fn visit_i128<E: Error>(self, value: i128) -> Result<Self::Value, E> {
+        Ok((value.to_string().into(), false))
+    }
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok((value.to_string().into(), false))
+    }
+    fn visit_u128<E: Error>(self, value: u128) -> Result<Self::Value, E> {
+        Ok((value.to_string().into(), false))
+    }
+    fn visit_f64<E: Error>(self, value: f64) -> Result<Self::Value, E> {
+        let key = match value {
+            _ if value.is_nan() => "NaN".into(),
+            f64::INFINITY => "Infinity".into(),
+            f64::NEG_INFINITY => "-Infinity".into(),
+            _ => value.to_string().into(),
+        };
+        Ok((key, false))
+    }
+    fn visit_bytes<E: Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok((String::from_utf8_lossy(value).into_owned().into(), false))
+    }
+    fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(("null".into(), false))
+    }
+    fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+        self.visit_unit()
+    }
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.deserialize(deserializer)
+    }
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut key = String::from("[");
+        let mut is_first = true;
+        while let Some(element) = seq.next_element_seed(KeySeed)? {
+            if !is_first {
+                key.push(',');
+            }
+            is_first = false;
+            push_element(&mut key, element);
+        }
+        key.push(']');
+        Ok((key.into(), false))
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut key = String::from("{");
+        let mut is_first = true;
+        while let Some((entry_key, value)) = map.next_entry_seed(KeySeed, KeySeed)? {
+            if !is_first {
+                key.push(',');
+            }
+            is_first = false;
+            push_json_string(&mut key, &entry_key.0);
+            key.push(':');
+            push_element(&mut key, value);
+        }
+        key.push('}');
+        Ok((key.into(), false))
+    }
+}
+
+/// Appends an element of a composite key, quoting it if it was a string.
+fn push_element(key: &mut String, (element, is_string): (Cow<str>, bool)) {
+    if is_string {
+        push_json_string(key, &element);
+    } else {
+        key.push_str(&element);
+    }
+}
@@ -170,24 +170,44 @@ let _: Visitor::Value = deserializer.deserialize_str(visitor);
 `[...]`
 
 */
+use std::cell::Cell;
+
 use once_cell::sync::Lazy;
-use serde::{de::DeserializeSeed, Deserialize, Deserializer};
+use serde::{
+    de::{DeserializeSeed, Error},
+    Deserialize, Deserializer, Serializer,
+};
 
-#[allow(unused_imports)]
-use serde::de::Visitor; // For docs above.
+use serde::de::Visitor;
 
 use crate::{Coalesce, Context, Schema};
 
+mod accumulator;
+#[cfg(feature = "embedded_json")]
+mod embedded;
 mod field;
+mod key;
 mod schema;
 mod schema_seed;
-
+mod settings;
+mod state;
+#[cfg(feature = "serde_json")]
+mod stream;
+mod transcode;
+mod validation;
+
+pub use accumulator::SchemaAccumulator;
+pub use settings::{AnalysisSettings, KeyNormalizer};
+pub use validation::{ValidationIssue, ValidationIssueKind, ValidationReport};
+
+pub(crate) use key::KeySeed;
 use schema::SchemaVisitor;
 use schema_seed::SchemaVisitorSeed;
+use state::{KeyBuffers, State};
 
 /// Since the context is never modified, we can store a default to avoid creating a new one
 /// each time.
-static DEFAULT_CONTEXT: Lazy<Context> = Lazy::new(Context::default);
+pub(crate) static DEFAULT_CONTEXT: Lazy<Context> = Lazy::new(Context::default);
 
 /**
 [InferredSchema] is at the heart of this crate, it is a wrapper around [Schema] that interfaces
@@ -200,6 +220,252 @@ pub struct InferredSchema {
     /// Where the juicy info lays.
     pub schema: Schema,
 }
+impl InferredSchema {
+    /// Summarizes the shapes of the documents found at the root, with the number of documents
+    /// of each shape.
+    ///
+    /// A root [Union](Schema::Union) yields one entry per variant, any other [Schema]
+    /// a single entry.
+    pub fn root_shape_summary(&self) -> Vec<(String, usize)> {
+        self.schema
+            .top_level_variants()
+            .into_iter()
+            .map(|s| (s.variant_name().to_string(), s.value_count()))
+            .collect()
+    }
+
+    /// Attaches a pre-built [Context] to the schema, so that the documents used to extend it are
+    /// analyzed with that context (and its [AnalysisSettings]) rather than the default one.
+    ///
+    /// ```
+    /// # use serde::de::DeserializeSeed;
+    /// # use schema_analysis::{Context, InferredSchema, Schema};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let inferred: InferredSchema = serde_json::from_str(r#"{ "id": 1 }"#)?;
+    ///
+    /// let mut context = Context::default();
+    /// context.settings.ignore_path(&["blob"]);
+    /// let mut inferred = inferred.with_context(context);
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{ "id": 2, "blob": [1] }"#);
+    /// inferred.deserialize(&mut deserializer)?;
+    ///
+    /// if let Schema::Struct { fields, .. } = &inferred.schema {
+    ///     assert!(fields["blob"].status.is_ignored);
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Note: the contexts already in the schema keep their own configuration, the new
+    /// [Context] is used as a template only for the nodes found from now on.
+    pub fn with_context(self, context: Context) -> InferredSchemaWithContext {
+        InferredSchemaWithContext {
+            schema: self.schema,
+            context,
+        }
+    }
+
+    /// The number of documents that contributed to the schema, derived from the count of the
+    /// root context (summed across variants for a root [Union](Schema::Union)).
+    ///
+    /// Useful to compute ratios, like how often a field is null across all documents.
+    pub fn documents_seen(&self) -> usize {
+        self.schema.value_count()
+    }
+
+    /// Runs the analysis on each document in turn and returns the combined schema, the first
+    /// deserializer is used to create the schema and the rest to expand it.
+    ///
+    /// Returns an error if there are no deserializers.
+    ///
+    /// ```
+    /// # use schema_analysis::{InferredSchema, Schema};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a_lot_of_json_files: &[&str] = &["1", "2", "1000"];
+    ///
+    /// let mut deserializers: Vec<_> = a_lot_of_json_files
+    ///     .iter()
+    ///     .map(|file| serde_json::Deserializer::from_str(file))
+    ///     .collect();
+    /// let inferred = InferredSchema::from_deserializers(deserializers.iter_mut())?;
+    ///
+    /// if let Schema::Integer(context) = inferred.schema {
+    ///     assert_eq!(context.count.0, 3);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_deserializers<'de, I, D>(deserializers: I) -> Result<Self, D::Error>
+    where
+        I: IntoIterator<Item = D>,
+        D: Deserializer<'de>,
+    {
+        let mut deserializers = deserializers.into_iter();
+
+        let mut inferred: InferredSchema = match deserializers.next() {
+            Some(first) => Deserialize::deserialize(first)?,
+            None => return Err(D::Error::custom("no documents found")),
+        };
+
+        for deserializer in deserializers {
+            let () = (&mut inferred).deserialize(deserializer)?;
+        }
+
+        Ok(inferred)
+    }
+
+    /// Runs the analysis while copying the document to `serializer`, so that a file can be
+    /// converted (or just re-written) and analyzed in a single pass.
+    ///
+    /// ```
+    /// # use schema_analysis::{InferredSchema, Schema};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut output = Vec::new();
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{ "id": 1 }"#);
+    /// let mut serializer = serde_json::Serializer::new(&mut output);
+    ///
+    /// let inferred = InferredSchema::transcode(&mut deserializer, &mut serializer)?;
+    ///
+    /// assert_eq!(output, br#"{"id":1}"#);
+    /// assert!(matches!(inferred.schema, Schema::Struct { .. }));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Limitations:
+    /// - the output is only as good as what the input format reports: the values are written
+    ///   with the same types they are visited with, so the output format must be able to
+    ///   represent those (a format that rejects maps with non-string keys will fail on them);
+    /// - the serializer writes the values as they come, so it should write to a concrete
+    ///   output (like `&mut serde_json::Serializer<W>`), its result is discarded;
+    /// - enums can't be transcoded (self-describing formats don't produce them anyway),
+    ///   and newtype structs are written as their content.
+    ///
+    /// Errors from the serializer are returned as custom errors of the deserializer.
+    pub fn transcode<'de, D, S>(deserializer: D, serializer: S) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        S: Serializer,
+    {
+        let ok = Cell::new(None);
+        Deserialize::deserialize(transcode::Tee {
+            deserializer,
+            serializer,
+            ok: &ok,
+        })
+    }
+
+    /// Like [Deserialize::deserialize], but the [Field]s found are marked as
+    /// [first_seen](crate::FieldStatus::first_seen) and
+    /// [last_seen](crate::FieldStatus::last_seen) in the document with the given `index`.
+    ///
+    /// The index can be any tick chosen by the caller, like a counter or a timestamp.
+    ///
+    /// To use a custom [Context] (or [AnalysisSettings]), see [Context::deserialize_document].
+    pub fn deserialize_document<'de, D>(index: usize, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let schema = analyze_document(&DEFAULT_CONTEXT, Some(index), deserializer)?;
+        Ok(InferredSchema { schema })
+    }
+
+    /// Like [DeserializeSeed::deserialize], but the new [Field]s found are marked as
+    /// [first_seen](crate::FieldStatus::first_seen) in the document with the given `index`,
+    /// and all the [Field]s found are marked as [last_seen](crate::FieldStatus::last_seen).
+    ///
+    /// The index can be any tick chosen by the caller, like a counter or a timestamp, and it
+    /// should grow with each document, as fields keep the lowest and highest index they were
+    /// found with.
+    ///
+    /// ```
+    /// # use schema_analysis::{InferredSchema, Schema};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let files: &[&str] = &[r#"{ "a": 1 }"#, r#"{ "a": 2 }"#, r#"{ "a": 3, "b": 4 }"#];
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(files[0]);
+    /// let mut inferred = InferredSchema::deserialize_document(0, &mut deserializer)?;
+    /// for (index, file) in files.iter().enumerate().skip(1) {
+    ///     let mut deserializer = serde_json::Deserializer::from_str(file);
+    ///     inferred.extend_with_document(index, &mut deserializer)?;
+    /// }
+    ///
+    /// if let Schema::Struct { fields, .. } = &inferred.schema {
+    ///     assert_eq!(fields["a"].status.first_seen, Some(0));
+    ///     assert_eq!(fields["b"].status.first_seen, Some(2));
+    ///     assert_eq!(fields["a"].status.last_seen, Some(2));
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// To use a custom [Context] (or [AnalysisSettings]), see
+    /// [InferredSchemaWithContext::extend_with_document].
+    pub fn extend_with_document<'de, D>(
+        &mut self,
+        index: usize,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        extend_with_document(
+            &mut self.schema,
+            &DEFAULT_CONTEXT,
+            Some(index),
+            deserializer,
+        )
+    }
+
+    /// Analyzes a JSON document whose root is an array one element at a time, returning the
+    /// schema of the elements (not a [Sequence](Schema::Sequence) wrapping them).
+    ///
+    /// Each element is analyzed as a document of its own and merged into the schema as soon as
+    /// it is read, so memory use depends on the size of the largest element rather than on the
+    /// size of the whole array.
+    /// The reader is read in small chunks, so wrapping it in a [BufReader](std::io::BufReader)
+    /// is recommended.
+    ///
+    /// Returns an error if the root is not an array, if the array is empty, or if anything
+    /// follows the array.
+    ///
+    /// ```
+    /// # use schema_analysis::{InferredSchema, Schema};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let file: &[u8] = br#"[{ "id": 1 }, { "id": 2, "name": "b" }]"#;
+    ///
+    /// let inferred = InferredSchema::from_json_array_stream(file)?;
+    ///
+    /// if let Schema::Struct { fields, context } = &inferred.schema {
+    ///     assert_eq!(context.count.0, 2);
+    ///     assert!(fields["name"].status.may_be_missing);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "serde_json")]
+    pub fn from_json_array_stream<R: std::io::Read>(reader: R) -> Result<Self, serde_json::Error> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let key_buffers = KeyBuffers::default();
+        let visitor = stream::ArrayStreamVisitor {
+            state: State::new(&DEFAULT_CONTEXT).with_key_buffers(&key_buffers),
+        };
+        let schema = deserializer.deserialize_seq(visitor)?;
+        deserializer.end()?;
+        match schema {
+            Some(schema) => Ok(InferredSchema { schema }),
+            None => Err(serde_json::Error::custom("no elements found")),
+        }
+    }
+}
 impl Coalesce for InferredSchema {
     fn coalesce(&mut self, other: Self)
     where
@@ -214,8 +480,9 @@ impl<'de> Deserialize<'de> for InferredSchema {
     where
         D: serde::Deserializer<'de>,
     {
+        let key_buffers = KeyBuffers::default();
         let visitor = SchemaVisitor {
-            context: &DEFAULT_CONTEXT,
+            state: State::new(&DEFAULT_CONTEXT).with_key_buffers(&key_buffers),
         };
         let schema = deserializer.deserialize_any(visitor)?;
         Ok(InferredSchema { schema })
@@ -229,8 +496,9 @@ impl<'de> DeserializeSeed<'de> for &mut InferredSchema {
     where
         D: serde::Deserializer<'de>,
     {
+        let key_buffers = KeyBuffers::default();
         let visitor = SchemaVisitorSeed {
-            context: &DEFAULT_CONTEXT,
+            state: State::new(&DEFAULT_CONTEXT).with_key_buffers(&key_buffers),
             schema: &mut self.schema,
         };
         deserializer.deserialize_any(visitor)?;
@@ -255,6 +523,13 @@ pub struct InferredSchemaWithContext {
     /// The context may be user-provided with additional aggregators.
     pub context: Context,
 }
+impl From<InferredSchemaWithContext> for InferredSchema {
+    fn from(inferred: InferredSchemaWithContext) -> Self {
+        InferredSchema {
+            schema: inferred.schema,
+        }
+    }
+}
 impl Coalesce for InferredSchemaWithContext {
     fn coalesce(&mut self, other: Self)
     where
@@ -263,6 +538,20 @@ impl Coalesce for InferredSchemaWithContext {
         self.schema.coalesce(other.schema);
     }
 }
+impl InferredSchemaWithContext {
+    /// Like [InferredSchema::extend_with_document], but the document is analyzed with the
+    /// [Context].
+    pub fn extend_with_document<'de, D>(
+        &mut self,
+        index: usize,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        extend_with_document(&mut self.schema, &self.context, Some(index), deserializer)
+    }
+}
 // (schema + context) -> (schema + context)
 impl<'de> DeserializeSeed<'de> for &mut InferredSchemaWithContext {
     type Value = ();
@@ -271,8 +560,9 @@ impl<'de> DeserializeSeed<'de> for &mut InferredSchemaWithContext {
     where
         D: serde::Deserializer<'de>,
     {
+        let key_buffers = KeyBuffers::default();
         let visitor = SchemaVisitorSeed {
-            context: &self.context,
+            state: State::new(&self.context).with_key_buffers(&key_buffers),
             schema: &mut self.schema,
         };
         deserializer.deserialize_any(visitor)?;
@@ -290,11 +580,108 @@ impl Context {
     where
         D: Deserializer<'de>,
     {
-        let visitor = SchemaVisitor { context: &self };
+        let key_buffers = KeyBuffers::default();
+        let visitor = SchemaVisitor {
+            state: State::new(&self).with_key_buffers(&key_buffers),
+        };
         let schema = deserializer.deserialize_any(visitor)?;
         Ok(InferredSchemaWithContext {
             context: self,
             schema,
         })
     }
+
+    /// Like [InferredSchema::deserialize_document], but the document is analyzed with the
+    /// context, like [Context::deserialize_schema].
+    ///
+    /// ```
+    /// # use schema_analysis::{Context, Schema};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut context = Context::default();
+    /// context.settings.ignore_path(&["blob"]);
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{ "a": 1, "blob": [1] }"#);
+    /// let mut inferred = context.deserialize_document(0, &mut deserializer)?;
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{ "a": 2, "b": 3 }"#);
+    /// inferred.extend_with_document(1, &mut deserializer)?;
+    ///
+    /// if let Schema::Struct { fields, .. } = &inferred.schema {
+    ///     assert!(fields["blob"].status.is_ignored);
+    ///     assert_eq!(fields["b"].status.first_seen, Some(1));
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deserialize_document<'de, D>(
+        self,
+        index: usize,
+        deserializer: D,
+    ) -> Result<InferredSchemaWithContext, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let schema = analyze_document(&self, Some(index), deserializer)?;
+        Ok(InferredSchemaWithContext {
+            context: self,
+            schema,
+        })
+    }
+}
+
+/// Analyzes a new document, marking its [Field]s as seen in the `document` with that index
+/// if any.
+pub(crate) fn analyze_document<'de, D>(
+    context: &Context,
+    document: Option<usize>,
+    deserializer: D,
+) -> Result<Schema, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let key_buffers = KeyBuffers::default();
+    let visitor = SchemaVisitor {
+        state: State {
+            document,
+            ..State::new(context)
+        }
+        .with_key_buffers(&key_buffers),
+    };
+    deserializer.deserialize_any(visitor)
+}
+
+/// Extends `schema` with a document, marking its [Field]s as seen in the `document` with that
+/// index if any.
+pub(crate) fn extend_with_document<'de, D>(
+    schema: &mut Schema,
+    context: &Context,
+    document: Option<usize>,
+    deserializer: D,
+) -> Result<(), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let key_buffers = KeyBuffers::default();
+    let visitor = SchemaVisitorSeed {
+        state: State {
+            document,
+            ..State::new(context)
+        }
+        .with_key_buffers(&key_buffers),
+        schema,
+    };
+    deserializer.deserialize_any(visitor)?;
+    Ok(())
+}
+
+/// Hands the next value to a visitor through [Deserializer::deserialize_any], to analyze the
+/// content of the variant of a tagged value.
+struct ContentSeed<V>(V);
+impl<'de, V: Visitor<'de>> DeserializeSeed<'de> for ContentSeed<V> {
+    type Value = V::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        deserializer.deserialize_any(self.0)
+    }
 }
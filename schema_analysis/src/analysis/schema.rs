@@ -1,16 +1,18 @@
-use std::collections::BTreeMap;
+use std::{cell::Cell, collections::BTreeMap};
 
-use serde::de::{Error, Visitor};
+use serde::de::{Error, IgnoredAny, VariantAccess, Visitor};
 
-use crate::{Aggregate, Field, Schema};
+use crate::{context::MonotonicityTracker, Aggregate, Field, Schema, StructKey};
 
 use super::{
     field::{FieldVisitor, FieldVisitorSeed},
-    Context,
+    key::KeySeed,
+    state::Path,
+    ContentSeed, State,
 };
 
 pub struct SchemaVisitor<'s> {
-    pub context: &'s Context,
+    pub state: State<'s>,
 }
 
 impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
@@ -21,32 +23,36 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
     }
 
     fn visit_bool<E: Error>(self, value: bool) -> Result<Self::Value, E> {
-        let mut aggregators = self.context.for_boolean();
-        aggregators.aggregate(&value);
+        let mut aggregators = self.state.context.for_boolean();
+        self.state.aggregate(&mut aggregators, &value);
 
         Ok(Schema::Boolean(aggregators))
     }
     fn visit_i128<E: Error>(self, value: i128) -> Result<Self::Value, E> {
-        let mut aggregators = self.context.for_integer();
-        aggregators.aggregate(&value);
+        self.state.record_number(value as f64);
+        let mut aggregators = self.state.context.for_integer();
+        self.state.aggregate(&mut aggregators, &value);
 
         Ok(Schema::Integer(aggregators))
     }
     fn visit_f64<E: Error>(self, value: f64) -> Result<Self::Value, E> {
-        let mut aggregators = self.context.for_float();
-        aggregators.aggregate(&value);
+        self.state.record_number(value);
+        let mut aggregators = self.state.context.for_float();
+        self.state.aggregate(&mut aggregators, &value);
 
         Ok(Schema::Float(aggregators))
     }
     fn visit_borrowed_str<E: Error>(self, value: &'de str) -> Result<Self::Value, E> {
-        let mut aggregators = self.context.for_string();
-        aggregators.aggregate(value);
+        let mut aggregators = self.state.context.for_string();
+        self.state.aggregate_string(&mut aggregators, value);
+        #[cfg(feature = "embedded_json")]
+        self.state.analyze_embedded_json(&mut aggregators, value);
 
         Ok(Schema::String(aggregators))
     }
     fn visit_borrowed_bytes<E: Error>(self, value: &'de [u8]) -> Result<Self::Value, E> {
-        let mut aggregators = self.context.for_bytes();
-        aggregators.aggregate(value);
+        let mut aggregators = self.state.context.for_bytes();
+        self.state.aggregate(&mut aggregators, value);
 
         Ok(Schema::Bytes(aggregators))
     }
@@ -105,7 +111,7 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
     /// This method should only be called if the Null value is at the root of the document,
     /// because otherwise null values are handled at the field level.
     fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
-        let mut aggregators = self.context.for_null();
+        let mut aggregators = self.state.context.for_null();
         aggregators.aggregate(&());
 
         Ok(Schema::Null(aggregators))
@@ -113,15 +119,13 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
     /// Some & None are handled at the field level, with the exception of the root where the
     /// schema itself might be Null in some formats.
     ///
-    /// Since the formats I am currently aware do not support explicitly marking a field as
-    /// optional (which would mean possibly calling visit_some at the root level),
-    /// I am marking this as unreachable!().
-    fn visit_some<D>(self, _deserializer: D) -> Result<Self::Value, D::Error>
+    /// Formats that explicitly mark the root as present (like deserializers wrapping an
+    /// [Option]) call this, and the inner value is analyzed in its place.
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        // self.deserialize(deserializer)
-        unreachable!()
+        deserializer.deserialize_any(self)
     }
     /// serde_json calls this method for `null`, so we assume `visit_unit == visit_none`.
     fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
@@ -132,21 +136,24 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
     where
         A: serde::de::SeqAccess<'de>,
     {
+        let element_number = Cell::new(None);
+        let mut monotonicity = MonotonicityTracker::default();
+        let state = self.state.nested()?.with_element_number(&element_number);
         let mut count = 0;
 
-        let initial_seed = FieldVisitor {
-            context: self.context,
-        };
+        let initial_seed = FieldVisitor { state };
 
         let mut field = match seq.next_element_seed(initial_seed)? {
             Some(mut field) => {
                 count += 1;
+                monotonicity.observe(element_number.take());
 
                 while let Some(()) = seq.next_element_seed(FieldVisitorSeed {
-                    context: self.context,
+                    state,
                     field: &mut field,
                 })? {
                     count += 1;
+                    monotonicity.observe(element_number.take());
                 }
 
                 field
@@ -159,8 +166,11 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
             field.status.may_be_missing = true;
         }
 
-        let mut aggregators = self.context.for_sequence();
-        aggregators.aggregate(&count);
+        let mut aggregators = self.state.context.for_sequence();
+        self.state.aggregate(&mut aggregators, &count);
+        if state.element_number.is_some() {
+            aggregators.aggregate_monotonicity(monotonicity.finish());
+        }
 
         Ok(Schema::Sequence {
             field: Box::new(field),
@@ -172,14 +182,23 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
     where
         A: serde::de::MapAccess<'de>,
     {
-        let mut keys = Vec::new();
-        let mut fields: BTreeMap<String, Field> = BTreeMap::new();
-
-        while let Some(key) = map.next_key::<String>()? {
-            match fields.get_mut(&key) {
+        let state = self.state.nested()?;
+        let mut keys = state.take_key_buffer();
+        let mut spellings = Vec::new();
+        let mut fields: BTreeMap<StructKey, Field> = BTreeMap::new();
+        let mut all_string_keys = true;
+
+        while let Some((key, is_string)) = map.next_key_seed(KeySeed)? {
+            all_string_keys &= is_string;
+            let key = state.normalize_key(key, &mut spellings);
+            let path = Path {
+                parent: state.path,
+                key: &key,
+            };
+            match fields.get_mut(key.as_ref()) {
                 Some(old_field) => {
                     map.next_value_seed(FieldVisitorSeed {
-                        context: self.context,
+                        state: state.with_path(&path),
                         field: old_field,
                     })?;
                     old_field.status.allow_duplicates(true);
@@ -187,17 +206,23 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
 
                 None => {
                     let new_field = map.next_value_seed(FieldVisitor {
-                        context: self.context,
+                        state: state.with_path(&path),
                     })?;
-                    fields.insert(key.clone(), new_field);
+                    fields.insert(state.intern_key(&key), new_field);
                 }
             }
 
-            keys.push(key.clone());
+            keys.push(key);
         }
 
-        let mut aggregators = self.context.for_map_struct();
-        aggregators.aggregate(&keys);
+        let mut aggregators = self.state.context.for_map_struct();
+        aggregators.aggregate_keys(&keys);
+        aggregators.aggregate_spellings(&spellings);
+        if state.context.settings.track_key_sets {
+            aggregators.aggregate_key_set(&keys);
+        }
+        aggregators.may_have_non_string_keys |= !all_string_keys;
+        state.return_key_buffer(keys);
 
         Ok(Schema::Struct {
             fields,
@@ -205,17 +230,22 @@ impl<'de, 's> Visitor<'de> for SchemaVisitor<'s> {
         })
     }
 
-    fn visit_newtype_struct<D>(self, _deserializer: D) -> Result<Self::Value, D::Error>
+    /// Newtype structs are a Rust construct, but deserializers wrapping Rust values may still
+    /// produce them, so the inner value is analyzed in their place.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        unreachable!("newtype structs are a rust construct")
+        deserializer.deserialize_any(self)
     }
 
-    fn visit_enum<A>(self, _data: A) -> Result<Self::Value, A::Error>
+    /// Enums are a Rust construct, but some formats use them for tagged values (like the
+    /// `!Tag value` of YAML), so the tag is dropped and the content is analyzed in its place.
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
     where
         A: serde::de::EnumAccess<'de>,
     {
-        unreachable!("enum types are usually not available from the format's side")
+        let (IgnoredAny, content) = data.variant()?;
+        content.newtype_variant_seed(ContentSeed(self))
     }
 }
@@ -1,15 +1,19 @@
-use serde::de::{Error, Visitor};
+use std::cell::Cell;
 
-use crate::{traits::Coalesce, Aggregate, Schema};
+use serde::de::{Error, IgnoredAny, VariantAccess, Visitor};
+
+use crate::{context::MonotonicityTracker, traits::Coalesce, Aggregate, Schema};
 
 use super::{
     field::{FieldVisitor, FieldVisitorSeed},
+    key::KeySeed,
     schema::SchemaVisitor,
-    Context,
+    state::Path,
+    ContentSeed, State,
 };
 
 pub struct SchemaVisitorSeed<'s> {
-    pub context: &'s Context,
+    pub state: State<'s>,
     pub schema: &'s mut Schema,
 }
 
@@ -23,13 +27,10 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
     fn visit_bool<E: Error>(mut self, value: bool) -> Result<Self::Value, E> {
         match &mut self.schema {
             // The schema matches
-            Schema::Boolean(aggregators) => aggregators.aggregate(&value),
+            Schema::Boolean(aggregators) => self.state.aggregate(aggregators, &value),
             // Extend a different schema
             schema => {
-                let new_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_bool(value)?;
+                let new_schema = SchemaVisitor { state: self.state }.visit_bool(value)?;
 
                 schema.coalesce(new_schema);
             }
@@ -39,13 +40,13 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
     fn visit_i128<E: Error>(mut self, value: i128) -> Result<Self::Value, E> {
         match &mut self.schema {
             // The schema matches
-            Schema::Integer(aggregators) => aggregators.aggregate(&value),
+            Schema::Integer(aggregators) => {
+                self.state.record_number(value as f64);
+                self.state.aggregate(aggregators, &value)
+            }
             // Extend a different schema
             schema => {
-                let new_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_i128(value)?;
+                let new_schema = SchemaVisitor { state: self.state }.visit_i128(value)?;
 
                 schema.coalesce(new_schema);
             }
@@ -55,13 +56,13 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
     fn visit_f64<E: Error>(mut self, value: f64) -> Result<Self::Value, E> {
         match &mut self.schema {
             // The schema matches
-            Schema::Float(aggregators) => aggregators.aggregate(&value),
+            Schema::Float(aggregators) => {
+                self.state.record_number(value);
+                self.state.aggregate(aggregators, &value)
+            }
             // Extend a different schema
             schema => {
-                let new_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_f64(value)?;
+                let new_schema = SchemaVisitor { state: self.state }.visit_f64(value)?;
 
                 schema.coalesce(new_schema);
             }
@@ -71,13 +72,14 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
     fn visit_borrowed_str<E: Error>(mut self, value: &'de str) -> Result<Self::Value, E> {
         match &mut self.schema {
             // The schema matches
-            Schema::String(aggregators) => aggregators.aggregate(value),
+            Schema::String(aggregators) => {
+                self.state.aggregate_string(aggregators, value);
+                #[cfg(feature = "embedded_json")]
+                self.state.analyze_embedded_json(aggregators, value);
+            }
             // Extend a different schema
             schema => {
-                let new_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_borrowed_str(value)?;
+                let new_schema = SchemaVisitor { state: self.state }.visit_borrowed_str(value)?;
 
                 schema.coalesce(new_schema);
             }
@@ -87,13 +89,10 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
     fn visit_borrowed_bytes<E: Error>(mut self, value: &'de [u8]) -> Result<Self::Value, E> {
         match &mut self.schema {
             // The schema matches
-            Schema::Bytes(aggregators) => aggregators.aggregate(value),
+            Schema::Bytes(aggregators) => self.state.aggregate(aggregators, value),
             // Extend a different schema
             schema => {
-                let new_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_borrowed_bytes(value)?;
+                let new_schema = SchemaVisitor { state: self.state }.visit_borrowed_bytes(value)?;
 
                 schema.coalesce(new_schema);
             }
@@ -162,10 +161,7 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
             }
             // Extend a different schema
             schema => {
-                let new_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_none()?;
+                let new_schema = SchemaVisitor { state: self.state }.visit_none()?;
 
                 schema.coalesce(new_schema);
             }
@@ -184,11 +180,12 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
         self.visit_none()
     }
 
-    fn visit_newtype_struct<D>(self, _deserializer: D) -> Result<Self::Value, D::Error>
+    /// Like [SchemaVisitor], the inner value is analyzed instead.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        unreachable!("newtype structs are a rust construct")
+        deserializer.deserialize_any(self)
     }
 
     fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -202,27 +199,28 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
                 field: ref mut boxed_field,
                 context: ref mut aggregators,
             } => {
+                let element_number = Cell::new(None);
+                let mut monotonicity = MonotonicityTracker::default();
+                let state = self.state.nested()?.with_element_number(&element_number);
                 let field = boxed_field.as_mut();
 
-                while let Some(()) = seq.next_element_seed(FieldVisitorSeed {
-                    context: self.context,
-                    field,
-                })? {
+                while let Some(()) = seq.next_element_seed(FieldVisitorSeed { state, field })? {
                     count += 1;
+                    monotonicity.observe(element_number.take());
                 }
 
                 if count == 0 {
                     field.status.may_be_missing = true;
                 }
 
-                aggregators.aggregate(&count);
+                state.aggregate(aggregators, &count);
+                if state.element_number.is_some() {
+                    aggregators.aggregate_monotonicity(monotonicity.finish());
+                }
             }
             // Extend a different schema
             schema => {
-                let sequence_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_seq(seq)?;
+                let sequence_schema = SchemaVisitor { state: self.state }.visit_seq(seq)?;
                 schema.coalesce(sequence_schema);
             }
         };
@@ -233,31 +231,39 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
     where
         A: serde::de::MapAccess<'de>,
     {
-        let mut keys = Vec::new();
+        let mut spellings = Vec::new();
         match &mut self.schema {
             Schema::Struct {
                 fields,
                 context: aggregators,
             } => {
-                while let Some(key) = map.next_key::<String>()? {
-                    match fields.get_mut(&key) {
+                let state = self.state.nested()?;
+                let mut keys = state.take_key_buffer();
+                while let Some((key, is_string)) = map.next_key_seed(KeySeed)? {
+                    aggregators.may_have_non_string_keys |= !is_string;
+                    let key = state.normalize_key(key, &mut spellings);
+                    let path = Path {
+                        parent: state.path,
+                        key: &key,
+                    };
+                    match fields.get_mut(key.as_ref()) {
                         Some(old_field) => {
                             old_field.status.allow_duplicates(keys.contains(&key));
                             map.next_value_seed(FieldVisitorSeed {
-                                context: self.context,
+                                state: state.with_path(&path),
                                 field: old_field,
                             })?;
                         }
 
                         None => {
                             let mut new_field = map.next_value_seed(FieldVisitor {
-                                context: self.context,
+                                state: state.with_path(&path),
                             })?;
                             // If we are adding it to an existing schema it means that it was
                             // missing when this schema was created.
                             new_field.status.may_be_missing = true;
                             new_field.status.allow_duplicates(keys.contains(&key));
-                            fields.insert(key.clone(), new_field);
+                            fields.insert(state.intern_key(&key), new_field);
                         }
                     }
 
@@ -265,28 +271,33 @@ impl<'de, 's> Visitor<'de> for SchemaVisitorSeed<'s> {
                 }
 
                 for (k, f) in fields {
-                    if !keys.contains(k) {
+                    if !keys.iter().any(|key| **key == **k) {
                         f.status.may_be_missing = true;
                     }
                 }
 
-                aggregators.aggregate(&keys);
+                aggregators.aggregate_keys(&keys);
+                aggregators.aggregate_spellings(&spellings);
+                if state.context.settings.track_key_sets {
+                    aggregators.aggregate_key_set(&keys);
+                }
+                state.return_key_buffer(keys);
             }
             schema => {
-                let sequence_schema = SchemaVisitor {
-                    context: self.context,
-                }
-                .visit_map(map)?;
+                let sequence_schema = SchemaVisitor { state: self.state }.visit_map(map)?;
                 schema.coalesce(sequence_schema);
             }
         }
         Ok(())
     }
 
-    fn visit_enum<A>(self, _data: A) -> Result<Self::Value, A::Error>
+    /// Enums are a Rust construct, but some formats use them for tagged values (like the
+    /// `!Tag value` of YAML), so the tag is dropped and the content is analyzed in its place.
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
     where
         A: serde::de::EnumAccess<'de>,
     {
-        unreachable!("enum types are usually not available from the format's side")
+        let (IgnoredAny, content) = data.variant()?;
+        content.newtype_variant_seed(ContentSeed(self))
     }
 }
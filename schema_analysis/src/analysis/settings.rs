@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+/// Settings that change how the analysis itself is run.
+///
+/// They are held by the [Context](crate::Context) so that they reach every part of the
+/// analysis, use [InferredSchemaWithContext](crate::InferredSchemaWithContext) to provide them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisSettings {
+    /// The values found under these paths are skipped without being analyzed.
+    ///
+    /// Each path is a list of struct keys starting from the root, sequences are transparent
+    /// (so `["a", "b"]` matches `{ "a": [ { "b": .. } ] }` too).
+    /// The [Field](crate::Field)s of ignored values are marked with
+    /// [is_ignored](crate::FieldStatus::is_ignored).
+    pub ignored_paths: Vec<Vec<String>>,
+    /// The maximum number of sequences and maps a value may be nested in, deeper documents
+    /// return an error instead of risking a stack overflow.
+    ///
+    /// Defaults to [DEFAULT_MAX_DEPTH](AnalysisSettings::DEFAULT_MAX_DEPTH), `None` disables the
+    /// check and should only be used with trusted input.
+    pub max_depth: Option<usize>,
+    /// If set, struct keys are normalized before being recorded, so that keys spelled
+    /// differently in different documents (like `UserId` and `userId`) end up in the same
+    /// [Field](crate::Field).
+    ///
+    /// The original spellings are recorded in the
+    /// [MapStructContext](crate::context::MapStructContext), and keys that collide within the
+    /// same struct are marked as duplicates.
+    pub key_normalizer: Option<KeyNormalizer>,
+    /// How many levels of JSON embedded in strings (like `"{\"a\": 1}"`) are analyzed, the
+    /// schema of the embedded documents is stored in the
+    /// [StringContext](crate::context::StringContext).
+    ///
+    /// Defaults to `0`, which only counts them.
+    #[cfg(feature = "embedded_json")]
+    pub embedded_json_depth: usize,
+    /// If `true`, sequences of numbers are checked for being sorted, see
+    /// [SequenceContext::monotonicity](crate::context::SequenceContext::monotonicity).
+    pub track_monotonicity: bool,
+    /// If `true`, [suspicious strings](crate::context::SuspiciousStrings) are recorded in their
+    /// normalized (lowercase) form, so that `N/A` and `n/a` are counted together. The original
+    /// spellings are recorded in
+    /// [suspicious_spellings](crate::context::StringContext::suspicious_spellings).
+    ///
+    /// Defaults to `false`, which counts each spelling separately.
+    pub normalize_suspicious_strings: bool,
+    /// If `true`, the distinct sets of keys found in each struct are counted, see
+    /// [MapStructContext::key_sets](crate::context::MapStructContext::key_sets).
+    pub track_key_sets: bool,
+}
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        Self {
+            ignored_paths: Vec::new(),
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+            key_normalizer: None,
+            #[cfg(feature = "embedded_json")]
+            embedded_json_depth: 0,
+            track_monotonicity: false,
+            normalize_suspicious_strings: false,
+            track_key_sets: false,
+        }
+    }
+}
+
+impl AnalysisSettings {
+    /// The default [max_depth](AnalysisSettings::max_depth), the same limit serde_json uses.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// Adds a path to [ignored_paths](AnalysisSettings::ignored_paths).
+    pub fn ignore_path(&mut self, path: &[&str]) {
+        self.ignored_paths
+            .push(path.iter().map(|s| s.to_string()).collect());
+    }
+}
+
+/// The ways in which struct keys can be normalized, see
+/// [key_normalizer](AnalysisSettings::key_normalizer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyNormalizer {
+    /// Lowercases the keys, so `UserId`, `userId` and `userid` are the same.
+    Lowercase,
+    /// Lowercases the keys and removes `_`, `-` and whitespace, so `user_id` and `User-Id` are
+    /// the same as `userId` too.
+    Fold,
+}
+impl KeyNormalizer {
+    /// Returns the normalized key, or `None` if the key is already normalized.
+    pub fn normalize(&self, key: &str) -> Option<String> {
+        let is_removed = |c: char| match self {
+            KeyNormalizer::Lowercase => false,
+            KeyNormalizer::Fold => c == '_' || c == '-' || c.is_whitespace(),
+        };
+        if !key.chars().any(|c| c.is_uppercase() || is_removed(c)) {
+            return None;
+        }
+        Some(
+            key.chars()
+                .filter(|c| !is_removed(*c))
+                .flat_map(char::to_lowercase)
+                .collect(),
+        )
+    }
+}
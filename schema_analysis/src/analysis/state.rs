@@ -0,0 +1,256 @@
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+};
+
+use serde::de::Error;
+
+use crate::{context::StringContext, Aggregate, Context, StructKey};
+
+/// The state shared by the visitors as they walk through a document.
+#[derive(Debug, Clone, Copy)]
+pub struct State<'s> {
+    /// The (possibly user-provided) context.
+    pub context: &'s Context,
+    /// The struct keys leading to the current value, `None` at the root.
+    pub path: Option<&'s Path<'s>>,
+    /// The number of sequences and maps the current value is nested in.
+    pub depth: usize,
+    /// The index of the document being analyzed, if provided by the user.
+    pub document: Option<usize>,
+    /// The number of JSON strings the current value is embedded in.
+    #[cfg(feature = "embedded_json")]
+    pub embedded_depth: usize,
+    /// Set when the current value is an element of a sequence whose monotonicity is tracked,
+    /// numbers record themselves here.
+    pub element_number: Option<&'s Cell<Option<f64>>>,
+    /// The buffers that maps can reuse to collect their keys, if any.
+    pub key_buffers: Option<&'s KeyBuffers>,
+}
+
+/// Cleared key buffers kept for reuse during the analysis of a document, so that each map
+/// does not allocate a new one.
+///
+/// A buffer is taken by each map being analyzed, so there are at most as many as the maps are
+/// nested, and they can be reused by all the sibling maps.
+///
+/// The buffers are created for each call that analyzes a document (or stream of documents),
+/// so a few allocations are still made per call, but not per map.
+///
+/// With the `intern_keys` feature, the keys of the new fields are also interned here, so that
+/// the same key found at different paths during the call shares its allocation.
+#[derive(Debug, Default)]
+pub struct KeyBuffers {
+    buffers: RefCell<Vec<Vec<Cow<'static, str>>>>,
+    #[cfg(feature = "intern_keys")]
+    interned: RefCell<std::collections::HashSet<StructKey>>,
+}
+
+/// Clears `buffer` and changes the lifetime of its (now absent) keys.
+///
+/// The allocation is only kept because the standard library reuses it when collecting a [Vec]
+/// iterator into a [Vec] with the same element layout. That is an optimization, not a
+/// guarantee: without it a new buffer is allocated, which is correct, only slower.
+fn recycle<'a, 'b>(mut buffer: Vec<Cow<'a, str>>) -> Vec<Cow<'b, str>> {
+    buffer.clear();
+    // The buffer is empty, so the closure is never called.
+    buffer.into_iter().map(|_| Cow::Borrowed("")).collect()
+}
+
+/// A linked list of the struct keys leading to a value, from the innermost key outwards.
+///
+/// Sequences do not add a segment, so all elements share the path of the sequence.
+#[derive(Debug)]
+pub struct Path<'s> {
+    pub parent: Option<&'s Path<'s>>,
+    pub key: &'s str,
+}
+
+impl<'s> State<'s> {
+    pub fn new(context: &'s Context) -> Self {
+        Self {
+            context,
+            path: None,
+            depth: 0,
+            document: None,
+            #[cfg(feature = "embedded_json")]
+            embedded_depth: 0,
+            element_number: None,
+            key_buffers: None,
+        }
+    }
+
+    /// Returns the state that reuses the key buffers of `key_buffers`.
+    pub fn with_key_buffers<'a>(&self, key_buffers: &'a KeyBuffers) -> State<'a>
+    where
+        's: 'a,
+    {
+        State {
+            key_buffers: Some(key_buffers),
+            ..*self
+        }
+    }
+
+    /// Returns an empty buffer for the keys of a map, reusing an old one if possible.
+    pub fn take_key_buffer<'de>(&self) -> Vec<Cow<'de, str>> {
+        self.key_buffers
+            .and_then(|key_buffers| key_buffers.buffers.borrow_mut().pop())
+            .map(recycle)
+            .unwrap_or_default()
+    }
+
+    /// Gives back a buffer returned by [take_key_buffer](State::take_key_buffer) once the map
+    /// has been analyzed.
+    pub fn return_key_buffer(&self, buffer: Vec<Cow<'_, str>>) {
+        if let Some(key_buffers) = self.key_buffers {
+            key_buffers.buffers.borrow_mut().push(recycle(buffer));
+        }
+    }
+
+    /// Returns the [StructKey] for a new field, shared with the other fields with the same key
+    /// when interning.
+    #[cfg(not(feature = "intern_keys"))]
+    pub fn intern_key(&self, key: &str) -> StructKey {
+        key.into()
+    }
+    /// Returns the [StructKey] for a new field, shared with the other fields with the same key
+    /// when interning.
+    #[cfg(feature = "intern_keys")]
+    pub fn intern_key(&self, key: &str) -> StructKey {
+        let key_buffers = match self.key_buffers {
+            Some(key_buffers) => key_buffers,
+            None => return key.into(),
+        };
+        let mut interned = key_buffers.interned.borrow_mut();
+        match interned.get(key) {
+            Some(interned) => interned.clone(),
+            None => {
+                let key: StructKey = key.into();
+                interned.insert(key.clone());
+                key
+            }
+        }
+    }
+
+    /// Returns the state for the value found under `path`, which should be a child of the
+    /// current path.
+    pub fn with_path<'a>(&self, path: &'a Path<'a>) -> State<'a>
+    where
+        's: 'a,
+    {
+        State {
+            path: Some(path),
+            ..*self
+        }
+    }
+
+    /// Returns the state for the values inside the current sequence or map, or an error if
+    /// that would exceed the [max_depth](crate::AnalysisSettings::max_depth).
+    pub fn nested<E: Error>(&self) -> Result<Self, E> {
+        let depth = self.depth + 1;
+        match self.context.settings.max_depth {
+            Some(max_depth) if depth > max_depth => Err(E::custom(format!(
+                "maximum nesting depth of {} exceeded",
+                max_depth
+            ))),
+            _ => Ok(Self {
+                depth,
+                element_number: None,
+                ..*self
+            }),
+        }
+    }
+
+    /// Returns the state for the elements of a sequence, which record their value in
+    /// `element_number` if they are numbers and
+    /// [track_monotonicity](crate::AnalysisSettings::track_monotonicity) is set.
+    pub fn with_element_number<'a>(&self, element_number: &'a Cell<Option<f64>>) -> State<'a>
+    where
+        's: 'a,
+    {
+        State {
+            element_number: Some(element_number)
+                .filter(|_| self.context.settings.track_monotonicity),
+            ..*self
+        }
+    }
+
+    /// Records a number if the current value is an element of a tracked sequence.
+    pub fn record_number(&self, value: f64) {
+        if let Some(element_number) = self.element_number {
+            element_number.set(Some(value));
+        }
+    }
+
+    /// Applies the [key_normalizer](crate::AnalysisSettings::key_normalizer), if any.
+    ///
+    /// When normalizing, the normalized key and the original spelling are also pushed to
+    /// `spellings`.
+    pub fn normalize_key<'de>(
+        &self,
+        original: Cow<'de, str>,
+        spellings: &mut Vec<(Cow<'de, str>, Cow<'de, str>)>,
+    ) -> Cow<'de, str> {
+        match &self.context.settings.key_normalizer {
+            Some(normalizer) => {
+                let key = match normalizer.normalize(&original) {
+                    Some(normalized) => Cow::Owned(normalized),
+                    None => original.clone(),
+                };
+                spellings.push((key.clone(), original));
+                key
+            }
+            None => original,
+        }
+    }
+
+    /// Records a value in `context`, along with the innermost struct key if there is one.
+    pub fn aggregate<V: ?Sized, A: Aggregate<V>>(&self, context: &mut A, value: &V) {
+        match self.path {
+            Some(path) => context.aggregate_with_key(path.key, value),
+            None => context.aggregate(value),
+        }
+    }
+
+    /// Records a string in `context`, following
+    /// [normalize_suspicious_strings](crate::AnalysisSettings::normalize_suspicious_strings).
+    pub fn aggregate_string(&self, context: &mut StringContext, value: &str) {
+        context.aggregate_with(
+            self.path.map(|path| path.key),
+            value,
+            self.context.settings.normalize_suspicious_strings,
+        );
+    }
+
+    /// Returns the keys of the current path, from the root.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        let mut current = self.path;
+        while let Some(path) = current {
+            keys.push(path.key.to_string());
+            current = path.parent;
+        }
+        keys.reverse();
+        keys
+    }
+
+    /// Returns `true` if the value at the current path should not be analyzed.
+    pub fn is_ignored(&self) -> bool {
+        self.context
+            .settings
+            .ignored_paths
+            .iter()
+            .any(|ignored| self.path_matches(ignored))
+    }
+
+    fn path_matches(&self, ignored: &[String]) -> bool {
+        let mut current = self.path;
+        for segment in ignored.iter().rev() {
+            match current {
+                Some(path) if path.key == segment => current = path.parent,
+                _ => return false,
+            }
+        }
+        current.is_none()
+    }
+}
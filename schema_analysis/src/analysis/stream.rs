@@ -0,0 +1,73 @@
+use std::fmt;
+
+use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+
+use crate::Schema;
+
+use super::{schema::SchemaVisitor, schema_seed::SchemaVisitorSeed, State};
+
+/// Visits a root sequence one element at a time, the first element creates the [Schema] and
+/// the rest extend it, so no element outlives its own analysis.
+///
+/// Returns `None` if the sequence is empty.
+pub struct ArrayStreamVisitor<'s> {
+    pub state: State<'s>,
+}
+
+impl<'de, 's> Visitor<'de> for ArrayStreamVisitor<'s> {
+    type Value = Option<Schema>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let state = self.state;
+
+        let mut schema = match seq.next_element_seed(Element { state })? {
+            Some(schema) => schema,
+            None => return Ok(None),
+        };
+        while let Some(()) = seq.next_element_seed(ElementSeed {
+            state,
+            schema: &mut schema,
+        })? {}
+
+        Ok(Some(schema))
+    }
+}
+
+struct Element<'s> {
+    state: State<'s>,
+}
+impl<'de, 's> DeserializeSeed<'de> for Element<'s> {
+    type Value = Schema;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SchemaVisitor { state: self.state })
+    }
+}
+
+struct ElementSeed<'s> {
+    state: State<'s>,
+    schema: &'s mut Schema,
+}
+impl<'de, 's> DeserializeSeed<'de> for ElementSeed<'s> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SchemaVisitorSeed {
+            state: self.state,
+            schema: self.schema,
+        })
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+};
+
+use serde::{
+    de::{self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, Visitor},
+    ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer},
+};
+
+/// A [Deserializer] that forwards everything to `deserializer`, and writes each value it hands
+/// out to `serializer` as it goes, in the style of
+/// [serde_transcode](https://github.com/sfackler/serde-transcode).
+///
+/// Nested values get their own serializer from the enclosing [SerializeSeq] or [SerializeMap],
+/// so they are handed to the visitor from within the [Serialize] implementation of
+/// [SeedValue].
+pub struct Tee<'a, D, S: Serializer> {
+    pub deserializer: D,
+    pub serializer: S,
+    /// Receives the output of the serializer once the value has been written.
+    pub ok: &'a Cell<Option<S::Ok>>,
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, D::Error> {
+                let visitor = TeeVisitor {
+                    visitor,
+                    serializer: self.serializer,
+                };
+                let (value, ok) = self.deserializer.$method($($arg,)* visitor)?;
+                self.ok.set(Some(ok));
+                Ok(value)
+            }
+        )*
+    };
+}
+
+impl<'de, D: Deserializer<'de>, S: Serializer> Deserializer<'de> for Tee<'_, D, S> {
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any(),
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_option(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_newtype_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(),
+    }
+
+    /// The values skipped by the analysis (like the ones under ignored paths) are still
+    /// copied, so they are deserialized like any other.
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+/// Writes each value to the serializer before handing it to the visitor.
+struct TeeVisitor<V, S> {
+    visitor: V,
+    serializer: S,
+}
+
+macro_rules! tee_visit {
+    ($($method:ident($ty:ty) => $serialize:ident),* $(,)?) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> Result<Self::Value, E> {
+                let ok = self.serializer.$serialize(value).map_err(E::custom)?;
+                Ok((self.visitor.$method(value)?, ok))
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>, S: Serializer> Visitor<'de> for TeeVisitor<V, S> {
+    type Value = (V::Value, S::Ok);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    tee_visit! {
+        visit_bool(bool) => serialize_bool,
+        visit_i8(i8) => serialize_i8,
+        visit_i16(i16) => serialize_i16,
+        visit_i32(i32) => serialize_i32,
+        visit_i64(i64) => serialize_i64,
+        visit_i128(i128) => serialize_i128,
+        visit_u8(u8) => serialize_u8,
+        visit_u16(u16) => serialize_u16,
+        visit_u32(u32) => serialize_u32,
+        visit_u64(u64) => serialize_u64,
+        visit_u128(u128) => serialize_u128,
+        visit_f32(f32) => serialize_f32,
+        visit_f64(f64) => serialize_f64,
+        visit_char(char) => serialize_char,
+        visit_str(&str) => serialize_str,
+        visit_borrowed_str(&'de str) => serialize_str,
+        visit_bytes(&[u8]) => serialize_bytes,
+        visit_borrowed_bytes(&'de [u8]) => serialize_bytes,
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+        let ok = self.serializer.serialize_str(&value).map_err(E::custom)?;
+        Ok((self.visitor.visit_string(value)?, ok))
+    }
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+        let ok = self.serializer.serialize_bytes(&value).map_err(E::custom)?;
+        Ok((self.visitor.visit_byte_buf(value)?, ok))
+    }
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        let ok = self.serializer.serialize_none().map_err(E::custom)?;
+        Ok((self.visitor.visit_none()?, ok))
+    }
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        let ok = self.serializer.serialize_unit().map_err(E::custom)?;
+        Ok((self.visitor.visit_unit()?, ok))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let value = SeedValue::new(SomeSeed(self.visitor), deserializer);
+        let serialized = self.serializer.serialize_some(&value);
+        value.finish(serialized)
+    }
+    /// Newtype structs have no name in the input, so they are written as their content.
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        let ok = Cell::new(None);
+        let value = self.visitor.visit_newtype_struct(Tee {
+            deserializer,
+            serializer: self.serializer,
+            ok: &ok,
+        })?;
+        match ok.take() {
+            Some(ok) => Ok((value, ok)),
+            None => Err(de::Error::custom(NOT_WRITTEN)),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        let mut state = self
+            .serializer
+            .serialize_seq(seq.size_hint())
+            .map_err(de::Error::custom)?;
+        let value = self.visitor.visit_seq(TeeSeq {
+            seq,
+            state: &mut state,
+        })?;
+        let ok = state.end().map_err(de::Error::custom)?;
+        Ok((value, ok))
+    }
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        let mut state = self
+            .serializer
+            .serialize_map(map.size_hint())
+            .map_err(de::Error::custom)?;
+        let value = self.visitor.visit_map(TeeMap {
+            map,
+            state: &mut state,
+        })?;
+        let ok = state.end().map_err(de::Error::custom)?;
+        Ok((value, ok))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, _data: A) -> Result<Self::Value, A::Error> {
+        Err(de::Error::custom("enums can't be transcoded"))
+    }
+}
+
+const NOT_WRITTEN: &str = "the value was not written to the serializer";
+
+/// Runs the seed against the deserializer when serialized, with the serializer it is given.
+///
+/// [Serialize::serialize] only takes `&self`, so the seed and the result are kept in cells.
+struct SeedValue<'de, T: DeserializeSeed<'de>, D: Deserializer<'de>> {
+    input: RefCell<Option<(T, D)>>,
+    output: RefCell<Option<Result<T::Value, D::Error>>>,
+}
+impl<'de, T: DeserializeSeed<'de>, D: Deserializer<'de>> SeedValue<'de, T, D> {
+    fn new(seed: T, deserializer: D) -> Self {
+        Self {
+            input: RefCell::new(Some((seed, deserializer))),
+            output: RefCell::new(None),
+        }
+    }
+    /// Returns the value produced by the seed along with the output of the serializer that
+    /// wrote it. Deserialization errors take precedence, as they are the root cause.
+    fn finish<O, E: ser::Error>(self, serialized: Result<O, E>) -> Result<(T::Value, O), D::Error> {
+        match (self.output.into_inner(), serialized) {
+            (Some(Err(error)), _) => Err(error),
+            (Some(Ok(value)), Ok(ok)) => Ok((value, ok)),
+            (_, Err(error)) => Err(de::Error::custom(error)),
+            (None, Ok(_)) => Err(de::Error::custom(NOT_WRITTEN)),
+        }
+    }
+}
+impl<'de, T: DeserializeSeed<'de>, D: Deserializer<'de>> Serialize for SeedValue<'de, T, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (seed, deserializer) = match self.input.borrow_mut().take() {
+            Some(input) => input,
+            None => return Err(ser::Error::custom("the value can only be written once")),
+        };
+        let ok = Cell::new(None);
+        let result = seed.deserialize(Tee {
+            deserializer,
+            serializer,
+            ok: &ok,
+        });
+        let failed = result.is_err();
+        *self.output.borrow_mut() = Some(result);
+        match ok.take() {
+            Some(ok) if !failed => Ok(ok),
+            _ => Err(ser::Error::custom("the value could not be read")),
+        }
+    }
+}
+
+/// Hands the content of a `Some` to a visitor.
+struct SomeSeed<V>(V);
+impl<'de, V: Visitor<'de>> DeserializeSeed<'de> for SomeSeed<V> {
+    type Value = V::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        self.0.visit_some(deserializer)
+    }
+}
+
+struct TeeSeq<'a, A, M> {
+    seq: A,
+    state: &'a mut M,
+}
+impl<'de, A: SeqAccess<'de>, M: SerializeSeq> SeqAccess<'de> for TeeSeq<'_, A, M> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, A::Error> {
+        self.seq.next_element_seed(TeeElement {
+            seed,
+            state: &mut *self.state,
+        })
+    }
+    fn size_hint(&self) -> Option<usize> {
+        self.seq.size_hint()
+    }
+}
+struct TeeElement<'a, T, M> {
+    seed: T,
+    state: &'a mut M,
+}
+impl<'de, T: DeserializeSeed<'de>, M: SerializeSeq> DeserializeSeed<'de> for TeeElement<'_, T, M> {
+    type Value = T::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T::Value, D::Error> {
+        let value = SeedValue::new(self.seed, deserializer);
+        let serialized = self.state.serialize_element(&value);
+        value.finish(serialized).map(|(value, ())| value)
+    }
+}
+
+struct TeeMap<'a, A, M> {
+    map: A,
+    state: &'a mut M,
+}
+impl<'de, A: MapAccess<'de>, M: SerializeMap> MapAccess<'de> for TeeMap<'_, A, M> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        self.map.next_key_seed(TeeEntry {
+            seed,
+            state: &mut *self.state,
+            is_key: true,
+        })
+    }
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.map.next_value_seed(TeeEntry {
+            seed,
+            state: &mut *self.state,
+            is_key: false,
+        })
+    }
+    fn size_hint(&self) -> Option<usize> {
+        self.map.size_hint()
+    }
+}
+struct TeeEntry<'a, T, M> {
+    seed: T,
+    state: &'a mut M,
+    is_key: bool,
+}
+impl<'de, T: DeserializeSeed<'de>, M: SerializeMap> DeserializeSeed<'de> for TeeEntry<'_, T, M> {
+    type Value = T::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T::Value, D::Error> {
+        let value = SeedValue::new(self.seed, deserializer);
+        let serialized = match self.is_key {
+            true => self.state.serialize_key(&value),
+            false => self.state.serialize_value(&value),
+        };
+        value.finish(serialized).map(|(value, ())| value)
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::BTreeSet;
+
+use serde::{
+    de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserializer,
+};
+
+use crate::{Context, FieldStatus, Schema};
+
+use super::{key::KeySeed, state::Path, State, DEFAULT_CONTEXT};
+
+/// The result of [Schema::validate], lists the ways in which a document did not conform to the
+/// [Schema].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    /// The issues found, in the order they were found in.
+    pub issues: Vec<ValidationIssue>,
+}
+impl ValidationReport {
+    /// Returns `true` if the document conforms to the [Schema].
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A single way in which a document did not conform to the [Schema].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The struct keys leading to the value, sequences add no segment.
+    pub path: Vec<String>,
+    /// What was wrong with the value.
+    pub kind: ValidationIssueKind,
+}
+
+/// The kinds of [ValidationIssue].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssueKind {
+    /// The document has a field that the [Schema] has never seen.
+    UnexpectedField,
+    /// The document is missing a field that was always present.
+    MissingField,
+    /// The value is null, but the [Field](crate::Field) was never found to be null.
+    UnexpectedNull,
+    /// The value is of a different type than the one(s) recorded in the [Schema].
+    TypeMismatch {
+        /// The types recorded in the [Schema] (like `"Integer"`).
+        expected: Vec<&'static str>,
+        /// The type of the value.
+        found: &'static str,
+    },
+}
+
+impl Schema {
+    /// Checks whether a document conforms to the [Schema], without modifying it.
+    ///
+    /// This is a cheap structural check: it reports fields that are unexpected, missing or
+    /// null when they never were, and values of a type the [Schema] has not recorded in that
+    /// place. Fields with no known schema, and ignored ones, accept anything.
+    ///
+    /// The document is read with the default [AnalysisSettings](crate::AnalysisSettings), use
+    /// [validate_with_context](Schema::validate_with_context) for a schema inferred with
+    /// custom ones.
+    ///
+    /// ```
+    /// # use schema_analysis::{analysis::ValidationIssueKind, InferredSchema};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let inferred: InferredSchema = serde_json::from_str(r#"{ "id": 1 }"#)?;
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{ "id": "1" }"#);
+    /// let report = inferred.schema.validate(&mut deserializer)?;
+    ///
+    /// assert_eq!(
+    ///     report.issues[0].kind,
+    ///     ValidationIssueKind::TypeMismatch { expected: vec!["Integer"], found: "String" }
+    /// );
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate<'de, D: Deserializer<'de>>(
+        &self,
+        deserializer: D,
+    ) -> Result<ValidationReport, D::Error> {
+        self.validate_with_context(&DEFAULT_CONTEXT, deserializer)
+    }
+
+    /// Like [validate](Schema::validate), but the document is read with the
+    /// [AnalysisSettings](crate::AnalysisSettings) of the [Context] used to infer the
+    /// [Schema], so that keys are normalized and ignored paths are skipped in the same way.
+    ///
+    /// ```
+    /// # use schema_analysis::{Context, KeyNormalizer};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut context = Context::default();
+    /// context.settings.key_normalizer = Some(KeyNormalizer::Lowercase);
+    /// context.settings.ignore_path(&["blob"]);
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{ "Id": 1 }"#);
+    /// let inferred = context.deserialize_schema(&mut deserializer)?;
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{ "ID": 2, "blob": 3 }"#);
+    /// let report = inferred
+    ///     .schema
+    ///     .validate_with_context(&inferred.context, &mut deserializer)?;
+    /// assert!(report.is_valid());
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_with_context<'de, D: Deserializer<'de>>(
+        &self,
+        context: &Context,
+        deserializer: D,
+    ) -> Result<ValidationReport, D::Error> {
+        let mut report = ValidationReport::default();
+        deserializer.deserialize_any(ValidationVisitor {
+            state: State::new(context),
+            status: None,
+            schema: Some(self),
+            issues: &mut report.issues,
+        })?;
+        Ok(report)
+    }
+}
+
+/// Checks a value against the [Schema] of a [Field](crate::Field), or the root [Schema] if
+/// there is no [FieldStatus].
+struct ValidationVisitor<'s, 'r> {
+    state: State<'s>,
+    status: Option<&'s FieldStatus>,
+    /// `None` if anything is accepted.
+    schema: Option<&'s Schema>,
+    issues: &'r mut Vec<ValidationIssue>,
+}
+
+impl<'s, 'r> ValidationVisitor<'s, 'r> {
+    fn report(&mut self, kind: ValidationIssueKind) {
+        self.issues.push(ValidationIssue {
+            path: self.state.keys(),
+            kind,
+        });
+    }
+
+    /// Returns the [Schema] (or [Union](Schema::Union) variant) matching the kind of the
+    /// value, reporting a mismatch if there is none.
+    ///
+    /// Returns `None` both when anything is accepted and when there was a mismatch.
+    fn expect(&mut self, found: &'static str) -> Option<&'s Schema> {
+        let schema = self.schema?;
+        let matching = schema
+            .top_level_variants()
+            .into_iter()
+            .find(|variant| variant.variant_name() == found);
+        if matching.is_none() {
+            let expected = schema
+                .top_level_variants()
+                .into_iter()
+                .map(Schema::variant_name)
+                .collect();
+            self.report(ValidationIssueKind::TypeMismatch { expected, found });
+        }
+        matching
+    }
+}
+
+impl<'de, 's, 'r> DeserializeSeed<'de> for ValidationVisitor<'s, 'r> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.schema {
+            Some(_) => deserializer.deserialize_any(self),
+            None => deserializer.deserialize_ignored_any(IgnoredAny).map(|_| ()),
+        }
+    }
+}
+
+macro_rules! method_impl {
+    ($method_name:ident, $type:ty, $found:literal) => {
+        fn $method_name<E: Error>(mut self, _value: $type) -> Result<Self::Value, E> {
+            self.expect($found);
+            Ok(())
+        }
+    };
+}
+
+impl<'de, 's, 'r> Visitor<'de> for ValidationVisitor<'s, 'r> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("anything")
+    }
+
+    method_impl!(visit_bool, bool, "Boolean");
+
+    method_impl!(visit_i8, i8, "Integer");
+    method_impl!(visit_i16, i16, "Integer");
+    method_impl!(visit_i32, i32, "Integer");
+    method_impl!(visit_i64, i64, "Integer");
+    method_impl!(visit_i128, i128, "Integer");
+    method_impl!(visit_u8, u8, "Integer");
+    method_impl!(visit_u16, u16, "Integer");
+    method_impl!(visit_u32, u32, "Integer");
+    method_impl!(visit_u64, u64, "Integer");
+    method_impl!(visit_u128, u128, "Integer");
+
+    method_impl!(visit_f32, f32, "Float");
+    method_impl!(visit_f64, f64, "Float");
+
+    method_impl!(visit_char, char, "String");
+    method_impl!(visit_str, &str, "String");
+    method_impl!(visit_string, String, "String");
+
+    method_impl!(visit_bytes, &[u8], "Bytes");
+    method_impl!(visit_byte_buf, Vec<u8>, "Bytes");
+
+    fn visit_none<E: Error>(mut self) -> Result<Self::Value, E> {
+        match self.status {
+            // Null values in structs and sequences are recorded at the field level.
+            Some(status) => {
+                if !status.may_be_null {
+                    self.report(ValidationIssueKind::UnexpectedNull);
+                }
+            }
+            None => {
+                self.expect("Null");
+            }
+        }
+        Ok(())
+    }
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+    fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+        self.visit_none()
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let state = self.state.nested()?;
+        let field = match self.expect("Sequence") {
+            Some(Schema::Sequence { field, .. }) => Some(&**field),
+            _ => None,
+        };
+
+        while let Some(()) = seq.next_element_seed(ValidationVisitor {
+            state,
+            status: field.map(|field| &field.status),
+            schema: field.and_then(|field| field.schema.as_ref()),
+            issues: &mut *self.issues,
+        })? {}
+
+        Ok(())
+    }
+
+    fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let state = self.state.nested()?;
+        let fields = match self.expect("Struct") {
+            Some(Schema::Struct { fields, .. }) => fields,
+            _ => {
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+                return Ok(());
+            }
+        };
+
+        let mut seen = BTreeSet::new();
+        let mut spellings = Vec::new();
+        while let Some((key, _)) = map.next_key_seed(KeySeed)? {
+            let key = state.normalize_key(key, &mut spellings);
+            spellings.clear();
+            let path = Path {
+                parent: state.path,
+                key: &key,
+            };
+            let state = state.with_path(&path);
+            match fields.get(key.as_ref()) {
+                _ if state.is_ignored() => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+                Some(field) if !field.status.is_ignored => {
+                    map.next_value_seed(ValidationVisitor {
+                        state,
+                        status: Some(&field.status),
+                        schema: field.schema.as_ref(),
+                        issues: &mut *self.issues,
+                    })?;
+                }
+                Some(_) => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                    self.issues.push(ValidationIssue {
+                        path: state.keys(),
+                        kind: ValidationIssueKind::UnexpectedField,
+                    });
+                }
+            }
+            seen.insert(key);
+        }
+
+        for (key, field) in fields {
+            if field.status.may_be_missing || field.status.is_ignored || seen.contains(&**key) {
+                continue;
+            }
+            let path = Path {
+                parent: state.path,
+                key,
+            };
+            let state = state.with_path(&path);
+            if !state.is_ignored() {
+                self.issues.push(ValidationIssue {
+                    path: state.keys(),
+                    kind: ValidationIssueKind::MissingField,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::{any::Any, fmt::Debug};
 
-use crate::{Aggregate, Coalesce, CoalescingAggregator};
+use crate::{Aggregate, Coalesce, CoalescingAggregator, StructuralEq};
 
 /// A collection of aggregators that should allow the user of the library to run arbitrary
 /// aggregation code on the data as it is being analyzed.
@@ -15,6 +15,20 @@ impl<V: ?Sized> Aggregate<V> for Aggregators<V> {
             a.aggregate(value)
         }
     }
+    fn aggregate_with_key(&mut self, key: &str, value: &'_ V) {
+        for a in &mut self.0 {
+            a.aggregate_with_key(key, value)
+        }
+    }
+}
+impl<V: ?Sized> Aggregators<V> {
+    /// Aggregates `value`, with its key if there is one.
+    pub(crate) fn aggregate_keyed(&mut self, key: Option<&str>, value: &V) {
+        match key {
+            Some(key) => self.aggregate_with_key(key, value),
+            None => self.aggregate(value),
+        }
+    }
 }
 impl<T: ?Sized + 'static> Coalesce for Aggregators<T> {
     fn coalesce(&mut self, other: Aggregators<T>)
@@ -35,6 +49,12 @@ impl<T: ?Sized + 'static> Coalesce for Aggregators<T> {
         }
     }
 }
+/// Custom aggregators can't be compared, so they are always considered structurally equal.
+impl<T: ?Sized> StructuralEq for Aggregators<T> {
+    fn structural_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
 impl<T: ?Sized> Clone for Aggregators<T> {
     fn clone(&self) -> Self {
         Aggregators(self.0.clone())
@@ -15,9 +15,17 @@ pub struct BytesContext {
 }
 impl Aggregate<[u8]> for BytesContext {
     fn aggregate(&mut self, value: &'_ [u8]) {
+        self.aggregate_keyed(None, value);
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &'_ [u8]) {
+        self.aggregate_keyed(Some(key), value);
+    }
+}
+impl BytesContext {
+    fn aggregate_keyed(&mut self, key: Option<&str>, value: &[u8]) {
         self.count.aggregate(value);
         self.min_max_length.aggregate(&value.len());
-        self.other_aggregators.aggregate(value);
+        self.other_aggregators.aggregate_keyed(key, value);
     }
 }
 impl Coalesce for BytesContext {
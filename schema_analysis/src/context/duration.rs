@@ -0,0 +1,326 @@
+use std::{collections::BTreeMap, fmt, ops::RangeInclusive, time::Duration};
+
+use crate::{traits::CoalescingAggregator, Aggregate, Coalesce};
+
+use super::shared::{Counter, MinMax};
+
+/// The number of histogram buckets for each doubling of the duration, each is ~4.4% wide.
+const BUCKETS_PER_DOUBLING: f64 = 16.0;
+
+/// The unit of numeric durations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    /// Seconds, like `1.5`.
+    Seconds,
+    /// Milliseconds, like `1500`.
+    Milliseconds,
+}
+impl TimeUnit {
+    /// Returns the unit given by the suffix of `key`, like `"timeout_ms"` or `"elapsedSecs"`.
+    ///
+    /// The suffix must follow a separator or start a new camelCase word, so that keys like
+    /// `"items"` are not read as milliseconds.
+    pub fn from_key(key: &str) -> Option<Self> {
+        if ["ms", "millis", "milliseconds"]
+            .iter()
+            .any(|suffix| has_unit_suffix(key, suffix))
+        {
+            Some(TimeUnit::Milliseconds)
+        } else if ["s", "secs", "seconds"]
+            .iter()
+            .any(|suffix| has_unit_suffix(key, suffix))
+        {
+            Some(TimeUnit::Seconds)
+        } else {
+            None
+        }
+    }
+    fn to_seconds(self, value: f64) -> f64 {
+        match self {
+            TimeUnit::Seconds => value,
+            TimeUnit::Milliseconds => value / 1000.0,
+        }
+    }
+}
+
+/// Returns `true` if `key` ends with `suffix` (ignoring case) as a word of its own.
+fn has_unit_suffix(key: &str, suffix: &str) -> bool {
+    let start = match key.len().checked_sub(suffix.len()) {
+        Some(start) if key.is_char_boundary(start) => start,
+        _ => return false,
+    };
+    let (head, tail) = key.split_at(start);
+    if !tail.eq_ignore_ascii_case(suffix) {
+        return false;
+    }
+    match head.chars().next_back() {
+        None | Some('_') | Some('-') | Some('.') | Some(' ') => true,
+        Some(previous) => {
+            (previous.is_lowercase() || previous.is_ascii_digit())
+                && tail.starts_with(|c: char| c.is_uppercase())
+        }
+    }
+}
+
+/// Like `Duration::try_from_secs_f64`, which needs a more recent Rust than the rest of the
+/// crate.
+fn duration_from_secs(secs: f64) -> Option<Duration> {
+    // Also false for NaN.
+    if secs >= 0.0 && secs < Duration::MAX.as_secs_f64() {
+        Some(Duration::from_secs_f64(secs))
+    } else {
+        None
+    }
+}
+
+/// An opt-in aggregator for numeric fields that hold durations, to be added to the
+/// [other_aggregators](super::NumberContext::other_aggregators) of the integer and/or the float
+/// context.
+///
+/// A field is treated as a duration if its key contains one of the
+/// [key_hints](DurationAnalysis::key_hints), or, if a
+/// [plausible_range](DurationAnalysis::plausible_range) is set, if all of its values fall in it.
+/// The unit is taken from the suffix of the key (see [TimeUnit::from_key]) and defaults to
+/// [unit](DurationAnalysis::unit).
+///
+/// The median is estimated from a histogram, so it is exact only if the values close to it are
+/// all the same, otherwise it is off by at most a few percent.
+///
+/// ```
+/// # use serde::de::DeserializeSeed;
+/// # use schema_analysis::{context::{Context, DurationAnalysis, TimeUnit}, Schema};
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut context = Context::default();
+/// let durations = DurationAnalysis::new(TimeUnit::Seconds, &["duration", "elapsed"]);
+/// context.integer.other_aggregators.0.push(Box::new(durations));
+///
+/// let data = r#"[{ "elapsed_ms": 120 }, { "elapsed_ms": 1500 }, { "elapsed_ms": 300 }]"#;
+/// let mut deserializer = serde_json::Deserializer::from_str(data);
+/// let schema = context.deserialize_schema(&mut deserializer)?.schema;
+///
+/// # let field = match schema {
+/// #     Schema::Sequence { field, .. } => field,
+/// #     _ => unreachable!(),
+/// # };
+/// # let elapsed = match field.schema {
+/// #     Some(Schema::Struct { mut fields, .. }) => fields.remove("elapsed_ms").unwrap().schema,
+/// #     _ => unreachable!(),
+/// # };
+/// if let Some(Schema::Integer(context)) = elapsed {
+///     let durations = context.other_aggregators.0[0]
+///         .as_any()
+///         .downcast_ref::<DurationAnalysis>()
+///         .unwrap();
+///     let summary = durations.summary().unwrap();
+///     assert_eq!(summary.to_string(), "120ms to 1.5s, median 300ms (3 values)");
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DurationAnalysis {
+    /// The unit of the values, unless the key has a unit suffix.
+    pub unit: TimeUnit,
+    /// Case-insensitive substrings of the keys of the fields that hold durations.
+    pub key_hints: Vec<String>,
+    /// If set, fields whose values are all durations in this range (in seconds) are treated
+    /// as durations even if their key has no hint.
+    pub plausible_range: Option<RangeInclusive<f64>>,
+    /// Set if a value was found under a key matching one of the hints.
+    pub key_matched: bool,
+    /// The number of durations aggregated.
+    pub count: Counter,
+    /// The range of the durations, in seconds.
+    pub min_max: MinMax<f64>,
+    /// The count of the positive durations in each bucket, along with their sum in seconds.
+    /// Bucket `i` holds the durations between `2^(i/16)` and `2^((i+1)/16)` seconds.
+    histogram: BTreeMap<i32, (usize, f64)>,
+    /// The number of zero durations, which have no bucket.
+    zeros: usize,
+}
+impl DurationAnalysis {
+    /// An aggregator for durations in `unit`, in the fields whose key contains one of
+    /// `key_hints`.
+    pub fn new(unit: TimeUnit, key_hints: &[&str]) -> Self {
+        Self {
+            unit,
+            key_hints: key_hints.iter().map(|hint| hint.to_lowercase()).collect(),
+            plausible_range: None,
+            key_matched: false,
+            count: Counter::default(),
+            min_max: MinMax::default(),
+            histogram: BTreeMap::new(),
+            zeros: 0,
+        }
+    }
+
+    /// Also treat fields as durations if all their values are in `range` (in seconds).
+    pub fn with_plausible_range(mut self, range: RangeInclusive<f64>) -> Self {
+        self.plausible_range = Some(range);
+        self
+    }
+
+    /// Returns `true` if the aggregated values look like durations, either because of their
+    /// key or because they are all in the [plausible_range](DurationAnalysis::plausible_range).
+    pub fn is_duration(&self) -> bool {
+        let (min, max) = match (self.min_max.min, self.min_max.max) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return false,
+        };
+        let in_range = |range: &RangeInclusive<f64>| range.contains(&min) && range.contains(&max);
+        // Negative values can't be durations, whatever the key.
+        min >= 0.0
+            && (self.key_matched || matches!(&self.plausible_range, Some(range) if in_range(range)))
+    }
+
+    /// Returns the distribution of the durations, or [None] if the values don't look like
+    /// durations (see [is_duration](DurationAnalysis::is_duration)) or are too long to fit a
+    /// [Duration].
+    pub fn summary(&self) -> Option<DurationSummary> {
+        if !self.is_duration() {
+            return None;
+        }
+        let (min, max) = (self.min_max.min?, self.min_max.max?);
+        Some(DurationSummary {
+            count: self.count.get(),
+            min: duration_from_secs(min)?,
+            median: duration_from_secs(self.median()?.max(min).min(max))?,
+            max: duration_from_secs(max)?,
+        })
+    }
+
+    /// Estimates the median as the mean of the values in the bucket holding it.
+    fn median(&self) -> Option<f64> {
+        let count = self.count.get();
+        let middle = count - count / 2;
+        if middle <= self.zeros {
+            return Some(0.0);
+        }
+        let mut seen = self.zeros;
+        for (count, sum) in self.histogram.values() {
+            seen += count;
+            if seen >= middle {
+                return Some(sum / *count as f64);
+            }
+        }
+        None
+    }
+
+    fn aggregate_seconds(&mut self, key: Option<&str>, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        let unit = key.and_then(TimeUnit::from_key).unwrap_or(self.unit);
+        let seconds = unit.to_seconds(value);
+        if let Some(key) = key {
+            let key = key.to_lowercase();
+            self.key_matched |= self.key_hints.iter().any(|hint| key.contains(hint));
+        }
+        self.count.aggregate(&seconds);
+        self.min_max.aggregate(&seconds);
+        if seconds > 0.0 && seconds.is_finite() {
+            let bucket = (seconds.log2() * BUCKETS_PER_DOUBLING).floor() as i32;
+            let (count, sum) = self.histogram.entry(bucket).or_insert((0, 0.0));
+            *count += 1;
+            *sum += seconds;
+        } else if seconds == 0.0 {
+            self.zeros += 1;
+        }
+    }
+}
+impl Aggregate<i128> for DurationAnalysis {
+    fn aggregate(&mut self, value: &i128) {
+        self.aggregate_seconds(None, *value as f64);
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &i128) {
+        self.aggregate_seconds(Some(key), *value as f64);
+    }
+}
+impl Aggregate<f64> for DurationAnalysis {
+    fn aggregate(&mut self, value: &f64) {
+        self.aggregate_seconds(None, *value);
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &f64) {
+        self.aggregate_seconds(Some(key), *value);
+    }
+}
+impl Coalesce for DurationAnalysis {
+    fn coalesce(&mut self, other: Self)
+    where
+        Self: Sized,
+    {
+        self.key_matched |= other.key_matched;
+        self.count.coalesce(other.count);
+        self.min_max.coalesce(other.min_max);
+        for (bucket, (count, sum)) in other.histogram {
+            let entry = self.histogram.entry(bucket).or_insert((0, 0.0));
+            entry.0 += count;
+            entry.1 += sum;
+        }
+        self.zeros += other.zeros;
+    }
+}
+impl CoalescingAggregator<i128> for DurationAnalysis {}
+impl CoalescingAggregator<f64> for DurationAnalysis {}
+
+/// The distribution of the values of a field holding durations, see [DurationAnalysis].
+///
+/// Its [Display](fmt::Display) is human-readable, like `120ms to 1.5s, median 300ms (3 values)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationSummary {
+    /// The number of values.
+    pub count: usize,
+    /// The shortest duration.
+    pub min: Duration,
+    /// The (estimated) median duration.
+    pub median: Duration,
+    /// The longest duration.
+    pub max: Duration,
+}
+impl fmt::Display for DurationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} to {}, median {} ({} values)",
+            HumanDuration(self.min),
+            HumanDuration(self.max),
+            HumanDuration(self.median),
+            self.count
+        )
+    }
+}
+
+/// Writes a duration in its largest unit with up to three significant digits, like `1.5s`,
+/// `300ms` or `2.25h`.
+struct HumanDuration(Duration);
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: &[(&str, f64)] = &[
+            ("d", 86_400.0),
+            ("h", 3_600.0),
+            ("min", 60.0),
+            ("s", 1.0),
+            ("ms", 1e-3),
+            ("µs", 1e-6),
+        ];
+        let seconds = self.0.as_secs_f64();
+        let (unit, scale) = UNITS
+            .iter()
+            .find(|(_, scale)| seconds >= *scale)
+            .unwrap_or(&("ns", 1e-9));
+        let value = seconds / scale;
+        let decimals = match value {
+            value if value >= 100.0 => 0,
+            value if value >= 10.0 => 1,
+            _ => 2,
+        };
+        let value = format!("{:.*}", decimals, value);
+        let value = match value.contains('.') {
+            true => value.trim_end_matches('0').trim_end_matches('.'),
+            false => &value,
+        };
+        write!(f, "{}{}", value, unit)
+    }
+}
@@ -1,20 +1,173 @@
 #![allow(missing_docs)]
 
+use std::collections::{BTreeMap, BTreeSet};
+
 use serde::{Deserialize, Serialize};
 
 use crate::{traits::Coalesce, Aggregate};
 
-use super::{Aggregators, Counter};
+use super::{
+    push_json_string, shared::MinMax, Aggregators, Counter, CountingSampler, CountingSet, Fnv1a,
+};
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MapStructContext {
     pub count: Counter,
+    /// The lowest and highest number of keys found in a struct, duplicates included.
+    /// A wide range points to sparse objects, where most fields are usually missing.
+    #[serde(default)]
+    pub key_count: MinMax<usize>,
+    /// Keeps track of the keys that were found more than once in the same struct, and in how
+    /// many structs that happened.
+    #[serde(default, skip_serializing_if = "CountingSet::is_empty")]
+    pub duplicate_keys: CountingSet<String>,
+    /// Some formats allow non-string keys, those are converted to strings for the analysis
+    /// and this flag is set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub may_have_non_string_keys: bool,
+    /// When a [key_normalizer](crate::AnalysisSettings::key_normalizer) is set, keeps track of
+    /// how each normalized key was originally spelled, and how many times.
+    /// More than one spelling means different keys were merged into the same field.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub key_spellings: BTreeMap<String, CountingSet<String>>,
+    /// When [track_key_sets](crate::AnalysisSettings::track_key_sets) is set, keeps track of
+    /// the distinct sets of keys found in the structs (see
+    /// [key_set_name](MapStructContext::key_set_name)), and how many structs had each.
+    /// This shows which optional fields appear together.
+    ///
+    /// Only a few sets are kept, the most common ones when merging.
+    #[serde(default, skip_serializing_if = "CountingSampler::is_empty")]
+    pub key_sets: CountingSampler<String>,
+    /// Starts `true`, and is cleared if two structs had the same set of keys in a different
+    /// order. Useful for formats where the order of the keys carries meaning.
+    ///
+    /// Only the first few sets of keys are compared, and the orders themselves are not stored,
+    /// so a deserialized schema only contributes its flag when merged.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub key_order_consistent: bool,
+    /// The hash of the order of the keys, for each hash of a set of keys, used to compute
+    /// [key_order_consistent](MapStructContext::key_order_consistent).
+    #[serde(skip)]
+    pub(crate) key_orders: BTreeMap<u64, u64>,
     #[serde(skip)]
     pub other_aggregators: Aggregators<[String]>,
 }
+impl Default for MapStructContext {
+    fn default() -> Self {
+        Self {
+            count: Default::default(),
+            key_count: Default::default(),
+            duplicate_keys: Default::default(),
+            may_have_non_string_keys: false,
+            key_spellings: Default::default(),
+            key_sets: Default::default(),
+            key_order_consistent: true,
+            key_orders: Default::default(),
+            other_aggregators: Default::default(),
+        }
+    }
+}
+impl MapStructContext {
+    /// Up to this many keys, duplicates are found by comparing all the keys with each other.
+    const LINEAR_SCAN_KEYS: usize = 16;
+    /// The orders of up to this many sets of keys are compared, see
+    /// [key_order_consistent](MapStructContext::key_order_consistent).
+    const MAX_KEY_ORDERS: usize = 32;
+
+    /// Same as [Aggregate::aggregate], but accepts borrowed keys so that the analysis does not
+    /// need to allocate them.
+    pub(crate) fn aggregate_keys<K: AsRef<str>>(&mut self, keys: &[K]) {
+        self.count.aggregate(keys);
+        self.key_count.aggregate(&keys.len());
+        self.aggregate_key_order(keys);
+
+        // Small maps are checked without allocating, as they are the most common.
+        if keys.len() <= Self::LINEAR_SCAN_KEYS {
+            for (i, key) in keys.iter().enumerate() {
+                let key = key.as_ref();
+                let mut earlier = keys[..i].iter().filter(|k| k.as_ref() == key);
+                // Recorded on the second occurrence only.
+                if earlier.next().is_some() && earlier.next().is_none() {
+                    self.duplicate_keys.insert(key);
+                }
+            }
+            return;
+        }
+
+        let mut seen = BTreeSet::new();
+        let mut duplicates = BTreeSet::new();
+        for key in keys {
+            let key = key.as_ref();
+            if !seen.insert(key) {
+                duplicates.insert(key);
+            }
+        }
+        for key in duplicates {
+            self.duplicate_keys.insert(key);
+        }
+    }
+    /// Hashes the set of keys (regardless of their order) and their order, without allocating.
+    fn aggregate_key_order<K: AsRef<str>>(&mut self, keys: &[K]) {
+        if keys.len() < 2 {
+            return;
+        }
+        let mut order = Fnv1a::default();
+        let mut set = 0u64;
+        for key in keys {
+            let mut hasher = Fnv1a::default();
+            hasher.write_str(key.as_ref());
+            set = set.wrapping_add(hasher.finish());
+            order.write_str(key.as_ref());
+        }
+        self.record_key_order(set, order.finish());
+    }
+    fn record_key_order(&mut self, set: u64, order: u64) {
+        match self.key_orders.get(&set) {
+            Some(known) => self.key_order_consistent &= *known == order,
+            None if self.key_orders.len() < Self::MAX_KEY_ORDERS => {
+                self.key_orders.insert(set, order);
+            }
+            None => {}
+        }
+    }
+    /// Returns the name of a set of keys as recorded in
+    /// [key_sets](MapStructContext::key_sets): the distinct keys sorted and quoted as JSON
+    /// strings, like `["a", "b"]`.
+    pub fn key_set_name<K: AsRef<str>>(keys: &[K]) -> String {
+        let mut keys: Vec<&str> = keys.iter().map(AsRef::as_ref).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        let mut name = String::from("[");
+        for (i, key) in keys.into_iter().enumerate() {
+            if i > 0 {
+                name.push_str(", ");
+            }
+            push_json_string(&mut name, key);
+        }
+        name.push(']');
+        name
+    }
+    /// Records the set of keys of a struct.
+    pub(crate) fn aggregate_key_set<K: AsRef<str>>(&mut self, keys: &[K]) {
+        self.key_sets.aggregate(Self::key_set_name(keys).as_str());
+    }
+    /// Records the original spelling of each normalized key.
+    pub(crate) fn aggregate_spellings<K: AsRef<str>>(&mut self, spellings: &[(K, K)]) {
+        for (key, original) in spellings {
+            match self.key_spellings.get_mut(key.as_ref()) {
+                Some(set) => set.insert(original.as_ref()),
+                None => {
+                    let mut set = CountingSet::default();
+                    set.insert(original.as_ref());
+                    self.key_spellings.insert(key.as_ref().to_string(), set);
+                }
+            }
+        }
+    }
+}
 impl Aggregate<[String]> for MapStructContext {
     fn aggregate(&mut self, value: &[String]) {
-        self.count.aggregate(value);
+        self.aggregate_keys(value);
     }
 }
 impl Coalesce for MapStructContext {
@@ -23,6 +176,20 @@ impl Coalesce for MapStructContext {
         Self: Sized,
     {
         self.count.coalesce(other.count);
+        self.key_count.coalesce(other.key_count);
+        self.duplicate_keys.coalesce(other.duplicate_keys);
+        self.may_have_non_string_keys |= other.may_have_non_string_keys;
+        for (key, spellings) in other.key_spellings {
+            self.key_spellings
+                .entry(key)
+                .or_default()
+                .coalesce(spellings);
+        }
+        self.key_sets.coalesce(other.key_sets);
+        self.key_order_consistent &= other.key_order_consistent;
+        for (set, order) in other.key_orders {
+            self.record_key_order(set, order);
+        }
     }
 }
 impl PartialEq for MapStructContext {
@@ -30,5 +197,18 @@ impl PartialEq for MapStructContext {
     /// provided by the user of the library.
     fn eq(&self, other: &Self) -> bool {
         self.count == other.count
+            && self.key_count == other.key_count
+            && self.duplicate_keys == other.duplicate_keys
+            && self.may_have_non_string_keys == other.may_have_non_string_keys
+            && self.key_spellings == other.key_spellings
+            && self.key_sets == other.key_sets
+            && self.key_order_consistent == other.key_order_consistent
     }
 }
+
+fn default_true() -> bool {
+    true
+}
+fn is_true(value: &bool) -> bool {
+    *value
+}
@@ -3,6 +3,7 @@
 mod aggregators;
 mod boolean;
 mod bytes;
+mod duration;
 mod map_struct;
 mod null;
 mod number;
@@ -13,15 +14,28 @@ mod string;
 pub use aggregators::Aggregators;
 pub use boolean::BooleanContext;
 pub use bytes::BytesContext;
+pub use duration::{DurationAnalysis, DurationSummary, TimeUnit};
 pub use map_struct::MapStructContext;
 pub use null::NullContext;
 pub use number::NumberContext;
-pub use sequence::SequenceContext;
-pub use shared::{Counter, CountingSet};
-pub use string::{SemanticExtractor, StringContext, SuspiciousStrings};
+pub(crate) use number::Orderly;
+pub(crate) use sequence::MonotonicityTracker;
+pub use sequence::{Monotonicity, SequenceContext};
+pub(crate) use shared::{push_json_string, Fnv1a};
+pub use shared::{Counter, CountingSampler, CountingSet, MinMax, Sampler};
+#[cfg(feature = "script_detection")]
+pub use string::ScriptCounter;
+#[cfg(feature = "embedded_json")]
+pub(crate) use string::{holds_json, may_hold_json};
+pub use string::{
+    BinaryEncodings, CharClass, PatternTemplate, PostalCodeCountry, SemanticExtractor,
+    StringContext, SuspiciousStrings, TemplateRun, UnitExtractor,
+};
 
 use serde::{Deserialize, Serialize};
 
+use crate::analysis::AnalysisSettings;
+
 /// The Context holds a fresh copy of the context that each [Schema](crate::Schema)
 /// copies when it's first created and then fills as the analysis proceeds.
 ///
@@ -46,9 +60,56 @@ pub struct Context {
     pub sequence: SequenceContext,
     /// The context for struct values.
     pub map_struct: MapStructContext,
+    /// The settings of the analysis.
+    #[serde(default)]
+    pub settings: AnalysisSettings,
 }
 
 impl Context {
+    /// A context for memory-sensitive runs, where the [NumberContext]s don't keep samples
+    /// and only record the count and the range of the values.
+    ///
+    /// ```
+    /// # use schema_analysis::context::Context;
+    /// let context = Context::lean();
+    /// assert!(context.integer.skip_samples && context.float.skip_samples);
+    /// ```
+    pub fn lean() -> Self {
+        Self {
+            integer: NumberContext::without_samples(),
+            float: NumberContext::without_samples(),
+            ..Default::default()
+        }
+    }
+
+    /// A context for small datasets, where integers, floats and strings keep all their
+    /// values as long as no more than `threshold` of them are found in the same place, see
+    /// [Sampler::keeping_all_below].
+    ///
+    /// ```
+    /// # use schema_analysis::{context::Context, Schema};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let context = Context::keeping_all_values_below(100);
+    /// let mut deserializer = serde_json::Deserializer::from_str("[1, 2, 3, 4, 5, 6, 7]");
+    /// let inferred = context.deserialize_schema(&mut deserializer)?;
+    ///
+    /// if let Schema::Sequence { field, .. } = inferred.schema {
+    ///     if let Some(Schema::Integer(context)) = field.schema {
+    ///         assert_eq!(context.samples.values().len(), 7);
+    ///         assert!(context.samples.is_exaustive());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn keeping_all_values_below(threshold: usize) -> Self {
+        let mut context = Self::default();
+        context.integer.samples = Sampler::keeping_all_below(threshold);
+        context.float.samples = Sampler::keeping_all_below(threshold);
+        context.string.samples = Sampler::keeping_all_below(threshold);
+        context
+    }
+
     /// Returns a fresh context for null schemas.
     pub fn for_null(&self) -> NullContext {
         self.null.clone()
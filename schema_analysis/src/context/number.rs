@@ -1,6 +1,9 @@
 #![allow(missing_docs)]
 
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{
+    de::{DeserializeOwned, Error, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 
 use crate::{traits::Coalesce, Aggregate, Aggregators};
 
@@ -14,31 +17,108 @@ use super::{
 /// Uses non-generic implementations and Orderly, a helper trait,
 /// to allow floats and integer to share the code.
 /// Might not be worth it, but oh well.
+///
+/// Integers and floats each get a context of their own, also when both are found for the same
+/// field (like `1` and `1.0` across YAML or TOML documents): the field then holds a
+/// [Union](crate::Schema::Union) of an [Integer](crate::Schema::Integer) and a
+/// [Float](crate::Schema::Float), whose counts show how inconsistent the data is.
+/// The `numeric_widening` of [CoalesceConfig](crate::CoalesceConfig) merges them instead.
+///
+/// The notation of the original value (like `1.5e10` as opposed to `15000000000.0`) is lost
+/// by the time it reaches the analysis, as the format has already parsed it.
+/// Formats that hand numbers over as text (like XML) are analyzed as
+/// [StringContext](super::StringContext)s instead, where the `"Scientific Float"` target of the
+/// [SemanticExtractor](super::SemanticExtractor) counts the values in scientific notation.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NumberContext<T: Orderly> {
     pub count: Counter,
+    /// Empty, and not exhaustive, if `skip_samples` is set.
+    #[serde(default, deserialize_with = "T::deserialize_samples")]
     pub samples: Sampler<T::Ordered>,
-    #[serde(flatten)]
+    #[serde(flatten, deserialize_with = "T::deserialize_min_max")]
     pub min_max: MinMax<T>,
+    /// Set if any integer could not be represented exactly by a JavaScript number, that is if
+    /// its magnitude is above [MAX_SAFE_INTEGER](NumberContext::MAX_SAFE_INTEGER).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exceeds_js_safe_integer: bool,
+    /// Set if any integer was above [i64::MAX], like the unsigned 64-bit ids near [u64::MAX]
+    /// some APIs use, see [is_u64](NumberContext::is_u64).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub saw_values_above_i64_max: bool,
+    /// If set, no samples are kept and only the count and the range of the values are
+    /// recorded, see [without_samples](NumberContext::without_samples).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_samples: bool,
     #[serde(skip)]
     pub other_aggregators: Aggregators<T>,
 }
+impl<T: Orderly> NumberContext<T> {
+    /// A context that doesn't sample values, for when memory matters more than the examples.
+    ///
+    /// The samples of each node are bounded, but on huge and varied data the distinct values
+    /// kept across all the numeric nodes may still add up.
+    pub fn without_samples() -> Self
+    where
+        Self: Default,
+    {
+        let mut context = Self {
+            skip_samples: true,
+            ..Default::default()
+        };
+        context.samples.discard();
+        context
+    }
+}
+impl NumberContext<i128> {
+    /// The largest integer a JavaScript number can represent exactly, `2^53 - 1`.
+    pub const MAX_SAFE_INTEGER: i128 = (1 << 53) - 1;
+
+    /// Returns `true` if the values need an unsigned 64-bit integer: some are above
+    /// [i64::MAX], and none are negative or above [u64::MAX].
+    pub fn is_u64(&self) -> bool {
+        self.saw_values_above_i64_max
+            && !matches!(self.min_max.min, Some(min) if min < 0)
+            && !matches!(self.min_max.max, Some(max) if max > i128::from(u64::MAX))
+    }
+}
 impl Aggregate<i128> for NumberContext<i128> {
     fn aggregate(&mut self, value: &i128) {
+        self.aggregate_keyed(None, value);
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &i128) {
+        self.aggregate_keyed(Some(key), value);
+    }
+}
+impl NumberContext<i128> {
+    fn aggregate_keyed(&mut self, key: Option<&str>, value: &i128) {
         self.count.aggregate(value);
-        self.samples.aggregate(value);
+        if !self.skip_samples {
+            self.samples.aggregate(value);
+        }
         self.min_max.aggregate(value);
-        self.other_aggregators.aggregate(value);
+        self.exceeds_js_safe_integer |= value.unsigned_abs() > Self::MAX_SAFE_INTEGER as u128;
+        self.saw_values_above_i64_max |= *value > i128::from(i64::MAX);
+        self.other_aggregators.aggregate_keyed(key, value);
     }
 }
 impl Aggregate<f64> for NumberContext<f64> {
     fn aggregate(&mut self, value: &'_ f64) {
+        self.aggregate_keyed(None, value);
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &'_ f64) {
+        self.aggregate_keyed(Some(key), value);
+    }
+}
+impl NumberContext<f64> {
+    fn aggregate_keyed(&mut self, key: Option<&str>, value: &f64) {
         self.count.aggregate(value);
-        self.samples.aggregate(value.into()); // ordered_float
+        if !self.skip_samples {
+            self.samples.aggregate(value.into()); // ordered_float
+        }
         if !value.is_nan() {
             self.min_max.aggregate(value);
         }
-        self.other_aggregators.aggregate(value);
+        self.other_aggregators.aggregate_keyed(key, value);
     }
 }
 impl<T: Clone + PartialOrd + Orderly + 'static> Coalesce for NumberContext<T> {
@@ -49,14 +129,45 @@ impl<T: Clone + PartialOrd + Orderly + 'static> Coalesce for NumberContext<T> {
         self.count.coalesce(other.count);
         self.samples.coalesce(other.samples);
         self.min_max.coalesce(other.min_max);
+        self.exceeds_js_safe_integer |= other.exceeds_js_safe_integer;
+        self.saw_values_above_i64_max |= other.saw_values_above_i64_max;
+        self.skip_samples |= other.skip_samples;
+        if self.skip_samples {
+            // The values of the side that skipped them are unknown.
+            self.samples.discard();
+        }
         self.other_aggregators.coalesce(other.other_aggregators);
     }
 }
+impl From<NumberContext<i128>> for NumberContext<f64> {
+    /// Integers that are too large lose precision, and the custom aggregators are dropped as
+    /// they can't be converted. Whether the integers exceeded the JavaScript safe range is
+    /// kept, as the floats can't represent them exactly either.
+    fn from(context: NumberContext<i128>) -> Self {
+        let to_float = |value: i128| value as f64;
+        NumberContext {
+            count: context.count,
+            samples: context.samples.map(|value| to_float(value).into()),
+            min_max: MinMax {
+                min: context.min_max.min.map(to_float),
+                max: context.min_max.max.map(to_float),
+            },
+            exceeds_js_safe_integer: context.exceeds_js_safe_integer,
+            saw_values_above_i64_max: false,
+            skip_samples: context.skip_samples,
+            other_aggregators: Default::default(),
+        }
+    }
+}
 impl<T: PartialEq + Orderly> PartialEq for NumberContext<T> {
     /// NOTE: [NumberContext]'s [PartialEq] implementation ignores the `other_aggregators`
     /// provided by the user of the library.
     fn eq(&self, other: &Self) -> bool {
-        self.count == other.count && self.min_max == other.min_max
+        self.count == other.count
+            && self.min_max == other.min_max
+            && self.exceeds_js_safe_integer == other.exceeds_js_safe_integer
+            && self.saw_values_above_i64_max == other.saw_values_above_i64_max
+            && self.skip_samples == other.skip_samples
     }
 }
 
@@ -65,11 +176,42 @@ impl<T: PartialEq + Orderly> PartialEq for NumberContext<T> {
 //
 
 /// A marker trait that
-pub trait Orderly: Sized {
+pub trait Orderly: Sized + DeserializeOwned {
     type Ordered: Ord + Clone + Serialize + DeserializeOwned;
+
+    /// Deserializes the samples of a [NumberContext].
+    fn deserialize_samples<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Sampler<Self::Ordered>, D::Error> {
+        Sampler::deserialize(deserializer)
+    }
+    /// Deserializes the [MinMax] of a [NumberContext].
+    fn deserialize_min_max<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MinMax<Self>, D::Error> {
+        MinMax::deserialize(deserializer)
+    }
 }
 impl Orderly for i128 {
     type Ordered = i128;
+
+    // Serde buffers the content of tagged enums and flattened structs, like [Schema](crate::Schema)
+    // and [Field](crate::Field), and the buffer does not support 128-bit integers.
+    // So we accept whichever integer the deserializer has instead.
+    fn deserialize_samples<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Sampler<Self::Ordered>, D::Error> {
+        Ok(Sampler::<AnyInteger>::deserialize(deserializer)?.map(|i| i.0))
+    }
+    fn deserialize_min_max<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MinMax<Self>, D::Error> {
+        let MinMax { min, max } = MinMax::<AnyInteger>::deserialize(deserializer)?;
+        Ok(MinMax {
+            min: min.map(|i| i.0),
+            max: max.map(|i| i.0),
+        })
+    }
 }
 impl Orderly for usize {
     type Ordered = usize;
@@ -80,3 +222,35 @@ impl Orderly for f64 {
 impl Orderly for f32 {
     type Ordered = ordered_float::OrderedFloat<f32>;
 }
+
+/// An [i128] deserialized from any integer.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct AnyInteger(i128);
+impl<'de> Deserialize<'de> for AnyInteger {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AnyIntegerVisitor)
+    }
+}
+struct AnyIntegerVisitor;
+impl<'de> Visitor<'de> for AnyIntegerVisitor {
+    type Value = AnyInteger;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an integer")
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(AnyInteger(value.into()))
+    }
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(AnyInteger(value.into()))
+    }
+    fn visit_i128<E: Error>(self, value: i128) -> Result<Self::Value, E> {
+        Ok(AnyInteger(value))
+    }
+    fn visit_u128<E: Error>(self, value: u128) -> Result<Self::Value, E> {
+        std::convert::TryFrom::try_from(value)
+            .map(AnyInteger)
+            .map_err(|_| E::custom(format!("integer {} is out of range", value)))
+    }
+}
@@ -10,14 +10,30 @@ use super::{shared::MinMax, Counter};
 pub struct SequenceContext {
     pub count: Counter,
     pub length: MinMax<usize>,
+    /// Set by [Schema::unify_scalar_and_sequence](crate::Schema::unify_scalar_and_sequence)
+    /// when the sequence absorbed scalar values found in its place.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub may_be_scalar: bool,
+    /// Whether the sequences have always been sorted, only recorded when
+    /// [track_monotonicity](crate::AnalysisSettings::track_monotonicity) is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monotonicity: Option<Monotonicity>,
     #[serde(skip)]
     pub other_aggregators: Aggregators<usize>,
 }
 impl Aggregate<usize> for SequenceContext {
     fn aggregate(&mut self, value: &usize) {
+        self.aggregate_keyed(None, value);
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &usize) {
+        self.aggregate_keyed(Some(key), value);
+    }
+}
+impl SequenceContext {
+    fn aggregate_keyed(&mut self, key: Option<&str>, value: &usize) {
         self.count.aggregate(value);
         self.length.aggregate(value);
-        self.other_aggregators.aggregate(value);
+        self.other_aggregators.aggregate_keyed(key, value);
     }
 }
 impl Coalesce for SequenceContext {
@@ -27,6 +43,10 @@ impl Coalesce for SequenceContext {
     {
         self.count.coalesce(other.count);
         self.length.coalesce(other.length);
+        self.may_be_scalar |= other.may_be_scalar;
+        if let Some(monotonicity) = other.monotonicity {
+            self.aggregate_monotonicity(monotonicity);
+        }
         self.other_aggregators.coalesce(other.other_aggregators);
     }
 }
@@ -34,6 +54,72 @@ impl PartialEq for SequenceContext {
     /// NOTE: [SequenceContext]'s [PartialEq] implementation ignores the `other_aggregators`
     /// provided by the user of the library.
     fn eq(&self, other: &Self) -> bool {
-        self.count == other.count && self.length == other.length
+        self.count == other.count
+            && self.length == other.length
+            && self.may_be_scalar == other.may_be_scalar
+            && self.monotonicity == other.monotonicity
+    }
+}
+impl SequenceContext {
+    /// Merges the [Monotonicity] of one more sequence.
+    pub fn aggregate_monotonicity(&mut self, monotonicity: Monotonicity) {
+        self.monotonicity = Some(match self.monotonicity {
+            Some(current) => Monotonicity {
+                is_increasing: current.is_increasing && monotonicity.is_increasing,
+                is_decreasing: current.is_decreasing && monotonicity.is_decreasing,
+            },
+            None => monotonicity,
+        });
+    }
+}
+
+/// Whether all the sequences seen were sorted.
+///
+/// Only sequences made entirely of numbers (integers and floats) can be sorted, any other
+/// element (including nulls) clears both flags. Equal consecutive values are allowed, and empty
+/// or single element sequences are both increasing and decreasing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Monotonicity {
+    pub is_increasing: bool,
+    pub is_decreasing: bool,
+}
+
+/// Checks the [Monotonicity] of a single sequence, one element at a time.
+#[derive(Debug)]
+pub(crate) struct MonotonicityTracker {
+    previous: Option<f64>,
+    monotonicity: Monotonicity,
+}
+impl Default for MonotonicityTracker {
+    fn default() -> Self {
+        Self {
+            previous: None,
+            monotonicity: Monotonicity {
+                is_increasing: true,
+                is_decreasing: true,
+            },
+        }
+    }
+}
+impl MonotonicityTracker {
+    /// `element` is `None` if the element is not a number.
+    pub fn observe(&mut self, element: Option<f64>) {
+        let element = match element {
+            Some(element) => element,
+            None => {
+                self.monotonicity.is_increasing = false;
+                self.monotonicity.is_decreasing = false;
+                return;
+            }
+        };
+        if let Some(previous) = self.previous {
+            self.monotonicity.is_increasing &= previous <= element;
+            self.monotonicity.is_decreasing &= previous >= element;
+        }
+        self.previous = Some(element);
+    }
+
+    pub fn finish(self) -> Monotonicity {
+        self.monotonicity
     }
 }
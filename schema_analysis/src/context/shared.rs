@@ -1,19 +1,56 @@
 use std::{
     borrow::Borrow,
     collections::{BTreeMap, BTreeSet},
+    fmt,
+    ops::{Add, AddAssign},
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::{traits::Coalesce, Aggregate};
+use crate::{traits::Coalesce, Aggregate, StructuralEq};
 
 //
 // Counter
 //
 
 /// As simple as an aggregato can be, counts the aggregated values.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
 pub struct Counter(pub usize);
+impl Counter {
+    /// Returns the count.
+    pub fn get(&self) -> usize {
+        self.0
+    }
+}
+impl From<usize> for Counter {
+    fn from(count: usize) -> Self {
+        Counter(count)
+    }
+}
+impl From<Counter> for usize {
+    fn from(counter: Counter) -> Self {
+        counter.0
+    }
+}
+impl Add for Counter {
+    type Output = Counter;
+
+    fn add(self, other: Self) -> Self::Output {
+        Counter(self.0 + other.0)
+    }
+}
+impl AddAssign for Counter {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+impl fmt::Display for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
 impl<T: ?Sized> Aggregate<T> for Counter {
     fn aggregate(&mut self, _value: &'_ T) {
         self.0 += 1;
@@ -24,7 +61,7 @@ impl Coalesce for Counter {
     where
         Self: Sized,
     {
-        self.0 += other.0;
+        *self += other;
     }
 }
 
@@ -37,10 +74,10 @@ impl Coalesce for Counter {
 pub struct CountingSet<T: Ord>(pub BTreeMap<T, usize>);
 impl<T: Clone + Ord> CountingSet<T> {
     /// adds a value to the set or increases its counter if it already exists.
-    pub fn insert<Q: ?Sized>(&mut self, key: &Q)
+    pub fn insert<Q>(&mut self, key: &Q)
     where
         T: Borrow<Q>,
-        Q: Ord + ToOwned<Owned = T>,
+        Q: ?Sized + Ord + ToOwned<Owned = T>,
     {
         match self.0.get_mut(key) {
             Some(v) => *v += 1,
@@ -50,10 +87,10 @@ impl<T: Clone + Ord> CountingSet<T> {
         };
     }
     /// Checks if a specific value is present inside.
-    pub fn contains_key<Q: ?Sized>(&mut self, key: &Q) -> bool
+    pub fn contains_key<Q>(&mut self, key: &Q) -> bool
     where
         T: Borrow<Q>,
-        Q: Ord,
+        Q: ?Sized + Ord,
     {
         self.0.contains_key(key)
     }
@@ -93,7 +130,9 @@ impl<T: Ord> Default for CountingSet<T> {
 /// It should not be fed NaN values, as it won't work if they are the first value presented.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct MinMax<T> {
+    /// The lowest value, if any value was found.
     pub min: Option<T>,
+    /// The highest value, if any value was found.
     pub max: Option<T>,
 }
 impl<T: Clone + PartialOrd> Aggregate<T> for MinMax<T> {
@@ -123,6 +162,79 @@ impl<T: Clone + PartialOrd> Coalesce for MinMax<T> {
         }
     }
 }
+impl MinMax<f64> {
+    /// Rounds the bounds to `significant_digits` significant digits (at least one), so that
+    /// values that only differ by floating point noise (like `0.30000000000000004` and `0.3`)
+    /// are serialized the same way.
+    ///
+    /// This is a presentation concern, meant to make stored schemas reproducible: the analysis
+    /// itself always compares the exact values, and the rounded bounds may no longer contain
+    /// the values that were found.
+    pub fn round_to_significant_digits(&mut self, significant_digits: usize) {
+        let precision = significant_digits.max(1) - 1;
+        for bound in self.min.iter_mut().chain(self.max.iter_mut()) {
+            if bound.is_finite() {
+                // The scientific notation rounds to a number of significant digits.
+                *bound = format!("{:.*e}", precision, bound)
+                    .parse()
+                    .expect("formatted float");
+            }
+        }
+    }
+}
+
+//
+// Fnv1a
+//
+
+/// The 64-bit [FNV-1a](http://www.isthe.com/chongo/tech/comp/fnv/) hash, used instead of the
+/// randomly seeded [DefaultHasher](std::collections::hash_map::DefaultHasher) where hashes
+/// must be stable, like [Schema::shape_fingerprint](crate::Schema::shape_fingerprint), and where
+/// speed matters more than resistance to collisions, like the key orders of
+/// [MapStructContext](super::MapStructContext).
+pub(crate) struct Fnv1a(u64);
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+impl Fnv1a {
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    pub(crate) fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+    /// Strings are prefixed by their length, so that consecutive ones can't be confused.
+    pub(crate) fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Appends `value` to `out` quoted and escaped like a JSON string, non-ASCII characters are
+/// kept as they are.
+pub(crate) fn push_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
 
 //
 // Sampler
@@ -130,21 +242,105 @@ impl<T: Clone + PartialOrd> Coalesce for MinMax<T> {
 
 /// Keeps track of the first [MAX_SAMPLE_COUNT] distinct samples.
 /// If more are passed it'll flip the is_exaustive flag.
+///
+/// For small datasets it can keep all the values instead, see [Sampler::keeping_all_below].
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Sampler<T: Ord> {
     values: BTreeSet<T>,
     is_exaustive: bool,
+    /// If not zero, all the values are kept as long as no more than this many are aggregated.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    keep_all_below: usize,
+    /// How many values were aggregated, only tracked while all of them are kept.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    seen: usize,
 }
 const MAX_SAMPLE_COUNT: usize = 5;
+impl<T: Ord> Sampler<T> {
+    /// A sampler that keeps all the values (and stays exhaustive) as long as no more than
+    /// `threshold` of them are aggregated, so that small datasets are captured in full.
+    ///
+    /// Once the threshold is exceeded it falls back to keeping [MAX_SAMPLE_COUNT] samples, and
+    /// stays exhaustive only if there were no more distinct values than that.
+    pub fn keeping_all_below(threshold: usize) -> Self {
+        Self {
+            keep_all_below: threshold,
+            ..Default::default()
+        }
+    }
+    /// The distinct values sampled so far, in order.
+    pub fn values(&self) -> &BTreeSet<T> {
+        &self.values
+    }
+    /// Returns `true` if all the distinct values aggregated are in the samples.
+    pub fn is_exaustive(&self) -> bool {
+        self.is_exaustive
+    }
+    /// Returns `true` if the sampler is still keeping all the values, see
+    /// [Sampler::keeping_all_below].
+    pub fn is_keeping_all(&self) -> bool {
+        self.keep_all_below > 0
+    }
+    /// Returns the only value aggregated, if all the values were the same.
+    pub fn single(&self) -> Option<&T> {
+        match (self.is_exaustive, self.values.len()) {
+            (true, 1) => self.values.iter().next(),
+            _ => None,
+        }
+    }
+    /// Drops all the samples, for contexts that stopped sampling. The sampler is no longer
+    /// exhaustive, as the values it was given are not known.
+    pub(crate) fn discard(&mut self) {
+        *self = Self {
+            is_exaustive: false,
+            ..Default::default()
+        };
+    }
+    /// Converts the samples, the conversion should preserve their order.
+    pub(crate) fn map<U: Ord>(self, f: impl FnMut(T) -> U) -> Sampler<U> {
+        Sampler {
+            values: self.values.into_iter().map(f).collect(),
+            is_exaustive: self.is_exaustive,
+            keep_all_below: self.keep_all_below,
+            seen: self.seen,
+        }
+    }
+    /// Stops keeping all the values and only keeps [MAX_SAMPLE_COUNT] of them.
+    fn stop_keeping_all(&mut self) {
+        self.keep_all_below = 0;
+        self.seen = 0;
+        if self.values.len() > MAX_SAMPLE_COUNT {
+            self.is_exaustive = false;
+            self.values = std::mem::take(&mut self.values)
+                .into_iter()
+                .take(MAX_SAMPLE_COUNT)
+                .collect();
+        }
+    }
+}
 impl<T, Q> Aggregate<Q> for Sampler<T>
 where
     T: Ord + Borrow<Q>,
     Q: Ord + ToOwned<Owned = T> + ?Sized,
 {
     fn aggregate(&mut self, value: &'_ Q) {
+        if self.keep_all_below > 0 {
+            self.seen += 1;
+            if self.seen <= self.keep_all_below {
+                if !self.values.contains(value) {
+                    self.values.insert(value.to_owned());
+                }
+                return;
+            }
+            self.stop_keeping_all();
+        }
+        // Known values are not copied, so repeated values don't allocate.
+        if self.values.contains(value) {
+            return;
+        }
         if self.values.len() <= MAX_SAMPLE_COUNT {
             self.values.insert(value.to_owned());
-        } else if self.is_exaustive && !self.values.contains(value) {
+        } else if self.is_exaustive {
             self.is_exaustive = false;
         }
     }
@@ -154,7 +350,20 @@ impl<T: Ord> Coalesce for Sampler<T> {
     where
         Self: Sized,
     {
+        // Both must still be keeping all the values, and the lower threshold wins.
+        let keep_all_below = match (self.keep_all_below, other.keep_all_below) {
+            (0, _) | (_, 0) => 0,
+            (threshold, other) => threshold.min(other),
+        };
         self.values.extend(other.values);
+        self.seen += other.seen;
+        if keep_all_below > 0 && self.seen <= keep_all_below {
+            self.keep_all_below = keep_all_below;
+            return;
+        }
+        self.keep_all_below = 0;
+        self.seen = 0;
+
         if self.values.len() > MAX_SAMPLE_COUNT {
             self.is_exaustive = false;
         }
@@ -169,6 +378,126 @@ impl<T: Ord> Default for Sampler<T> {
         Self {
             values: Default::default(),
             is_exaustive: true,
+            keep_all_below: 0,
+            seen: 0,
         }
     }
 }
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+//
+// CountingSampler
+//
+
+/// Like [Sampler], keeps track of the first [MAX_SAMPLE_COUNT] distinct samples, but also
+/// counts how many times each of them occurred.
+/// If more distinct values are passed it'll flip the is_exaustive flag.
+///
+/// When merging would go over the limit, the most frequent samples are kept.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CountingSampler<T: Ord> {
+    values: CountingSet<T>,
+    is_exaustive: bool,
+}
+impl<T: Ord> CountingSampler<T> {
+    /// The samples and how many times they occurred.
+    pub fn counts(&self) -> &BTreeMap<T, usize> {
+        &self.values.0
+    }
+    /// Returns `true` if nothing has been sampled.
+    pub fn is_empty(&self) -> bool {
+        self.values.0.is_empty()
+    }
+    /// Returns `false` if some distinct values were not sampled.
+    pub fn is_exaustive(&self) -> bool {
+        self.is_exaustive
+    }
+    /// Returns the most frequent sample (the smallest one in case of a tie).
+    ///
+    /// If the samples are not exhaustive, a value that was not sampled might be more frequent.
+    pub fn mode(&self) -> Option<&T> {
+        self.values
+            .0
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| **count)
+            .map(|(value, _)| value)
+    }
+}
+impl<T, Q> Aggregate<Q> for CountingSampler<T>
+where
+    T: Ord + Clone + Borrow<Q>,
+    Q: Ord + ToOwned<Owned = T> + ?Sized,
+{
+    fn aggregate(&mut self, value: &'_ Q) {
+        if self.values.len() < MAX_SAMPLE_COUNT || self.values.contains_key(value) {
+            self.values.insert(value);
+        } else {
+            self.is_exaustive = false;
+        }
+    }
+}
+impl<T: Ord> Coalesce for CountingSampler<T> {
+    fn coalesce(&mut self, other: Self)
+    where
+        Self: Sized,
+    {
+        self.values.coalesce(other.values);
+        self.is_exaustive &= other.is_exaustive;
+        if self.values.0.len() > MAX_SAMPLE_COUNT {
+            self.is_exaustive = false;
+
+            let mut values: Vec<(T, usize)> =
+                std::mem::take(&mut self.values.0).into_iter().collect();
+            // Stable, so ties keep the smallest values.
+            values.sort_by(|(_, a), (_, b)| b.cmp(a));
+            values.truncate(MAX_SAMPLE_COUNT);
+            self.values.0 = values.into_iter().collect();
+        }
+    }
+}
+impl<T: Ord> Default for CountingSampler<T> {
+    fn default() -> Self {
+        Self {
+            values: Default::default(),
+            is_exaustive: true,
+        }
+    }
+}
+
+//
+// StructuralEq
+//
+
+// The aggregators above only hold sample-dependent metadata, which [StructuralEq] ignores, so
+// they always match. This way custom contexts built out of them can implement [StructuralEq]
+// by comparing their fields, and only the semantic ones will matter.
+
+impl StructuralEq for Counter {
+    fn structural_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+impl<T: Ord> StructuralEq for CountingSet<T> {
+    fn structural_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+impl<T> StructuralEq for MinMax<T> {
+    fn structural_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+impl<T: Ord> StructuralEq for Sampler<T> {
+    fn structural_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+impl<T: Ord> StructuralEq for CountingSampler<T> {
+    fn structural_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
@@ -1,15 +1,17 @@
 #![allow(missing_docs)]
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use once_cell::sync::Lazy;
-use regex::Regex;
+use regex::{Regex, RegexSet};
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "embedded_json")]
+use crate::Schema;
 use crate::{traits::Coalesce, Aggregate};
 
 use super::{
-    shared::{Counter, CountingSet, MinMax, Sampler},
+    shared::{Counter, CountingSampler, CountingSet, MinMax, Sampler},
     Aggregators,
 };
 
@@ -23,18 +25,92 @@ pub struct StringContext {
     /// Runs regexes on the strings to check whether they have interesting values.
     #[serde(default, skip_serializing_if = "SemanticExtractor::is_empty")]
     pub semantic_extractor: SemanticExtractor,
+    /// Keeps track of the units of strings that are a number followed by a unit.
+    #[serde(default, skip_serializing_if = "UnitExtractor::is_empty")]
+    pub unit_extractor: UnitExtractor,
+    /// Counts the values that look like bytes encoded as base64 or hex.
+    #[serde(default, skip_serializing_if = "BinaryEncodings::is_empty")]
+    pub binary_encodings: BinaryEncodings,
+    /// A template shared by the values, like `[A-Z]{2}-\d{4}` for `AB-1234`.
+    #[serde(default, skip_serializing_if = "PatternTemplate::is_empty")]
+    pub pattern_template: PatternTemplate,
+    /// Counts the characters of each Unicode script, to find the dominant one.
+    #[cfg(feature = "script_detection")]
+    #[serde(default, skip_serializing_if = "ScriptCounter::is_empty")]
+    pub scripts: ScriptCounter,
+    /// When [normalize_suspicious_strings](crate::AnalysisSettings::normalize_suspicious_strings)
+    /// is set, keeps track of how each normalized suspicious string was originally spelled, and
+    /// how many times.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub suspicious_spellings: BTreeMap<String, CountingSet<String>>,
     pub min_max_length: MinMax<usize>,
+    /// Set if any of the values was a native datetime of the format, which the analysis
+    /// itself cannot tell apart from a string (see [crate::helpers::toml]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_datetime: bool,
+    /// The number of values that are themselves JSON objects or arrays, which happens when
+    /// JSON is encoded twice.
+    #[cfg(feature = "embedded_json")]
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub embedded_json: Counter,
+    /// The schema of the embedded JSON values, only present if
+    /// [embedded_json_depth](crate::AnalysisSettings::embedded_json_depth) allowed analyzing them.
+    #[cfg(feature = "embedded_json")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedded_schema: Option<Box<Schema>>,
     #[serde(skip)]
     pub other_aggregators: Aggregators<str>,
 }
-impl Aggregate<str> for StringContext {
-    fn aggregate(&mut self, value: &'_ str) {
+impl StringContext {
+    /// Same as [Aggregate::aggregate_with_key], but the key is optional and suspicious strings
+    /// are recorded in their normalized form if `normalize_suspicious` is set.
+    ///
+    /// Embedded JSON is not counted, the analysis does it while parsing the value (see
+    /// [embedded_json_depth](crate::AnalysisSettings::embedded_json_depth)) so that it is only
+    /// parsed once.
+    pub(crate) fn aggregate_with(
+        &mut self,
+        key: Option<&str>,
+        value: &str,
+        normalize_suspicious: bool,
+    ) {
         self.count.aggregate(value);
         self.samples.aggregate(value);
-        self.suspicious_strings.aggregate(value);
+        if normalize_suspicious {
+            if let Some(normalized) = SuspiciousStrings::normalize(value) {
+                self.suspicious_strings.0.insert(normalized.as_str());
+                self.suspicious_spellings
+                    .entry(normalized)
+                    .or_default()
+                    .insert(value);
+            }
+        } else {
+            self.suspicious_strings.aggregate(value);
+        }
         self.semantic_extractor.aggregate(value);
+        self.unit_extractor.aggregate(value);
+        self.binary_encodings.aggregate(value);
+        self.pattern_template.aggregate(value);
+        #[cfg(feature = "script_detection")]
+        self.scripts.aggregate(value);
         self.min_max_length.aggregate(&value.len());
-        self.other_aggregators.aggregate(value);
+        self.other_aggregators.aggregate_keyed(key, value);
+    }
+    /// Like [aggregate_with](StringContext::aggregate_with), also counting embedded JSON.
+    fn aggregate_checking_json(&mut self, key: Option<&str>, value: &str) {
+        self.aggregate_with(key, value, false);
+        #[cfg(feature = "embedded_json")]
+        if holds_json(value) {
+            self.embedded_json.aggregate(value);
+        }
+    }
+}
+impl Aggregate<str> for StringContext {
+    fn aggregate(&mut self, value: &'_ str) {
+        self.aggregate_checking_json(None, value);
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &'_ str) {
+        self.aggregate_checking_json(Some(key), value);
     }
 }
 impl Coalesce for StringContext {
@@ -45,8 +121,31 @@ impl Coalesce for StringContext {
         self.count.coalesce(other.count);
         self.samples.coalesce(other.samples);
         self.suspicious_strings.coalesce(other.suspicious_strings);
+        for (normalized, spellings) in other.suspicious_spellings {
+            self.suspicious_spellings
+                .entry(normalized)
+                .or_default()
+                .coalesce(spellings);
+        }
         self.semantic_extractor.coalesce(other.semantic_extractor);
+        self.unit_extractor.coalesce(other.unit_extractor);
+        self.binary_encodings.coalesce(other.binary_encodings);
+        self.pattern_template.coalesce(other.pattern_template);
+        #[cfg(feature = "script_detection")]
+        self.scripts.coalesce(other.scripts);
         self.min_max_length.coalesce(other.min_max_length);
+        self.is_datetime |= other.is_datetime;
+        #[cfg(feature = "embedded_json")]
+        {
+            self.embedded_json.coalesce(other.embedded_json);
+            self.embedded_schema = match (self.embedded_schema.take(), other.embedded_schema) {
+                (Some(mut schema), Some(other)) => {
+                    schema.coalesce(*other);
+                    Some(schema)
+                }
+                (schema, other) => schema.or(other),
+            };
+        }
         self.other_aggregators.coalesce(other.other_aggregators);
     }
 }
@@ -57,9 +156,161 @@ impl PartialEq for StringContext {
         self.count == other.count
             && self.samples == other.samples
             && self.suspicious_strings == other.suspicious_strings
+            && self.suspicious_spellings == other.suspicious_spellings
             && self.semantic_extractor == other.semantic_extractor
+            && self.unit_extractor == other.unit_extractor
+            && self.binary_encodings == other.binary_encodings
+            && self.pattern_template == other.pattern_template
             && self.min_max_length == other.min_max_length
+            && self.is_datetime == other.is_datetime
+            && self.embedded_eq(other)
+            && self.scripts_eq(other)
+    }
+}
+
+impl StringContext {
+    /// Returns the unit shared by all the values, if they are all a number followed by the
+    /// same unit (like `"45%"` and `"3.5 %"`).
+    pub fn single_unit(&self) -> Option<&str> {
+        let units = &self.unit_extractor.0;
+        match units.counts().iter().next() {
+            Some((unit, count)) if units.counts().len() == 1 && *count == self.count.get() => {
+                Some(unit)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the [JSON Schema format](https://json-schema.org/understanding-json-schema/reference/string#built-in-formats)
+    /// matched by all the values, if any (like `"email"` or `"uuid"`).
+    ///
+    /// Based on the [SemanticExtractor], only the `date`, `email`, `uuid`, `uri`, `ipv4` and
+    /// `ipv6` formats are detected. The patterns of these targets check the syntax of their
+    /// format (like the days in each month of a date), so that the format is only emitted
+    /// when the values would pass a validator, but they may miss rarer valid values (like
+    /// emails with a quoted local part).
+    pub fn json_schema_format(&self) -> Option<&'static str> {
+        const FORMATS: [(&str, &str); 6] = [
+            ("Date 2001-12-31", "date"),
+            ("Email", "email"),
+            ("UUID", "uuid"),
+            ("URI", "uri"),
+            ("IPv4", "ipv4"),
+            ("IPv6", "ipv6"),
+        ];
+        if self.count.get() == 0 {
+            return None;
+        }
+        FORMATS
+            .iter()
+            .find(|(target, _)| self.semantic_extractor.count(target) == self.count.get())
+            .map(|(_, format)| *format)
+    }
+
+    /// The fraction of values that must look encoded for [is_base64](StringContext::is_base64),
+    /// [is_base64_url](StringContext::is_base64_url) and [is_hex](StringContext::is_hex) to
+    /// return `true`.
+    pub const BINARY_ENCODING_FRACTION: f64 = 0.9;
+
+    /// Returns `true` if most values look like bytes encoded as base64, see
+    /// [BinaryEncodings::is_base64].
+    pub fn is_base64(&self) -> bool {
+        self.mostly(
+            self.binary_encodings.base64.get(),
+            Self::BINARY_ENCODING_FRACTION,
+        )
+    }
+    /// Returns `true` if most values look like bytes encoded as url-safe base64, see
+    /// [BinaryEncodings::is_base64_url].
+    pub fn is_base64_url(&self) -> bool {
+        self.mostly(
+            self.binary_encodings.base64_url.get(),
+            Self::BINARY_ENCODING_FRACTION,
+        )
     }
+    /// Returns `true` if most values look like bytes encoded as hex, see
+    /// [BinaryEncodings::is_hex].
+    pub fn is_hex(&self) -> bool {
+        self.mostly(
+            self.binary_encodings.hex.get(),
+            Self::BINARY_ENCODING_FRACTION,
+        )
+    }
+    /// The fraction of values that must match for [is_phone_number](StringContext::is_phone_number)
+    /// and [is_postal_code](StringContext::is_postal_code) to return `true`.
+    ///
+    /// It is high because the patterns are loose: a column of five digit ids may still be
+    /// mistaken for ZIP codes, so treat the result as a hint to flag possible PII.
+    pub const PII_FRACTION: f64 = 0.95;
+
+    /// Returns `true` if most values look like phone numbers, see [PII_FRACTION](StringContext::PII_FRACTION).
+    ///
+    /// Only numbers with an international prefix or with separators are recognized.
+    pub fn is_phone_number(&self) -> bool {
+        self.mostly(
+            self.semantic_extractor.count("Phone Number"),
+            Self::PII_FRACTION,
+        )
+    }
+    /// Returns `true` if most values look like postal codes, see [PII_FRACTION](StringContext::PII_FRACTION).
+    ///
+    /// With no `country` the values may be in the format of any of the [PostalCodeCountry]s,
+    /// otherwise they must be in the format of the given one.
+    pub fn is_postal_code(&self, country: Option<PostalCodeCountry>) -> bool {
+        let target = country.map_or(POSTAL_CODE, PostalCodeCountry::target);
+        self.mostly(self.semantic_extractor.count(target), Self::PII_FRACTION)
+    }
+
+    fn mostly(&self, matches: usize, fraction: f64) -> bool {
+        self.count.get() > 0 && matches as f64 >= self.count.get() as f64 * fraction
+    }
+
+    /// The fraction of values that must be embedded JSON for
+    /// [is_embedded_json](StringContext::is_embedded_json) to return `true`.
+    #[cfg(feature = "embedded_json")]
+    pub const EMBEDDED_JSON_FRACTION: f64 = 0.9;
+
+    /// Returns `true` if most values are JSON objects or arrays encoded as strings, see
+    /// [EMBEDDED_JSON_FRACTION](StringContext::EMBEDDED_JSON_FRACTION).
+    #[cfg(feature = "embedded_json")]
+    pub fn is_embedded_json(&self) -> bool {
+        self.mostly(self.embedded_json.get(), Self::EMBEDDED_JSON_FRACTION)
+    }
+
+    #[cfg(feature = "embedded_json")]
+    fn embedded_eq(&self, other: &Self) -> bool {
+        self.embedded_json == other.embedded_json && self.embedded_schema == other.embedded_schema
+    }
+    #[cfg(not(feature = "embedded_json"))]
+    fn embedded_eq(&self, _other: &Self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "script_detection")]
+    fn scripts_eq(&self, other: &Self) -> bool {
+        self.scripts == other.scripts
+    }
+    #[cfg(not(feature = "script_detection"))]
+    fn scripts_eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Returns `true` if the string holds a JSON object or array.
+#[cfg(feature = "embedded_json")]
+pub(crate) fn holds_json(value: &str) -> bool {
+    may_hold_json(value) && serde_json::from_str::<serde::de::IgnoredAny>(value).is_ok()
+}
+/// Returns `true` if the string starts like a JSON object or array, so that only these are
+/// parsed.
+#[cfg(feature = "embedded_json")]
+pub(crate) fn may_hold_json(value: &str) -> bool {
+    let value = value.trim_start();
+    value.starts_with('{') || value.starts_with('[')
+}
+#[cfg(feature = "embedded_json")]
+fn is_zero(counter: &Counter) -> bool {
+    counter.0 == 0
 }
 
 //
@@ -78,10 +329,27 @@ impl SuspiciousStrings {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+    /// Returns the normalized form of `value` if it is suspicious.
+    pub fn normalize(value: &str) -> Option<String> {
+        match Self::is_suspicious(value) {
+            true => Some(value.to_lowercase()),
+            false => None,
+        }
+    }
+    /// Like [normalize](SuspiciousStrings::normalize), without allocating.
+    ///
+    /// The known strings are all ASCII, and no non-ASCII character is lowercased into one of
+    /// their characters, so ignoring the ASCII case is enough.
+    fn is_suspicious(value: &str) -> bool {
+        (NORMALIZED_SUSPICIOUS_STRINGS.iter()).any(|known| known.eq_ignore_ascii_case(value))
+    }
 }
 impl Aggregate<str> for SuspiciousStrings {
+    /// Records the original spelling, see
+    /// [normalize_suspicious_strings](crate::AnalysisSettings::normalize_suspicious_strings)
+    /// to group them instead.
     fn aggregate(&mut self, value: &'_ str) {
-        if NORMALIZED_SUSPICIOUS_STRINGS.contains(&value.to_lowercase().as_str()) {
+        if Self::is_suspicious(value) {
             self.0.insert(value);
         }
     }
@@ -100,23 +368,169 @@ impl Coalesce for SuspiciousStrings {
 // This is a POC, more targets should be later added if it works well.
 //
 
-const RAW_SEMANTIC_TARGETS: [(&str, &str); 5] = [
+const RAW_SEMANTIC_TARGETS: [(&str, &str); 18] = [
     ("Integer", r"[-+]?\d+"),
     ("Simple Float", r"\d+[.,]\d+"),
+    // Like `1.5e10`, see [NumberContext](super::NumberContext) for why this is a string target.
+    (
+        "Scientific Float",
+        r"[-+]?(?:\d+(?:\.\d*)?|\.\d+)[eE][-+]?\d+",
+    ),
     ("Date 31-12-2001", r"\d{2}-\d{2}-\d{4}"),
-    ("Date 2001-12-31", r"\d{4}-\d{2}-\d{2}"),
+    ("Date 2001-12-31", DATE),
     // `(?i)` sets and `(?-i)` clears the case-insensitive flag.
     ("Boolean", r"(?i)(true|yes|false|no)(?-i)"),
+    ("IPv4", IPV4),
+    ("IPv6", IPV6),
+    ("Hostname", HOSTNAME),
+    ("Email", EMAIL),
+    (
+        "UUID",
+        r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}",
+    ),
+    ("URI", URI),
+    // Phone numbers and postal codes are ambiguous, see [StringContext::is_phone_number] and
+    // [StringContext::is_postal_code].
+    ("Phone Number", PHONE_NUMBER),
+    ("Postal Code US", POSTAL_CODE_US),
+    ("Postal Code GB", POSTAL_CODE_GB),
+    ("Postal Code CA", POSTAL_CODE_CA),
+    ("Postal Code NL", POSTAL_CODE_NL),
+    ("Postal Code BR", POSTAL_CODE_BR),
 ];
 
-static SEMANTIC_TARGETS: Lazy<BTreeMap<String, Regex>> = Lazy::new(|| {
-    fn from_pattern(p: &str) -> Regex {
-        Regex::new(&format!(r"^\s*{}\s*$", p)).unwrap()
+/// An RFC 3339 full date (like `2001-12-31`), the day must exist in its month (leap years
+/// included).
+const DATE: &str = concat!(
+    r"(?:\d{4}-(?:(?:0[13578]|1[02])-(?:0[1-9]|[12]\d|3[01])",
+    r"|(?:0[469]|11)-(?:0[1-9]|[12]\d|30)",
+    r"|02-(?:0[1-9]|1\d|2[0-8]))",
+    r"|(?:\d{2}(?:0[48]|[2468][048]|[13579][26])|(?:[02468][048]|[13579][26])00)-02-29)"
+);
+/// An RFC 5321 mailbox with a dot-atom local part and a domain name (like `a.b@example.com`).
+/// Quoted local parts and address literals are valid too, but too rare to be worth the
+/// ambiguity.
+const EMAIL: &str = concat!(
+    r"[a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+)*",
+    r"@(?:[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?\.)+[a-zA-Z]{2,63}"
+);
+/// An RFC 3986 URI with an authority (like `https://example.com/a?b=c#d`), made only of the
+/// characters allowed in each of its parts or percent-encoded.
+/// A bare `scheme:` would also match many strings that are not URIs, like `key:value`.
+const URI: &str = concat!(
+    r"[a-zA-Z][a-zA-Z0-9+.-]*://",
+    r"(?:[a-zA-Z0-9._~!$&'()*+,;=:@\[\]-]|%[0-9a-fA-F]{2})*",
+    r"(?:/(?:[a-zA-Z0-9._~!$&'()*+,;=:@-]|%[0-9a-fA-F]{2})*)*",
+    r"(?:\?(?:[a-zA-Z0-9._~!$&'()*+,;=:@/?-]|%[0-9a-fA-F]{2})*)?",
+    r"(?:#(?:[a-zA-Z0-9._~!$&'()*+,;=:@/?-]|%[0-9a-fA-F]{2})*)?"
+);
+/// A dotted quad, each octet between 0 and 255 without leading zeros.
+const IPV4: &str =
+    r"(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)";
+/// One alternative per position of the `::` compression, plus link-local addresses with a zone
+/// index and addresses ending in an embedded IPv4.
+/// Adapted from <https://stackoverflow.com/a/17871737>, the regex crate has no lookarounds.
+const IPV6: &str = concat!(
+    r"(?:",
+    r"(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}",
+    r"|(?:[0-9a-fA-F]{1,4}:){1,7}:",
+    r"|(?:[0-9a-fA-F]{1,4}:){1,6}:[0-9a-fA-F]{1,4}",
+    r"|(?:[0-9a-fA-F]{1,4}:){1,5}(?::[0-9a-fA-F]{1,4}){1,2}",
+    r"|(?:[0-9a-fA-F]{1,4}:){1,4}(?::[0-9a-fA-F]{1,4}){1,3}",
+    r"|(?:[0-9a-fA-F]{1,4}:){1,3}(?::[0-9a-fA-F]{1,4}){1,4}",
+    r"|(?:[0-9a-fA-F]{1,4}:){1,2}(?::[0-9a-fA-F]{1,4}){1,5}",
+    r"|[0-9a-fA-F]{1,4}:(?::[0-9a-fA-F]{1,4}){1,6}",
+    r"|:(?:(?::[0-9a-fA-F]{1,4}){1,7}|:)",
+    r"|[fF][eE]80:(?::[0-9a-fA-F]{0,4}){0,4}%[0-9a-zA-Z]+",
+    r"|::(?:[fF]{4}(?::0{1,4})?:)?(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)",
+    r"|(?:[0-9a-fA-F]{1,4}:){1,4}:(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)",
+    r")"
+);
+/// RFC 1123 labels separated by dots, ending with an alphabetic top-level domain (like `com`,
+/// `uk` or `shop`).
+///
+/// Many other values are dotted names too, and only some can be told apart: the names whose
+/// last label is a common [file extension](FILE_EXTENSIONS) (like `photo.jpg` or `main.rs`) are
+/// not counted, while others (like `john.doe`) are, so this is only a hint.
+const HOSTNAME: &str = concat!(
+    r"(?:[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?\.)+",
+    r"[a-zA-Z]{2,63}"
+);
+/// The extensions of common files, whose names would otherwise be counted as hostnames.
+///
+/// A few are also top-level domains (like `rs` or `md`), but in data they are far more likely to
+/// be files. Those of widely used domains (like `pl`) are left out.
+const FILE_EXTENSIONS: &[&str] = &[
+    "7z", "avi", "bak", "bat", "bin", "bmp", "bz2", "cfg", "conf", "cpp", "css", "csv", "dat",
+    "dll", "doc", "docx", "exe", "flac", "gif", "go", "gz", "hpp", "htm", "html", "ico", "ini",
+    "java", "jpeg", "jpg", "js", "json", "jsx", "kt", "lock", "log", "md", "mjs", "mkv", "mov",
+    "mp3", "mp4", "ogg", "pdf", "php", "png", "ppt", "pptx", "py", "rar", "rb", "rs", "sh", "so",
+    "svg", "tar", "tgz", "tif", "tiff", "tmp", "toml", "ts", "tsv", "tsx", "txt", "wav", "webm",
+    "webp", "xls", "xlsx", "xml", "xz", "yaml", "yml", "zip",
+];
+
+/// E.164 (like `+14155552671`), international numbers with separators (like `+44 20 7946 0958`
+/// or `0033 1 23 45 67 89`), and North American numbers with separators (like `(415) 555-2671`).
+/// Plain digits are left out, as they can't be told apart from any other number.
+const PHONE_NUMBER: &str = concat!(
+    r"(?:",
+    r"\+[1-9]\d{6,14}",
+    r"|(?:\+|00)[1-9]\d{0,3}(?:[ .-]\(?\d{1,4}\)?){2,5}",
+    r"|\(?\d{3}\)?[ .-]?\d{3}[ .-]\d{4}",
+    r")"
+);
+/// A ZIP or ZIP+4 code.
+const POSTAL_CODE_US: &str = r"\d{5}(?:-\d{4})?";
+/// Like `SW1A 1AA` or `M1 1AE`.
+const POSTAL_CODE_GB: &str = r"(?i:[A-Z]{1,2}\d[A-Z\d]? ?\d[A-Z]{2})";
+/// Like `K1A 0B1`.
+const POSTAL_CODE_CA: &str = r"(?i:[A-Z]\d[A-Z] ?\d[A-Z]\d)";
+/// Like `1234 AB`.
+const POSTAL_CODE_NL: &str = r"(?i:\d{4} ?[A-Z]{2})";
+/// Like `12345-678`.
+const POSTAL_CODE_BR: &str = r"\d{5}-\d{3}";
+/// The target counting the values that matched the postal code of any country, without
+/// running another pattern.
+const POSTAL_CODE: &str = "Postal Code";
+
+/// The countries whose postal codes are recognized, see [StringContext::is_postal_code].
+///
+/// Purely numeric codes shorter than five digits (like most European ones) are left out, as
+/// they are indistinguishable from small numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PostalCodeCountry {
+    /// A ZIP or ZIP+4 code, like `94105` or `10001-1234`.
+    US,
+    /// Like `SW1A 1AA` or `M1 1AE`.
+    GB,
+    /// Like `K1A 0B1`.
+    CA,
+    /// Like `1234 AB`.
+    NL,
+    /// Like `12345-678`.
+    BR,
+}
+impl PostalCodeCountry {
+    /// The [SemanticExtractor] target counting the postal codes of the country.
+    pub fn target(self) -> &'static str {
+        match self {
+            PostalCodeCountry::US => "Postal Code US",
+            PostalCodeCountry::GB => "Postal Code GB",
+            PostalCodeCountry::CA => "Postal Code CA",
+            PostalCodeCountry::NL => "Postal Code NL",
+            PostalCodeCountry::BR => "Postal Code BR",
+        }
     }
-    RAW_SEMANTIC_TARGETS
-        .iter()
-        .map(|(n, p)| (n.to_string(), from_pattern(p)))
-        .collect()
+}
+
+/// All the [RAW_SEMANTIC_TARGETS] in a single set, so that each value is scanned once.
+static SEMANTIC_TARGETS: Lazy<RegexSet> = Lazy::new(|| {
+    RegexSet::new(
+        RAW_SEMANTIC_TARGETS
+            .iter()
+            .map(|(_, p)| format!(r"^\s*{}\s*$", p)),
+    )
+    .unwrap()
 });
 /// Runs regexes on the strings to check whether they have interesting values.
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -126,16 +540,43 @@ impl SemanticExtractor {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+    /// Returns how many strings matched `target` (like `"IPv4"`).
+    pub fn count(&self, target: &str) -> usize {
+        self.0 .0.get(target).copied().unwrap_or(0)
+    }
+    /// The targets that were matched and how many strings matched each.
+    pub fn counts(&self) -> &BTreeMap<String, usize> {
+        &self.0 .0
+    }
 }
 impl Aggregate<str> for SemanticExtractor {
+    /// Values matching the postal code of any country are also counted under `"Postal Code"`.
     fn aggregate(&mut self, value: &'_ str) {
-        for (target, regex) in SEMANTIC_TARGETS.iter() {
-            if regex.is_match(value) {
-                self.0.insert(target);
+        // Most values match no target, and matching does not allocate like listing the matches.
+        if !SEMANTIC_TARGETS.is_match(value) {
+            return;
+        }
+        let mut is_postal_code = false;
+        for i in SEMANTIC_TARGETS.matches(value).iter() {
+            let target = RAW_SEMANTIC_TARGETS[i].0;
+            if target == "Hostname" && has_file_extension(value) {
+                continue;
             }
+            self.0.insert(target);
+            is_postal_code |= target.starts_with(POSTAL_CODE);
+        }
+        if is_postal_code {
+            self.0.insert(POSTAL_CODE);
         }
     }
 }
+/// Returns `true` if the last label of `value` is one of the [FILE_EXTENSIONS].
+fn has_file_extension(value: &str) -> bool {
+    let extension = value.trim().rsplit('.').next().unwrap_or_default();
+    FILE_EXTENSIONS
+        .iter()
+        .any(|known| known.eq_ignore_ascii_case(extension))
+}
 impl Coalesce for SemanticExtractor {
     fn coalesce(&mut self, other: Self)
     where
@@ -144,3 +585,557 @@ impl Coalesce for SemanticExtractor {
         self.0.coalesce(other.0);
     }
 }
+
+//
+// UnitExtractor
+//
+
+/// A number, optionally negative or decimal, followed by a unit and an optional space.
+/// Units are percent and per mille signs, or letters optionally followed by a `/` and more
+/// letters, a leading `°` or `µ`, and a trailing `²` or `³` (like `kg`, `°C`, `km/h` or `m²`).
+static NUMBER_WITH_UNIT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*[-+]?(?:\d+(?:[.,]\d+)?|[.,]\d+) ?([%‰]|[°µ]?[a-zA-Z]+(?:/[a-zA-Z]+)?[²³]?)\s*$",
+    )
+    .unwrap()
+});
+/// Detects strings that are a number followed by a unit (like `"45%"`, `"12kg"` or
+/// `"-3.5 ms"`), and counts how many values had each unit.
+///
+/// Only the first few distinct units are kept, a column with many different ones is unlikely
+/// to hold quantities anyway.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct UnitExtractor(pub CountingSampler<String>);
+impl UnitExtractor {
+    /// Returns `true` if no number with a unit has been found.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Returns the unit of `value`, if it is a number followed by a unit.
+    pub fn unit(value: &str) -> Option<&str> {
+        // Most values have no unit, and matching does not allocate like capturing.
+        if !NUMBER_WITH_UNIT.is_match(value) {
+            return None;
+        }
+        NUMBER_WITH_UNIT
+            .captures(value)
+            .and_then(|captures| captures.get(1))
+            .map(|unit| unit.as_str())
+    }
+}
+impl Aggregate<str> for UnitExtractor {
+    fn aggregate(&mut self, value: &'_ str) {
+        if let Some(unit) = Self::unit(value) {
+            self.0.aggregate(unit);
+        }
+    }
+}
+impl Coalesce for UnitExtractor {
+    fn coalesce(&mut self, other: Self)
+    where
+        Self: Sized,
+    {
+        self.0.coalesce(other.0);
+    }
+}
+
+//
+// BinaryEncodings
+//
+
+/// Shorter values are too likely to be ordinary words or numbers.
+const MIN_ENCODED_LENGTH: usize = 8;
+
+/// Counts the values that look like bytes encoded as text, as many formats (like JSON) have
+/// no bytes of their own.
+///
+/// A value is counted as hex before base64, as the hex digits are also valid base64.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BinaryEncodings {
+    pub base64: Counter,
+    /// Values using the url-safe alphabet (`-` and `_` instead of `+` and `/`).
+    #[serde(default)]
+    pub base64_url: Counter,
+    pub hex: Counter,
+}
+// The crate avoids std APIs that need a recent Rust (`is_multiple_of` is from 1.87), so the
+// lengths are checked with `%`, like `DurationAnalysis` avoids `div_ceil`.
+#[allow(clippy::manual_is_multiple_of)]
+impl BinaryEncodings {
+    /// Returns `true` if no encoded value has been found.
+    pub fn is_empty(&self) -> bool {
+        self.base64.get() == 0 && self.base64_url.get() == 0 && self.hex.get() == 0
+    }
+    /// Returns `true` if `value` is an even number of hex digits, with the letters all in the
+    /// same case and at least one of them (otherwise it is more likely to be a number).
+    pub fn is_hex(value: &str) -> bool {
+        value.len() >= MIN_ENCODED_LENGTH
+            && value.len() % 2 == 0
+            && value.bytes().all(|b| b.is_ascii_hexdigit())
+            && (value.bytes().all(|b| !b.is_ascii_uppercase())
+                || value.bytes().all(|b| !b.is_ascii_lowercase()))
+            && value.bytes().any(|b| b.is_ascii_alphabetic())
+    }
+    /// Returns `true` if `value` is base64 using the standard alphabet, with its length a
+    /// multiple of four.
+    ///
+    /// Plenty of identifiers (like `SKU12345` or `AbCdEfGh`) are also valid base64, so the
+    /// value must either be padded with `=` or hold a `+` or `/`.
+    pub fn is_base64(value: &str) -> bool {
+        if value.len() % 4 != 0 {
+            return false;
+        }
+        let (body, is_padded) = match Self::base64_body(value) {
+            Some(split) => split,
+            None => return false,
+        };
+        let is_standard = |b: u8| b.is_ascii_alphanumeric() || b == b'+' || b == b'/';
+        body.bytes().all(is_standard) && (is_padded || body.bytes().any(|b| b == b'+' || b == b'/'))
+    }
+    /// Returns `true` if `value` is base64 using the url-safe alphabet, where the padding is
+    /// optional.
+    ///
+    /// The value must hold a `-` or `_`, and if it is not padded it must also mix digits with
+    /// upper and lowercase letters, so that names like `user_name` are not counted.
+    pub fn is_base64_url(value: &str) -> bool {
+        let (body, is_padded) = match Self::base64_body(value) {
+            Some(split) => split,
+            None => return false,
+        };
+        // A single character past a multiple of four does not encode a whole byte.
+        if (is_padded && value.len() % 4 != 0) || body.len() % 4 == 1 {
+            return false;
+        }
+        let is_url_safe = |b: u8| b.is_ascii_alphanumeric() || b == b'-' || b == b'_';
+        if !body.bytes().all(is_url_safe) || !body.bytes().any(|b| b == b'-' || b == b'_') {
+            return false;
+        }
+        is_padded
+            || (body.bytes().any(|b| b.is_ascii_digit())
+                && body.bytes().any(|b| b.is_ascii_uppercase())
+                && body.bytes().any(|b| b.is_ascii_lowercase()))
+    }
+    /// Splits off at most two characters of padding, returning the body and whether there
+    /// was any.
+    fn base64_body(value: &str) -> Option<(&str, bool)> {
+        if value.len() < MIN_ENCODED_LENGTH {
+            return None;
+        }
+        let body = value.trim_end_matches('=');
+        match value.len() - body.len() {
+            0 => Some((body, false)),
+            1 | 2 => Some((body, true)),
+            _ => None,
+        }
+    }
+}
+impl Aggregate<str> for BinaryEncodings {
+    fn aggregate(&mut self, value: &'_ str) {
+        if Self::is_hex(value) {
+            self.hex.aggregate(value);
+        } else if Self::is_base64(value) {
+            self.base64.aggregate(value);
+        } else if Self::is_base64_url(value) {
+            self.base64_url.aggregate(value);
+        }
+    }
+}
+impl Coalesce for BinaryEncodings {
+    fn coalesce(&mut self, other: Self)
+    where
+        Self: Sized,
+    {
+        self.base64.coalesce(other.base64);
+        self.base64_url.coalesce(other.base64_url);
+        self.hex.coalesce(other.hex);
+    }
+}
+
+//
+// PatternTemplate
+//
+
+/// Values with more runs than this are only kept as a [Loose](PatternTemplate::Loose) template.
+const MAX_TEMPLATE_RUNS: usize = 16;
+/// A [Loose](PatternTemplate::Loose) template seeing more distinct classes than this accepts
+/// any character.
+const MAX_LOOSE_CLASSES: usize = 16;
+
+/// The characters accepted at a position of a [PatternTemplate].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(tag = "class", content = "char")]
+pub enum CharClass {
+    /// `[A-Z]`
+    Upper,
+    /// `[a-z]`
+    Lower,
+    /// `[A-Za-z]`
+    Alpha,
+    /// `\d`, ASCII only.
+    Digit,
+    /// `[A-Za-z0-9]`
+    Alphanumeric,
+    /// Any other character, as is.
+    Literal(char),
+}
+impl CharClass {
+    fn of(c: char) -> Self {
+        match c {
+            'A'..='Z' => CharClass::Upper,
+            'a'..='z' => CharClass::Lower,
+            '0'..='9' => CharClass::Digit,
+            _ => CharClass::Literal(c),
+        }
+    }
+    fn is_letter(self) -> bool {
+        matches!(self, CharClass::Upper | CharClass::Lower | CharClass::Alpha)
+    }
+    /// The narrowest class including both, if any.
+    fn merge(self, other: Self) -> Option<Self> {
+        use CharClass::*;
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (Literal(_), _) | (_, Literal(_)) => None,
+            (Upper | Lower | Alpha, Upper | Lower | Alpha) => Some(Alpha),
+            _ => Some(Alphanumeric),
+        }
+    }
+    fn regex(self) -> String {
+        match self {
+            CharClass::Upper => "[A-Z]".into(),
+            CharClass::Lower => "[a-z]".into(),
+            CharClass::Alpha => "[A-Za-z]".into(),
+            CharClass::Digit => r"\d".into(),
+            CharClass::Alphanumeric => "[A-Za-z0-9]".into(),
+            // Only special within sets, so it is left as is for readability.
+            CharClass::Literal('-') => "-".into(),
+            CharClass::Literal(c) => regex::escape(c.encode_utf8(&mut [0; 4])),
+        }
+    }
+}
+
+/// A run of characters of the same [CharClass], repeated between `min` and `max` times.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TemplateRun {
+    pub class: CharClass,
+    pub min: usize,
+    pub max: usize,
+}
+
+/// Infers a template shared by the values, like `[A-Z]{2}-\d{4}-[A-Z]{2}` for SKUs like
+/// `AB-1234-XY`, see [regex](PatternTemplate::regex).
+///
+/// Each value is split into runs of letters (`[A-Z]`, `[a-z]` or both), digits or any other
+/// repeated character. Values with runs of compatible classes in the same order are merged
+/// run by run, widening the classes (`[A-Z]` and `[a-z]` become `[A-Za-z]`) and the repetitions.
+/// Values that don't line up fall back to a [Loose](PatternTemplate::Loose) template.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum PatternTemplate {
+    /// No values were found.
+    #[default]
+    Empty,
+    /// All the values are made of these runs.
+    Runs { runs: Vec<TemplateRun> },
+    /// The values only share the classes of their characters, [None] if there were too many
+    /// to list, and a range of lengths (in characters).
+    Loose {
+        classes: Option<BTreeSet<CharClass>>,
+        min_len: usize,
+        max_len: usize,
+    },
+}
+impl PatternTemplate {
+    /// Returns `true` if no values have been found.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, PatternTemplate::Empty)
+    }
+
+    /// The template of a single value.
+    pub fn of(value: &str) -> Self {
+        let runs: Vec<TemplateRun> = Self::runs_of(value).collect();
+        match runs.len() > MAX_TEMPLATE_RUNS {
+            true => PatternTemplate::Runs { runs }.loosen(),
+            false => PatternTemplate::Runs { runs },
+        }
+    }
+
+    /// The runs of a value, computed lazily so that they can be merged without allocating.
+    fn runs_of(value: &str) -> impl Iterator<Item = TemplateRun> + '_ {
+        let mut chars = value.chars().peekable();
+        std::iter::from_fn(move || {
+            let mut run = TemplateRun {
+                class: CharClass::of(chars.next()?),
+                min: 1,
+                max: 1,
+            };
+            while let Some(class) = chars.peek().map(|c| CharClass::of(*c)) {
+                if run.class != class && !(run.class.is_letter() && class.is_letter()) {
+                    break;
+                }
+                run.class = run.class.merge(class).unwrap_or(run.class);
+                run.min += 1;
+                run.max += 1;
+                chars.next();
+            }
+            Some(run)
+        })
+    }
+
+    /// A regex (without anchors) matching all the values found, [None] if there were none.
+    pub fn regex(&self) -> Option<String> {
+        fn repetitions(min: usize, max: usize) -> String {
+            match (min, max) {
+                (1, 1) => String::new(),
+                (min, max) if min == max => format!("{{{}}}", min),
+                (min, max) => format!("{{{},{}}}", min, max),
+            }
+        }
+        match self {
+            PatternTemplate::Empty => None,
+            PatternTemplate::Runs { runs } => Some(
+                runs.iter()
+                    .map(|run| run.class.regex() + &repetitions(run.min, run.max))
+                    .collect(),
+            ),
+            PatternTemplate::Loose {
+                classes,
+                min_len,
+                max_len,
+            } => {
+                let set = match classes {
+                    Some(classes) => {
+                        let has = |wanted: &[CharClass]| classes.iter().any(|c| wanted.contains(c));
+                        let mut set = String::from("[");
+                        if has(&[CharClass::Upper, CharClass::Alpha, CharClass::Alphanumeric]) {
+                            set.push_str("A-Z");
+                        }
+                        if has(&[CharClass::Lower, CharClass::Alpha, CharClass::Alphanumeric]) {
+                            set.push_str("a-z");
+                        }
+                        if has(&[CharClass::Digit, CharClass::Alphanumeric]) {
+                            set.push_str("0-9");
+                        }
+                        for class in classes {
+                            if let CharClass::Literal(c) = class {
+                                set.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+                            }
+                        }
+                        set.push(']');
+                        set
+                    }
+                    None => ".".into(),
+                };
+                let repetitions = match (min_len, max_len) {
+                    (0, 0) => return Some(String::new()),
+                    (min, max) if min == max => format!("{{{}}}", min),
+                    (min, max) => format!("{{{},{}}}", min, max),
+                };
+                Some(set + &repetitions)
+            }
+        }
+    }
+
+    /// Merges the templates, falling back to a [Loose](PatternTemplate::Loose) one if their
+    /// runs don't line up.
+    fn merge(self, other: Self) -> Self {
+        use PatternTemplate::*;
+        match (self, other) {
+            (Empty, template) | (template, Empty) => template,
+            (Runs { runs: a }, Runs { runs: b }) if a.len() == b.len() => {
+                let merged = a
+                    .iter()
+                    .zip(&b)
+                    .map(|(a, b)| {
+                        Some(TemplateRun {
+                            class: a.class.merge(b.class)?,
+                            min: a.min.min(b.min),
+                            max: a.max.max(b.max),
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match merged {
+                    Some(runs) => Runs { runs },
+                    None => Runs { runs: a }.loosen().merge(Runs { runs: b }.loosen()),
+                }
+            }
+            (
+                Loose {
+                    classes,
+                    min_len,
+                    max_len,
+                },
+                Loose {
+                    classes: other_classes,
+                    min_len: other_min_len,
+                    max_len: other_max_len,
+                },
+            ) => {
+                let classes = match (classes, other_classes) {
+                    (Some(mut classes), Some(other)) => {
+                        classes.extend(other);
+                        Some(classes).filter(|classes| classes.len() <= MAX_LOOSE_CLASSES)
+                    }
+                    _ => None,
+                };
+                Loose {
+                    classes,
+                    min_len: min_len.min(other_min_len),
+                    max_len: max_len.max(other_max_len),
+                }
+            }
+            (a, b) => a.loosen().merge(b.loosen()),
+        }
+    }
+
+    /// Keeps only the classes and the length range.
+    fn loosen(self) -> Self {
+        match self {
+            PatternTemplate::Runs { runs } => {
+                let classes: BTreeSet<CharClass> = runs.iter().map(|run| run.class).collect();
+                PatternTemplate::Loose {
+                    min_len: runs.iter().map(|run| run.min).sum(),
+                    max_len: runs.iter().map(|run| run.max).sum(),
+                    classes: Some(classes).filter(|classes| classes.len() <= MAX_LOOSE_CLASSES),
+                }
+            }
+            template => template,
+        }
+    }
+}
+impl Aggregate<str> for PatternTemplate {
+    /// Values that fit the template are merged in place, only a change of shape (like the
+    /// first value, or a value that doesn't line up) builds a new template.
+    fn aggregate(&mut self, value: &'_ str) {
+        match self {
+            PatternTemplate::Runs { runs }
+                if PatternTemplate::runs_of(value).count() == runs.len()
+                    && (runs.iter())
+                        .zip(PatternTemplate::runs_of(value))
+                        .all(|(run, other)| run.class.merge(other.class).is_some()) =>
+            {
+                for (run, other) in runs.iter_mut().zip(PatternTemplate::runs_of(value)) {
+                    run.class = run.class.merge(other.class).unwrap_or(run.class);
+                    run.min = run.min.min(other.min);
+                    run.max = run.max.max(other.max);
+                }
+            }
+            PatternTemplate::Loose {
+                classes,
+                min_len,
+                max_len,
+            } => {
+                if let Some(set) = classes {
+                    set.extend(PatternTemplate::runs_of(value).map(|run| run.class));
+                    if set.len() > MAX_LOOSE_CLASSES {
+                        *classes = None;
+                    }
+                }
+                let len = value.chars().count();
+                *min_len = (*min_len).min(len);
+                *max_len = (*max_len).max(len);
+            }
+            _ => *self = std::mem::take(self).merge(PatternTemplate::of(value)),
+        }
+    }
+}
+impl Coalesce for PatternTemplate {
+    fn coalesce(&mut self, other: Self)
+    where
+        Self: Sized,
+    {
+        *self = std::mem::take(self).merge(other);
+    }
+}
+
+//
+// ScriptCounter
+//
+
+/// The scripts that are told apart, characters of other scripts (and the ones shared by all,
+/// like digits, punctuation and spaces) are ignored.
+/// Chinese, Japanese and Korean text routinely mixes scripts, so they are all counted as `CJK`.
+#[cfg(feature = "script_detection")]
+const RAW_SCRIPTS: [(&str, &str); 10] = [
+    ("Latin", r"\p{Latin}"),
+    ("Greek", r"\p{Greek}"),
+    ("Cyrillic", r"\p{Cyrillic}"),
+    ("Armenian", r"\p{Armenian}"),
+    ("Hebrew", r"\p{Hebrew}"),
+    ("Arabic", r"\p{Arabic}"),
+    ("Devanagari", r"\p{Devanagari}"),
+    ("Thai", r"\p{Thai}"),
+    ("Georgian", r"\p{Georgian}"),
+    (
+        "CJK",
+        r"[\p{Han}\p{Hiragana}\p{Katakana}\p{Hangul}\p{Bopomofo}]",
+    ),
+];
+/// A single regex matching runs of any of the scripts, so each value is only scanned once.
+#[cfg(feature = "script_detection")]
+static SCRIPTS: Lazy<Regex> = Lazy::new(|| {
+    let runs: Vec<String> = RAW_SCRIPTS.iter().map(|(_, p)| format!("{}+", p)).collect();
+    Regex::new(&runs.join("|")).unwrap()
+});
+/// The scripts on their own, to tell which one a run (found with [SCRIPTS]) belongs to.
+#[cfg(feature = "script_detection")]
+static SCRIPT_CLASSES: Lazy<Vec<Regex>> = Lazy::new(|| {
+    RAW_SCRIPTS
+        .iter()
+        .map(|(_, p)| Regex::new(&format!("^{}", p)).unwrap())
+        .collect()
+});
+/// Counts how many characters of each Unicode script (like `Latin`, `Cyrillic` or `CJK`) have
+/// been found across all values.
+#[cfg(feature = "script_detection")]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ScriptCounter(pub BTreeMap<String, usize>);
+#[cfg(feature = "script_detection")]
+impl ScriptCounter {
+    /// Returns `true` if no character of a known script has been found.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Returns the script with the most characters, if any.
+    pub fn dominant(&self) -> Option<&str> {
+        self.0
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(script, _)| script.as_str())
+    }
+    /// Returns `true` if characters of more than one script have been found.
+    pub fn is_mixed(&self) -> bool {
+        self.0.len() > 1
+    }
+}
+#[cfg(feature = "script_detection")]
+impl Aggregate<str> for ScriptCounter {
+    fn aggregate(&mut self, value: &'_ str) {
+        // Finding the runs and their script does not allocate, unlike capture groups.
+        for run in SCRIPTS.find_iter(value) {
+            let script = (SCRIPT_CLASSES.iter().zip(RAW_SCRIPTS.iter()))
+                .find(|(class, _)| class.is_match(run.as_str()))
+                .map(|(_, (script, _))| *script);
+            if let Some(script) = script {
+                let count = run.as_str().chars().count();
+                match self.0.get_mut(script) {
+                    Some(total) => *total += count,
+                    None => {
+                        self.0.insert(script.to_string(), count);
+                    }
+                }
+            }
+        }
+    }
+}
+#[cfg(feature = "script_detection")]
+impl Coalesce for ScriptCounter {
+    fn coalesce(&mut self, other: Self)
+    where
+        Self: Sized,
+    {
+        for (script, count) in other.0 {
+            *self.0.entry(script).or_insert(0) += count;
+        }
+    }
+}
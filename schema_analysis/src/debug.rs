@@ -0,0 +1,461 @@
+//! A size-bounded alternative to the derived [Debug] of [Schema](crate::Schema), see
+//! [DebugCompact].
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Debug, Display},
+};
+
+use serde::{ser, Serialize};
+
+/// Collections of values (like samples) keep at most this many entries.
+const MAX_ENTRIES: usize = 3;
+/// Strings keep at most this many characters.
+const MAX_CHARS: usize = 32;
+
+/**
+A [Debug] wrapper that prints a compact view of any serializable value, mainly meant for
+[Schema](crate::Schema)s (see [Schema::debug_compact](crate::Schema::debug_compact)).
+
+The derived [Debug] prints everything, which quickly becomes a wall of text for large schemas.
+This view instead:
+- truncates collections of values, like samples, to a few entries followed by an ellipsis,
+- truncates long strings,
+- omits fields that are empty or have their default value, like `false` flags and zero counts,
+- prints [Schema](crate::Schema) variants by name, like `Integer { count: 3, .. }`.
+
+Collections holding structures, like the fields of a struct or the variants of a union, are
+printed in full so that the shape of the schema is never hidden.
+
+The view goes through the [Serialize] implementation, so it shows what serialization would
+keep (custom [Aggregators](crate::Aggregators) are not shown), and it is lossy by design: use
+the derived [Debug] or serialization when all the information is needed.
+
+```
+# use schema_analysis::{DebugCompact, InferredSchema};
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let inferred: InferredSchema = serde_json::from_str(r#"[1, 2, 3, 4, 5, 6]"#)?;
+
+let compact = format!("{:?}", inferred.schema.debug_compact());
+assert!(compact.starts_with("Sequence {"));
+assert!(compact.contains("values: [1, 2, 3, … 3 more]"));
+assert!(compact.len() < format!("{:?}", inferred.schema).len());
+# Ok(())
+# }
+```
+*/
+pub struct DebugCompact<'a, T: ?Sized>(pub &'a T);
+
+impl<T: Serialize + ?Sized> Debug for DebugCompact<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.serialize(NodeSerializer) {
+            Ok(node) => Debug::fmt(&node, f),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+/// An intermediate representation of the serialized value, so that empty fields can be found
+/// and collections truncated before printing.
+enum Node {
+    Empty,
+    Bool(bool),
+    Integer(i128),
+    /// A [Counter](crate::context::Counter) that has not counted anything, unlike a zero
+    /// [Integer](Node::Integer) (like a minimum) it carries no information.
+    ZeroCount,
+    Float(f64),
+    Str(String),
+    UnitVariant(&'static str),
+    Seq(Vec<Node>),
+    Map(Vec<(Node, Node)>),
+    Struct(String, Vec<(String, Node)>),
+}
+impl Node {
+    fn is_empty(&self) -> bool {
+        match self {
+            Node::Empty | Node::Bool(false) | Node::ZeroCount => true,
+            Node::Str(value) => value.is_empty(),
+            Node::Seq(entries) => entries.is_empty(),
+            Node::Map(entries) => entries.is_empty(),
+            Node::Struct(_, fields) => fields.is_empty(),
+            Node::Bool(true) | Node::Integer(_) | Node::Float(_) | Node::UnitVariant(_) => false,
+        }
+    }
+    /// Collections of structures, like fields and variants, are never truncated.
+    fn is_structure(&self) -> bool {
+        matches!(self, Node::Struct(..))
+    }
+    /// Builds a struct, dropping the empty fields.
+    /// The `type` tag of internally tagged enums (like [Schema](crate::Schema)) becomes the name.
+    fn new_struct(name: &str, fields: Vec<(String, Node)>) -> Node {
+        let mut name = name.to_string();
+        let mut kept = Vec::with_capacity(fields.len());
+        for (key, value) in fields {
+            match value {
+                Node::Str(tag) if key == "type" => name = tag,
+                value if value.is_empty() => {}
+                value => kept.push((key, value)),
+            }
+        }
+        Node::Struct(name, kept)
+    }
+}
+impl Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Empty => f.write_str("None"),
+            Node::Bool(value) => Debug::fmt(value, f),
+            Node::Integer(value) => Debug::fmt(value, f),
+            Node::ZeroCount => f.write_str("0"),
+            Node::Float(value) => Debug::fmt(value, f),
+            Node::Str(value) if value.chars().count() > MAX_CHARS => {
+                let truncated: String = value.chars().take(MAX_CHARS).collect();
+                write!(f, "{:?}…", truncated)
+            }
+            Node::Str(value) => Debug::fmt(value, f),
+            Node::UnitVariant(name) => f.write_str(name),
+            Node::Seq(entries) => {
+                let shown = shown_entries(entries.len(), !entries.iter().any(Node::is_structure));
+                let mut list = f.debug_list();
+                list.entries(&entries[..shown]);
+                if shown < entries.len() {
+                    list.entry(&Ellipsis(entries.len() - shown));
+                }
+                list.finish()
+            }
+            Node::Map(entries) => {
+                let has_structures = entries.iter().any(|(_, value)| value.is_structure());
+                let shown = shown_entries(entries.len(), !has_structures);
+                let mut map = f.debug_map();
+                map.entries(entries[..shown].iter().map(|(k, v)| (k, v)));
+                if shown < entries.len() {
+                    map.entry(&Ellipsis(entries.len() - shown), &Ellipsis(0));
+                }
+                map.finish()
+            }
+            Node::Struct(name, fields) => {
+                let mut debug = f.debug_struct(name);
+                for (key, value) in fields {
+                    debug.field(key, value);
+                }
+                debug.finish()
+            }
+        }
+    }
+}
+fn shown_entries(len: usize, truncate: bool) -> usize {
+    if truncate {
+        len.min(MAX_ENTRIES)
+    } else {
+        len
+    }
+}
+
+/// Stands for the entries that were left out, or for their values if `0`.
+struct Ellipsis(usize);
+impl Debug for Ellipsis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("…"),
+            hidden => write!(f, "… {} more", hidden),
+        }
+    }
+}
+
+//
+// Serializer
+//
+
+#[derive(Debug)]
+struct Error(String);
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl std::error::Error for Error {}
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+struct NodeSerializer;
+
+impl ser::Serializer for NodeSerializer {
+    type Ok = Node;
+    type Error = Error;
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = SeqBuilder;
+    type SerializeTupleStruct = SeqBuilder;
+    type SerializeTupleVariant = VariantBuilder<SeqBuilder>;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = StructBuilder;
+    type SerializeStructVariant = VariantBuilder<StructBuilder>;
+
+    fn serialize_bool(self, v: bool) -> Result<Node, Error> {
+        Ok(Node::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_i128(self, v: i128) -> Result<Node, Error> {
+        Ok(Node::Integer(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Node, Error> {
+        Ok(Node::Integer(v.into()))
+    }
+    fn serialize_u128(self, v: u128) -> Result<Node, Error> {
+        match i128::try_from(v) {
+            Ok(v) => Ok(Node::Integer(v)),
+            Err(_) => Ok(Node::Str(v.to_string())),
+        }
+    }
+    fn serialize_f32(self, v: f32) -> Result<Node, Error> {
+        Ok(Node::Float(v.into()))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Node, Error> {
+        Ok(Node::Float(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Node, Error> {
+        Ok(Node::Str(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<Node, Error> {
+        Ok(Node::Str(v.to_string()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Node, Error> {
+        Ok(Node::Seq(
+            v.iter().map(|b| Node::Integer((*b).into())).collect(),
+        ))
+    }
+    fn serialize_none(self) -> Result<Node, Error> {
+        Ok(Node::Empty)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Node, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Node, Error> {
+        Ok(Node::Empty)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Node, Error> {
+        Ok(Node::Empty)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Node, Error> {
+        Ok(Node::UnitVariant(variant))
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Node, Error> {
+        // Like Counter, these are just a wrapper.
+        match value.serialize(self)? {
+            Node::Integer(0) if name == "Counter" => Ok(Node::ZeroCount),
+            node => Ok(node),
+        }
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Node, Error> {
+        Ok(Node::new_struct(
+            variant,
+            vec![("0".to_string(), value.serialize(self)?)],
+        ))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder, Error> {
+        Ok(SeqBuilder(Vec::with_capacity(len.unwrap_or(0))))
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SeqBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantBuilder<SeqBuilder>, Error> {
+        Ok(VariantBuilder(variant, self.serialize_seq(Some(len))?))
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<MapBuilder, Error> {
+        Ok(MapBuilder {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<StructBuilder, Error> {
+        Ok(StructBuilder(name, Vec::with_capacity(len)))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantBuilder<StructBuilder>, Error> {
+        Ok(VariantBuilder(
+            variant,
+            self.serialize_struct(variant, len)?,
+        ))
+    }
+}
+
+struct SeqBuilder(Vec<Node>);
+impl SeqBuilder {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.push(value.serialize(NodeSerializer)?);
+        Ok(())
+    }
+}
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Node;
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::Seq(self.0))
+    }
+}
+impl ser::SerializeTuple for SeqBuilder {
+    type Ok = Node;
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::Seq(self.0))
+    }
+}
+impl ser::SerializeTupleStruct for SeqBuilder {
+    type Ok = Node;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::Seq(self.0))
+    }
+}
+
+/// Maps that are really structs (like those with flattened fields or an internal tag) are
+/// printed as structs.
+struct MapBuilder {
+    entries: Vec<(Node, Node)>,
+    key: Option<Node>,
+}
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Node;
+    type Error = Error;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(NodeSerializer)?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error("value serialized before its key".to_string()))?;
+        self.entries.push((key, value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+    fn end(self) -> Result<Node, Error> {
+        let is_tagged = matches!(
+            self.entries.first(),
+            Some((Node::Str(key), Node::Str(_))) if key == "type"
+        );
+        if !is_tagged
+            || !self
+                .entries
+                .iter()
+                .all(|(key, _)| matches!(key, Node::Str(_)))
+        {
+            return Ok(Node::Map(self.entries));
+        }
+        let fields = (self.entries.into_iter())
+            .filter_map(|(key, value)| match key {
+                Node::Str(key) => Some((key, value)),
+                _ => None,
+            })
+            .collect();
+        Ok(Node::new_struct("", fields))
+    }
+}
+
+struct StructBuilder(&'static str, Vec<(String, Node)>);
+impl ser::SerializeStruct for StructBuilder {
+    type Ok = Node;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.1
+            .push((key.to_string(), value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::new_struct(self.0, self.1))
+    }
+}
+
+struct VariantBuilder<B>(&'static str, B);
+impl ser::SerializeTupleVariant for VariantBuilder<SeqBuilder> {
+    type Ok = Node;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.1.push(value)
+    }
+    fn end(self) -> Result<Node, Error> {
+        let fields = (self.1 .0.into_iter().enumerate())
+            .map(|(i, value)| (i.to_string(), value))
+            .collect();
+        Ok(Node::new_struct(self.0, fields))
+    }
+}
+impl ser::SerializeStructVariant for VariantBuilder<StructBuilder> {
+    type Ok = Node;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(&mut self.1, key, value)
+    }
+    fn end(self) -> Result<Node, Error> {
+        ser::SerializeStruct::end(self.1)
+    }
+}
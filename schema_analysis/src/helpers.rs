@@ -1,18 +1,97 @@
 //! A module for any useful helper functions.
 
+#[cfg(feature = "serde_json")]
+use crate::{Coalesce, Schema};
+
+/// Merges two [Schema]s stored as JSON, like the ones inferred on separate shards of a dataset,
+/// without running the analysis again.
+///
+/// Both schemas are deserialized, [coalesced](Coalesce) and serialized back.
+/// Custom [Aggregators](crate::Aggregators) are never stored, so only the default contexts
+/// take part in the merge.
+///
+/// Returns an error if either schema is not [normalized](Schema::is_normalized) (like a
+/// union nested in another), as those can't be coalesced.
+///
+/// ```
+/// # use schema_analysis::{helpers, InferredSchema};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let a: InferredSchema = serde_json::from_str("[1, 2]")?;
+/// let b: InferredSchema = serde_json::from_str(r#"["x"]"#)?;
+///
+/// let merged = helpers::merge_schema_json(
+///     &serde_json::to_string(&a.schema)?,
+///     &serde_json::to_string(&b.schema)?,
+/// )?;
+/// assert!(merged.contains("Union"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "serde_json")]
+pub fn merge_schema_json(a: &str, b: &str) -> Result<String, serde_json::Error> {
+    fn parse(json: &str) -> Result<Schema, serde_json::Error> {
+        let schema: Schema = serde_json::from_str(json)?;
+        if !schema.is_normalized() {
+            return Err(serde::de::Error::custom(
+                "the schema is not normalized, see Schema::is_normalized",
+            ));
+        }
+        Ok(schema)
+    }
+    let mut schema = parse(a)?;
+    schema.coalesce(parse(b)?);
+    serde_json::to_string(&schema)
+}
+
+#[cfg(feature = "yaml")]
+pub mod yaml {
+    //! A module for yaml helper functions.
+    //! Check individual functions for details.
+
+    use serde::de::{DeserializeSeed, Error};
+
+    use crate::InferredSchema;
+
+    /// Yaml files may hold multiple documents separated by `---`.
+    ///
+    /// This function runs the analysis on all documents in the stream and coalesces their schemas.
+    /// A stream without separators is treated as a single document, and empty documents are
+    /// treated as null (as per the yaml spec).
+    pub fn infer_multi_document(input: &str) -> Result<InferredSchema, serde_yaml::Error> {
+        let mut documents = serde_yaml::Deserializer::from_str(input);
+
+        let mut inferred: InferredSchema = match documents.next() {
+            Some(first) => serde::Deserialize::deserialize(first)?,
+            None => return Err(serde_yaml::Error::custom("no yaml documents found")),
+        };
+
+        for document in documents {
+            let () = (&mut inferred).deserialize(document)?;
+        }
+
+        Ok(inferred)
+    }
+}
+
 pub mod xml {
     //! A module for xml cleaning helper functions.
     //! Check individual functions for details.
 
-    use crate::{Field, Schema};
+    use crate::{
+        context::SequenceContext,
+        traits::{walk_field, walk_schema},
+        Coalesce, Field, Schema, SchemaVisitorMut,
+    };
 
     /// A wrapper function that applies all XML cleaning transformations.
     ///
     /// [clean_solitary_nested_values]
+    /// + [turn_positional_records_into_sequences]
     /// + [turn_duplicates_into_sequence_field]
     /// + [clean_empty_structs_in_field]
     pub fn cleanup_xml_schema(schema: &mut Schema) {
         clean_solitary_nested_values(schema);
+        turn_positional_records_into_sequences(schema);
         turn_duplicates_into_sequence_field(schema);
         clean_empty_structs_in_field(schema);
     }
@@ -23,35 +102,74 @@ pub mod xml {
     /// This function simply finds [Schema::Struct]s with a single field named `$value` and
     /// replaces them with the schema inside the `$value` field.
     pub fn clean_solitary_nested_values(schema: &mut Schema) {
-        use Schema::*;
-        match schema {
-            Null(_) | Boolean(_) | Integer(_) | Float(_) | String(_) | Bytes(_) => {}
-            Sequence { field, .. } => match &mut field.schema {
-                Some(schema) => clean_solitary_nested_values(schema),
-                None => {}
-            },
-            Struct { fields, .. } => {
-                // If the only field is $value, then we 'bring it up'.
-                if fields.len() == 1 && fields.contains_key("$value") {
-                    if let Some(Field {
-                        schema: Some(inner_schema),
-                        ..
-                    }) = fields.remove("$value")
+        SolitaryNestedValues.visit_schema(schema);
+
+        struct SolitaryNestedValues;
+        impl SchemaVisitorMut for SolitaryNestedValues {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                match schema {
+                    // If the only field is $value, then we 'bring it up'.
+                    Schema::Struct { fields, .. }
+                        if fields.len() == 1 && fields.contains_key("$value") =>
                     {
-                        *schema = inner_schema;
-                    }
-                } else {
-                    for (_, field) in fields.iter_mut() {
-                        match &mut field.schema {
-                            Some(schema) => clean_solitary_nested_values(schema),
-                            None => {}
+                        if let Some(Field {
+                            schema: Some(inner_schema),
+                            ..
+                        }) = fields.remove("$value")
+                        {
+                            *schema = inner_schema;
                         }
                     }
+                    schema => walk_schema(self, schema),
                 }
             }
-            Union { variants } => {
-                for value in variants {
-                    clean_solitary_nested_values(value);
+        }
+    }
+
+    /// The tag names commonly given to the children of an element when only their position
+    /// matters, see [turn_positional_records_into_sequences].
+    pub const GENERIC_TAGS: &[&str] = &["item", "entry", "li", "value"];
+
+    /// XML children always have a name, so ordered records (like
+    /// `<row><item>1</item><item>a</item></row>`) repeat a generic tag instead.
+    ///
+    /// This function finds [Schema::Struct]s whose fields are all named after one of the
+    /// [GENERIC_TAGS], with at least one of them repeated, and replaces them with a
+    /// [Schema::Sequence] of the values of those fields.
+    ///
+    /// It relies on the duplicate annotations, so it needs to run before
+    /// [turn_duplicates_into_sequence_field].
+    pub fn turn_positional_records_into_sequences(schema: &mut Schema) {
+        PositionalRecords.visit_schema(schema);
+
+        struct PositionalRecords;
+        impl SchemaVisitorMut for PositionalRecords {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                walk_schema(self, schema);
+                match schema {
+                    Schema::Struct { fields, context }
+                        if !fields.is_empty()
+                            && fields.keys().all(|k| GENERIC_TAGS.contains(&k.as_ref()))
+                            && fields.values().any(|f| f.status.may_be_duplicate) =>
+                    {
+                        let mut values = std::mem::take(fields).into_values();
+                        let mut field = values.next().unwrap();
+                        for other in values {
+                            field.coalesce(other);
+                        }
+                        field.status.may_be_duplicate = false;
+
+                        *schema = Schema::Sequence {
+                            field: Box::new(field),
+                            // Each child of a record is one of its items.
+                            context: SequenceContext {
+                                count: context.count,
+                                length: context.key_count.clone(),
+                                ..Default::default()
+                            },
+                        };
+                    }
+                    _ => {}
                 }
             }
         }
@@ -63,24 +181,24 @@ pub mod xml {
     /// To help with this the inference software annotates duplicate fields, and this function
     /// takes the schema in that field and places it into a [Schema::Sequence].
     pub fn turn_duplicates_into_sequence_field(schema: &mut Schema) {
-        clean_field_recursively(schema, _inner_field_cleaning);
+        DuplicatesIntoSequences.visit_schema(schema);
 
-        fn _inner_field_cleaning(field: &mut Field) {
-            match &mut field.schema {
-                Some(schema) => clean_field_recursively(schema, _inner_field_cleaning),
-                None => {}
-            }
-            // In xml, sequences are simply registered as a field appearing more than once,
-            // the parser records this but now we need to move the duplicate field into its own sequence.
-            if field.status.may_be_duplicate {
-                *field = Field {
-                    status: field.status.clone(),
-                    schema: Some(Schema::Sequence {
-                        field: Box::new(field.clone()),
-                        context: Default::default(),
-                    }),
-                };
-                field.status.may_be_duplicate = false;
+        struct DuplicatesIntoSequences;
+        impl SchemaVisitorMut for DuplicatesIntoSequences {
+            fn visit_field(&mut self, field: &mut Field) {
+                walk_field(self, field);
+                // In xml, sequences are simply registered as a field appearing more than once,
+                // the parser records this but now we need to move the duplicate field into its own sequence.
+                if field.status.may_be_duplicate {
+                    *field = Field {
+                        status: field.status.clone(),
+                        schema: Some(Schema::Sequence {
+                            field: Box::new(field.clone()),
+                            context: Default::default(),
+                        }),
+                    };
+                    field.status.may_be_duplicate = false;
+                }
             }
         }
     }
@@ -90,34 +208,473 @@ pub mod xml {
     /// This function replaces those fields with empty [Schema::Struct] with fields of
     /// unknown schema.
     pub fn clean_empty_structs_in_field(schema: &mut Schema) {
-        clean_field_recursively(schema, _inner_field_cleaning);
+        EmptyStructsInField.visit_schema(schema);
 
-        fn _inner_field_cleaning(field: &mut Field) {
-            match &mut field.schema {
-                Some(Schema::Struct { fields, .. }) if fields.is_empty() => {
-                    field.schema = None;
+        struct EmptyStructsInField;
+        impl SchemaVisitorMut for EmptyStructsInField {
+            fn visit_field(&mut self, field: &mut Field) {
+                match &mut field.schema {
+                    Some(Schema::Struct { fields, .. }) if fields.is_empty() => {
+                        field.schema = None;
+                    }
+                    _ => walk_field(self, field),
                 }
-                Some(schema) => clean_field_recursively(schema, _inner_field_cleaning),
-                None => {}
             }
         }
     }
+}
+
+pub mod toml {
+    //! A module for toml cleaning helper functions.
+    //! Check individual functions for details.
+
+    use crate::{traits::walk_schema, Coalesce, Field, Schema, SchemaVisitorMut};
 
-    fn clean_field_recursively(schema: &mut Schema, clean_field: fn(&mut Field)) {
-        use Schema::*;
-        match schema {
-            Null(_) | Boolean(_) | Integer(_) | Float(_) | String(_) | Bytes(_) => {}
-            Schema::Sequence { field, .. } => clean_field(field),
-            Schema::Struct { fields, .. } => {
-                for (_, field) in fields.iter_mut() {
-                    clean_field(field);
+    /// The key under which the `toml` crate passes datetimes to serde.
+    const DATETIME_FIELD: &str = "$__toml_private_datetime";
+
+    /// A wrapper function that applies all TOML cleaning transformations.
+    ///
+    /// [clean_datetimes]
+    pub fn cleanup_toml_schema(schema: &mut Schema) {
+        clean_datetimes(schema);
+    }
+
+    /// TOML has native offset date-times, local date-times, local dates and local times, which
+    /// the `toml` crate passes to serde as a struct with a single private field holding the
+    /// value as a string.
+    ///
+    /// This function replaces those structs with the [Schema::String] inside them and sets its
+    /// `is_datetime` flag. Unions left with two strings (like a field that is sometimes a
+    /// datetime and sometimes a plain string) are merged back.
+    pub fn clean_datetimes(schema: &mut Schema) {
+        Datetimes.visit_schema(schema);
+
+        struct Datetimes;
+        impl SchemaVisitorMut for Datetimes {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                walk_schema(self, schema);
+                match schema {
+                    Schema::Struct { fields, .. }
+                        if fields.len() == 1 && fields.contains_key(DATETIME_FIELD) =>
+                    {
+                        if let Some(Field {
+                            schema: Some(Schema::String(mut context)),
+                            ..
+                        }) = fields.remove(DATETIME_FIELD)
+                        {
+                            context.is_datetime = true;
+                            *schema = Schema::String(context);
+                        }
+                    }
+                    Schema::Union { variants }
+                        if variants
+                            .iter()
+                            .filter(|variant| matches!(variant, Schema::String(_)))
+                            .count()
+                            > 1 =>
+                    {
+                        let mut variants = std::mem::take(variants).into_iter();
+                        if let Some(first) = variants.next() {
+                            *schema =
+                                variants.fold(first, |merged, variant| merged.coalesced(variant));
+                        }
+                    }
+                    _ => {}
                 }
             }
-            Schema::Union { variants } => {
-                for value in variants {
-                    clean_field_recursively(value, clean_field);
+        }
+    }
+}
+
+#[cfg(feature = "detect_format")]
+pub mod detect_format {
+    //! A module to analyze inputs whose format is not known in advance.
+    //! Check individual functions for details.
+
+    use std::fmt;
+
+    use crate::InferredSchema;
+
+    /// The formats [detect_and_infer] can recognize.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum DetectedFormat {
+        /// [JSON](https://www.json.org/).
+        Json,
+        /// [YAML](https://yaml.org/), possibly with multiple documents.
+        Yaml,
+        /// [TOML](https://toml.io/).
+        Toml,
+        /// [CBOR](https://cbor.io/).
+        Cbor,
+        /// [BSON](https://bsonspec.org/).
+        Bson,
+        /// [XML](https://www.w3.org/XML/).
+        Xml,
+    }
+    impl fmt::Display for DetectedFormat {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let s = match self {
+                DetectedFormat::Json => "json",
+                DetectedFormat::Yaml => "yaml",
+                DetectedFormat::Toml => "toml",
+                DetectedFormat::Cbor => "cbor",
+                DetectedFormat::Bson => "bson",
+                DetectedFormat::Xml => "xml",
+            };
+            f.write_str(s)
+        }
+    }
+
+    /// Returned by [detect_and_infer] when no format could parse the input, it holds the error
+    /// of each format that was tried.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct DetectionError {
+        /// The formats that were tried in order, and why they failed.
+        pub attempts: Vec<(DetectedFormat, String)>,
+    }
+    impl fmt::Display for DetectionError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if self.attempts.is_empty() {
+                return f.write_str("the input is empty");
+            }
+            f.write_str("could not detect the format of the input")?;
+            for (format, error) in &self.attempts {
+                write!(f, "\n  {}: {}", format, error)?;
+            }
+            Ok(())
+        }
+    }
+    impl std::error::Error for DetectionError {}
+
+    /**
+    Tries the supported formats in order, and returns the schema inferred by the first one
+    that can parse the whole input, along with the format that was used.
+
+    Since some formats accept almost anything (any text is a valid YAML string), the formats
+    are tried in this order:
+    1. BSON, only if the input starts with its own length, as all BSON documents do.
+    2. JSON.
+    3. XML, only if the input starts with `<`.
+    4. TOML.
+    5. YAML, after JSON (which it is a superset of) and TOML (as `a = 1` is a YAML string).
+    6. CBOR, last as it is a binary format with no header to recognize it by.
+
+    The text formats are skipped if the input is not valid UTF-8.
+    The schemas are returned as found, so for example XML and TOML inputs might benefit from
+    the [xml](super::xml) and [toml](super::toml) cleanup helpers.
+
+    ```
+    # use schema_analysis::{helpers::detect_format::{detect_and_infer, DetectedFormat}, Schema};
+    #
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (format, inferred) = detect_and_infer(b"name = \"schema\"\nversion = 1\n")?;
+
+    assert_eq!(format, DetectedFormat::Toml);
+    assert!(matches!(inferred.schema, Schema::Struct { .. }));
+    #
+    # Ok(())
+    # }
+    ```
+    */
+    pub fn detect_and_infer(
+        data: &[u8],
+    ) -> Result<(DetectedFormat, InferredSchema), DetectionError> {
+        let mut attempts = Vec::new();
+        let text = std::str::from_utf8(data).ok();
+        if data.iter().all(u8::is_ascii_whitespace) {
+            return Err(DetectionError { attempts });
+        }
+
+        macro_rules! attempt {
+            ($format:expr, $result:expr) => {
+                match $result {
+                    Ok(inferred) => return Ok(($format, inferred)),
+                    Err(error) => attempts.push(($format, error.to_string())),
+                }
+            };
+        }
+
+        if has_bson_header(data) {
+            attempt!(DetectedFormat::Bson, bson::from_slice(data));
+        }
+        if let Some(text) = text {
+            attempt!(DetectedFormat::Json, serde_json::from_str(text));
+            if text.trim_start().starts_with('<') {
+                attempt!(DetectedFormat::Xml, quick_xml::de::from_str(text));
+            }
+            attempt!(DetectedFormat::Toml, ::toml::from_str(text));
+            attempt!(
+                DetectedFormat::Yaml,
+                super::yaml::infer_multi_document(text)
+            );
+        }
+        attempt!(DetectedFormat::Cbor, serde_cbor::from_slice(data));
+
+        Err(DetectionError { attempts })
+    }
+
+    /// BSON documents start with their total length (as a little-endian `i32`) and end with a
+    /// null byte.
+    fn has_bson_header(data: &[u8]) -> bool {
+        match data {
+            [a, b, c, d, .., 0] => i32::from_le_bytes([*a, *b, *c, *d]) as usize == data.len(),
+            _ => false,
+        }
+    }
+}
+
+pub mod jsonpath_filter {
+    //! A module to analyze only a part of a document, selected by a JSONPath-like [Selector].
+    //! Check individual functions for details.
+
+    use std::{fmt, str::FromStr};
+
+    use serde::de::{
+        DeserializeSeed, Deserializer, Error, IgnoredAny, MapAccess, SeqAccess, Visitor,
+    };
+
+    use crate::{
+        analysis::{analyze_document, extend_with_document, KeySeed, DEFAULT_CONTEXT},
+        Context, InferredSchema, InferredSchemaWithContext, Schema,
+    };
+
+    /// A simple subset of [JSONPath](https://www.rfc-editor.org/rfc/rfc9535): dotted keys and
+    /// `[*]` for all the elements of a sequence, like `$.results[*].address`.
+    ///
+    /// The leading `$` is optional.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+    pub struct Selector(pub Vec<Segment>);
+
+    /// A step of a [Selector].
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum Segment {
+        /// The value under this key of a struct (`.key`).
+        Key(String),
+        /// Every element of a sequence (`[*]`).
+        AnyElement,
+    }
+
+    /// Returned when a [Selector] can't be parsed.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct InvalidSelector {
+        /// The selector that was being parsed.
+        pub selector: String,
+        /// What went wrong.
+        pub reason: &'static str,
+    }
+    impl fmt::Display for InvalidSelector {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "invalid selector `{}`: {}", self.selector, self.reason)
+        }
+    }
+    impl std::error::Error for InvalidSelector {}
+
+    impl FromStr for Selector {
+        type Err = InvalidSelector;
+
+        fn from_str(selector: &str) -> Result<Self, Self::Err> {
+            let invalid = |reason| InvalidSelector {
+                selector: selector.to_string(),
+                reason,
+            };
+
+            // Without the leading `$`, a selector may start with a bare key, like `results[*]`.
+            let (mut rest, mut expect_key) = match selector.strip_prefix('$') {
+                Some(rest) => (rest, false),
+                None => (selector, !selector.is_empty() && !selector.starts_with('[')),
+            };
+
+            let mut segments = Vec::new();
+            while expect_key || !rest.is_empty() {
+                if let Some(after) = rest.strip_prefix(".") {
+                    rest = after;
+                    expect_key = true;
+                }
+                if expect_key {
+                    let end = rest.find(['.', '['].as_ref()).unwrap_or(rest.len());
+                    if end == 0 {
+                        return Err(invalid("empty key"));
+                    }
+                    segments.push(Segment::Key(rest[..end].to_string()));
+                    rest = &rest[end..];
+                    expect_key = false;
+                } else if let Some(after) = rest.strip_prefix("[*]") {
+                    segments.push(Segment::AnyElement);
+                    rest = after;
+                } else {
+                    return Err(invalid("expected `.key` or `[*]`"));
+                }
+            }
+
+            Ok(Selector(segments))
+        }
+    }
+
+    /**
+    Runs the analysis only on the values selected by `selector`, all other values are skipped
+    without being analyzed.
+
+    The first selected value creates the schema and the others extend it, so with `[*]` the
+    result is the schema of the elements.
+    Returns `None` if nothing was selected.
+
+    ```
+    # use schema_analysis::{helpers::jsonpath_filter::{infer_selected, Selector}, Schema};
+    #
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let data = r#"{ "meta": { "page": 1 }, "results": [{ "id": 1 }, { "id": 2 }] }"#;
+    let selector: Selector = "$.results[*].id".parse()?;
+
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let inferred = infer_selected(&selector, &mut deserializer)?.unwrap();
+
+    if let Schema::Integer(context) = inferred.schema {
+        assert_eq!(context.count.0, 2);
+    }
+    #
+    # Ok(())
+    # }
+    ```
+    */
+    pub fn infer_selected<'de, D: Deserializer<'de>>(
+        selector: &Selector,
+        deserializer: D,
+    ) -> Result<Option<InferredSchema>, D::Error> {
+        let schema = select(selector, &DEFAULT_CONTEXT, deserializer)?;
+        Ok(schema.map(|schema| InferredSchema { schema }))
+    }
+
+    /// Like [infer_selected], but the selected values are analyzed with the [Context] (and its
+    /// [AnalysisSettings](crate::AnalysisSettings)), like [Context::deserialize_schema].
+    pub fn infer_selected_with_context<'de, D: Deserializer<'de>>(
+        selector: &Selector,
+        context: Context,
+        deserializer: D,
+    ) -> Result<Option<InferredSchemaWithContext>, D::Error> {
+        let schema = select(selector, &context, deserializer)?;
+        Ok(schema.map(|schema| InferredSchemaWithContext { schema, context }))
+    }
+
+    fn select<'de, D: Deserializer<'de>>(
+        selector: &Selector,
+        context: &Context,
+        deserializer: D,
+    ) -> Result<Option<Schema>, D::Error> {
+        let mut schema = None;
+        Select {
+            segments: &selector.0,
+            context,
+            schema: &mut schema,
+        }
+        .deserialize(deserializer)?;
+        Ok(schema)
+    }
+
+    /// Follows the remaining `segments`, and analyzes the value once they are exhausted.
+    struct Select<'a> {
+        segments: &'a [Segment],
+        context: &'a Context,
+        schema: &'a mut Option<Schema>,
+    }
+    impl<'de, 'a> DeserializeSeed<'de> for Select<'a> {
+        type Value = ();
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+            match (self.segments, self.schema) {
+                ([], Some(schema)) => {
+                    extend_with_document(schema, self.context, None, deserializer)
+                }
+                ([], schema) => {
+                    *schema = Some(analyze_document(self.context, None, deserializer)?);
+                    Ok(())
+                }
+                (segments, schema) => deserializer.deserialize_any(Select {
+                    segments,
+                    context: self.context,
+                    schema,
+                }),
+            }
+        }
+    }
+
+    macro_rules! skip {
+        ($method_name:ident, $type:ty) => {
+            fn $method_name<E: Error>(self, _: $type) -> Result<Self::Value, E> {
+                Ok(())
+            }
+        };
+    }
+
+    /// Values that don't match the next segment (like a scalar where a struct was expected) are
+    /// skipped.
+    impl<'de, 'a> Visitor<'de> for Select<'a> {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("anything")
+        }
+
+        skip!(visit_bool, bool);
+        skip!(visit_i64, i64);
+        skip!(visit_i128, i128);
+        skip!(visit_u64, u64);
+        skip!(visit_u128, u128);
+        skip!(visit_f64, f64);
+        skip!(visit_str, &str);
+        skip!(visit_bytes, &[u8]);
+
+        fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+            Ok(())
+        }
+        fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+            Ok(())
+        }
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+        fn visit_newtype_struct<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            match self.segments {
+                [Segment::AnyElement, rest @ ..] => {
+                    while let Some(()) = seq.next_element_seed(Select {
+                        segments: rest,
+                        context: self.context,
+                        schema: &mut *self.schema,
+                    })? {}
+                }
+                _ => while let Some(IgnoredAny) = seq.next_element()? {},
+            }
+            Ok(())
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let (key, rest) = match self.segments {
+                [Segment::Key(key), rest @ ..] => (Some(key), rest),
+                _ => (None, self.segments),
+            };
+            // Keys are borrowed when possible, and stringified like in the analysis.
+            while let Some((found, _)) = map.next_key_seed(KeySeed)? {
+                if Some(found.as_ref()) == key.map(String::as_str) {
+                    map.next_value_seed(Select {
+                        segments: rest,
+                        context: self.context,
+                        schema: &mut *self.schema,
+                    })?;
+                } else {
+                    map.next_value::<IgnoredAny>()?;
                 }
             }
+            Ok(())
         }
     }
 }
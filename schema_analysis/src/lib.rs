@@ -110,6 +110,10 @@ threads) you can use the [Coalesce] trait to merge them after-the-fact.
 You are in luck! You can check out [here](targets) the integrations with
 [json_typegen](json_typegen_shared) and [Schemars](schemars) to convert the analysis into useful
 files like Rust types and json schemas.
+There is also a [C#](targets::csharp) target for System.Text.Json classes.
+[Mermaid](targets::mermaid) class diagrams can be generated with `schema.to_mermaid("Root")`.
+[CUE](targets::cue) definitions can be generated with `schema.to_cue("Root")`.
+[Elm](targets::elm) type aliases and their `Json.Decode` decoders can be generated with `schema.to_elm("Root")`.
 You can also find a demo website [here](https://schema-analysis.com/).
 
 ## How does this work?
@@ -134,6 +138,10 @@ from that info.
 
 <sup>b</sup> ~2.7GB compressed. This one seems like it would be a worst-case scenario because it includes decompression overhead and the files had a section that was formatted text which resulted in crazy schemas. (The json pretty printed schema was almost 0.5GB!)
 
+For reproducible numbers, there are [Criterion](https://github.com/bheisler/criterion.rs) benchmarks
+covering inference on a few representative shapes and the coalescing of many schemas.
+Run them with `cargo bench -p schema_analysis`.
+
 
 [~180MB]: https://github.com/zemirco/sf-city-lots-json/blob/master/citylots.json
 [~650MB]: https://catalog.data.gov/dataset/forestry-planting-spaces
@@ -142,7 +150,9 @@ from that info.
 [~13.3GB]: https://ftp.ncbi.nlm.nih.gov/pub/pmc/oa_bulk/
 */
 
+mod debug;
 mod schema;
+mod view;
 
 use traits::CoalescingAggregator;
 
@@ -152,7 +162,14 @@ pub mod helpers;
 pub mod targets;
 pub mod traits;
 
-pub use analysis::{InferredSchema, InferredSchemaWithContext};
+pub use analysis::{
+    AnalysisSettings, InferredSchema, InferredSchemaWithContext, KeyNormalizer, SchemaAccumulator,
+};
 pub use context::{Aggregators, Context};
-pub use schema::{Field, FieldStatus, Schema};
-pub use traits::{Aggregate, Coalesce, StructuralEq};
+pub use debug::DebugCompact;
+pub use schema::{
+    CoalesceConfig, ConstantValue, Field, FieldStatus, Nullability, Schema, StructKey,
+    StructuralEqOptions, UnionOverflow,
+};
+pub use traits::{Aggregate, Coalesce, CoalesceRef, ContextMapper, SchemaVisitorMut, StructuralEq};
+pub use view::{SchemaView, SerializeMode};
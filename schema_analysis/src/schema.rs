@@ -1,13 +1,20 @@
 use std::collections::BTreeMap;
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{
+        value::MapAccessDeserializer, DeserializeSeed, Error as _, IntoDeserializer, MapAccess,
+        Visitor,
+    },
+    Deserialize, Deserializer, Serialize,
+};
 
 use crate::{
     context::{
-        BooleanContext, BytesContext, MapStructContext, NullContext, NumberContext,
+        BooleanContext, BytesContext, Counter, Fnv1a, MapStructContext, NullContext, NumberContext,
         SequenceContext, StringContext,
     },
-    Coalesce, StructuralEq,
+    traits::{walk_field, walk_schema, ContextMapper},
+    Aggregate, Coalesce, DebugCompact, SchemaView, SchemaVisitorMut, SerializeMode, StructuralEq,
 };
 
 /// This enum is the core output of the analysis, it describes the structure of a document.
@@ -47,7 +54,7 @@ pub enum Schema {
     Struct {
         /// Each [String] key gets assigned a [Field].
         /// Currently we are using a [BTreeMap], but that might change in the future.
-        fields: BTreeMap<String, Field>,
+        fields: BTreeMap<StructKey, Field>,
         /// The context aggregates information about the struct.
         /// It is passed a vector of the key names.
         context: MapStructContext,
@@ -55,6 +62,10 @@ pub enum Schema {
     /// Simply a vector of [Schema]s, it should never contain an Union or multiple instances of the
     /// same variant inside.
     ///
+    /// Each variant keeps its own context, so the number of values of each kind is preserved.
+    /// For example integers and floats found in the same place are not merged, and their counts
+    /// show how inconsistent the data is.
+    ///
     /// Note: content needs to be a struct variant to work with `#[serde(tag = "type")]`.
     Union {
         /// A list of the possible schemas that were found.
@@ -64,18 +75,45 @@ pub enum Schema {
     // Map(..),
 }
 
+/// The type of the keys of a [Struct](Schema::Struct).
+///
+/// By default it is a [String]. With the `intern_keys` feature it is an
+/// [`Arc<str>`](std::sync::Arc), and the same key found at different paths during an analysis
+/// shares a single allocation, which saves memory on wide data with repeated nested structures.
+///
+/// Code that only builds keys with `.into()` and reads them through `&str` works with both.
+#[cfg(not(feature = "intern_keys"))]
+pub type StructKey = String;
+/// The type of the keys of a [Struct](Schema::Struct).
+///
+/// By default it is a [String]. With the `intern_keys` feature it is an
+/// [`Arc<str>`](std::sync::Arc), and the same key found at different paths during an analysis
+/// shares a single allocation, which saves memory on wide data with repeated nested structures.
+///
+/// Code that only builds keys with `.into()` and reads them through `&str` works with both.
+#[cfg(feature = "intern_keys")]
+pub type StructKey = std::sync::Arc<str>;
+
 /// A [Field] is a useful abstraction to record metadata that does not belong or would be unyieldy
 /// to place into the [Schema] and to account for cases in which the existence of a [Field] might be
 /// known, but nothing is known about its shape.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+///
+/// Note: the status and the schema are serialized under two separate keys rather than flattened
+/// together, as flattening the internally tagged [Schema] relies on buffering the whole value,
+/// which is fragile and not supported by formats that are not self-describing.
+///
+/// Schemas stored before this change, with the status and the schema flattened into a single
+/// map (like `{ "type": "Integer", "may_be_null": true, .. }`), are still read correctly, while
+/// unknown keys and unknown schema types are rejected so that no data is silently lost.
+/// The form is picked from the keys as they are read, so a [Field] is not buffered.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct Field {
     /// The status holds information on the the field, like whether it might be null or
     /// missing altogether. Duplicate fields are also recorded.
-    #[serde(flatten)]
     pub status: FieldStatus,
     /// The inner Schema is optional because we might have no information on the shape of the field
     /// (like for an empty array).
-    #[serde(flatten)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub schema: Option<Schema>,
 }
 
@@ -96,13 +134,742 @@ pub struct FieldStatus {
     /// This is useful to spot suspicious data, but also to detect sequences in xml files.
     /// See [here](crate::helpers::xml) for more info.
     pub may_be_duplicate: bool,
+    /// The [Field] has been found to be an empty [Sequence](Schema::Sequence) or
+    /// [Struct](Schema::Struct), like `[]` or `{}`.
+    ///
+    /// This tells apart collections that are sometimes empty from those that always had
+    /// items, while [may_be_missing](FieldStatus::may_be_missing) is about the field itself.
+    /// It is ignored by [StructuralEq].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub may_be_empty_collection: bool,
+    /// The [Field] was found under an ignored path (see
+    /// [AnalysisSettings](crate::analysis::AnalysisSettings)), so its values were skipped
+    /// without being analyzed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_ignored: bool,
+    /// The index of the first document in which the [Field] was found, only recorded when the
+    /// documents are analyzed with an index (see
+    /// [InferredSchema::extend_with_document](crate::InferredSchema::extend_with_document)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<usize>,
+    /// The index of the last document in which the [Field] was found, recorded like
+    /// [first_seen](FieldStatus::first_seen).
+    ///
+    /// Useful to spot fields that have gone stale.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<usize>,
+}
+
+/// A single category summarizing the null/missing flags of a [FieldStatus].
+///
+/// Returned by [FieldStatus::nullability], it exists so that targets do not need to re-derive
+/// the same logic from the individual flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nullability {
+    /// The [Field] has always been present and never null.
+    Required,
+    /// The [Field] may be null, but it has never been missing.
+    Optional,
+    /// The [Field] may be missing, but it has never been null.
+    Sometimes,
+    /// The [Field] may be both null and missing.
+    OptionalAndSometimes,
+    /// No value (normal, null or missing) has ever been recorded for the [Field].
+    NeverSeen,
+}
+
+/// The single value a [Field] has always had, returned by [Field::constant_value].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstantValue<'a> {
+    /// A constant boolean.
+    Boolean(bool),
+    /// A constant integer.
+    Integer(i128),
+    /// A constant float.
+    Float(f64),
+    /// A constant string.
+    String(&'a str),
+}
+
+/// Options to relax the comparison done by [Schema::structural_eq_with].
+///
+/// The [Default] is as strict as [StructuralEq::structural_eq].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct StructuralEqOptions {
+    /// If `true`, a [Sequence](Schema::Sequence) whose element [Field] has no [Schema]
+    /// (like one that was only found empty) matches any other [Sequence](Schema::Sequence),
+    /// regardless of the element [Field]s.
+    pub untyped_sequences_match_any: bool,
+}
+
+/// Policies for [Schema::coalesce_with].
+///
+/// The [Default] behaves like [Coalesce::coalesce].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CoalesceConfig {
+    /// If `true`, an [Integer](Schema::Integer) and a [Float](Schema::Float) found in the same
+    /// place are merged into a single [Float](Schema::Float) instead of being kept as a
+    /// [Union](Schema::Union). The counts and samples of the integers are preserved, but
+    /// their custom aggregators are dropped.
+    pub numeric_widening: bool,
+}
+
+/// The variants dropped from a [Union](Schema::Union) by [Schema::cap_union].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnionOverflow {
+    /// The struct keys leading to the union, sequences add no segment.
+    pub path: Vec<String>,
+    /// The names of the dropped variants (like `"Integer"`), most common first.
+    pub variants: Vec<&'static str>,
+    /// How many values the dropped variants accounted for.
+    pub count: usize,
 }
 
 //
 // Schema implementations
 //
-impl StructuralEq for Schema {
-    fn structural_eq(&self, other: &Self) -> bool {
+impl Schema {
+    /// Some data sources return a single value in some documents and a sequence of those
+    /// values in others (`"tags": "x"` vs `"tags": ["x", "y"]`), resulting in a
+    /// [Union](Schema::Union) of `T` and [Sequence](Schema::Sequence) of `T`.
+    ///
+    /// This function collapses those unions into the [Sequence](Schema::Sequence), merging the
+    /// scalar into the element schema and setting the `may_be_scalar` flag on the
+    /// [SequenceContext].
+    /// Each scalar is counted as a sequence of one element, so the counts still add up to the
+    /// number of values found.
+    /// A scalar is absorbed if it is of the same kind as the sequence elements, or if the
+    /// elements have no known schema.
+    pub fn unify_scalar_and_sequence(&mut self) {
+        UnifyScalarAndSequence.visit_schema(self);
+
+        struct UnifyScalarAndSequence;
+        impl SchemaVisitorMut for UnifyScalarAndSequence {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                walk_schema(self, schema);
+
+                let variants = match schema {
+                    Schema::Union { variants } => variants,
+                    _ => return,
+                };
+                let sequence_index = match variants
+                    .iter()
+                    .position(|v| matches!(v, Schema::Sequence { .. }))
+                {
+                    Some(index) => index,
+                    None => return,
+                };
+                let (mut field, mut context) = match variants.remove(sequence_index) {
+                    Schema::Sequence { field, context } => (field, context),
+                    _ => unreachable!("checked above"),
+                };
+
+                let mut remaining = Vec::new();
+                // Where the sequence goes back among the remaining variants, to keep their order.
+                let mut sequence_position = 0;
+                for (index, variant) in variants.drain(..).enumerate() {
+                    let absorb = !matches!(variant, Schema::Null(_))
+                        && match &field.schema {
+                            Some(element) => {
+                                std::mem::discriminant(element) == std::mem::discriminant(&variant)
+                            }
+                            None => true,
+                        };
+                    if absorb {
+                        // Each scalar now counts as a sequence holding only itself.
+                        let scalars = variant.value_count();
+                        context.count += Counter::from(scalars);
+                        if scalars > 0 {
+                            context.length.aggregate(&1);
+                        }
+                        let mut scalar = Field::with_schema(variant);
+                        scalar.status.may_be_normal = true;
+                        field.coalesce(scalar);
+                        context.may_be_scalar = true;
+                    } else {
+                        if index < sequence_index {
+                            sequence_position += 1;
+                        }
+                        remaining.push(variant);
+                    }
+                }
+
+                let sequence = Schema::Sequence { field, context };
+                *schema = if remaining.is_empty() {
+                    sequence
+                } else {
+                    remaining.insert(sequence_position, sequence);
+                    Schema::Union {
+                        variants: remaining,
+                    }
+                };
+            }
+        }
+    }
+
+    /// Collapses each [Union](Schema::Union) into its most common variant, if that variant
+    /// accounts for at least `min_fraction` (between 0 and 1) of the values found in its place.
+    /// Other unions are left intact.
+    ///
+    /// This is useful for lenient code generation, where a field that is almost always an
+    /// integer is better typed as one. The other variants, and what was recorded about them,
+    /// are discarded.
+    pub fn resolve_dominant(&mut self, min_fraction: f64) {
+        ResolveDominant { min_fraction }.visit_schema(self);
+
+        struct ResolveDominant {
+            min_fraction: f64,
+        }
+        impl SchemaVisitorMut for ResolveDominant {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                walk_schema(self, schema);
+
+                let variants = match schema {
+                    Schema::Union { variants } => variants,
+                    _ => return,
+                };
+                let total = variants.iter().map(Schema::value_count).sum::<usize>();
+                let dominant = variants
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, variant)| variant.value_count());
+                if let Some((index, variant)) = dominant {
+                    if total > 0 && variant.value_count() as f64 / total as f64 >= self.min_fraction
+                    {
+                        *schema = variants.swap_remove(index);
+                    }
+                }
+            }
+            fn visit_field(&mut self, field: &mut Field) {
+                let was_union = matches!(field.schema, Some(Schema::Union { .. }));
+                walk_field(self, field);
+                if !was_union || matches!(field.schema, Some(Schema::Union { .. })) {
+                    return;
+                }
+                // The status described all the variants, only the kept one may be empty now.
+                field.status.may_be_empty_collection = match &field.schema {
+                    Some(Schema::Sequence { context, .. }) => context.length.min == Some(0),
+                    Some(Schema::Struct { context, .. }) => context.key_count.min == Some(0),
+                    _ => false,
+                };
+            }
+        }
+    }
+
+    /// Keeps only the `n` most common variants of each [Union](Schema::Union) (at least one),
+    /// and returns what was dropped from each, in depth-first order.
+    ///
+    /// Unlike [resolve_dominant](Schema::resolve_dominant), the common cases are all preserved
+    /// while the size of the output is bounded. A union left with a single variant is replaced
+    /// by it. Variants with the same count keep their order.
+    pub fn cap_union(&mut self, n: usize) -> Vec<UnionOverflow> {
+        let mut visitor = CapUnion {
+            n: n.max(1),
+            path: Vec::new(),
+            overflows: Vec::new(),
+        };
+        visitor.visit_schema(self);
+        return visitor.overflows;
+
+        struct CapUnion {
+            n: usize,
+            path: Vec<String>,
+            overflows: Vec<UnionOverflow>,
+        }
+        impl SchemaVisitorMut for CapUnion {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                walk_schema(self, schema);
+
+                let variants = match schema {
+                    Schema::Union { variants } if variants.len() > self.n => variants,
+                    _ => return,
+                };
+                let mut ranked: Vec<(usize, Schema)> = variants.drain(..).enumerate().collect();
+                // Stable, so ties keep their order.
+                ranked.sort_by_key(|(_, variant)| std::cmp::Reverse(variant.value_count()));
+                let dropped = ranked.split_off(self.n);
+                self.overflows.push(UnionOverflow {
+                    path: self.path.clone(),
+                    variants: dropped.iter().map(|(_, v)| v.variant_name()).collect(),
+                    count: dropped.iter().map(|(_, v)| v.value_count()).sum(),
+                });
+
+                ranked.sort_by_key(|(index, _)| *index);
+                variants.extend(ranked.into_iter().map(|(_, variant)| variant));
+                if variants.len() == 1 {
+                    *schema = variants.remove(0);
+                }
+            }
+            fn visit_struct(
+                &mut self,
+                fields: &mut BTreeMap<StructKey, Field>,
+                _context: &mut MapStructContext,
+            ) {
+                for (key, field) in fields.iter_mut() {
+                    self.path.push(key.to_string());
+                    self.visit_field(field);
+                    self.path.pop();
+                }
+            }
+        }
+    }
+
+    /// Merges the [Struct](Schema::Struct) variants of the [Union](Schema::Union)s found as the
+    /// elements of [Sequence](Schema::Sequence)s into a single struct, where the fields not
+    /// found in every variant may be missing.
+    /// If no other variant is left, the union is replaced by the merged struct.
+    ///
+    /// The analysis already merges structs found in the same place, but schemas built by hand
+    /// or produced elsewhere often describe arrays of slightly different objects as a union
+    /// of one struct per shape (see [is_normalized](Schema::is_normalized)), which is verbose
+    /// and makes for poor code generation.
+    pub fn merge_sequences_of_unions(&mut self) {
+        MergeSequencesOfUnions.visit_schema(self);
+
+        struct MergeSequencesOfUnions;
+        impl SchemaVisitorMut for MergeSequencesOfUnions {
+            fn visit_sequence(&mut self, field: &mut Field, _context: &mut SequenceContext) {
+                walk_field(self, field);
+
+                let variants = match &mut field.schema {
+                    Some(Schema::Union { variants }) => variants,
+                    _ => return,
+                };
+                let struct_count = variants
+                    .iter()
+                    .filter(|v| matches!(v, Schema::Struct { .. }))
+                    .count();
+                if struct_count < 2 {
+                    return;
+                }
+
+                let mut merged: Option<(usize, BTreeMap<StructKey, Field>, MapStructContext)> =
+                    None;
+                let mut remaining = Vec::with_capacity(variants.len() - struct_count + 1);
+                for variant in variants.drain(..) {
+                    let (fields, context) = match variant {
+                        Schema::Struct { fields, context } => (fields, context),
+                        other => {
+                            remaining.push(other);
+                            continue;
+                        }
+                    };
+                    let (merged_fields, merged_context) = match &mut merged {
+                        Some((_, merged_fields, merged_context)) => (merged_fields, merged_context),
+                        None => {
+                            merged = Some((remaining.len(), fields, context));
+                            continue;
+                        }
+                    };
+                    coalesce_fields(merged_fields, fields);
+                    merged_context.coalesce(context);
+                }
+
+                let (index, fields, context) = merged.expect("at least two structs");
+                remaining.insert(index, Schema::Struct { fields, context });
+                field.schema = Some(if remaining.len() == 1 {
+                    remaining.remove(0)
+                } else {
+                    Schema::Union {
+                        variants: remaining,
+                    }
+                });
+            }
+        }
+    }
+
+    /// Rounds the range of every [Float](Schema::Float) to `significant_digits` significant
+    /// digits, see [MinMax::round_to_significant_digits](crate::context::MinMax::round_to_significant_digits).
+    ///
+    /// Meant to be called right before serializing, so that schemas analyzed, stored and
+    /// merged again in different runs give byte-for-byte the same output.
+    pub fn round_float_ranges(&mut self, significant_digits: usize) {
+        RoundFloatRanges(significant_digits).visit_schema(self);
+
+        struct RoundFloatRanges(usize);
+        impl SchemaVisitorMut for RoundFloatRanges {
+            fn visit_leaf(&mut self, schema: &mut Schema) {
+                if let Schema::Float(context) = schema {
+                    context.min_max.round_to_significant_digits(self.0);
+                }
+            }
+        }
+    }
+
+    /// Recursively rewrites as [Boolean](Schema::Boolean)s the [Integer](Schema::Integer)s
+    /// whose samples are exhaustively `{0, 1}`, for sources that encode booleans as numbers.
+    ///
+    /// Integers that skip samples or found any other value (even just `0` or `1` alone) are
+    /// left alone, to avoid mistaking genuine counts for flags. The samples don't record how
+    /// many times each value was found, only that both were, so `trues` and `falses` of the
+    /// new [BooleanContext] are set to one each, as a lower bound.
+    pub fn promote_int_booleans(&mut self) {
+        PromoteIntBooleans.visit_schema(self);
+
+        struct PromoteIntBooleans;
+        impl SchemaVisitorMut for PromoteIntBooleans {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                walk_schema(self, schema);
+
+                match schema {
+                    Schema::Integer(context)
+                        if !context.skip_samples
+                            && context.samples.is_exaustive()
+                            && context.samples.values().iter().eq([0, 1].iter()) =>
+                    {
+                        *schema = Schema::Boolean(BooleanContext {
+                            count: context.count,
+                            trues: Counter(1),
+                            falses: Counter(1),
+                        });
+                    }
+                    // A promoted integer joins the booleans already in the union.
+                    Schema::Union { variants } => {
+                        let mut booleans = variants
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, v)| matches!(v, Schema::Boolean(_)))
+                            .map(|(index, _)| index);
+                        if let (Some(first), Some(second)) = (booleans.next(), booleans.next()) {
+                            let second = variants.remove(second);
+                            variants[first].coalesce(second);
+                        }
+                        if variants.len() == 1 {
+                            *schema = variants.remove(0);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Like [Coalesce::coalesce], but follows the policies in the [CoalesceConfig].
+    ///
+    /// The policies are applied to the whole merged [Schema], so for example with
+    /// `numeric_widening` an existing [Union](Schema::Union) of integers and floats is widened
+    /// too.
+    pub fn coalesce_with(&mut self, other: Self, config: &CoalesceConfig) {
+        self.coalesce(other);
+        if config.numeric_widening {
+            WidenNumbers.visit_schema(self);
+        }
+
+        struct WidenNumbers;
+        impl SchemaVisitorMut for WidenNumbers {
+            fn visit_schema(&mut self, schema: &mut Schema) {
+                walk_schema(self, schema);
+                schema.widen_numbers();
+            }
+        }
+    }
+
+    /// `true` for a [Sequence](Schema::Sequence) whose elements are a mix of integers and
+    /// floats, that is a [Union](Schema::Union) of an [Integer](Schema::Integer) and a
+    /// [Float](Schema::Float) and nothing else.
+    ///
+    /// Such arrays can be widened to floats with [Schema::widen_numeric_sequence].
+    pub fn is_numeric_mixed(&self) -> bool {
+        let variants = match self {
+            Schema::Sequence { field, .. } => match &field.schema {
+                Some(Schema::Union { variants }) => variants,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        variants.iter().any(|v| matches!(v, Schema::Integer(_)))
+            && variants.iter().any(|v| matches!(v, Schema::Float(_)))
+            && variants
+                .iter()
+                .all(|v| matches!(v, Schema::Integer(_) | Schema::Float(_)))
+    }
+
+    /// If [Schema::is_numeric_mixed], merges the elements of the [Sequence](Schema::Sequence)
+    /// into a single [Float](Schema::Float), like the `numeric_widening` of [CoalesceConfig]
+    /// does for the whole schema. Returns whether the elements were widened.
+    pub fn widen_numeric_sequence(&mut self) -> bool {
+        if !self.is_numeric_mixed() {
+            return false;
+        }
+        if let Schema::Sequence { field, .. } = self {
+            if let Some(element) = &mut field.schema {
+                element.widen_numbers();
+            }
+        }
+        true
+    }
+
+    /// Recursively removes the [Struct](Schema::Struct) fields for which `keep` returns `false`,
+    /// for example to redact sensitive fields before sharing a schema.
+    ///
+    /// `keep` is given the path of the field (the struct keys from the root, sequences add no
+    /// segment) and the field itself. Structs left without fields are kept, and the removed
+    /// keys are also dropped from their [MapStructContext].
+    pub fn retain_fields(&mut self, keep: impl FnMut(&[String], &Field) -> bool) {
+        RetainFields {
+            keep,
+            path: Vec::new(),
+        }
+        .visit_schema(self);
+
+        struct RetainFields<F> {
+            keep: F,
+            path: Vec<String>,
+        }
+        impl<F: FnMut(&[String], &Field) -> bool> SchemaVisitorMut for RetainFields<F> {
+            fn visit_struct(
+                &mut self,
+                fields: &mut BTreeMap<StructKey, Field>,
+                context: &mut MapStructContext,
+            ) {
+                fields.retain(|key, field| {
+                    self.path.push(key.to_string());
+                    let keep = (self.keep)(&self.path, field);
+                    self.path.pop();
+                    keep
+                });
+                context
+                    .duplicate_keys
+                    .0
+                    .retain(|key, _| fields.contains_key(key.as_str()));
+
+                for (key, field) in fields.iter_mut() {
+                    self.path.push(key.to_string());
+                    self.visit_field(field);
+                    self.path.pop();
+                }
+            }
+        }
+    }
+
+    /// Replaces the context of every node with an empty one, dropping the statistics and
+    /// keeping only the shape of the [Schema] (and the [FieldStatus]es).
+    ///
+    /// The result is [structurally equal](StructuralEq) to the original, but much smaller to
+    /// store and to compare.
+    pub fn strip_contexts(self) -> Self {
+        return self.map_contexts(&mut StripContexts);
+
+        struct StripContexts;
+        impl ContextMapper for StripContexts {
+            fn map_null(&mut self, _: NullContext) -> NullContext {
+                Default::default()
+            }
+            fn map_boolean(&mut self, _: BooleanContext) -> BooleanContext {
+                Default::default()
+            }
+            fn map_integer(&mut self, _: NumberContext<i128>) -> NumberContext<i128> {
+                Default::default()
+            }
+            fn map_float(&mut self, _: NumberContext<f64>) -> NumberContext<f64> {
+                Default::default()
+            }
+            fn map_string(&mut self, _: StringContext) -> StringContext {
+                Default::default()
+            }
+            fn map_bytes(&mut self, _: BytesContext) -> BytesContext {
+                Default::default()
+            }
+            fn map_sequence(&mut self, _: SequenceContext) -> SequenceContext {
+                Default::default()
+            }
+            fn map_struct(&mut self, _: MapStructContext) -> MapStructContext {
+                Default::default()
+            }
+        }
+    }
+
+    /// Same as `self.clone().strip_contexts()`, but the contexts are never cloned, which makes
+    /// it much cheaper when they hold many samples or custom aggregators.
+    ///
+    /// Useful to get a skeleton of the [Schema], for example to diff it or as a template.
+    pub fn clone_shape_only(&self) -> Self {
+        match self {
+            Schema::Null(_) => Schema::Null(Default::default()),
+            Schema::Boolean(_) => Schema::Boolean(Default::default()),
+            Schema::Integer(_) => Schema::Integer(Default::default()),
+            Schema::Float(_) => Schema::Float(Default::default()),
+            Schema::String(_) => Schema::String(Default::default()),
+            Schema::Bytes(_) => Schema::Bytes(Default::default()),
+            Schema::Sequence { field, .. } => Schema::Sequence {
+                field: Box::new(field.clone_shape_only()),
+                context: Default::default(),
+            },
+            Schema::Struct { fields, .. } => Schema::Struct {
+                fields: fields
+                    .iter()
+                    .map(|(key, field)| (key.clone(), field.clone_shape_only()))
+                    .collect(),
+                context: Default::default(),
+            },
+            Schema::Union { variants } => Schema::Union {
+                variants: variants.iter().map(Schema::clone_shape_only).collect(),
+            },
+        }
+    }
+
+    /// Replaces the context of every node with the one returned by the respective method of
+    /// the [ContextMapper], keeping the shape of the [Schema] (and the [FieldStatus]es).
+    ///
+    /// Nodes are mapped depth-first, children before their parents.
+    pub fn map_contexts<M: ContextMapper + ?Sized>(self, mapper: &mut M) -> Self {
+        match self {
+            Schema::Null(context) => Schema::Null(mapper.map_null(context)),
+            Schema::Boolean(context) => Schema::Boolean(mapper.map_boolean(context)),
+            Schema::Integer(context) => Schema::Integer(mapper.map_integer(context)),
+            Schema::Float(context) => Schema::Float(mapper.map_float(context)),
+            Schema::String(context) => Schema::String(mapper.map_string(context)),
+            Schema::Bytes(context) => Schema::Bytes(mapper.map_bytes(context)),
+            Schema::Sequence { field, context } => {
+                let field = Box::new(field.map_contexts(mapper));
+                Schema::Sequence {
+                    field,
+                    context: mapper.map_sequence(context),
+                }
+            }
+            Schema::Struct { fields, context } => {
+                let fields = fields
+                    .into_iter()
+                    .map(|(key, field)| (key, field.map_contexts(mapper)))
+                    .collect();
+                Schema::Struct {
+                    fields,
+                    context: mapper.map_struct(context),
+                }
+            }
+            Schema::Union { variants } => Schema::Union {
+                variants: variants
+                    .into_iter()
+                    .map(|variant| variant.map_contexts(mapper))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Returns every node of the schema along with its
+    /// [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901), following the layout of the
+    /// generated json schemas: `/properties/<key>` for struct fields, `/items` for sequence
+    /// elements and `/anyOf/<index>` for union variants.
+    ///
+    /// The root is at the empty pointer `""` and nodes are returned in depth-first order.
+    /// Fields without a schema have no node.
+    pub fn json_pointers(&self) -> Vec<(String, &Schema)> {
+        let mut pointers = Vec::new();
+        collect_json_pointers(self, String::new(), &mut pointers);
+        pointers
+    }
+
+    /// Returns `true` if no [Union](Schema::Union) in the schema holds another union or two
+    /// schemas of the same variant.
+    ///
+    /// [Coalesce] relies on this and preserves it, so the schemas produced by the analysis
+    /// always are normalized, but a [Schema] built or edited by hand might not be.
+    pub fn is_normalized(&self) -> bool {
+        self.json_pointers()
+            .into_iter()
+            .all(|(_, schema)| match schema {
+                Schema::Union { variants } => variants.iter().enumerate().all(|(i, variant)| {
+                    !matches!(variant, Schema::Union { .. })
+                        && variants[..i]
+                            .iter()
+                            .all(|other| other.variant_name() != variant.variant_name())
+                }),
+                _ => true,
+            })
+    }
+
+    /// Returns the keys of the fields of a [Struct](Schema::Struct) that were found in every
+    /// instance, the ones the json schema targets list as `required`.
+    /// Fields that may be null are still required, as long as they are never missing.
+    ///
+    /// Any other [Schema] has no fields and returns an empty list.
+    pub fn required_field_names(&self) -> Vec<&str> {
+        self.field_names_where(|field| !field.status.nullability().may_be_missing())
+    }
+
+    /// Returns the keys of the fields of a [Struct](Schema::Struct) that may be missing,
+    /// the complement of [required_field_names](Schema::required_field_names).
+    ///
+    /// Any other [Schema] has no fields and returns an empty list.
+    pub fn optional_field_names(&self) -> Vec<&str> {
+        self.field_names_where(|field| field.status.nullability().may_be_missing())
+    }
+
+    /// Returns the key of the field that tells apart the [Struct](Schema::Struct) variants of
+    /// a [Union](Schema::Union), like the `"type"` of a tagged-union API.
+    ///
+    /// A discriminator is a string field found in every variant, never null or missing, whose
+    /// [constant_value](Field::constant_value) is different for each variant. If there are
+    /// several candidates, the first key in order is returned.
+    /// Returns [None] if the schema is not a union of at least two structs, or if any variant
+    /// is not a struct.
+    pub fn union_discriminator(&self) -> Option<String> {
+        match self {
+            Schema::Union { variants } => {
+                Schema::discriminator(&variants.iter().collect::<Vec<_>>())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a [Debug] view of the schema that truncates samples and omits empty context
+    /// fields, to keep `dbg!` output readable.
+    /// See [DebugCompact] for details, the derived [Debug] is still lossless.
+    pub fn debug_compact(&self) -> DebugCompact<'_, Self> {
+        DebugCompact(self)
+    }
+
+    /// Returns a [Serialize] view of the schema that keeps everything, only the statistics,
+    /// or only the shape, depending on the [SerializeMode]. See [SchemaView] for details.
+    pub fn view(&self, mode: SerializeMode) -> SchemaView<'_> {
+        SchemaView(self, mode)
+    }
+
+    fn field_names_where(&self, predicate: impl Fn(&Field) -> bool) -> Vec<&str> {
+        match self {
+            Schema::Struct { fields, .. } => fields
+                .iter()
+                .filter(|(_, field)| predicate(field))
+                .map(|(key, _)| key.as_ref())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+fn collect_json_pointers<'s>(
+    schema: &'s Schema,
+    pointer: String,
+    pointers: &mut Vec<(String, &'s Schema)>,
+) {
+    pointers.push((pointer.clone(), schema));
+    match schema {
+        Schema::Sequence { field, .. } => {
+            if let Some(element) = &field.schema {
+                collect_json_pointers(element, format!("{}/items", pointer), pointers);
+            }
+        }
+        Schema::Struct { fields, .. } => {
+            for (key, field) in fields {
+                if let Some(value) = &field.schema {
+                    // RFC 6901 escaping, `~` must go first.
+                    let key = key.replace('~', "~0").replace('/', "~1");
+                    let child = format!("{}/properties/{}", pointer, key);
+                    collect_json_pointers(value, child, pointers);
+                }
+            }
+        }
+        Schema::Union { variants } => {
+            for (i, variant) in variants.iter().enumerate() {
+                collect_json_pointers(variant, format!("{}/anyOf/{}", pointer, i), pointers);
+            }
+        }
+        _ => {}
+    }
+}
+impl Schema {
+    /// Like [structural_eq](StructuralEq::structural_eq), but the comparison can be relaxed
+    /// using [StructuralEqOptions].
+    pub fn structural_eq_with(&self, other: &Self, options: &StructuralEqOptions) -> bool {
         use Schema::*;
         match (self, other) {
             (Null(_), Null(_)) => true,
@@ -113,7 +880,9 @@ impl StructuralEq for Schema {
             (Bytes(_), Bytes(_)) => true,
 
             (Sequence { field: field_1, .. }, Sequence { field: field_2, .. }) => {
-                field_1.structural_eq(field_2)
+                let untyped = field_1.schema.is_none() || field_2.schema.is_none();
+                (options.untyped_sequences_match_any && untyped)
+                    || field_1.structural_eq_with(field_2, options)
             }
 
             (
@@ -123,14 +892,23 @@ impl StructuralEq for Schema {
                 Struct {
                     fields: fields_2, ..
                 },
-            ) => fields_1.structural_eq(fields_2),
+            ) => {
+                fields_1.len() == fields_2.len()
+                    && fields_1
+                        .iter()
+                        .zip(fields_2)
+                        .all(|((k1, f1), (k2, f2))| k1 == k2 && f1.structural_eq_with(f2, options))
+            }
 
             (Union { variants: s }, Union { variants: o }) => {
                 let mut s = s.clone();
                 let mut o = o.clone();
                 s.sort_by(schema_cmp);
                 o.sort_by(schema_cmp);
-                s.structural_eq(&o)
+                s.len() == o.len()
+                    && s.iter()
+                        .zip(&o)
+                        .all(|(s, o)| s.structural_eq_with(o, options))
             }
 
             // Listing these out makes sure it fails if new variants are added.
@@ -145,11 +923,80 @@ impl StructuralEq for Schema {
             | (Union { .. }, _) => false,
         }
     }
+
+    /// Returns a hash of the shape of the schema, which is the same for any two schemas that
+    /// are [structurally equal](StructuralEq), so it can be used as a stable ID to cache or
+    /// deduplicate schemas.
+    ///
+    /// Like [StructuralEq], it ignores the contexts and the order of the
+    /// [Union](Schema::Union) variants. It also ignores the document indices in
+    /// [FieldStatus], which depend on the samples.
+    /// The hash (64-bit FNV-1a) does not depend on the process or the platform,
+    /// so it can be stored and compared across runs.
+    pub fn shape_fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        hash_shape(self, &mut hasher);
+        hasher.finish()
+    }
+}
+fn hash_shape(schema: &Schema, hasher: &mut Fnv1a) {
+    hasher.write_str(schema.variant_name());
+    match schema {
+        Schema::Null(_)
+        | Schema::Boolean(_)
+        | Schema::Integer(_)
+        | Schema::Float(_)
+        | Schema::String(_)
+        | Schema::Bytes(_) => {}
+        Schema::Sequence { field, .. } => hash_field_shape(field, hasher),
+        Schema::Struct { fields, .. } => {
+            hasher.write_u64(fields.len() as u64);
+            // BTreeMap keys are already sorted.
+            for (key, field) in fields {
+                hasher.write_str(key);
+                hash_field_shape(field, hasher);
+            }
+        }
+        Schema::Union { variants } => {
+            let mut variants: Vec<u64> = variants.iter().map(Schema::shape_fingerprint).collect();
+            variants.sort_unstable();
+            hasher.write_u64(variants.len() as u64);
+            for variant in variants {
+                hasher.write_u64(variant);
+            }
+        }
+    }
+}
+fn hash_field_shape(field: &Field, hasher: &mut Fnv1a) {
+    let status = &field.status;
+    hasher.write(&[
+        status.may_be_null as u8,
+        status.may_be_normal as u8,
+        status.may_be_missing as u8,
+        status.may_be_duplicate as u8,
+        status.is_ignored as u8,
+    ]);
+    match &field.schema {
+        Some(schema) => {
+            hasher.write(&[1]);
+            hash_shape(schema, hasher);
+        }
+        None => hasher.write(&[0]),
+    }
+}
+impl StructuralEq for Schema {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.structural_eq_with(other, &Default::default())
+    }
 }
+/// Structs are merged field by field. A field found in only one of the two structs is marked
+/// as [may_be_missing](FieldStatus::may_be_missing), so coalescing the schemas of two documents
+/// gives the same shape as extending the schema of one with the other.
 impl Coalesce for Schema {
     fn coalesce(&mut self, other: Self) {
         use Schema::*;
         match (self, other) {
+            (Null(s), Null(o)) => s.coalesce(o),
             (Boolean(s), Boolean(o)) => s.coalesce(o),
             (Integer(s), Integer(o)) => s.coalesce(o),
             (Float(s), Float(o)) => s.coalesce(o),
@@ -181,12 +1028,7 @@ impl Coalesce for Schema {
                 },
             ) => {
                 self_agg.coalesce(other_agg);
-                for (name, other_schema) in other_fields {
-                    self_fields
-                        .entry(name)
-                        .and_modify(|schema| schema.coalesce(other_schema.clone()))
-                        .or_insert_with(|| other_schema);
-                }
+                coalesce_fields(self_fields, other_fields);
             }
             (
                 Union {
@@ -244,6 +1086,10 @@ impl Coalesce for Schema {
                     }
 
                     // If they are the same, go ahead and coalesce!
+                    (Null(s), Null(o)) => {
+                        s.coalesce(o);
+                        return;
+                    }
                     (Boolean(s), Boolean(o)) => {
                         s.coalesce(o);
                         return;
@@ -291,12 +1137,7 @@ impl Coalesce for Schema {
                         },
                     ) => {
                         self_agg.coalesce(other_agg);
-                        for (name, other_schema) in other_fields {
-                            self_fields
-                                .entry(name)
-                                .and_modify(|schema| schema.coalesce(other_schema.clone()))
-                                .or_insert_with(|| other_schema);
-                        }
+                        coalesce_fields(self_fields, other_fields);
                         return;
                     }
 
@@ -378,6 +1219,56 @@ impl Field {
             schema: Some(schema),
         }
     }
+
+    /// Returns the value of the [Field] if it has always been the same, like a version or
+    /// a discriminator, so that targets can emit a constant/literal type.
+    ///
+    /// Only booleans, integers, floats and strings are considered, and null or missing values
+    /// are ignored (check the [FieldStatus] for those).
+    pub fn constant_value(&self) -> Option<ConstantValue<'_>> {
+        match self.schema.as_ref()? {
+            Schema::Boolean(context) => match (context.trues.get(), context.falses.get()) {
+                (0, 0) => None,
+                (_, 0) => Some(ConstantValue::Boolean(true)),
+                (0, _) => Some(ConstantValue::Boolean(false)),
+                _ => None,
+            },
+            Schema::Integer(context) => context
+                .samples
+                .single()
+                .copied()
+                .map(ConstantValue::Integer),
+            Schema::Float(context) => context
+                .samples
+                .single()
+                .map(|value| ConstantValue::Float(value.into_inner())),
+            Schema::String(context) => context
+                .samples
+                .single()
+                .map(|value| ConstantValue::String(value)),
+            _ => None,
+        }
+    }
+}
+/// Merges the fields of two structs, a field only present on one side may be missing.
+fn coalesce_fields(
+    self_fields: &mut BTreeMap<StructKey, Field>,
+    other_fields: BTreeMap<StructKey, Field>,
+) {
+    for (key, field) in self_fields.iter_mut() {
+        if !other_fields.contains_key(key) {
+            field.status.may_be_missing = true;
+        }
+    }
+    for (key, mut field) in other_fields {
+        match self_fields.get_mut(&key) {
+            Some(self_field) => self_field.coalesce(field),
+            None => {
+                field.status.may_be_missing = true;
+                self_fields.insert(key, field);
+            }
+        }
+    }
 }
 impl Coalesce for Field {
     fn coalesce(&mut self, other: Self)
@@ -396,9 +1287,257 @@ impl Coalesce for Field {
         }
     }
 }
+impl Field {
+    /// Like [Schema::strip_contexts], the [FieldStatus] is kept as is.
+    pub fn strip_contexts(self) -> Self {
+        Field {
+            status: self.status,
+            schema: self.schema.map(Schema::strip_contexts),
+        }
+    }
+
+    /// Like [Schema::clone_shape_only], the [FieldStatus] is kept as is.
+    pub fn clone_shape_only(&self) -> Self {
+        Field {
+            status: self.status.clone(),
+            schema: self.schema.as_ref().map(Schema::clone_shape_only),
+        }
+    }
+
+    /// Like [Schema::map_contexts], the [FieldStatus] is kept as is.
+    pub fn map_contexts<M: ContextMapper + ?Sized>(self, mapper: &mut M) -> Self {
+        Field {
+            status: self.status,
+            schema: self.schema.map(|schema| schema.map_contexts(mapper)),
+        }
+    }
+
+    /// Like [structural_eq](StructuralEq::structural_eq), but the comparison can be relaxed
+    /// using [StructuralEqOptions].
+    pub fn structural_eq_with(&self, other: &Self, options: &StructuralEqOptions) -> bool {
+        // Whether a collection was found empty, or in which documents, does not change its
+        // shape.
+        let status = |field: &Field| FieldStatus {
+            may_be_empty_collection: false,
+            first_seen: None,
+            last_seen: None,
+            ..field.status.clone()
+        };
+        status(self) == status(other)
+            && match (&self.schema, &other.schema) {
+                (Some(s), Some(o)) => s.structural_eq_with(o, options),
+                (Some(_), None) | (None, Some(_)) => false,
+                (None, None) => true,
+            }
+    }
+}
 impl StructuralEq for Field {
     fn structural_eq(&self, other: &Self) -> bool {
-        self.status == other.status && self.schema.structural_eq(&other.schema)
+        self.structural_eq_with(other, &Default::default())
+    }
+}
+
+/// The keys of the current serialized form of a [Field].
+const FIELD_KEYS: &[&str] = &["status", "schema"];
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Field", FIELD_KEYS, FieldReprVisitor)
+    }
+}
+
+/// Reads either serialized form of a [Field], depending on the first key that is not part of
+/// the legacy flattened status.
+struct FieldReprVisitor;
+impl<'de> Visitor<'de> for FieldReprVisitor {
+    type Value = Field;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a field")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Field, A::Error> {
+        let mut legacy = LegacyStatus::default();
+        let first = loop {
+            match map.next_key::<String>()? {
+                Some(key) if legacy.read(&key, &mut map)? => continue,
+                Some(key) => break key,
+                None if legacy.seen.is_empty() => return Ok(Field::default()),
+                None => return legacy.finish(None),
+            }
+        };
+
+        if legacy.seen.is_empty() && FIELD_KEYS.contains(&first.as_str()) {
+            return read_current_field(first, map);
+        }
+
+        let mut keys = Vec::new();
+        let schema = Schema::deserialize(MapAccessDeserializer::new(LegacySchemaAccess {
+            map: &mut map,
+            pending: Some(first),
+            status: &mut legacy,
+            keys: &mut keys,
+        }))?;
+        let allowed = legacy_schema_keys(&schema);
+        if let Some(key) = keys
+            .iter()
+            .find(|key| *key != "type" && !allowed.contains(&key.as_str()))
+        {
+            return Err(A::Error::unknown_field(key, allowed));
+        }
+        legacy.finish(Some(schema))
+    }
+}
+
+/// Reads the rest of the `{ "status", "schema" }` form, `first` is the key already read.
+fn read_current_field<'de, A: MapAccess<'de>>(
+    first: String,
+    mut map: A,
+) -> Result<Field, A::Error> {
+    let mut status = None;
+    let mut schema = None;
+    let mut key = Some(first);
+    while let Some(current) = key {
+        match current.as_str() {
+            "status" if status.is_some() => return Err(A::Error::duplicate_field("status")),
+            "status" => status = Some(map.next_value()?),
+            "schema" if schema.is_some() => return Err(A::Error::duplicate_field("schema")),
+            "schema" => schema = Some(map.next_value()?),
+            other => return Err(A::Error::unknown_field(other, FIELD_KEYS)),
+        }
+        key = map.next_key()?;
+    }
+    Ok(Field {
+        status: status.unwrap_or_default(),
+        schema: schema.unwrap_or_default(),
+    })
+}
+
+/// The [FieldStatus] of the legacy form, flattened next to the keys of the schema.
+#[derive(Default)]
+struct LegacyStatus {
+    status: FieldStatus,
+    seen: Vec<&'static str>,
+}
+impl LegacyStatus {
+    /// Reads the value of `key` if it is a key of the status, returns `false` otherwise.
+    fn read<'de, A: MapAccess<'de>>(&mut self, key: &str, map: &mut A) -> Result<bool, A::Error> {
+        macro_rules! read {
+            ($($name:ident),*) => {
+                match key {
+                    $(stringify!($name) => {
+                        self.status.$name = map.next_value()?;
+                        stringify!($name)
+                    })*
+                    _ => return Ok(false),
+                }
+            };
+        }
+        let key = read!(
+            may_be_null,
+            may_be_normal,
+            may_be_missing,
+            may_be_duplicate,
+            may_be_empty_collection,
+            is_ignored,
+            first_seen,
+            last_seen
+        );
+        if self.seen.contains(&key) {
+            return Err(A::Error::duplicate_field(key));
+        }
+        self.seen.push(key);
+        Ok(true)
+    }
+
+    fn finish<E: serde::de::Error>(self, schema: Option<Schema>) -> Result<Field, E> {
+        // The flags that were always written, unlike the ones that are skipped when unset.
+        for required in [
+            "may_be_null",
+            "may_be_normal",
+            "may_be_missing",
+            "may_be_duplicate",
+        ] {
+            if !self.seen.contains(&required) {
+                return Err(E::missing_field(required));
+            }
+        }
+        Ok(Field {
+            status: self.status,
+            schema,
+        })
+    }
+}
+
+/// Hands the keys of the legacy form to the [Schema], except the ones of the [LegacyStatus],
+/// and records them to check them against the [legacy_schema_keys] once the type is known.
+struct LegacySchemaAccess<'a, A> {
+    map: &'a mut A,
+    pending: Option<String>,
+    status: &'a mut LegacyStatus,
+    keys: &'a mut Vec<String>,
+}
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for LegacySchemaAccess<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        let key = match self.pending.take() {
+            Some(key) => key,
+            None => loop {
+                match self.map.next_key::<String>()? {
+                    Some(key) if self.status.read(&key, self.map)? => continue,
+                    Some(key) => break key,
+                    None => return Ok(None),
+                }
+            },
+        };
+        self.keys.push(key.clone());
+        seed.deserialize(IntoDeserializer::<A::Error>::into_deserializer(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
+
+/// The keys the legacy form was written with for each kind of [Schema], besides `"type"`.
+///
+/// The legacy form is no longer written, so these do not change as contexts gain new fields.
+fn legacy_schema_keys(schema: &Schema) -> &'static [&'static str] {
+    match schema {
+        Schema::Null(_) => &["count"],
+        Schema::Boolean(_) => &["count", "trues", "falses"],
+        Schema::Integer(_) | Schema::Float(_) => &[
+            "count",
+            "samples",
+            "min",
+            "max",
+            "exceeds_js_safe_integer",
+            "saw_values_above_i64_max",
+            "skip_samples",
+        ],
+        Schema::String(_) => &[
+            "count",
+            "samples",
+            "suspicious_strings",
+            "semantic_extractor",
+            "unit_extractor",
+            "binary_encodings",
+            "scripts",
+            "suspicious_spellings",
+            "min_max_length",
+            "is_datetime",
+            "embedded_json",
+            "embedded_schema",
+        ],
+        Schema::Bytes(_) => &["count", "min_max_length"],
+        Schema::Sequence { .. } => &["field", "context"],
+        Schema::Struct { .. } => &["fields", "context"],
+        Schema::Union { .. } => &["variants"],
     }
 }
 
@@ -413,7 +1552,41 @@ impl FieldStatus {
     }
     /// `true` if the status allows for null or missing values.
     pub fn is_option(&self) -> bool {
-        self.may_be_null || self.may_be_missing
+        self.nullability().is_option()
+    }
+    /// Updates [last_seen](FieldStatus::last_seen) if `document` is later.
+    pub(crate) fn record_last_seen(&mut self, document: Option<usize>) {
+        self.last_seen = self.last_seen.max(document);
+    }
+    /// Classifies the status into a single [Nullability] category.
+    ///
+    /// `may_be_duplicate` does not affect the result.
+    pub fn nullability(&self) -> Nullability {
+        match (self.may_be_null, self.may_be_missing) {
+            (true, true) => Nullability::OptionalAndSometimes,
+            (true, false) => Nullability::Optional,
+            (false, true) => Nullability::Sometimes,
+            (false, false) if self.may_be_normal => Nullability::Required,
+            (false, false) => Nullability::NeverSeen,
+        }
+    }
+}
+
+//
+// Nullability implementations
+//
+impl Nullability {
+    /// `true` if the value may be null.
+    pub fn may_be_null(&self) -> bool {
+        matches!(self, Self::Optional | Self::OptionalAndSometimes)
+    }
+    /// `true` if the value may be missing.
+    pub fn may_be_missing(&self) -> bool {
+        matches!(self, Self::Sometimes | Self::OptionalAndSometimes)
+    }
+    /// `true` if the value may be null or missing.
+    pub fn is_option(&self) -> bool {
+        self.may_be_null() || self.may_be_missing()
     }
 }
 impl Coalesce for FieldStatus {
@@ -425,6 +1598,13 @@ impl Coalesce for FieldStatus {
         self.may_be_normal |= other.may_be_normal;
         self.may_be_missing |= other.may_be_missing;
         self.may_be_duplicate |= other.may_be_duplicate;
+        self.may_be_empty_collection |= other.may_be_empty_collection;
+        self.is_ignored |= other.is_ignored;
+        self.first_seen = match (self.first_seen, other.first_seen) {
+            (Some(s), Some(o)) => Some(s.min(o)),
+            (s, o) => s.or(o),
+        };
+        self.record_last_seen(other.last_seen);
     }
 }
 
@@ -432,68 +1612,176 @@ impl Coalesce for FieldStatus {
 // Helper functions
 //
 
+impl Schema {
+    /// The discriminator of a union of these `variants`, see [Schema::union_discriminator].
+    pub(crate) fn discriminator(variants: &[&Schema]) -> Option<String> {
+        if variants.len() < 2 {
+            return None;
+        }
+        let structs = variants
+            .iter()
+            .map(|variant| match variant {
+                Schema::Struct { fields, .. } => Some(fields),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        structs[0]
+            .keys()
+            .find(|key| {
+                let mut values = Vec::with_capacity(structs.len());
+                for fields in &structs {
+                    let field = match fields.get(*key) {
+                        Some(field) if field.status.nullability() == Nullability::Required => field,
+                        _ => return false,
+                    };
+                    match field.constant_value() {
+                        Some(ConstantValue::String(value)) if !values.contains(&value) => {
+                            values.push(value)
+                        }
+                        _ => return false,
+                    }
+                }
+                true
+            })
+            .map(|key| key.to_string())
+    }
+    /// The name of the variant, used in summaries.
+    pub(crate) fn variant_name(&self) -> &'static str {
+        match self {
+            Schema::Null(_) => "Null",
+            Schema::Boolean(_) => "Boolean",
+            Schema::Integer(_) => "Integer",
+            Schema::Float(_) => "Float",
+            Schema::String(_) => "String",
+            Schema::Bytes(_) => "Bytes",
+            Schema::Sequence { .. } => "Sequence",
+            Schema::Struct { .. } => "Struct",
+            Schema::Union { .. } => "Union",
+        }
+    }
+    /// How many values have been recorded by the context(s) of the [Schema].
+    pub(crate) fn value_count(&self) -> usize {
+        match self {
+            Schema::Null(context) => context.count.get(),
+            Schema::Boolean(context) => context.count.get(),
+            Schema::Integer(context) => context.count.get(),
+            Schema::Float(context) => context.count.get(),
+            Schema::String(context) => context.count.get(),
+            Schema::Bytes(context) => context.count.get(),
+            Schema::Sequence { context, .. } => context.count.get(),
+            Schema::Struct { context, .. } => context.count.get(),
+            Schema::Union { variants } => variants.iter().map(Schema::value_count).sum(),
+        }
+    }
+    /// Merges the [Integer](Schema::Integer) of a [Union](Schema::Union) into its
+    /// [Float](Schema::Float), if it has both. Not recursive.
+    pub(crate) fn widen_numbers(&mut self) {
+        let variants = match self {
+            Schema::Union { variants } => variants,
+            _ => return,
+        };
+        let integer = variants
+            .iter()
+            .position(|v| matches!(v, Schema::Integer(_)));
+        let has_float = variants.iter().any(|v| matches!(v, Schema::Float(_)));
+        let integer = match (integer, has_float) {
+            (Some(index), true) => match variants.remove(index) {
+                Schema::Integer(context) => context,
+                _ => unreachable!("checked above"),
+            },
+            _ => return,
+        };
+        for variant in variants.iter_mut() {
+            if let Schema::Float(context) = variant {
+                context.coalesce(integer.into());
+                break;
+            }
+        }
+        if variants.len() == 1 {
+            *self = variants.remove(0);
+        }
+    }
+    /// The top level variants of the [Schema], in a stable order.
+    pub(crate) fn top_level_variants(&self) -> Vec<&Schema> {
+        match self {
+            Schema::Union { variants } => {
+                let mut variants: Vec<&Schema> = variants.iter().collect();
+                variants.sort_by(|a, b| schema_cmp(a, b));
+                variants
+            }
+            schema => vec![schema],
+        }
+    }
+}
+
 /// A helper function that returns the [Ordering](std::cmp::Ordering) of two [Schema]s
 /// to help in comparing two [Schema::Union].
-/// Since a [Schema::Union] should never hold two schemas of the same type, it is enough to
-/// just compare the top level without recursion.
+///
+/// [Schema]s are ordered by their kind first. A [Schema::Union] should never hold two schemas
+/// of the same kind, but this is a total order anyway (comparing the element schema of
+/// sequences, the keys and field schemas of structs, and the variants of unions) so that
+/// sorting stays deterministic even if that invariant is temporarily broken.
+/// Contexts and [FieldStatus]es are ignored, so schemas with the same shape are `Equal`.
 fn schema_cmp(first: &Schema, second: &Schema) -> std::cmp::Ordering {
-    use std::cmp::Ordering::*;
+    use std::cmp::Ordering::{self, *};
     use Schema::*;
-    match first {
-        Null(_) => match second {
-            Null(_) => Equal,
-            _ => Less,
-        },
-        Boolean(_) => match second {
-            Null(_) | Boolean(_) => Equal,
-            _ => Less,
-        },
-        Integer(_) => match second {
-            Null(_) | Boolean(_) => Greater,
-            Integer(_) => Equal,
-            _ => Less,
-        },
-        Float(_) => match second {
-            Null(_) | Boolean(_) | Integer(_) => Greater,
-            Float(_) => Equal,
-            _ => Less,
-        },
-        String(_) => match second {
-            Null(_) | Boolean(_) | Integer(_) | Float(_) => Greater,
-            String(_) => Equal,
-            _ => Less,
-        },
-        Bytes(_) => match second {
-            Null(_) | Boolean(_) | Integer(_) | Float(_) | String(_) => Greater,
-            Bytes(_) => Equal,
-            _ => Less,
-        },
-        Sequence { .. } => match second {
-            Null(_) | Boolean(_) | Integer(_) | Float(_) | String(_) | Bytes(_) => Greater,
-            Sequence { .. } => Equal,
-            _ => Less,
-        },
-        Struct { .. } => match second {
-            Null(_)
-            | Boolean(_)
-            | Integer(_)
-            | Float(_)
-            | String(_)
-            | Bytes(_)
-            | Sequence { .. } => Greater,
-            Struct { .. } => Equal,
-            _ => Less,
-        },
-        Union { .. } => match second {
-            Null(_)
-            | Boolean(_)
-            | Integer(_)
-            | Float(_)
-            | String(_)
-            | Bytes(_)
-            | Sequence { .. }
-            | Struct { .. } => Greater,
-            Union { .. } => Equal,
-        },
+
+    fn rank(schema: &Schema) -> u8 {
+        match schema {
+            Null(_) => 0,
+            Boolean(_) => 1,
+            Integer(_) => 2,
+            Float(_) => 3,
+            String(_) => 4,
+            Bytes(_) => 5,
+            Sequence { .. } => 6,
+            Struct { .. } => 7,
+            Union { .. } => 8,
+        }
+    }
+    fn field_cmp(first: &Field, second: &Field) -> Ordering {
+        match (&first.schema, &second.schema) {
+            (Some(first), Some(second)) => schema_cmp(first, second),
+            (first, second) => first.is_some().cmp(&second.is_some()),
+        }
     }
+    /// Compares two sequences of orderings lexicographically, shorter first if one is a
+    /// prefix of the other.
+    fn lexicographic(
+        orderings: impl Iterator<Item = Ordering>,
+        lengths: (usize, usize),
+    ) -> Ordering {
+        orderings
+            .fold(Equal, Ordering::then)
+            .then(lengths.0.cmp(&lengths.1))
+    }
+
+    rank(first)
+        .cmp(&rank(second))
+        .then_with(|| match (first, second) {
+            (Sequence { field: first, .. }, Sequence { field: second, .. }) => {
+                field_cmp(first, second)
+            }
+            (Struct { fields: first, .. }, Struct { fields: second, .. }) => {
+                first.keys().cmp(second.keys()).then_with(|| {
+                    let fields = first.values().zip(second.values());
+                    lexicographic(
+                        fields.map(|(first, second)| field_cmp(first, second)),
+                        (first.len(), second.len()),
+                    )
+                })
+            }
+            (Union { variants: first }, Union { variants: second }) => {
+                let mut first: Vec<&Schema> = first.iter().collect();
+                let mut second: Vec<&Schema> = second.iter().collect();
+                first.sort_by(|a, b| schema_cmp(a, b));
+                second.sort_by(|a, b| schema_cmp(a, b));
+                lexicographic(
+                    first.iter().zip(&second).map(|(a, b)| schema_cmp(a, b)),
+                    (first.len(), second.len()),
+                )
+            }
+            _ => Equal,
+        })
 }
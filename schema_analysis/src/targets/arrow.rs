@@ -0,0 +1,152 @@
+/*!
+Integration with [Arrow](https://arrow.apache.org/) schemas, which can also be used to write
+[Parquet](https://parquet.apache.org/) files.
+
+You can:
+```rust
+# use schema_analysis::{targets::arrow::ArrowOptions, InferredSchema};
+# use arrow_schema::DataType;
+#
+let InferredSchema { schema } = serde_json::from_str(r#"[{ "id": 1, "name": null }]"#).unwrap();
+
+// Convert to an Arrow schema.
+let arrow_schema: arrow_schema::Schema = schema.to_arrow_schema();
+assert_eq!(arrow_schema.field_with_name("id").unwrap().data_type(), &DataType::Int64);
+assert!(arrow_schema.field_with_name("name").unwrap().is_nullable());
+
+// Convert to an Arrow schema with custom options.
+let options = ArrowOptions { unions_as_strings: true };
+let arrow_schema: arrow_schema::Schema = schema.to_arrow_schema_with_options(&options);
+```
+
+The columns of the Arrow schema are the fields of the root [Struct](Schema::Struct), or of the
+structs in the root [Sequence](Schema::Sequence) (each being a row, if there are null rows all
+the columns are nullable).
+Any other root becomes a single column named `value`.
+*/
+
+use std::sync::Arc;
+
+use arrow_schema::{DataType, Field as ArrowField, Fields, UnionFields, UnionMode};
+
+use crate::{Field, Schema, StructKey};
+
+impl Schema {
+    /// Convert a [Schema] to an Arrow schema.
+    pub fn to_arrow_schema(&self) -> arrow_schema::Schema {
+        self.to_arrow_schema_with_options(&Default::default())
+    }
+
+    /// Convert a [Schema] to an Arrow schema using the provided [ArrowOptions].
+    pub fn to_arrow_schema_with_options(&self, options: &ArrowOptions) -> arrow_schema::Schema {
+        let fields = match self {
+            Schema::Struct { fields, .. } => struct_fields(options, fields, false),
+            Schema::Sequence { field, .. } => match &field.schema {
+                Some(Schema::Struct { fields, .. }) => {
+                    struct_fields(options, fields, field.status.nullability().may_be_null())
+                }
+                _ => value_field(options, self),
+            },
+            _ => value_field(options, self),
+        };
+        arrow_schema::Schema::new(fields)
+    }
+}
+
+/// Options for the conversion to an Arrow schema.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ArrowOptions {
+    /// If `true`, [Union](Schema::Union)s are converted to [Utf8](DataType::Utf8) (or
+    /// [LargeBinary](DataType::LargeBinary) if they may hold bytes) instead of Arrow's dense
+    /// unions, as Parquet does not support unions.
+    pub unions_as_strings: bool,
+}
+
+fn value_field(options: &ArrowOptions, schema: &Schema) -> Fields {
+    let nullable = matches!(schema, Schema::Null(_));
+    vec![ArrowField::new(
+        "value",
+        data_type(options, schema),
+        nullable,
+    )]
+    .into()
+}
+
+/// If `nullable` all the fields are nullable, like the columns of rows that may be null.
+fn struct_fields<'a>(
+    options: &ArrowOptions,
+    fields: impl IntoIterator<Item = (&'a StructKey, &'a Field)>,
+    nullable: bool,
+) -> Fields {
+    fields
+        .into_iter()
+        .map(|(name, field)| {
+            convert_field(options, name, field, nullable || field.status.is_option())
+        })
+        .collect()
+}
+
+/// This function also takes `nullable` because fields in structs are nullable also if they are
+/// missing, while sequences whose fields may be missing are merely empty.
+fn convert_field(options: &ArrowOptions, name: &str, field: &Field, nullable: bool) -> ArrowField {
+    match &field.schema {
+        Some(schema) => ArrowField::new(name, data_type(options, schema), nullable),
+        // Nothing is known about the values, so they can only be null.
+        None => ArrowField::new(name, DataType::Null, true),
+    }
+}
+
+/// The largest integer with the 38 digits of a [Decimal128](DataType::Decimal128).
+const MAX_DECIMAL_128: i128 = 10_i128.pow(38) - 1;
+
+fn data_type(options: &ArrowOptions, schema: &Schema) -> DataType {
+    match schema {
+        Schema::Null(_) => DataType::Null,
+        Schema::Boolean(_) => DataType::Boolean,
+        Schema::Integer(context) => {
+            let within = |low: i128, high: i128| match (context.min_max.min, context.min_max.max) {
+                (Some(min), Some(max)) => low <= min && max <= high,
+                // Without a range, values above i64::MAX could be of any size.
+                _ => !context.saw_values_above_i64_max,
+            };
+            if within(i128::from(i64::MIN), i128::from(i64::MAX)) {
+                DataType::Int64
+            } else if within(-MAX_DECIMAL_128, MAX_DECIMAL_128) {
+                // The widest integers Arrow supports.
+                DataType::Decimal128(38, 0)
+            } else {
+                // Kept as text rather than overflowing.
+                DataType::Utf8
+            }
+        }
+        Schema::Float(_) => DataType::Float64,
+        Schema::String(_) => DataType::Utf8,
+        Schema::Bytes(_) => DataType::Binary,
+        Schema::Sequence { field, .. } => DataType::List(Arc::new(convert_field(
+            options,
+            "item",
+            field,
+            field.status.may_be_null,
+        ))),
+        Schema::Struct { fields, .. } => DataType::Struct(struct_fields(options, fields, false)),
+        Schema::Union { variants } if options.unions_as_strings => {
+            if variants.iter().any(|v| matches!(v, Schema::Bytes(_))) {
+                DataType::LargeBinary
+            } else {
+                DataType::Utf8
+            }
+        }
+        Schema::Union { variants } => {
+            let fields: UnionFields = variants
+                .iter()
+                .enumerate()
+                .map(|(i, variant)| {
+                    let name = variant.variant_name().to_lowercase();
+                    let field = ArrowField::new(name, data_type(options, variant), true);
+                    (i as i8, Arc::new(field))
+                })
+                .collect();
+            DataType::Union(fields, UnionMode::Dense)
+        }
+    }
+}
@@ -0,0 +1,351 @@
+/*!
+C# classes for [System.Text.Json](https://learn.microsoft.com/dotnet/standard/serialization/system-text-json/overview).
+
+You can:
+```rust
+# use schema_analysis::InferredSchema;
+#
+let InferredSchema { schema } = serde_json::from_str(r#"{ "user_id": 1 }"#).unwrap();
+
+let output: String = schema.to_csharp("Root").unwrap();
+assert!(output.contains("public class Root"));
+assert!(output.contains(r#"[JsonPropertyName("user_id")]"#));
+assert!(output.contains("public long UserId { get; set; }"));
+```
+
+Every [Struct](Schema::Struct) becomes a separate top-level class named after the field holding
+it, the root struct (or the struct elements of a root sequence) is named after `root`.
+Other roots have no class of their own.
+Properties are nullable (`T?`) when the field may be null or missing, and
+[Union](Schema::Union)s become `object`.
+Strings are `string` by default, even when they look like base64: a wrong guess would make
+System.Text.Json fail to read the data.
+[CSharpOptions::base64_as_bytes] maps them to `byte[]` instead (see
+[StringContext::is_base64](crate::context::StringContext::is_base64)), which System.Text.Json
+reads from base64.
+
+Recursive data (like a tree of comments) produces a new class for every level of nesting,
+[CSharpOptions::max_depth] limits that by reusing the class of an ancestor instead.
+*/
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Write},
+};
+
+use crate::{Field, Schema, StructKey, StructuralEq};
+
+use super::{unique, TargetError};
+
+impl Schema {
+    /// Convert into C# classes meant to be used with System.Text.Json.
+    pub fn to_csharp(&self, root: &str) -> Result<String, TargetError> {
+        self.to_csharp_with_options(root, &Default::default())
+    }
+
+    /// Convert into C# classes meant to be used with System.Text.Json using the provided
+    /// [CSharpOptions].
+    pub fn to_csharp_with_options(
+        &self,
+        root: &str,
+        options: &CSharpOptions,
+    ) -> Result<String, TargetError> {
+        Ok(schema_to_csharp(self, root, options)?)
+    }
+}
+
+/// Options for the conversion to C#.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CSharpOptions {
+    /// If set, structs nested deeper than this (the root struct being at depth `0`) don't get
+    /// a class of their own.
+    /// They use the class of the closest enclosing struct that has all of their keys with the same
+    /// types, which makes recursive data produce a self-referential class, or `object` if there
+    /// is none.
+    pub max_depth: Option<usize>,
+    /// If set, strings that look like base64 (see
+    /// [StringContext::is_base64](crate::context::StringContext::is_base64)) become `byte[]`.
+    ///
+    /// The check is a heuristic, and a single value that is not valid base64 makes
+    /// System.Text.Json fail to read the whole document.
+    pub base64_as_bytes: bool,
+}
+
+fn schema_to_csharp(
+    schema: &Schema,
+    root: &str,
+    options: &CSharpOptions,
+) -> Result<String, fmt::Error> {
+    let mut generator = Generator {
+        max_depth: options.max_depth,
+        base64_as_bytes: options.base64_as_bytes,
+        ..Default::default()
+    };
+    generator.schema_type(schema, root, None);
+
+    let mut output = String::new();
+    writeln!(output, "#nullable enable")?;
+    writeln!(output)?;
+    writeln!(output, "using System.Collections.Generic;")?;
+    writeln!(output, "using System.Text.Json.Serialization;")?;
+
+    // New classes are pushed at the end while writing, so they are written in order.
+    let mut next = 0;
+    while let Some(class) = generator.classes.get(next) {
+        let (class_name, fields) = (class.name.clone(), class.fields);
+        writeln!(output)?;
+        writeln!(output, "public class {}", class_name)?;
+        writeln!(output, "{{")?;
+
+        // Members can't share the name of the enclosing class.
+        let mut property_names = BTreeSet::new();
+        property_names.insert(class_name.clone());
+
+        for (i, (key, field)) in fields.iter().enumerate() {
+            let property_name = unique(&mut property_names, pascal_case(key));
+            let is_option = field.status.nullability().is_option();
+            let property_type = generator.field_type(field, key, is_option, Some(next));
+
+            if i > 0 {
+                writeln!(output)?;
+            }
+            writeln!(output, "    [JsonPropertyName(\"{}\")]", escape(key))?;
+            writeln!(
+                output,
+                "    public {} {} {{ get; set; }}",
+                property_type, property_name
+            )?;
+        }
+
+        writeln!(output, "}}")?;
+        next += 1;
+    }
+
+    Ok(output)
+}
+
+/// A class to be written, `parent` is the index of the class of the enclosing struct.
+struct Class<'s> {
+    name: String,
+    fields: &'s BTreeMap<StructKey, Field>,
+    parent: Option<usize>,
+}
+
+/// Keeps track of the classes that need to be written and of the names already taken.
+#[derive(Default)]
+struct Generator<'s> {
+    classes: Vec<Class<'s>>,
+    class_names: BTreeSet<String>,
+    max_depth: Option<usize>,
+    base64_as_bytes: bool,
+}
+impl<'s> Generator<'s> {
+    fn class_name(&mut self, name: &str) -> String {
+        unique(&mut self.class_names, pascal_case(name))
+    }
+
+    /// The indices of the class `parent` and of the classes enclosing it, innermost first.
+    fn ancestors(&self, parent: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(parent, move |&i| self.classes[i].parent)
+    }
+
+    /// The type of a struct found within `parent`, adding a new class unless it is nested
+    /// too deep (see [CSharpOptions::max_depth]).
+    fn struct_type(
+        &mut self,
+        fields: &'s BTreeMap<StructKey, Field>,
+        name: &str,
+        parent: Option<usize>,
+    ) -> String {
+        let depth = self.ancestors(parent).count();
+        if matches!(self.max_depth, Some(max_depth) if depth > max_depth) {
+            let ancestor = self.ancestors(parent).find(|&i| {
+                let ancestor_fields = self.classes[i].fields;
+                !fields.is_empty()
+                    && (fields.iter()).all(|(key, field)| {
+                        matches!(ancestor_fields.get(key), Some(ancestor) if fits(field, ancestor))
+                    })
+            });
+            return match ancestor {
+                Some(i) => self.classes[i].name.clone(),
+                None => "object".into(),
+            };
+        }
+
+        let class_name = self.class_name(name);
+        self.classes.push(Class {
+            name: class_name.clone(),
+            fields,
+            parent,
+        });
+        class_name
+    }
+
+    /// Returns the C# type of a schema, adding a new class if it is (or contains) a struct.
+    /// `name` is used for the class of a struct, `parent` is the class it is found in.
+    fn schema_type(&mut self, schema: &'s Schema, name: &str, parent: Option<usize>) -> String {
+        match schema {
+            Schema::Null(_) => "object".into(),
+            Schema::Boolean(_) => "bool".into(),
+            Schema::Integer(context) if context.is_u64() => "ulong".into(),
+            Schema::Integer(_) => "long".into(),
+            Schema::Float(_) => "double".into(),
+            Schema::String(context) if self.base64_as_bytes && context.is_base64() => {
+                "byte[]".into()
+            }
+            Schema::String(_) => "string".into(),
+            Schema::Bytes(_) => "byte[]".into(),
+            Schema::Sequence { field, .. } => {
+                let is_option = field.status.nullability().may_be_null();
+                format!("List<{}>", self.field_type(field, name, is_option, parent))
+            }
+            Schema::Struct { fields, .. } => self.struct_type(fields, name, parent),
+            Schema::Union { .. } => "object".into(),
+        }
+    }
+
+    fn field_type(
+        &mut self,
+        field: &'s Field,
+        name: &str,
+        is_option: bool,
+        parent: Option<usize>,
+    ) -> String {
+        let inner = match &field.schema {
+            Some(schema) => self.schema_type(schema, name, parent),
+            None => "object".into(),
+        };
+        if is_option {
+            format!("{}?", inner)
+        } else {
+            inner
+        }
+    }
+}
+
+/// Returns `true` if a field of a struct nested too deep can be read into the property of an
+/// ancestor class.
+///
+/// Nested structs are not compared, as they are where the data recurses (and they are cut off
+/// at different depths), and sequences found empty fit any other sequence.
+fn fits(field: &Field, ancestor: &Field) -> bool {
+    let nullable = |field: &Field| field.status.nullability().may_be_null();
+    if nullable(field) && !nullable(ancestor) {
+        return false;
+    }
+    match (&field.schema, &ancestor.schema) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(Schema::Struct { .. }), Some(Schema::Struct { .. })) => true,
+        (
+            Some(Schema::Sequence { field, .. }),
+            Some(Schema::Sequence {
+                field: ancestor, ..
+            }),
+        ) => fits(field, ancestor),
+        (Some(schema), Some(ancestor)) => schema.structural_eq(ancestor),
+    }
+}
+
+/// Converts a key to a valid PascalCase C# identifier.
+fn pascal_case(key: &str) -> String {
+    let mut name = super::pascal_case(key);
+    // Keywords are all lowercase so this is only a safety net.
+    if RESERVED_WORDS.contains(&name.as_str()) {
+        name.insert(0, '@');
+    }
+    name
+}
+
+/// Escapes a key so that it can be used in a C# string literal.
+fn escape(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            '"' => "\\\"".into(),
+            '\\' => "\\\\".into(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// The C# keywords, which can only be used as identifiers with an `@` prefix.
+const RESERVED_WORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "checked",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "delegate",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "extern",
+    "false",
+    "finally",
+    "fixed",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "implicit",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "out",
+    "override",
+    "params",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sbyte",
+    "sealed",
+    "short",
+    "sizeof",
+    "stackalloc",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "uint",
+    "ulong",
+    "unchecked",
+    "unsafe",
+    "ushort",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
@@ -0,0 +1,147 @@
+/*!
+[CUE](https://cuelang.org/) definitions, a starting point to validate and constrain
+configuration.
+
+You can:
+```rust
+# use schema_analysis::InferredSchema;
+#
+let InferredSchema { schema } =
+    serde_json::from_str(r#"[{ "id": 1, "tags": ["a"] }, { "id": 2, "name": "b" }]"#).unwrap();
+
+let output: String = schema.to_cue("Root").unwrap();
+let expected = "#Root: [...{
+\tid: int
+\tname?: string
+\ttags?: [...string]
+}]
+";
+assert_eq!(output, expected);
+```
+
+The root becomes a definition named after `root`, while nested [Struct](Schema::Struct)s
+are written inline.
+Fields that may be missing are optional (`field?:`), values that may be null are a disjunction
+with `null`, [Sequence](Schema::Sequence)s are open lists (`[...T]`) and
+[Union](Schema::Union)s are disjunctions (`A | B`).
+Fields that were only ever null or missing are `null` or top (`_`).
+*/
+
+use std::fmt::{self, Write};
+
+use crate::{Field, Schema};
+
+use super::{pascal_case, TargetError};
+
+impl Schema {
+    /// Convert into a CUE definition named after `root`.
+    pub fn to_cue(&self, root: &str) -> Result<String, TargetError> {
+        let mut output = String::new();
+        write!(output, "#{}: ", pascal_case(root))?;
+        write_schema(&mut output, self, 0)?;
+        writeln!(output)?;
+        Ok(output)
+    }
+}
+
+fn write_schema(output: &mut String, schema: &Schema, indent: usize) -> fmt::Result {
+    match schema {
+        Schema::Null(_) => output.write_str("null"),
+        Schema::Boolean(_) => output.write_str("bool"),
+        Schema::Integer(_) => output.write_str("int"),
+        Schema::Float(_) => output.write_str("number"),
+        Schema::String(_) => output.write_str("string"),
+        Schema::Bytes(_) => output.write_str("bytes"),
+        Schema::Sequence { field, .. } => {
+            output.write_str("[...")?;
+            write_field(output, field, indent)?;
+            output.write_str("]")
+        }
+        Schema::Struct { fields, .. } => {
+            if fields.is_empty() {
+                return output.write_str("{}");
+            }
+            writeln!(output, "{{")?;
+            for (key, field) in fields {
+                let optional = if field.status.may_be_missing { "?" } else { "" };
+                write!(output, "{}{}{}: ", tabs(indent + 1), label(key), optional)?;
+                write_field(output, field, indent + 1)?;
+                writeln!(output)?;
+            }
+            write!(output, "{}}}", tabs(indent))
+        }
+        Schema::Union { variants } => {
+            for (i, variant) in variants.iter().enumerate() {
+                if i > 0 {
+                    output.write_str(" | ")?;
+                }
+                write_schema(output, variant, indent)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Writes the type of the values of a field, ignoring whether it may be missing.
+fn write_field(output: &mut String, field: &Field, indent: usize) -> fmt::Result {
+    let may_be_null = field.status.may_be_null;
+    match &field.schema {
+        Some(Schema::Null(_)) | None if may_be_null => output.write_str("null"),
+        None => output.write_str("_"),
+        Some(schema) => {
+            write_schema(output, schema, indent)?;
+            if may_be_null {
+                output.write_str(" | null")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn tabs(indent: usize) -> String {
+    "\t".repeat(indent)
+}
+
+/// Keywords and predeclared identifiers that are quoted to avoid any ambiguity.
+///
+/// An unquoted field named like a predeclared type would shadow it within the struct, so
+/// `{ int: 1, x: 2 }` would end up as `x: int` referring to the field rather than the type.
+#[rustfmt::skip]
+const RESERVED: &[&str] = &[
+    // Keywords.
+    "package", "import", "for", "in", "if", "let", "true", "false", "null",
+    // Predeclared types.
+    "bool", "string", "bytes", "rune", "number", "int", "uint", "float", "top",
+    "int8", "int16", "int32", "int64", "int128",
+    "uint8", "uint16", "uint32", "uint64", "uint128",
+    "float32", "float64",
+    // Predeclared functions.
+    "len", "close", "and", "or", "div", "mod", "quo", "rem",
+];
+
+/// Returns the key as is if it is a valid identifier, or quoted otherwise.
+///
+/// Identifiers starting with `_` (hidden fields) or `#` (definitions) are quoted too, as they
+/// would change the meaning of the field.
+fn label(key: &str) -> String {
+    let mut chars = key.chars();
+    let is_identifier = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier && !RESERVED.contains(&key) {
+        return key.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in key.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,344 @@
+/*!
+[Elm](https://elm-lang.org/) type aliases and the
+[Json.Decode](https://package.elm-lang.org/packages/elm/json/latest/Json-Decode) decoders that
+read them.
+
+You can:
+```rust
+# use schema_analysis::InferredSchema;
+#
+let InferredSchema { schema } =
+    serde_json::from_str(r#"{ "user_id": 1, "tags": ["a"] }"#).unwrap();
+
+let output: String = schema.to_elm("Root").unwrap();
+let expected = r#"import Json.Decode as Decode exposing (Decoder, Value)
+
+
+type alias Root =
+    { tags : List String
+    , userId : Int
+    }
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    Decode.succeed Root
+        |> andMap (Decode.field "tags" (Decode.list Decode.string))
+        |> andMap (Decode.field "user_id" Decode.int)
+
+
+andMap : Decoder a -> Decoder (a -> b) -> Decoder b
+andMap =
+    Decode.map2 (|>)
+"#;
+assert_eq!(output, expected);
+```
+
+The root gets a type alias and a decoder named after `root` (`Root` and `decodeRoot`), other
+[Struct](Schema::Struct)s get their own record alias and decoder named after the field holding
+them, with an `Item` suffix for the elements of a [Sequence](Schema::Sequence).
+Records are decoded by piping each field into the record constructor with `andMap`, which is
+written at the end, so that they are not limited to the eight fields of `Decode.map8`.
+
+Fields that may be missing or null are `Maybe`, [Sequence](Schema::Sequence)s are `List`s.
+Elm has no untagged unions, so [Union](Schema::Union)s (and [Bytes](Schema::Bytes), which JSON
+can't hold) are left as a raw `Value`, with a comment listing what was found.
+Field names are converted to lowerCamelCase, avoiding the reserved words, while the decoders
+still read the original keys.
+*/
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Write},
+};
+
+use crate::{Field, Schema, StructKey};
+
+use super::{unique, TargetError};
+
+impl Schema {
+    /// Convert into Elm type aliases and `Json.Decode` decoders, the root ones named after
+    /// `root`.
+    pub fn to_elm(&self, root: &str) -> Result<String, TargetError> {
+        Ok(schema_to_elm(self, root)?)
+    }
+}
+
+fn schema_to_elm(schema: &Schema, root: &str) -> Result<String, fmt::Error> {
+    let mut generator = Generator {
+        type_names: RESERVED_TYPE_NAMES.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    };
+
+    let mut output = String::new();
+    writeln!(
+        output,
+        "import Json.Decode as Decode exposing (Decoder, Value)"
+    )?;
+
+    // A root struct is written like any other record, otherwise the root gets an alias.
+    if !matches!(schema, Schema::Struct { .. }) {
+        let name = generator.type_name(root);
+        let (root_type, root_decoder) = generator.schema_type(schema, &name);
+        writeln!(output)?;
+        writeln!(output)?;
+        writeln!(output, "type alias {} =", name)?;
+        writeln!(output, "    {}", root_type)?;
+        write_decoder_signature(&mut output, &name)?;
+        writeln!(output, "    {}", root_decoder)?;
+    } else {
+        generator.schema_type(schema, root);
+    }
+
+    // New records are pushed at the end while writing, so they are written in order.
+    let mut next = 0;
+    let mut uses_and_map = false;
+    while let Some(record) = generator.records.get(next) {
+        let (name, fields) = (record.name.clone(), record.fields);
+        next += 1;
+
+        let mut field_names = BTreeSet::new();
+        let mut lines = Vec::with_capacity(fields.len());
+        for (key, field) in fields {
+            let field_name = unique(&mut field_names, camel_case(key));
+            let (field_type, decoder) = generator.record_field(key, field);
+            lines.push((field_name, field_type, decoder));
+        }
+
+        writeln!(output)?;
+        writeln!(output)?;
+        writeln!(output, "type alias {} =", name)?;
+        if lines.is_empty() {
+            writeln!(output, "    {{}}")?;
+        }
+        for (i, (field_name, field_type, _)) in lines.iter().enumerate() {
+            let separator = if i == 0 { '{' } else { ',' };
+            writeln!(output, "    {} {} : {}", separator, field_name, field_type)?;
+        }
+        if !lines.is_empty() {
+            writeln!(output, "    }}")?;
+        }
+
+        write_decoder_signature(&mut output, &name)?;
+        if lines.is_empty() {
+            writeln!(output, "    Decode.succeed {{}}")?;
+        } else {
+            writeln!(output, "    Decode.succeed {}", name)?;
+            uses_and_map = true;
+        }
+        for (_, _, decoder) in &lines {
+            writeln!(output, "        |> andMap {}", parens(decoder))?;
+        }
+    }
+
+    if uses_and_map {
+        writeln!(output)?;
+        writeln!(output)?;
+        writeln!(
+            output,
+            "andMap : Decoder a -> Decoder (a -> b) -> Decoder b"
+        )?;
+        writeln!(output, "andMap =")?;
+        writeln!(output, "    Decode.map2 (|>)")?;
+    }
+
+    Ok(output)
+}
+
+fn write_decoder_signature(output: &mut String, name: &str) -> fmt::Result {
+    writeln!(output)?;
+    writeln!(output)?;
+    writeln!(output, "{} : Decoder {}", decoder_name(name), name)?;
+    writeln!(output, "{} =", decoder_name(name))
+}
+
+fn decoder_name(type_name: &str) -> String {
+    format!("decode{}", type_name)
+}
+
+/// A record to be written.
+struct Record<'s> {
+    name: String,
+    fields: &'s BTreeMap<StructKey, Field>,
+}
+
+/// Keeps track of the records that need to be written and of the type names already taken.
+#[derive(Default)]
+struct Generator<'s> {
+    records: Vec<Record<'s>>,
+    type_names: BTreeSet<String>,
+}
+impl<'s> Generator<'s> {
+    fn type_name(&mut self, name: &str) -> String {
+        unique(&mut self.type_names, pascal_case(name))
+    }
+
+    /// Returns the Elm type of a schema and the expression decoding it, adding a new record
+    /// if it is (or contains) a struct, which is named after `name`.
+    fn schema_type(&mut self, schema: &'s Schema, name: &str) -> (String, String) {
+        let (elm_type, decoder) = match schema {
+            Schema::Null(_) => ("()", "Decode.null ()"),
+            Schema::Boolean(_) => ("Bool", "Decode.bool"),
+            Schema::Integer(_) => ("Int", "Decode.int"),
+            Schema::Float(_) => ("Float", "Decode.float"),
+            Schema::String(_) => ("String", "Decode.string"),
+            Schema::Bytes(_) => ("Value {- Bytes -}", "Decode.value"),
+            Schema::Sequence { field, .. } => {
+                let (elm_type, decoder) = self.field_type(field, &format!("{}Item", name));
+                let (elm_type, decoder) = if field.status.nullability().may_be_null() {
+                    maybe(&elm_type, &format!("Decode.nullable {}", parens(&decoder)))
+                } else {
+                    (elm_type, decoder)
+                };
+                return (
+                    format!("List {}", parens(&elm_type)),
+                    format!("Decode.list {}", parens(&decoder)),
+                );
+            }
+            Schema::Struct { fields, .. } => {
+                let name = self.type_name(name);
+                self.records.push(Record {
+                    name: name.clone(),
+                    fields,
+                });
+                let decoder = decoder_name(&name);
+                return (name, decoder);
+            }
+            Schema::Union { variants } => {
+                let found: Vec<&str> = variants.iter().map(variant_description).collect();
+                return (
+                    format!("Value {{- {} -}}", found.join(" | ")),
+                    "Decode.value".into(),
+                );
+            }
+        };
+        (elm_type.into(), decoder.into())
+    }
+
+    /// Like [Generator::schema_type], but fields without a schema are a raw `Value`.
+    fn field_type(&mut self, field: &'s Field, name: &str) -> (String, String) {
+        match &field.schema {
+            Some(schema) => self.schema_type(schema, name),
+            None => ("Value".into(), "Decode.value".into()),
+        }
+    }
+
+    /// Returns the type of the field of a record and the expression decoding it from `key`.
+    fn record_field(&mut self, key: &str, field: &'s Field) -> (String, String) {
+        let (elm_type, decoder) = self.field_type(field, key);
+        let key = escape(key);
+        let nullability = field.status.nullability();
+        match (nullability.may_be_missing(), nullability.may_be_null()) {
+            (true, true) => {
+                let decoder = format!(
+                    "Decode.field \"{}\" (Decode.nullable {})",
+                    key,
+                    parens(&decoder)
+                );
+                let decoder = format!("Decode.oneOf [ {}, Decode.succeed Nothing ]", decoder);
+                maybe(&elm_type, &decoder)
+            }
+            (true, false) => {
+                let decoder = format!("Decode.field \"{}\" {}", key, parens(&decoder));
+                maybe(&elm_type, &format!("Decode.maybe ({})", decoder))
+            }
+            (false, true) => {
+                let decoder = format!("Decode.nullable {}", parens(&decoder));
+                maybe(
+                    &elm_type,
+                    &format!("Decode.field \"{}\" ({})", key, decoder),
+                )
+            }
+            (false, false) => {
+                let decoder = format!("Decode.field \"{}\" {}", key, parens(&decoder));
+                (elm_type, decoder)
+            }
+        }
+    }
+}
+
+fn maybe(elm_type: &str, decoder: &str) -> (String, String) {
+    (format!("Maybe {}", parens(elm_type)), decoder.into())
+}
+
+/// Wraps a type or an expression in parentheses if it is made of several parts.
+fn parens(code: &str) -> String {
+    if code.contains(' ') {
+        format!("({})", code)
+    } else {
+        code.into()
+    }
+}
+
+/// What a variant of a union would have been in Elm, for the comment next to the `Value`.
+fn variant_description(schema: &Schema) -> &'static str {
+    match schema {
+        Schema::Null(_) => "null",
+        Schema::Boolean(_) => "Bool",
+        Schema::Integer(_) => "Int",
+        Schema::Float(_) => "Float",
+        Schema::String(_) => "String",
+        Schema::Bytes(_) => "Bytes",
+        Schema::Sequence { .. } => "List",
+        Schema::Struct { .. } => "record",
+        Schema::Union { .. } => "Value",
+    }
+}
+
+/// Converts a key to a valid PascalCase Elm type name.
+fn pascal_case(key: &str) -> String {
+    let name = super::pascal_case(key);
+    // Type names must start with an uppercase letter.
+    match name.strip_prefix('_') {
+        Some(name) => format!("N{}", name),
+        None => name,
+    }
+}
+
+/// Converts a key to a valid lowerCamelCase Elm field name.
+fn camel_case(key: &str) -> String {
+    let name = super::pascal_case(key);
+    let mut name = match name.strip_prefix('_') {
+        // Names must start with a lowercase letter.
+        Some(name) => format!("n{}", name),
+        None => {
+            let mut chars = name.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_lowercase)
+                .chain(chars)
+                .collect()
+        }
+    };
+    if RESERVED_WORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+/// Escapes a key so that it can be used in an Elm string literal.
+fn escape(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            '"' => "\\\"".into(),
+            '\\' => "\\\\".into(),
+            '\n' => "\\n".into(),
+            '\t' => "\\t".into(),
+            c if c.is_control() => format!("\\u{{{:04X}}}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// The Elm keywords, which can't be used as field names.
+const RESERVED_WORDS: &[&str] = &[
+    "alias", "as", "case", "else", "exposing", "if", "import", "in", "infix", "let", "module",
+    "of", "port", "then", "type", "where",
+];
+
+/// The types that are always in scope (or imported above), which records can't shadow.
+const RESERVED_TYPE_NAMES: &[&str] = &[
+    "Bool", "Char", "Cmd", "Decode", "Decoder", "Float", "Int", "List", "Maybe", "Never", "Order",
+    "Result", "String", "Sub", "Value",
+];
@@ -0,0 +1,189 @@
+/*!
+A lightweight json schema emitter that only depends on [serde_json].
+
+It produces the same output as the [schemars](super::schemars) target with its default
+options, for users that do not want the extra dependency.
+
+```rust
+# use schema_analysis::{InferredSchema, targets::json_schema_native::JsonSchemaVersion};
+# use serde_json::json;
+#
+let InferredSchema { schema } = serde_json::from_str(r#"{ "hello": [1, 2] }"#).unwrap();
+
+let json_schema = schema.to_json_schema_native(JsonSchemaVersion::Draft07);
+assert_eq!(
+    json_schema,
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "type": "object",
+        "required": [ "hello" ],
+        "properties": {
+            "hello": { "type": "array", "items": { "type": "integer" } },
+        },
+    })
+);
+```
+*/
+
+use serde_json::{json, Map, Value};
+
+use crate::{Field, Schema};
+
+impl Schema {
+    /// Convert into a json schema of the given version.
+    pub fn to_json_schema_native(&self, version: JsonSchemaVersion) -> Value {
+        let mut root = match schema_to_json(self, version) {
+            Value::Object(object) => object,
+            _ => unreachable!("schemas are always converted to objects"),
+        };
+        root.insert("$schema".into(), version.meta_schema().into());
+        Value::Object(root)
+    }
+}
+
+/// The currently supported json schema versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum JsonSchemaVersion {
+    /// `schemars::gen::SchemaSettings::draft07`
+    Draft07,
+    /// `schemars::gen::SchemaSettings::draft2019_09`
+    #[default]
+    Draft2019_09,
+    /// `schemars::gen::SchemaSettings::openapi3`
+    OpenApi3,
+}
+impl JsonSchemaVersion {
+    /// The uri of the meta schema for this version.
+    pub fn meta_schema(&self) -> &'static str {
+        match self {
+            JsonSchemaVersion::Draft07 => "http://json-schema.org/draft-07/schema#",
+            JsonSchemaVersion::Draft2019_09 => "https://json-schema.org/draft/2019-09/schema",
+            JsonSchemaVersion::OpenApi3 => {
+                "https://spec.openapis.org/oas/3.0/schema/2019-04-02#/definitions/Schema"
+            }
+        }
+    }
+}
+
+fn schema_to_json(schema: &Schema, version: JsonSchemaVersion) -> Value {
+    match schema {
+        Schema::Null(_) => json!({ "type": "null" }),
+        Schema::Boolean(_) => json!({ "type": "boolean" }),
+        Schema::Integer(_) => json!({ "type": "integer" }),
+        Schema::Float(_) => json!({ "type": "number" }),
+        Schema::String(_) => json!({ "type": "string" }),
+        // Same as schemars' Vec<u8>.
+        Schema::Bytes(_) => json!({
+            "type": "array",
+            "items": { "type": "integer", "format": "uint8", "minimum": 0.0 },
+        }),
+        Schema::Sequence { field, .. } => json!({
+            "type": "array",
+            "items": field_to_json(field, version),
+        }),
+        Schema::Struct { fields, .. } => {
+            let mut object = Map::new();
+            object.insert("type".into(), "object".into());
+
+            // Null values are handled in the Field function.
+            let required: Vec<Value> = schema
+                .required_field_names()
+                .into_iter()
+                .map(Value::from)
+                .collect();
+            if !required.is_empty() {
+                object.insert("required".into(), required.into());
+            }
+
+            let properties: Map<String, Value> = fields
+                .iter()
+                .map(|(k, field)| (k.to_string(), field_to_json(field, version)))
+                .collect();
+            if !properties.is_empty() {
+                object.insert("properties".into(), properties.into());
+            }
+
+            Value::Object(object)
+        }
+        // Like in schemars, null variants make the rest of the union nullable.
+        Schema::Union { variants } => {
+            let (nulls, others): (Vec<&Schema>, Vec<&Schema>) = variants
+                .iter()
+                .partition(|variant| matches!(variant, Schema::Null(_)));
+            let schema = match others.as_slice() {
+                [] => json!({ "type": "null" }),
+                [single] => schema_to_json(single, version),
+                others => json!({
+                    "anyOf": others
+                        .iter()
+                        .map(|s| schema_to_json(s, version))
+                        .collect::<Vec<_>>(),
+                }),
+            };
+            if nulls.is_empty() {
+                schema
+            } else {
+                make_nullable(schema, version)
+            }
+        }
+    }
+}
+
+/// Mirrors how schemars handles [Option]s, adding `null` to the type or marking the schema
+/// as `nullable` depending on the version.
+fn field_to_json(field: &Field, version: JsonSchemaVersion) -> Value {
+    let schema = match &field.schema {
+        Some(schema) => schema_to_json(schema, version),
+        None => Value::Bool(true),
+    };
+
+    if !field.status.nullability().may_be_null() {
+        return schema;
+    }
+    make_nullable(schema, version)
+}
+
+fn make_nullable(schema: Value, version: JsonSchemaVersion) -> Value {
+    match version {
+        JsonSchemaVersion::Draft07 | JsonSchemaVersion::Draft2019_09 => add_null_type(schema),
+        JsonSchemaVersion::OpenApi3 => {
+            let mut object = match schema {
+                Value::Object(object) => object,
+                _ => Map::new(),
+            };
+            object.insert("nullable".into(), true.into());
+            Value::Object(object)
+        }
+    }
+}
+
+fn add_null_type(mut schema: Value) -> Value {
+    let object = match &mut schema {
+        Value::Object(object) => object,
+        _ => return schema,
+    };
+    let is_only_any_of = object.len() == 1 && object.contains_key("anyOf");
+    match object.get_mut("type") {
+        Some(Value::String(ty)) => {
+            if ty != "null" {
+                let ty = std::mem::take(ty);
+                object.insert("type".into(), json!([ty, "null"]));
+            }
+            schema
+        }
+        Some(Value::Array(types)) => {
+            if !types.contains(&"null".into()) {
+                types.push("null".into());
+            }
+            schema
+        }
+        // A union gets null as one more variant, rather than being nested in another union.
+        _ if is_only_any_of => {
+            if let Some(Value::Array(variants)) = object.get_mut("anyOf") {
+                variants.push(json!({ "type": "null" }));
+            }
+            schema
+        }
+        _ => json!({ "anyOf": [schema, { "type": "null" }] }),
+    }
+}
@@ -4,16 +4,25 @@ Integration with [json_typegen](https://github.com/evestera/json_typegen)
 You can:
 ```rust
 # use schema_analysis::Schema;
-# use schema_analysis::targets::json_typegen::{Shape, OutputMode, Options};
+# use schema_analysis::targets::json_typegen::{Shape, ShapeOptions, OutputMode, Options};
 #
 # let schema: Schema = Schema::Boolean(Default::default());
 #
 // Convert to a json_typegen Shape.
 let shape: Shape = schema.to_json_typegen_shape();
 
+// Convert to a json_typegen Shape with custom options.
+let options = ShapeOptions { unsafe_integers_as_strings: true, ..Default::default() };
+let shape: Shape = schema.to_json_typegen_shape_with_options(&options);
+
 // Convert to a specific json_typegen output with default options.
 let output: String = schema.process_with_json_typegen(OutputMode::Rust).unwrap();
 
+// Convert to a json_typegen output with custom options for both the output and the Shape.
+let output: String = schema
+    .process_with_json_typegen_with_shape_options("Root", &Options::default(), &options)
+    .unwrap();
+
 // Convert a json_typegen Shape with custom options.
 let output: String = json_typegen_shared::codegen_from_shape("Root", &Shape::Bool, Options::default()).unwrap();
 ```
@@ -21,81 +30,440 @@ let output: String = json_typegen_shared::codegen_from_shape("Root", &Shape::Boo
 
 pub use json_typegen_shared::{codegen_from_shape, ErrorKind, JTError, Options, OutputMode, Shape};
 
-use crate::{Field, Schema};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{ConstantValue, Field, Schema, StructKey};
+
+use super::{pascal_case, unique, TargetError};
 
 impl Schema {
     /// Convert a [Schema] to a json_typegen [Shape].
     pub fn to_json_typegen_shape(&self) -> Shape {
-        schema_to_shape(self)
+        self.to_json_typegen_shape_with_options(&Default::default())
+    }
+
+    /// Convert a [Schema] to a json_typegen [Shape] using the provided [ShapeOptions].
+    pub fn to_json_typegen_shape_with_options(&self, options: &ShapeOptions) -> Shape {
+        ShapeConverter::new(options).schema_to_shape(self, 0, "")
     }
 
     /// Convert a [Schema] to a supported json_typegen output
-    pub fn process_with_json_typegen(&self, mode: OutputMode) -> Result<String, JTError> {
+    pub fn process_with_json_typegen(&self, mode: OutputMode) -> Result<String, TargetError> {
         let mut options = Options::default();
         options.output_mode = mode;
         self.process_with_json_typegen_options("Root", &options)
     }
 
     /// Convert a [Schema] to a supported json_typegen output using custom settings.
+    ///
+    /// Fields are emitted in the same order as they are stored in [Schema::Struct]
+    /// (json_typegen does not re-sort them), which is currently alphabetical.
+    ///
+    /// Keys that are not valid identifiers in the output language (like `first-name` or `type`
+    /// in Rust) are turned into valid field names, and the original key is kept with a rename
+    /// attribute (like `#[serde(rename = "first-name")]`).
+    ///
+    /// json_typegen drops the nullability of the elements of arrays from its
+    /// [JsonSchema](OutputMode::JsonSchema) output, so it is added back to their `type`
+    /// (like `["string", "null"]`) when the [Schema] records it.
+    ///
+    /// json_typegen has no enums, so for [Rust](OutputMode::Rust) the unions of structs that
+    /// share a discriminator (see [Schema::union_discriminator]) are generated here as enums
+    /// with `#[serde(tag = "...")]`, with a variant per value of the discriminator wrapping a
+    /// struct with the remaining fields. Other outputs still get [Shape::Any] for them.
     pub fn process_with_json_typegen_options(
         &self,
         name: &str,
         options: &Options,
-    ) -> Result<String, JTError> {
-        let shape = self.to_json_typegen_shape();
-        codegen_from_shape(name, &shape, options.clone())
+    ) -> Result<String, TargetError> {
+        self.process_with_json_typegen_with_shape_options(name, options, &Default::default())
+    }
+
+    /// Like [process_with_json_typegen_options](Schema::process_with_json_typegen_options), but
+    /// the [Shape] is built with the provided [ShapeOptions] (for example to turn the integers
+    /// unsafe in JavaScript into strings for TypeScript).
+    pub fn process_with_json_typegen_with_shape_options(
+        &self,
+        name: &str,
+        options: &Options,
+        shape_options: &ShapeOptions,
+    ) -> Result<String, TargetError> {
+        if options.output_mode == OutputMode::Rust {
+            return rust_with_tagged_enums(self, name, options, shape_options);
+        }
+
+        let shape = self.to_json_typegen_shape_with_options(shape_options);
+        let output = codegen_from_shape(name, &shape, options.clone())?;
+        if options.output_mode != OutputMode::JsonSchema {
+            return Ok(output);
+        }
+
+        let mut json_schema: serde_json::Value = serde_json::from_str(&output)?;
+        add_nullable_items(self, &mut json_schema);
+        Ok(serde_json::to_string_pretty(&json_schema)?)
     }
 }
 
+/// Options for the conversion to a [Shape].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ShapeOptions {
+    /// If `true`, integers that can't be represented exactly by a JavaScript number
+    /// (see [exceeds_js_safe_integer](crate::context::NumberContext::exceeds_js_safe_integer))
+    /// are converted to strings, so that the TypeScript outputs do not lose precision.
+    pub unsafe_integers_as_strings: bool,
+    /// If set, structs nested deeper than this (the outermost struct being at depth `0`) are
+    /// converted to [Shape::Any], so that recursive data (like a tree of comments) doesn't
+    /// produce a new type for every level of nesting.
+    pub max_depth: Option<usize>,
+}
+
 impl From<Schema> for Shape {
     fn from(schema: Schema) -> Self {
-        schema_to_shape(&schema)
+        schema.to_json_typegen_shape()
     }
 }
 
-fn schema_to_shape(schema: &Schema) -> Shape {
-    match schema {
-        Schema::Null(_) => Shape::Null,
-        Schema::Boolean(_) => Shape::Bool,
-        Schema::Integer(_) => Shape::Integer,
-        Schema::Float(_) => Shape::Floating,
-        Schema::String(_) => Shape::StringT,
-        Schema::Bytes(_) => Shape::Any,
-        Schema::Sequence { field, .. } => Shape::VecT {
-            elem_type: Box::new(convert_field(field.as_ref(), field.status.may_be_null)),
-        },
-        Schema::Struct { fields, .. } => Shape::Struct {
+/// A union of structs to be generated as a Rust enum tagged by the discriminator.
+struct TaggedEnum {
+    name: String,
+    tag: String,
+    /// The value of the discriminator, the name of the variant, and the name and [Shape] of
+    /// the struct it wraps (without the discriminator).
+    variants: Vec<(String, String, String, Shape)>,
+}
+
+/// Converts [Schema]s to [Shape]s.
+struct ShapeConverter<'a> {
+    options: &'a ShapeOptions,
+    /// If set, discriminated unions are collected here and referenced by name with
+    /// [Shape::Opaque], instead of becoming [Shape::Any].
+    tagged_enums: Option<Vec<TaggedEnum>>,
+    /// If set, the structs nested in other structs are collected here with a unique name (and
+    /// the name they were found under) and referenced with [Shape::Opaque], so that each is
+    /// generated exactly once.
+    nested_structs: Option<Vec<(String, String, Shape)>>,
+    /// The name of the root type, reserved in `type_names` so that no other type takes it.
+    root_name: Option<String>,
+    type_names: BTreeSet<String>,
+}
+impl<'a> ShapeConverter<'a> {
+    fn new(options: &'a ShapeOptions) -> Self {
+        Self {
+            options,
+            tagged_enums: None,
+            nested_structs: None,
+            root_name: None,
+            type_names: BTreeSet::new(),
+        }
+    }
+
+    /// `depth` is the number of structs enclosing `schema`, and `name` is the name a tagged
+    /// enum found there would get.
+    fn schema_to_shape(&mut self, schema: &Schema, depth: usize, name: &str) -> Shape {
+        match schema {
+            Schema::Null(_) => Shape::Null,
+            Schema::Boolean(_) => Shape::Bool,
+            Schema::Integer(context)
+                if self.options.unsafe_integers_as_strings && context.exceeds_js_safe_integer =>
+            {
+                Shape::StringT
+            }
+            Schema::Integer(_) => Shape::Integer,
+            Schema::Float(_) => Shape::Floating,
+            Schema::String(_) => Shape::StringT,
+            Schema::Bytes(_) => Shape::Any,
+            Schema::Sequence { field, .. } => Shape::VecT {
+                elem_type: Box::new(self.convert_field(
+                    field.as_ref(),
+                    field.status.nullability().may_be_null(),
+                    depth,
+                    &format!("{}Item", name),
+                )),
+            },
+            Schema::Struct { .. } if self.is_too_deep(depth) => Shape::Any,
+            Schema::Struct { fields, .. } => {
+                let shape = self.struct_to_shape(fields, depth);
+                self.nested_struct(shape, depth, name)
+            }
+            // From Shape docs:
+            // `Any` represents conflicting inference information that can not be represented by any
+            //   single shape
+            // Null variants only make the rest optional, so that a union of a single type and null
+            // is the same as an optional field of that type.
+            Schema::Union { variants } => {
+                let (nulls, others): (Vec<&Schema>, Vec<&Schema>) = variants
+                    .iter()
+                    .partition(|variant| matches!(variant, Schema::Null(_)));
+                match (nulls.is_empty(), others.as_slice()) {
+                    (_, []) => Shape::Null,
+                    (true, [single]) => self.schema_to_shape(single, depth, name),
+                    (false, [single]) => optional(self.schema_to_shape(single, depth, name)),
+                    (true, _) => self.tagged_enum(&others, depth, name).unwrap_or(Shape::Any),
+                    (false, _) => {
+                        optional(self.tagged_enum(&others, depth, name).unwrap_or(Shape::Any))
+                    }
+                }
+            }
+        }
+    }
+
+    fn struct_to_shape(&mut self, fields: &BTreeMap<StructKey, Field>, depth: usize) -> Shape {
+        Shape::Struct {
             fields: fields
                 .iter()
-                .map(|(name, field)| (name.clone(), convert_field(field, field.status.is_option())))
+                .map(|(key, field)| {
+                    let is_option = field.status.nullability().is_option();
+                    let shape = self.convert_field(field, is_option, depth + 1, &pascal_case(key));
+                    (key.to_string(), shape)
+                })
                 .collect(),
-        },
+        }
+    }
+
+    /// Collects `shape` as a nested struct and returns a reference to it, if nested structs
+    /// are being collected and `depth` is not the outermost struct.
+    ///
+    /// An identical struct found under the same name (like the same object in two variants of
+    /// a tagged enum) is reused instead of being generated again.
+    fn nested_struct(&mut self, shape: Shape, depth: usize, name: &str) -> Shape {
+        let nested_structs = match &mut self.nested_structs {
+            Some(nested_structs) if depth > 0 => nested_structs,
+            _ => return shape,
+        };
+        let name = pascal_case(name);
+        let existing = nested_structs
+            .iter()
+            .find(|(found_as, _, other)| *found_as == name && *other == shape);
+        if let Some((_, unique_name, _)) = existing {
+            return Shape::Opaque(unique_name.clone());
+        }
+        let unique_name = unique(&mut self.type_names, name.clone());
+        nested_structs.push((name, unique_name.clone(), shape));
+        Shape::Opaque(unique_name)
+    }
+
+    /// Collects the `variants` as a [TaggedEnum] and returns a reference to it, if tagged
+    /// enums are being collected and the variants are structs sharing a discriminator.
+    fn tagged_enum(&mut self, variants: &[&Schema], depth: usize, name: &str) -> Option<Shape> {
+        if self.tagged_enums.is_none() || self.is_too_deep(depth) {
+            return None;
+        }
+        let tag = Schema::discriminator(variants)?;
+        let name = match pascal_case(name) {
+            // The root itself is the enum.
+            name if depth == 0 && self.root_name.as_ref() == Some(&name) => name,
+            name => unique(&mut self.type_names, name),
+        };
+        // Reserve the place, so that enums are listed before the ones nested in them.
+        let index = self.tagged_enums.as_ref().map_or(0, Vec::len);
+
+        let mut variant_names = BTreeSet::new();
+        let mut tagged_variants = Vec::with_capacity(variants.len());
+        for variant in variants {
+            let fields = match variant {
+                Schema::Struct { fields, .. } => fields,
+                _ => unreachable!("checked by the discriminator"),
+            };
+            let value = match fields[tag.as_str()].constant_value() {
+                Some(ConstantValue::String(value)) => value.to_string(),
+                _ => unreachable!("checked by the discriminator"),
+            };
+            let variant_name = unique(&mut variant_names, pascal_case(&value));
+            let struct_name = unique(&mut self.type_names, format!("{}{}", name, variant_name));
+            // The variant structs are generated on their own, even if nested.
+            let mut shape = self.struct_to_shape(fields, depth);
+            if let Shape::Struct { fields } = &mut shape {
+                fields.remove(&tag);
+            }
+            tagged_variants.push((value, variant_name, struct_name, shape));
+        }
+
+        self.tagged_enums.as_mut()?.insert(
+            index,
+            TaggedEnum {
+                name: name.clone(),
+                tag,
+                variants: tagged_variants,
+            },
+        );
+        Some(Shape::Opaque(name))
+    }
+
+    fn is_too_deep(&self, depth: usize) -> bool {
+        matches!(self.options.max_depth, Some(max) if depth > max)
+    }
+
+    /// This function also takes `is_option` because fields in structs are considered 'optional'
+    /// also if they are missing, while sequences whose fields may be missing are merely empty.
+    ///
+    /// In both cases the field is optional if it may have a value of null/none.
+    fn convert_field(&mut self, field: &Field, is_option: bool, depth: usize, name: &str) -> Shape {
         // From Shape docs:
-        // `Any` represents conflicting inference information that can not be represented by any
-        //   single shape
-        Schema::Union { .. } => Shape::Any,
+        // `Bottom` represents the absence of any inference information
+        // `Optional(T)` represents that a value is nullable, or not always present
+        // `Null` represents optionality with no further information. [Equivalent to `Optional(Bottom)`]
+
+        // So:
+        // `Bottom` would be equivalent to a field with a `None` schema.
+        // `Optional(T)` would be equivalent to a field marked as possibly missing or possibly null.
+        // `Null` would be equivalent to a field that is both missing/null and has no schema.
+        //
+        // But json_typegen only checks for `Optional` to decide whether a field is required, so
+        // the last case is written out as `Optional(Bottom)` when the field may be missing.
+        // A field that is always present, even if only ever null, stays required.
+
+        match &field.schema {
+            Some(s) if is_option => optional(self.schema_to_shape(s, depth, name)),
+            Some(s) => self.schema_to_shape(s, depth, name),
+            None if field.status.may_be_missing => Shape::Optional(Box::new(Shape::Bottom)),
+            None if is_option => Shape::Null,
+            None => Shape::Bottom,
+        }
     }
 }
 
-/// This function also takes `is_option` because fields in structs are considered 'optional' also
-/// if they are missing, while sequences whose fields may be missing are merely empty.
+/// Generates the Rust types with json_typegen, adding the tagged enums it can't generate.
 ///
-/// In both cases the field is optional if it may have a value of null/none.
-fn convert_field(field: &Field, is_option: bool) -> Shape {
-    // From Shape docs:
-    // `Bottom` represents the absence of any inference information
-    // `Optional(T)` represents that a value is nullable, or not always present
-    // `Null` represents optionality with no further information. [Equivalent to `Optional(Bottom)`]
-
-    // So:
-    // `Bottom` would be equivalent to a field with a `None` schema.
-    // `Optional(T)` would be equivalent to a field marked as possibly missing or possibly null.
-    // `Null` would be equivalent to a field that is both missing/null and has no schema.
-
-    match &field.schema {
-        Some(s) if is_option => Shape::Optional(Box::new(schema_to_shape(s))),
-        Some(s) => schema_to_shape(s),
-        None if is_option => Shape::Null,
-        None => Shape::Bottom,
+/// Each struct is generated by a separate json_typegen call, with the structs nested in it
+/// referenced by name, so that a struct shared by several variants is generated only once and
+/// different structs found under the same key get different names. The imports of the calls
+/// are deduplicated, and the enums reuse the derives json_typegen picked (apart from
+/// [Default]).
+fn rust_with_tagged_enums(
+    schema: &Schema,
+    name: &str,
+    options: &Options,
+    shape_options: &ShapeOptions,
+) -> Result<String, TargetError> {
+    let mut converter = ShapeConverter::new(shape_options);
+    converter.tagged_enums = Some(Vec::new());
+    converter.nested_structs = Some(Vec::new());
+    let root_name = unique(&mut converter.type_names, pascal_case(name));
+    converter.root_name = Some(root_name);
+    let shape = converter.schema_to_shape(schema, 0, name);
+    let tagged_enums = converter.tagged_enums.unwrap_or_default();
+    if tagged_enums.is_empty() {
+        // Without enums json_typegen can generate everything in one go.
+        let shape = ShapeConverter::new(shape_options).schema_to_shape(schema, 0, name);
+        return Ok(codegen_from_shape(name, &shape, options.clone())?);
+    }
+
+    // If the root is itself a tagged enum there is nothing else to generate for it.
+    let mut sections = Vec::new();
+    if !matches!(&shape, Shape::Opaque(_)) {
+        sections.push(codegen_from_shape(name, &shape, options.clone())?);
+    }
+    for (_, struct_name, shape) in converter.nested_structs.unwrap_or_default() {
+        sections.push(codegen_from_shape(&struct_name, &shape, options.clone())?);
+    }
+    let mut enums = Vec::with_capacity(tagged_enums.len());
+    for tagged_enum in &tagged_enums {
+        let mut structs = Vec::with_capacity(tagged_enum.variants.len());
+        for (_, _, struct_name, shape) in &tagged_enum.variants {
+            structs.push(codegen_from_shape(struct_name, shape, options.clone())?);
+        }
+        enums.push((tagged_enum, structs));
+    }
+
+    let derive = sections
+        .iter()
+        .chain(enums.iter().flat_map(|(_, structs)| structs))
+        .flat_map(|section| section.lines())
+        .find(|line| line.starts_with("#[derive("))
+        .unwrap_or("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]")
+        .replace("Default, ", "");
+
+    for (tagged_enum, structs) in enums {
+        let mut output = String::new();
+        writeln!(output, "{}", derive)?;
+        writeln!(output, "#[serde(tag = {:?})]", tagged_enum.tag)?;
+        writeln!(output, "pub enum {} {{", tagged_enum.name)?;
+        for (value, variant_name, struct_name, _) in &tagged_enum.variants {
+            if value != variant_name {
+                writeln!(output, "    #[serde(rename = {:?})]", value)?;
+            }
+            writeln!(output, "    {}({}),", variant_name, struct_name)?;
+        }
+        writeln!(output, "}}")?;
+        sections.push(output);
+        sections.extend(structs);
+    }
+
+    // Every section brings its own imports.
+    let mut imports = Vec::new();
+    let mut body = Vec::new();
+    for section in &sections {
+        let (section_imports, section_body): (Vec<&str>, Vec<&str>) = section
+            .trim()
+            .lines()
+            .partition(|line| line.starts_with("use "));
+        for import in section_imports {
+            if !imports.contains(&import) {
+                imports.push(import);
+            }
+        }
+        let section_body = section_body.join("\n");
+        if !section_body.trim().is_empty() {
+            body.push(section_body.trim().to_string());
+        }
+    }
+    let mut output = String::new();
+    if !imports.is_empty() {
+        writeln!(output, "{}\n", imports.join("\n"))?;
+    }
+    writeln!(output, "{}", body.join("\n\n"))?;
+    Ok(output)
+}
+
+/// Adds `"null"` to the type of the `items` of the arrays whose elements may be null, as
+/// json_typegen ignores [Shape::Optional] there.
+fn add_nullable_items(schema: &Schema, json_schema: &mut serde_json::Value) {
+    match schema {
+        Schema::Sequence { field, .. } => {
+            let items = match json_schema.get_mut("items") {
+                Some(items) => items,
+                None => return,
+            };
+            let element_may_be_null = field.status.nullability().may_be_null()
+                || matches!(&field.schema, Some(Schema::Union { variants })
+                    if variants.iter().any(|v| matches!(v, Schema::Null(_))));
+            if element_may_be_null {
+                if let Some(serde_json::Value::String(t)) = items.get("type") {
+                    items["type"] = serde_json::json!([t, "null"]);
+                }
+            }
+            if let Some(element) = &field.schema {
+                add_nullable_items(element, items);
+            }
+        }
+        Schema::Struct { fields, .. } => {
+            for (key, field) in fields {
+                let property = json_schema
+                    .get_mut("properties")
+                    .and_then(|properties| properties.get_mut(&**key));
+                if let (Some(schema), Some(property)) = (&field.schema, property) {
+                    add_nullable_items(schema, property);
+                }
+            }
+        }
+        // A union with null and a single other variant is emitted as that variant.
+        Schema::Union { variants } => {
+            let mut others = variants.iter().filter(|v| !matches!(v, Schema::Null(_)));
+            if let (Some(single), None) = (others.next(), others.next()) {
+                add_nullable_items(single, json_schema);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Wraps the shape in [Shape::Optional], unless it is already optional.
+fn optional(shape: Shape) -> Shape {
+    match shape {
+        Shape::Optional(_) | Shape::Null => shape,
+        shape => Shape::Optional(Box::new(shape)),
     }
 }
@@ -0,0 +1,166 @@
+/*!
+[Mermaid](https://mermaid.js.org/) class diagrams, which can be embedded in Markdown (including
+GitHub READMEs and wikis).
+
+You can:
+```rust
+# use schema_analysis::InferredSchema;
+#
+let InferredSchema { schema } =
+    serde_json::from_str(r#"{ "id": 1, "address": { "city": "a" } }"#).unwrap();
+
+let output: String = schema.to_mermaid("Root");
+assert!(output.starts_with("classDiagram\n"));
+assert!(output.contains("    class Root {\n"));
+assert!(output.contains("    Root *-- Address : address\n"));
+```
+
+Every [Struct](Schema::Struct) becomes a class named after the field holding it, the root
+struct (or the struct elements of a root sequence) is named after `root`.
+Other roots have no class of their own.
+Structs nested in other structs are linked to their parent with a composition, with a `"*"`
+cardinality if they are inside a sequence.
+Optional fields (null or missing) have a `?` after their type.
+*/
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt::Write,
+};
+
+use crate::{Field, Schema, StructKey};
+
+use super::{pascal_case, unique};
+
+impl Schema {
+    /// Convert into a Mermaid class diagram.
+    pub fn to_mermaid(&self, root: &str) -> String {
+        let mut generator = Generator::default();
+        match self {
+            Schema::Sequence { field, .. } => {
+                if let Some(schema) = &field.schema {
+                    generator.schema_type(schema, root, None);
+                }
+            }
+            schema => {
+                generator.schema_type(schema, root, None);
+            }
+        }
+
+        let mut output = String::from("classDiagram\n");
+        while let Some((class_name, fields)) = generator.pending.pop_front() {
+            let mut relations = Vec::new();
+
+            // Writing to a String can't fail.
+            let _ = writeln!(output, "    class {} {{", class_name);
+            for (key, field) in fields {
+                let parent = Parent {
+                    class_name: &class_name,
+                    key,
+                    is_many: false,
+                    relations: &mut relations,
+                };
+                let field_type =
+                    generator.field_type(field, key, field.status.is_option(), Some(parent));
+                let _ = writeln!(output, "        {} {}", field_type, member_name(key));
+            }
+            let _ = writeln!(output, "    }}");
+
+            for relation in relations {
+                let _ = writeln!(output, "    {}", relation);
+            }
+        }
+
+        output
+    }
+}
+
+/// Keeps track of the classes that still need to be written and of the names already taken.
+#[derive(Default)]
+struct Generator<'s> {
+    pending: VecDeque<(String, Vec<(&'s StructKey, &'s Field)>)>,
+    class_names: BTreeSet<String>,
+}
+
+/// The class holding a field, used to record the relationship with nested classes.
+struct Parent<'a> {
+    class_name: &'a str,
+    key: &'a str,
+    /// Whether the value is inside a sequence.
+    is_many: bool,
+    relations: &'a mut Vec<String>,
+}
+
+impl<'s> Generator<'s> {
+    /// Returns the type of a schema, queueing a new class if it is (or contains) a struct.
+    /// `name` is used for the class of a struct.
+    fn schema_type(&mut self, schema: &'s Schema, name: &str, parent: Option<Parent>) -> String {
+        match schema {
+            Schema::Null(_) => "Null".into(),
+            Schema::Boolean(_) => "Boolean".into(),
+            Schema::Integer(_) => "Integer".into(),
+            Schema::Float(_) => "Float".into(),
+            Schema::String(_) => "String".into(),
+            Schema::Bytes(_) => "Bytes".into(),
+            Schema::Sequence { field, .. } => {
+                let is_option = field.status.nullability().may_be_null();
+                let parent = parent.map(|parent| Parent {
+                    is_many: true,
+                    ..parent
+                });
+                format!("List~{}~", self.field_type(field, name, is_option, parent))
+            }
+            Schema::Struct { fields, .. } => {
+                let class_name = unique(&mut self.class_names, pascal_case(name));
+                if let Some(parent) = parent {
+                    let cardinality = if parent.is_many { "\"*\" " } else { "" };
+                    parent.relations.push(format!(
+                        "{} *-- {}{} : {}",
+                        parent.class_name,
+                        cardinality,
+                        class_name,
+                        member_name(parent.key)
+                    ));
+                }
+                self.pending
+                    .push_back((class_name.clone(), fields.iter().collect()));
+                class_name
+            }
+            Schema::Union { .. } => "Union".into(),
+        }
+    }
+
+    fn field_type(
+        &mut self,
+        field: &'s Field,
+        name: &str,
+        is_option: bool,
+        parent: Option<Parent>,
+    ) -> String {
+        let inner = match &field.schema {
+            Some(schema) => self.schema_type(schema, name, parent),
+            None => "Any".into(),
+        };
+        if is_option {
+            format!("{}?", inner)
+        } else {
+            inner
+        }
+    }
+}
+
+/// Replaces the characters that would break the Mermaid syntax.
+fn member_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '_' || c == '-' => c,
+            _ => '_',
+        })
+        .collect();
+    if name.is_empty() {
+        "_".into()
+    } else {
+        name
+    }
+}
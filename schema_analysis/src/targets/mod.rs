@@ -1,6 +1,112 @@
 //! A module containing some integrations that enable useful applications.
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod csharp;
+pub mod cue;
+pub mod elm;
+#[cfg(feature = "json_schema_native")]
+pub mod json_schema_native;
 #[cfg(feature = "json_typegen")]
 pub mod json_typegen;
+pub mod mermaid;
+#[cfg(feature = "serde_json")]
+pub mod sample;
 #[cfg(feature = "schemars_integration")]
 pub mod schemars;
+#[cfg(feature = "serde_json")]
+pub mod summary;
+
+use std::{collections::BTreeSet, error::Error, fmt};
+
+/// The error returned by the targets that can fail.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TargetError {
+    /// The output could not be serialized to JSON.
+    #[cfg(feature = "serde_json")]
+    Serialization(serde_json::Error),
+    /// The output could not be written.
+    Formatting(fmt::Error),
+    /// json_typegen could not generate the output.
+    ///
+    /// Only the [ErrorKind](json_typegen_shared::ErrorKind) is kept, as the full
+    /// [JTError](json_typegen_shared::JTError) is not [Sync].
+    #[cfg(feature = "json_typegen")]
+    JsonTypegen(json_typegen_shared::ErrorKind),
+}
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "serde_json")]
+            TargetError::Serialization(error) => write!(f, "serialization failed: {}", error),
+            TargetError::Formatting(error) => write!(f, "formatting failed: {}", error),
+            #[cfg(feature = "json_typegen")]
+            TargetError::JsonTypegen(error) => write!(f, "json_typegen failed: {}", error),
+        }
+    }
+}
+impl Error for TargetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "serde_json")]
+            TargetError::Serialization(error) => Some(error),
+            TargetError::Formatting(error) => Some(error),
+            #[cfg(feature = "json_typegen")]
+            TargetError::JsonTypegen(_) => None,
+        }
+    }
+}
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Error> for TargetError {
+    fn from(error: serde_json::Error) -> Self {
+        TargetError::Serialization(error)
+    }
+}
+impl From<fmt::Error> for TargetError {
+    fn from(error: fmt::Error) -> Self {
+        TargetError::Formatting(error)
+    }
+}
+#[cfg(feature = "json_typegen")]
+impl From<json_typegen_shared::JTError> for TargetError {
+    fn from(error: json_typegen_shared::JTError) -> Self {
+        TargetError::JsonTypegen(error.0)
+    }
+}
+
+/// Converts a key to a PascalCase identifier made of alphanumeric characters and underscores,
+/// which does not start with a number.
+fn pascal_case(key: &str) -> String {
+    let mut name: String = key
+        .split(|c: char| !c.is_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect();
+
+    if name.is_empty() {
+        name.push_str("Field");
+    }
+    if name.starts_with(|c: char| c.is_numeric()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Appends a number to `name` until it's not in `taken`, then takes it.
+fn unique(taken: &mut BTreeSet<String>, name: String) -> String {
+    let mut candidate = name.clone();
+    let mut i = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{}{}", name, i);
+        i += 1;
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
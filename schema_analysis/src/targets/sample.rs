@@ -0,0 +1,93 @@
+/*!
+Example documents that conform to a [Schema], useful to document or mock an API.
+
+```rust
+# use schema_analysis::InferredSchema;
+# use serde_json::json;
+#
+let InferredSchema { schema } =
+    serde_json::from_str(r#"[{ "id": 7, "tags": ["a", "b"] }, { "id": 3 }]"#).unwrap();
+
+assert_eq!(schema.sample_document(), json!([{ "id": 3 }]));
+```
+*/
+
+use serde_json::{Map, Number, Value};
+
+use crate::{Field, Schema};
+
+impl Schema {
+    /// Builds an example JSON value that conforms to the [Schema].
+    ///
+    /// - Scalars use the first value sampled in the context, or a default (like `0` or `""`)
+    ///   if there is none.
+    /// - Structs contain every field that was never missing.
+    /// - Sequences contain a single element, unless no element was ever seen.
+    /// - Unions use their first variant.
+    ///
+    /// Bytes become an empty array, matching the json schema targets.
+    pub fn sample_document(&self) -> Value {
+        match self {
+            Schema::Null(_) => Value::Null,
+            Schema::Boolean(context) => Value::Bool(context.trues.get() >= context.falses.get()),
+            Schema::Integer(context) => context
+                .samples
+                .values()
+                .iter()
+                .find_map(|value| integer_to_json(*value))
+                .unwrap_or_else(|| 0.into()),
+            Schema::Float(context) => context
+                .samples
+                .values()
+                .iter()
+                .find_map(|value| Number::from_f64(value.into_inner()))
+                .map_or_else(|| 0.0.into(), Value::Number),
+            Schema::String(context) => context
+                .samples
+                .values()
+                .iter()
+                .next()
+                .cloned()
+                .unwrap_or_default()
+                .into(),
+            Schema::Bytes(_) => Value::Array(Vec::new()),
+            Schema::Sequence { field, .. } => match field_sample(field) {
+                Some(element) => Value::Array(vec![element]),
+                None => Value::Array(Vec::new()),
+            },
+            Schema::Struct { fields, .. } => {
+                let object: Map<String, Value> = fields
+                    .iter()
+                    .filter(|(_, field)| !field.status.may_be_missing)
+                    .map(|(key, field)| {
+                        (key.to_string(), field_sample(field).unwrap_or(Value::Null))
+                    })
+                    .collect();
+                Value::Object(object)
+            }
+            Schema::Union { variants } => variants
+                .first()
+                .map_or(Value::Null, Schema::sample_document),
+        }
+    }
+}
+
+/// Returns `None` if the [Field] was never seen with a value.
+fn field_sample(field: &Field) -> Option<Value> {
+    match &field.schema {
+        Some(schema) if field.status.may_be_normal => Some(schema.sample_document()),
+        _ if field.status.may_be_null => Some(Value::Null),
+        // Only possible for incomplete statuses, the schema is still the best guess.
+        Some(schema) => Some(schema.sample_document()),
+        None => None,
+    }
+}
+
+pub(super) fn integer_to_json(value: i128) -> Option<Value> {
+    use std::convert::TryFrom;
+
+    i64::try_from(value)
+        .map(Value::from)
+        .or_else(|_| u64::try_from(value).map(Value::from))
+        .ok()
+}
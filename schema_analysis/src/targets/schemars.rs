@@ -1,14 +1,16 @@
 //! Integration with [schemars](https://github.com/GREsau/schemars)
 
-use std::error::Error;
-
 use schemars::schema as schemars_types;
 
 use crate::Schema;
 
+use super::TargetError;
+
+pub use super::json_schema_native::JsonSchemaVersion;
+
 impl Schema {
     /// Convert into a json_schema using the default settings.
-    pub fn to_json_schema_with_schemars(&self) -> Result<String, impl Error> {
+    pub fn to_json_schema_with_schemars(&self) -> Result<String, TargetError> {
         self.to_json_schema_with_schemars_version(&Default::default())
     }
 
@@ -16,12 +18,21 @@ impl Schema {
     pub fn to_json_schema_with_schemars_version(
         &self,
         version: &JsonSchemaVersion,
-    ) -> Result<String, impl Error> {
+    ) -> Result<String, TargetError> {
+        self.to_json_schema_with_schemars_options(version, &Default::default())
+    }
+
+    /// Convert into a specific version of json_schema using the provided [JsonSchemaOptions].
+    pub fn to_json_schema_with_schemars_options(
+        &self,
+        version: &JsonSchemaVersion,
+        options: &JsonSchemaOptions,
+    ) -> Result<String, TargetError> {
         let settings: schemars::gen::SchemaSettings = version.to_schemars_settings();
         let mut generator: schemars::gen::SchemaGenerator = settings.into();
 
-        let root = self.to_schemars_schema(&mut generator);
-        serde_json::to_string_pretty(&root)
+        let root = self.to_schemars_schema_with_options(&mut generator, options);
+        Ok(serde_json::to_string_pretty(&root)?)
     }
 
     /// Convert using a provided generator (which also holds the settings) to a json schema.
@@ -29,26 +40,67 @@ impl Schema {
         &self,
         generator: &mut schemars::gen::SchemaGenerator,
     ) -> schemars_types::RootSchema {
-        let inner = helpers::inferred_to_schemars(generator, self);
+        self.to_schemars_schema_with_options(generator, &Default::default())
+    }
+
+    /// Convert using a provided generator (which also holds the settings) and
+    /// [JsonSchemaOptions] to a json schema.
+    pub fn to_schemars_schema_with_options(
+        &self,
+        generator: &mut schemars::gen::SchemaGenerator,
+        options: &JsonSchemaOptions,
+    ) -> schemars_types::RootSchema {
+        let inner = helpers::inferred_to_schemars(generator, options, self);
         helpers::wrap_in_root(inner, generator.settings())
     }
-}
 
-/// The currently supported json schema versions.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum JsonSchemaVersion {
-    /// `schemars::gen::SchemaSettings::draft07`
-    Draft07,
-    /// `schemars::gen::SchemaSettings::draft2019_09`
-    Draft2019_09,
-    /// `schemars::gen::SchemaSettings::openapi3`
-    OpenApi3,
-}
-impl Default for JsonSchemaVersion {
-    fn default() -> Self {
-        Self::Draft2019_09
+    /// Convert into OpenAPI component schemas, returned as
+    /// `{ "components": { "schemas": { .. } }, "$ref": "#/components/schemas/<root>" }`.
+    ///
+    /// Each struct is hoisted into its own component (referenced with a `$ref`), named after
+    /// `root` followed by the keys leading to it in PascalCase (so `{ "home_address": {..} }`
+    /// produces `Root` and `RootHomeAddress`). Identical structs share a single component, also
+    /// when some of them are nullable: those are referenced through an `allOf` marked `nullable`.
+    /// The root always gets a component, even if it is not a struct.
+    pub fn to_openapi_components(&self, root: &str) -> Result<serde_json::Value, TargetError> {
+        let settings = JsonSchemaVersion::OpenApi3.to_schemars_settings();
+        let mut generator: schemars::gen::SchemaGenerator = settings.into();
+
+        let mut schema = helpers::inferred_to_schemars(&mut generator, &Default::default(), self);
+        let root_name = helpers::component_name(root, "");
+        helpers::hoist_objects(&mut generator, &mut schema, &root_name);
+        let root_ref = match schema {
+            schemars_types::Schema::Object(schemars_types::SchemaObject {
+                reference: Some(reference),
+                ..
+            }) => reference,
+            schema => helpers::register_component(&mut generator, &root_name, schema),
+        };
+
+        Ok(serde_json::json!({
+            "components": { "schemas": serde_json::to_value(generator.definitions())? },
+            "$ref": root_ref,
+        }))
     }
 }
+
+/// Options for the conversion that are not covered by the schemars settings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct JsonSchemaOptions {
+    /// If `true`, all generated objects are closed with `additionalProperties: false`
+    /// so that unexpected keys are rejected.
+    pub deny_additional_properties: bool,
+    /// If `true`, fields that have always had the same value
+    /// (see [Field::constant_value](crate::Field::constant_value)) and are never null get a
+    /// `const` keyword.
+    pub emit_constants: bool,
+    /// If `true`, strings that all match a known semantic (like emails or UUIDs) get the
+    /// corresponding `format` keyword (see [StringContext::json_schema_format]).
+    ///
+    /// [StringContext::json_schema_format]: crate::context::StringContext::json_schema_format
+    pub emit_formats: bool,
+}
+
 impl JsonSchemaVersion {
     /// Convert the version to full settings.
     pub fn to_schemars_settings(&self) -> schemars::gen::SchemaSettings {
@@ -67,7 +119,9 @@ mod helpers {
 
     use schemars::schema as schemars_types;
 
-    use crate::{Field, Schema};
+    use crate::{ConstantValue, Field, Schema};
+
+    use super::JsonSchemaOptions;
 
     /// Wraps a [Schema](schemars_types::Schema) in a [RootSchema](schemars_types::RootSchema).
     pub fn wrap_in_root(
@@ -84,6 +138,7 @@ mod helpers {
     /// Converts an inferred [Schema] to a schemars [Schema](schemars_types::Schema).
     pub fn inferred_to_schemars(
         generator: &mut schemars::gen::SchemaGenerator,
+        options: &JsonSchemaOptions,
         inferred: &Schema,
     ) -> schemars_types::Schema {
         // Note: we can use the generator even if we don't generate the final root schema
@@ -106,13 +161,23 @@ mod helpers {
             }
             .into(),
 
-            Schema::String(_) => generator.subschema_for::<String>(),
+            Schema::String(context) => match context.json_schema_format() {
+                Some(format) if options.emit_formats => schemars_types::SchemaObject {
+                    instance_type: Some(schemars_types::InstanceType::String.into()),
+                    format: Some(format.to_string()),
+                    ..Default::default()
+                }
+                .into(),
+                _ => generator.subschema_for::<String>(),
+            },
             Schema::Bytes(_) => generator.subschema_for::<Vec<u8>>(),
 
             Schema::Sequence { field, .. } => schemars_types::SchemaObject {
                 instance_type: Some(schemars_types::InstanceType::Array.into()),
                 array: Some(Box::new(schemars_types::ArrayValidation {
-                    items: Some(internal_field_to_schemars_schema(generator, field).into()),
+                    items: Some(
+                        internal_field_to_schemars_schema(generator, options, field).into(),
+                    ),
                     ..Default::default()
                 })),
                 ..Default::default()
@@ -120,18 +185,18 @@ mod helpers {
             .into(),
 
             Schema::Struct { fields, .. } => {
-                let required: BTreeSet<String> = fields
-                    .iter()
-                    // Null values are handled in the Field function.
-                    .filter(|(_, v)| !v.status.may_be_missing)
-                    .map(|(k, _)| k.clone())
+                // Null values are handled in the Field function.
+                let required: BTreeSet<String> = inferred
+                    .required_field_names()
+                    .into_iter()
+                    .map(String::from)
                     .collect();
                 let properties = fields
                     .iter()
                     .map(|(k, field)| {
                         (
-                            k.clone(),
-                            internal_field_to_schemars_schema(generator, field),
+                            k.to_string(),
+                            internal_field_to_schemars_schema(generator, options, field),
                         )
                     })
                     .collect();
@@ -140,6 +205,11 @@ mod helpers {
                     object: Some(Box::new(schemars_types::ObjectValidation {
                         required,
                         properties,
+                        additional_properties: if options.deny_additional_properties {
+                            Some(Box::new(schemars_types::Schema::Bool(false)))
+                        } else {
+                            None
+                        },
                         ..Default::default()
                     })),
                     ..Default::default()
@@ -147,19 +217,35 @@ mod helpers {
                 .into()
             }
 
+            // Null variants make the rest of the union nullable, so that a union of a single
+            // type and null is the same as a nullable field of that type.
             Schema::Union { variants } => {
-                let json_schemas = variants
+                let (nulls, others): (Vec<&Schema>, Vec<&Schema>) = variants
                     .iter()
-                    .map(|s| inferred_to_schemars(generator, s))
-                    .collect();
-                schemars_types::SchemaObject {
-                    subschemas: Some(Box::new(schemars_types::SubschemaValidation {
-                        any_of: Some(json_schemas),
-                        ..Default::default()
-                    })),
-                    ..Default::default()
+                    .partition(|variant| matches!(variant, Schema::Null(_)));
+                let schema = match others.as_slice() {
+                    [] => generator.subschema_for::<()>(),
+                    [single] => inferred_to_schemars(generator, options, single),
+                    others => {
+                        let json_schemas = others
+                            .iter()
+                            .map(|s| inferred_to_schemars(generator, options, s))
+                            .collect();
+                        schemars_types::SchemaObject {
+                            subschemas: Some(Box::new(schemars_types::SubschemaValidation {
+                                any_of: Some(json_schemas),
+                                ..Default::default()
+                            })),
+                            ..Default::default()
+                        }
+                        .into()
+                    }
+                };
+                if nulls.is_empty() {
+                    schema
+                } else {
+                    make_nullable(generator, schema)
                 }
-                .into()
             }
         }
     }
@@ -167,6 +253,7 @@ mod helpers {
     /// Converts a [Field] into a [Schema](schemars_types::Schema).
     fn internal_field_to_schemars_schema(
         generator: &mut schemars::gen::SchemaGenerator,
+        options: &JsonSchemaOptions,
         field: &Field,
     ) -> schemars_types::Schema {
         // Note: we can use the generator even if we don't generate the final root schema
@@ -174,44 +261,202 @@ mod helpers {
         //  Do not use for complex values.
 
         let mut schema = match &field.schema {
-            Some(schema) => inferred_to_schemars(generator, schema),
+            Some(schema) => inferred_to_schemars(generator, options, schema),
             None => schemars_types::Schema::Bool(true),
         };
 
-        if field.status.may_be_null {
-            // Taken from:
-            // https://github.com/GREsau/schemars/blob/master/schemars/src/json_schema_impls/core.rs
-            if generator.settings().option_add_null_type {
-                schema = match schema {
-                    schemars_types::Schema::Bool(true) => schemars_types::Schema::Bool(true),
-                    schemars_types::Schema::Bool(false) => generator.subschema_for::<()>(),
-                    schemars_types::Schema::Object(schemars_types::SchemaObject {
-                        instance_type: Some(ref mut instance_type),
-                        ..
-                    }) => {
-                        add_null_type(instance_type);
-                        schema
+        if options.emit_constants && !field.status.nullability().may_be_null() {
+            if let Some(value) = field.constant_value().and_then(constant_to_json) {
+                let mut schema_obj = schema.into_object();
+                schema_obj.const_value = Some(value);
+                schema = schemars_types::Schema::Object(schema_obj);
+            }
+        }
+
+        if field.status.nullability().may_be_null() {
+            schema = make_nullable(generator, schema);
+        }
+        schema
+    }
+
+    /// Makes `schema` also accept null, following the generator settings.
+    fn make_nullable(
+        generator: &mut schemars::gen::SchemaGenerator,
+        mut schema: schemars_types::Schema,
+    ) -> schemars_types::Schema {
+        // Taken from:
+        // https://github.com/GREsau/schemars/blob/master/schemars/src/json_schema_impls/core.rs
+        if generator.settings().option_add_null_type {
+            schema = match schema {
+                schemars_types::Schema::Bool(true) => schemars_types::Schema::Bool(true),
+                schemars_types::Schema::Bool(false) => generator.subschema_for::<()>(),
+                schemars_types::Schema::Object(schemars_types::SchemaObject {
+                    instance_type: Some(ref mut instance_type),
+                    ..
+                }) => {
+                    add_null_type(instance_type);
+                    schema
+                }
+                // A union gets null as one more variant, rather than being nested in
+                // another union.
+                schemars_types::Schema::Object(mut object) if is_only_any_of(&object) => {
+                    let null = generator.subschema_for::<()>();
+                    if let Some(any_of) = object.subschemas.as_mut().and_then(|s| s.any_of.as_mut())
+                    {
+                        any_of.push(null);
                     }
-                    schema => schemars_types::SchemaObject {
-                        // TODO technically the schema already accepts null, so this may be unnecessary
+                    object.into()
+                }
+                schema => schemars_types::SchemaObject {
+                    // TODO technically the schema already accepts null, so this may be unnecessary
+                    subschemas: Some(Box::new(schemars_types::SubschemaValidation {
+                        any_of: Some(vec![schema, generator.subschema_for::<()>()]),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                }
+                .into(),
+            }
+        }
+        if generator.settings().option_nullable {
+            let mut schema_obj = schema.into_object();
+            schema_obj
+                .extensions
+                .insert("nullable".to_owned(), serde_json::json!(true));
+            schema = schemars_types::Schema::Object(schema_obj);
+        };
+        schema
+    }
+
+    /// Replaces the objects found in `schema` (including itself) with references to components
+    /// in the generator definitions, `name` is the component name for `schema`.
+    pub fn hoist_objects(
+        generator: &mut schemars::gen::SchemaGenerator,
+        schema: &mut schemars_types::Schema,
+        name: &str,
+    ) {
+        let object = match schema {
+            schemars_types::Schema::Object(object) => object,
+            schemars_types::Schema::Bool(_) => return,
+        };
+
+        if let Some(validation) = &mut object.object {
+            for (key, property) in validation.properties.iter_mut() {
+                hoist_objects(generator, property, &component_name(name, key));
+            }
+        }
+        if let Some(validation) = &mut object.array {
+            if let Some(schemars_types::SingleOrVec::Single(items)) = &mut validation.items {
+                hoist_objects(generator, items, name);
+            }
+        }
+        if let Some(subschemas) = &mut object.subschemas {
+            for variant in subschemas.any_of.iter_mut().flatten() {
+                hoist_objects(generator, variant, name);
+            }
+        }
+
+        if object.object.is_some() {
+            // Nullability belongs to the place the object is found in, so that nullable and
+            // non-nullable uses share a component.
+            let nullable = object.extensions.remove("nullable");
+            let hoisted = std::mem::replace(schema, schemars_types::Schema::Bool(true));
+            let reference = register_component(generator, name, hoisted);
+            *schema = match nullable {
+                None => schemars_types::Schema::new_ref(reference),
+                // OpenAPI 3.0 ignores the keywords next to a `$ref`, so it is wrapped.
+                Some(nullable) => {
+                    let mut wrapper = schemars_types::SchemaObject {
                         subschemas: Some(Box::new(schemars_types::SubschemaValidation {
-                            any_of: Some(vec![schema, generator.subschema_for::<()>()]),
+                            all_of: Some(vec![schemars_types::Schema::new_ref(reference)]),
                             ..Default::default()
                         })),
                         ..Default::default()
-                    }
-                    .into(),
+                    };
+                    wrapper.extensions.insert("nullable".to_owned(), nullable);
+                    wrapper.into()
                 }
-            }
-            if generator.settings().option_nullable {
-                let mut schema_obj = schema.into_object();
-                schema_obj
-                    .extensions
-                    .insert("nullable".to_owned(), serde_json::json!(true));
-                schema = schemars_types::Schema::Object(schema_obj);
             };
         }
-        schema
+    }
+
+    /// Adds `schema` to the generator definitions and returns its reference.
+    ///
+    /// If an identical schema is already present that one is reused, otherwise a number is
+    /// appended to `name` until it's unique.
+    pub fn register_component(
+        generator: &mut schemars::gen::SchemaGenerator,
+        name: &str,
+        schema: schemars_types::Schema,
+    ) -> String {
+        let definitions_path = generator.settings().definitions_path.clone();
+        let definitions = generator.definitions_mut();
+
+        let name = match definitions
+            .iter()
+            .find(|(_, existing)| **existing == schema)
+        {
+            Some((existing_name, _)) => existing_name.clone(),
+            None => {
+                let mut unique = name.to_string();
+                let mut i = 2;
+                while definitions.contains_key(&unique) {
+                    unique = format!("{}{}", name, i);
+                    i += 1;
+                }
+                definitions.insert(unique.clone(), schema);
+                unique
+            }
+        };
+        format!("{}{}", definitions_path, name)
+    }
+
+    /// Appends `key` in PascalCase to `parent`, dropping the characters not allowed in
+    /// component names.
+    pub fn component_name(parent: &str, key: &str) -> String {
+        let key: String = key
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .flat_map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase())
+                    .into_iter()
+                    .chain(chars)
+            })
+            .collect();
+        format!("{}{}", parent, key)
+    }
+
+    /// Returns `true` if the schema is nothing but an `anyOf`, like the ones made for unions.
+    fn is_only_any_of(object: &schemars_types::SchemaObject) -> bool {
+        match &object.subschemas {
+            Some(subschemas) if subschemas.any_of.is_some() => {
+                let only_any_of = schemars_types::SubschemaValidation {
+                    any_of: subschemas.any_of.clone(),
+                    ..Default::default()
+                };
+                **subschemas == only_any_of
+                    && *object
+                        == schemars_types::SchemaObject {
+                            subschemas: Some(Box::new(only_any_of)),
+                            ..Default::default()
+                        }
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `None` for values that json can't represent, like NaN or huge integers.
+    fn constant_to_json(value: ConstantValue) -> Option<serde_json::Value> {
+        match value {
+            ConstantValue::Boolean(value) => Some(value.into()),
+            ConstantValue::Integer(value) => serde_json::to_value(value).ok(),
+            ConstantValue::Float(value) => {
+                serde_json::Number::from_f64(value).map(serde_json::Value::Number)
+            }
+            ConstantValue::String(value) => Some(value.into()),
+        }
     }
 
     /// Taken from:
@@ -0,0 +1,212 @@
+/*!
+A compact, human-oriented overview of a [Schema], useful to quickly inspect an unknown dataset.
+
+```rust
+# use schema_analysis::InferredSchema;
+#
+let InferredSchema { schema } =
+    serde_json::from_str(r#"[{ "id": 7, "name": "ab" }, { "id": 3, "name": "abcd" }]"#).unwrap();
+
+let summary = schema.to_summary_json();
+let name = &summary["items"]["fields"]["name"];
+assert_eq!(name["type"], "String");
+assert_eq!(name["count"], 2);
+assert_eq!(name["minLength"], 2);
+assert_eq!(name["maxLength"], 4);
+```
+
+Every node has a `type` and, apart from unions, a `count`.
+Scalars list a handful of `samples` and their range (`min`/`max`, or `minLength`/`maxLength`
+for strings, bytes and sequences), while the flags are only present when they are set.
+Struct fields and sequence items also carry `mayBeNull`, `mayBeMissing` and `mayBeDuplicate`
+when relevant, and have type `Any` if no value was ever seen.
+*/
+
+use serde_json::{Map, Number, Value};
+
+use crate::{Field, Schema};
+
+use super::sample::integer_to_json;
+
+/// How many samples are listed for each node.
+const SUMMARY_SAMPLES: usize = 5;
+
+impl Schema {
+    /// Builds a compact JSON summary of the [Schema] and its contexts.
+    ///
+    /// Unlike serializing the [Schema] directly, the output is meant to be read by people:
+    /// keys are stable and camelCase, empty or unset details are left out, and only a handful
+    /// of samples are kept. See the [module](crate::targets::summary) for the shape.
+    pub fn to_summary_json(&self) -> Value {
+        let mut node = Map::new();
+        node.insert("type".into(), self.variant_name().into());
+
+        match self {
+            Schema::Null(context) => {
+                node.insert("count".into(), context.count.get().into());
+            }
+            Schema::Boolean(context) => {
+                node.insert("count".into(), context.count.get().into());
+                node.insert("trues".into(), context.trues.get().into());
+                node.insert("falses".into(), context.falses.get().into());
+            }
+            Schema::Integer(context) => {
+                node.insert("count".into(), context.count.get().into());
+                let samples = context.samples.values().iter().copied();
+                insert_samples(&mut node, samples.map(integer_value));
+                insert_range(
+                    &mut node,
+                    "min",
+                    "max",
+                    (&context.min_max.min, &context.min_max.max),
+                    |v| integer_value(*v),
+                );
+                insert_flag(
+                    &mut node,
+                    "exceedsJsSafeInteger",
+                    context.exceeds_js_safe_integer,
+                );
+                insert_flag(&mut node, "aboveI64Max", context.saw_values_above_i64_max);
+            }
+            Schema::Float(context) => {
+                node.insert("count".into(), context.count.get().into());
+                let samples = context.samples.values().iter();
+                insert_samples(&mut node, samples.map(|v| float_value(v.into_inner())));
+                insert_range(
+                    &mut node,
+                    "min",
+                    "max",
+                    (&context.min_max.min, &context.min_max.max),
+                    |v| float_value(*v),
+                );
+            }
+            Schema::String(context) => {
+                node.insert("count".into(), context.count.get().into());
+                let samples = context.samples.values().iter().cloned();
+                insert_samples(&mut node, samples.map(Value::from));
+                insert_range(
+                    &mut node,
+                    "minLength",
+                    "maxLength",
+                    (&context.min_max_length.min, &context.min_max_length.max),
+                    |v| (*v).into(),
+                );
+                if !context.suspicious_strings.is_empty() {
+                    let suspicious = context.suspicious_strings.0 .0.keys().cloned();
+                    node.insert("suspicious".into(), suspicious.collect());
+                }
+                if !context.semantic_extractor.is_empty() {
+                    let semantic = context.semantic_extractor.counts().iter();
+                    let semantic =
+                        semantic.map(|(target, count)| (target.clone(), (*count).into()));
+                    node.insert("semantic".into(), Value::Object(semantic.collect()));
+                }
+                if let Some(unit) = context.single_unit() {
+                    node.insert("unit".into(), unit.into());
+                }
+                insert_flag(&mut node, "isDatetime", context.is_datetime);
+                insert_flag(&mut node, "isBase64", context.is_base64());
+                insert_flag(&mut node, "isBase64Url", context.is_base64_url());
+                insert_flag(&mut node, "isHex", context.is_hex());
+            }
+            Schema::Bytes(context) => {
+                node.insert("count".into(), context.count.get().into());
+                insert_range(
+                    &mut node,
+                    "minLength",
+                    "maxLength",
+                    (&context.min_max_length.min, &context.min_max_length.max),
+                    |v| (*v).into(),
+                );
+            }
+            Schema::Sequence { field, context } => {
+                node.insert("count".into(), context.count.get().into());
+                insert_range(
+                    &mut node,
+                    "minLength",
+                    "maxLength",
+                    (&context.length.min, &context.length.max),
+                    |v| (*v).into(),
+                );
+                insert_flag(&mut node, "mayBeScalar", context.may_be_scalar);
+                node.insert("items".into(), field_summary(field));
+            }
+            Schema::Struct { fields, context } => {
+                node.insert("count".into(), context.count.get().into());
+                if !context.duplicate_keys.is_empty() {
+                    let duplicates = context.duplicate_keys.0.keys().cloned();
+                    node.insert("duplicateKeys".into(), duplicates.collect());
+                }
+                insert_flag(
+                    &mut node,
+                    "mayHaveNonStringKeys",
+                    context.may_have_non_string_keys,
+                );
+                let fields = fields
+                    .iter()
+                    .map(|(key, field)| (key.to_string(), field_summary(field)));
+                node.insert("fields".into(), Value::Object(fields.collect()));
+            }
+            Schema::Union { variants } => {
+                let variants = variants.iter().map(Schema::to_summary_json);
+                node.insert("variants".into(), variants.collect());
+            }
+        }
+
+        Value::Object(node)
+    }
+}
+
+fn field_summary(field: &Field) -> Value {
+    let mut summary = match &field.schema {
+        Some(schema) => schema.to_summary_json(),
+        None => {
+            let mut node = Map::new();
+            node.insert("type".into(), "Any".into());
+            Value::Object(node)
+        }
+    };
+    if let Value::Object(node) = &mut summary {
+        insert_flag(node, "mayBeNull", field.status.may_be_null);
+        insert_flag(node, "mayBeMissing", field.status.may_be_missing);
+        insert_flag(node, "mayBeDuplicate", field.status.may_be_duplicate);
+    }
+    summary
+}
+
+fn insert_samples(node: &mut Map<String, Value>, samples: impl Iterator<Item = Value>) {
+    let samples: Vec<Value> = samples.take(SUMMARY_SAMPLES).collect();
+    if !samples.is_empty() {
+        node.insert("samples".into(), samples.into());
+    }
+}
+
+fn insert_range<T>(
+    node: &mut Map<String, Value>,
+    min_key: &str,
+    max_key: &str,
+    (min, max): (&Option<T>, &Option<T>),
+    to_value: impl Fn(&T) -> Value,
+) {
+    if let Some(min) = min {
+        node.insert(min_key.into(), to_value(min));
+    }
+    if let Some(max) = max {
+        node.insert(max_key.into(), to_value(max));
+    }
+}
+
+fn insert_flag(node: &mut Map<String, Value>, key: &str, flag: bool) {
+    if flag {
+        node.insert(key.into(), true.into());
+    }
+}
+
+/// Integers that don't fit in JSON numbers are written as strings.
+fn integer_value(value: i128) -> Value {
+    integer_to_json(value).unwrap_or_else(|| value.to_string().into())
+}
+
+fn float_value(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
@@ -1,9 +1,21 @@
 //! A module holding the crate's public traits.
 
-use std::{any::Any, collections::BTreeMap, fmt::Debug};
+use std::{
+    any::Any,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
 
 use downcast_rs::Downcast;
 
+use crate::{
+    context::{
+        BooleanContext, BytesContext, MapStructContext, NullContext, NumberContext,
+        SequenceContext, StringContext,
+    },
+    Field, Schema, StructKey,
+};
+
 /**
 This trait defines a way to merge two instances of the same type.
 
@@ -39,6 +51,86 @@ pub trait Coalesce {
     fn coalesce(&mut self, other: Self)
     where
         Self: Sized;
+
+    /// Merge `other` into `self` and return the result.
+    ///
+    /// Useful when both values are owned, see [coalesce_owned] for an example.
+    fn coalesced(mut self, other: Self) -> Self
+    where
+        Self: Sized,
+    {
+        self.coalesce(other);
+        self
+    }
+}
+/**
+Merges two owned values, a free function version of [Coalesce::coalesced] which plays well
+with iterator methods like [reduce](Iterator::reduce) and [fold](Iterator::fold).
+
+```
+# use schema_analysis::{traits::coalesce_owned, InferredSchema, Schema};
+#
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let files: &[&str] = &["1", "2", "1000"];
+
+let schemas = files
+    .iter()
+    .map(|file| serde_json::from_str::<InferredSchema>(file).map(|inferred| inferred.schema))
+    .collect::<Result<Vec<Schema>, _>>()?;
+
+let merged: Schema = schemas.into_iter().reduce(coalesce_owned).unwrap();
+
+if let Schema::Integer(context) = merged {
+    assert_eq!(context.count.0, 3);
+} else {
+    unreachable!()
+}
+#
+# Ok(())
+# }
+```
+*/
+pub fn coalesce_owned<T: Coalesce>(first: T, second: T) -> T {
+    first.coalesced(second)
+}
+/**
+This trait allows merging from a shared reference, for when `other` needs to be kept around
+(for example when it lives in a cache).
+
+It has a blanket implementation on any [Clone] type implementing [Coalesce], which clones
+`other` in full before merging it.
+For a [Schema] this is a deep copy, so it costs as much as the whole tree being merged in,
+including the samples and any custom aggregators. If `other` is not needed afterwards,
+[Coalesce::coalesce] avoids the copy.
+
+```
+# use schema_analysis::{traits::CoalesceRef, InferredSchema, Schema};
+#
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let cached: Schema = serde_json::from_str::<InferredSchema>("1")?.schema;
+
+let mut schema: Schema = serde_json::from_str::<InferredSchema>("2")?.schema;
+schema.coalesce_ref(&cached);
+schema.coalesce_ref(&cached); // Still available.
+
+if let Schema::Integer(context) = schema {
+    assert_eq!(context.count.0, 3);
+} else {
+    unreachable!()
+}
+#
+# Ok(())
+# }
+```
+*/
+pub trait CoalesceRef {
+    /// Merge a copy of `other` into `self`.
+    fn coalesce_ref(&mut self, other: &Self);
+}
+impl<T: Coalesce + Clone> CoalesceRef for T {
+    fn coalesce_ref(&mut self, other: &Self) {
+        self.coalesce(other.clone());
+    }
 }
 /// This trait allows the merging of a type with an arbitrary trait object.
 ///
@@ -68,6 +160,16 @@ impl<T: Coalesce + 'static> CoalesceAny for T {
 pub trait Aggregate<V: ?Sized> {
     /// Run the internal logic on value
     fn aggregate(&mut self, value: &'_ V);
+    /// Like [aggregate](Aggregate::aggregate), for values found in a struct under `key` (the
+    /// innermost key if the value is nested in sequences).
+    ///
+    /// By default the key is ignored, aggregators that treat some fields specially (like `id`)
+    /// can override it. The contexts pass the key on to their
+    /// [Aggregators](crate::context::Aggregators).
+    fn aggregate_with_key(&mut self, key: &str, value: &'_ V) {
+        let _ = key;
+        self.aggregate(value)
+    }
 }
 /// A trait used by [crate::context::Aggregators].
 /// It's an experimental feature meant to allow library users to run arbitrary aggregation logic on
@@ -101,6 +203,20 @@ pub trait StructuralEq<Rhs: ?Sized = Self> {
         !self.structural_eq(other)
     }
 }
+macro_rules! structural_eq_by_value {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl StructuralEq for $t {
+                fn structural_eq(&self, other: &Self) -> bool {
+                    self == other
+                }
+            }
+        )*
+    };
+}
+structural_eq_by_value!(
+    bool, char, str, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
 impl StructuralEq for String {
     fn structural_eq(&self, other: &Self) -> bool {
         self == other
@@ -120,6 +236,16 @@ impl<T: StructuralEq> StructuralEq for Option<T> {
         }
     }
 }
+impl<T: StructuralEq + ?Sized> StructuralEq for Box<T> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        (**self).structural_eq(other)
+    }
+}
+impl<T: StructuralEq> StructuralEq for BTreeSet<T> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(s, o)| s.structural_eq(o))
+    }
+}
 impl<K: StructuralEq, V: StructuralEq> StructuralEq for BTreeMap<K, V> {
     fn structural_eq(&self, other: &Self) -> bool {
         self.len() == other.len()
@@ -129,3 +255,165 @@ impl<K: StructuralEq, V: StructuralEq> StructuralEq for BTreeMap<K, V> {
                 .all(|((sk, sv), (ok, ov))| sk.structural_eq(ok) && sv.structural_eq(ov))
     }
 }
+
+/**
+This trait allows the mutable traversal of a [Schema], so that transformations can be written
+without reimplementing the recursion each time.
+
+All methods recurse by default, so only the ones relevant to the transformation need to be
+overridden. If an overriding method still wishes to recurse, it can call the respective
+`walk_*` function ([walk_schema], [walk_field]) or the default methods it delegates to.
+
+```
+# use std::collections::BTreeMap;
+# use schema_analysis::{Field, Schema, SchemaVisitorMut, StructKey, context::MapStructContext};
+/// Removes all fields whose name starts with an underscore.
+struct RemovePrivate;
+impl SchemaVisitorMut for RemovePrivate {
+    fn visit_struct(&mut self, fields: &mut BTreeMap<StructKey, Field>, _: &mut MapStructContext) {
+        fields.retain(|name, _| !name.starts_with('_'));
+        for field in fields.values_mut() {
+            self.visit_field(field);
+        }
+    }
+}
+
+let mut schema = Schema::Struct {
+    fields: vec![
+        ("_id".into(), Field::with_schema(Schema::Integer(Default::default()))),
+        ("name".into(), Field::with_schema(Schema::String(Default::default()))),
+    ]
+    .into_iter()
+    .collect(),
+    context: Default::default(),
+};
+RemovePrivate.visit_schema(&mut schema);
+
+if let Schema::Struct { fields, .. } = &schema {
+    assert_eq!(fields.keys().map(|key| &**key).collect::<Vec<_>>(), vec!["name"]);
+}
+```
+*/
+pub trait SchemaVisitorMut {
+    /// Called on every [Schema], by default it dispatches to the more specific methods below.
+    ///
+    /// Override this to replace a [Schema] altogether.
+    fn visit_schema(&mut self, schema: &mut Schema) {
+        walk_schema(self, schema)
+    }
+    /// Called on every [Field], by default it visits the inner [Schema] if present.
+    fn visit_field(&mut self, field: &mut Field) {
+        walk_field(self, field)
+    }
+    /// Called on [Schema]s that hold no other [Schema] (like [Schema::Boolean]).
+    fn visit_leaf(&mut self, _schema: &mut Schema) {}
+    /// Called on the content of [Schema::Sequence], by default it visits the inner [Field].
+    fn visit_sequence(&mut self, field: &mut Field, _context: &mut SequenceContext) {
+        self.visit_field(field)
+    }
+    /// Called on the content of [Schema::Struct], by default it visits all the [Field]s.
+    fn visit_struct(
+        &mut self,
+        fields: &mut BTreeMap<StructKey, Field>,
+        _context: &mut MapStructContext,
+    ) {
+        for field in fields.values_mut() {
+            self.visit_field(field)
+        }
+    }
+    /// Called on the content of [Schema::Union], by default it visits all the variants.
+    fn visit_union(&mut self, variants: &mut Vec<Schema>) {
+        for variant in variants {
+            self.visit_schema(variant)
+        }
+    }
+}
+/// The default traversal of [SchemaVisitorMut::visit_schema], it dispatches to the specific
+/// method for the [Schema] variant.
+pub fn walk_schema<V: SchemaVisitorMut + ?Sized>(visitor: &mut V, schema: &mut Schema) {
+    use Schema::*;
+    match schema {
+        Null(_) | Boolean(_) | Integer(_) | Float(_) | String(_) | Bytes(_) => {
+            visitor.visit_leaf(schema)
+        }
+        Sequence { field, context } => visitor.visit_sequence(field, context),
+        Struct { fields, context } => visitor.visit_struct(fields, context),
+        Union { variants } => visitor.visit_union(variants),
+    }
+}
+/// The default traversal of [SchemaVisitorMut::visit_field], it visits the inner [Schema] if
+/// present.
+pub fn walk_field<V: SchemaVisitorMut + ?Sized>(visitor: &mut V, field: &mut Field) {
+    if let Some(schema) = &mut field.schema {
+        visitor.visit_schema(schema)
+    }
+}
+
+/**
+This trait transforms the contexts of a [Schema] while keeping its shape, see
+[Schema::map_contexts].
+
+There is one method per kind of context, each receives the context of a node and returns the
+one to replace it with. All methods return the context unchanged by default, so only the ones
+relevant to the transformation need to be overridden.
+
+```
+# use schema_analysis::{context::NumberContext, traits::ContextMapper, InferredSchema, Schema};
+#
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// Drops the samples of the integers.
+struct NoIntegerSamples;
+impl ContextMapper for NoIntegerSamples {
+    fn map_integer(&mut self, context: NumberContext<i128>) -> NumberContext<i128> {
+        NumberContext { samples: Default::default(), ..context }
+    }
+}
+
+let inferred: InferredSchema = serde_json::from_str("[1, 2, 3]")?;
+let schema = inferred.schema.map_contexts(&mut NoIntegerSamples);
+
+if let Schema::Sequence { field, .. } = schema {
+    if let Some(Schema::Integer(context)) = field.schema {
+        assert_eq!(context.count.0, 3);
+        assert_eq!(context.samples, Default::default());
+    }
+}
+#
+# Ok(())
+# }
+```
+*/
+pub trait ContextMapper {
+    /// Maps the context of a [Schema::Null].
+    fn map_null(&mut self, context: NullContext) -> NullContext {
+        context
+    }
+    /// Maps the context of a [Schema::Boolean].
+    fn map_boolean(&mut self, context: BooleanContext) -> BooleanContext {
+        context
+    }
+    /// Maps the context of a [Schema::Integer].
+    fn map_integer(&mut self, context: NumberContext<i128>) -> NumberContext<i128> {
+        context
+    }
+    /// Maps the context of a [Schema::Float].
+    fn map_float(&mut self, context: NumberContext<f64>) -> NumberContext<f64> {
+        context
+    }
+    /// Maps the context of a [Schema::String].
+    fn map_string(&mut self, context: StringContext) -> StringContext {
+        context
+    }
+    /// Maps the context of a [Schema::Bytes].
+    fn map_bytes(&mut self, context: BytesContext) -> BytesContext {
+        context
+    }
+    /// Maps the context of a [Schema::Sequence], after the inner [Field].
+    fn map_sequence(&mut self, context: SequenceContext) -> SequenceContext {
+        context
+    }
+    /// Maps the context of a [Schema::Struct], after all the [Field]s.
+    fn map_struct(&mut self, context: MapStructContext) -> MapStructContext {
+        context
+    }
+}
@@ -0,0 +1,291 @@
+//! Serialization of a [Schema] with more or less detail, see [SchemaView].
+
+use std::collections::BTreeMap;
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{
+    context::{
+        BooleanContext, BytesContext, MapStructContext, MinMax, NullContext, NumberContext,
+        Orderly, SequenceContext, StringContext,
+    },
+    Field, FieldStatus, Schema,
+};
+
+/// How much of a [Schema] a [SchemaView] serializes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SerializeMode {
+    /// Everything, exactly like serializing the [Schema] itself.
+    #[default]
+    Full,
+    /// The statistics of the contexts (counts, ranges and flags), without the sampled values:
+    /// the samples of numbers and strings and the key sets of structs are left empty.
+    Summary,
+    /// Only the shape: the `type` of each node, the keys and [FieldStatus](crate::FieldStatus)es
+    /// of the fields, and the variants of the unions. The contexts are left out entirely.
+    ShapeOnly,
+}
+
+/**
+A [Serialize] wrapper that serializes a [Schema] according to a [SerializeMode], so that the
+same schema can be handed out with full statistics or as a lightweight shape.
+
+```
+# use schema_analysis::{InferredSchema, SerializeMode};
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let inferred: InferredSchema = serde_json::from_str(r#"{ "id": 7 }"#)?;
+
+let shape = serde_json::to_string(&inferred.schema.view(SerializeMode::ShapeOnly))?;
+assert_eq!(
+    shape,
+    r#"{"type":"Struct","fields":{"id":{"status":{"may_be_null":false,"may_be_normal":true,"may_be_missing":false,"may_be_duplicate":false},"schema":{"type":"Integer"}}}}"#
+);
+# Ok(())
+# }
+```
+
+Only [Full](SerializeMode::Full) output can be deserialized back into a [Schema], the other
+modes drop information the contexts need.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct SchemaView<'a>(pub &'a Schema, pub SerializeMode);
+
+impl Serialize for SchemaView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.1 {
+            SerializeMode::Full => self.0.serialize(serializer),
+            SerializeMode::Summary => Summary::new(self.0).serialize(serializer),
+            SerializeMode::ShapeOnly => ShapeOnly(self.0).serialize(serializer),
+        }
+    }
+}
+
+/// Borrows a [Schema] and serializes it like the [Schema] itself, but with the sampled values of
+/// the contexts left empty, for [SerializeMode::Summary].
+///
+/// Only the contexts that hold samples are copied, without their samples, so summarizing a large
+/// schema does not clone it.
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum Summary<'a> {
+    Null(&'a NullContext),
+    Boolean(&'a BooleanContext),
+    Integer(NumberContext<i128>),
+    Float(NumberContext<f64>),
+    String(StringSummary<'a>),
+    Bytes(&'a BytesContext),
+    Sequence {
+        field: Box<SummaryField<'a>>,
+        context: &'a SequenceContext,
+    },
+    Struct {
+        fields: BTreeMap<&'a str, SummaryField<'a>>,
+        context: MapStructContext,
+    },
+    Union {
+        variants: Vec<Summary<'a>>,
+    },
+}
+impl<'a> Summary<'a> {
+    fn new(schema: &'a Schema) -> Self {
+        match schema {
+            Schema::Null(context) => Summary::Null(context),
+            Schema::Boolean(context) => Summary::Boolean(context),
+            Schema::Integer(context) => Summary::Integer(number_summary(context)),
+            Schema::Float(context) => Summary::Float(number_summary(context)),
+            Schema::String(context) => Summary::String(StringSummary::new(context)),
+            Schema::Bytes(context) => Summary::Bytes(context),
+            Schema::Sequence { field, context } => Summary::Sequence {
+                field: Box::new(SummaryField::new(field)),
+                context,
+            },
+            Schema::Struct { fields, context } => Summary::Struct {
+                fields: fields
+                    .iter()
+                    .map(|(key, field)| (key.as_ref(), SummaryField::new(field)))
+                    .collect(),
+                context: map_struct_summary(context),
+            },
+            Schema::Union { variants } => Summary::Union {
+                variants: variants.iter().map(Summary::new).collect(),
+            },
+        }
+    }
+}
+
+/// Serializes a [Field] like [Summary] does its [Schema].
+#[derive(Serialize)]
+#[serde(rename = "Field")]
+struct SummaryField<'a> {
+    status: &'a FieldStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<Summary<'a>>,
+}
+impl<'a> SummaryField<'a> {
+    fn new(field: &'a Field) -> Self {
+        Self {
+            status: &field.status,
+            schema: field.schema.as_ref().map(Summary::new),
+        }
+    }
+}
+
+/// A [StringContext] without samples, whose embedded schema is also summarized.
+#[derive(Serialize)]
+struct StringSummary<'a> {
+    #[serde(flatten)]
+    context: StringContext,
+    #[cfg(feature = "embedded_json")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    embedded_schema: Option<Box<Summary<'a>>>,
+    #[cfg(not(feature = "embedded_json"))]
+    #[serde(skip)]
+    embedded_schema: std::marker::PhantomData<&'a ()>,
+}
+impl<'a> StringSummary<'a> {
+    fn new(context: &'a StringContext) -> Self {
+        // Destructured so that any new field has to be considered here.
+        let StringContext {
+            count,
+            samples: _,
+            suspicious_strings,
+            semantic_extractor,
+            unit_extractor,
+            binary_encodings,
+            pattern_template,
+            #[cfg(feature = "script_detection")]
+            scripts,
+            suspicious_spellings,
+            min_max_length,
+            is_datetime,
+            #[cfg(feature = "embedded_json")]
+            embedded_json,
+            #[cfg(feature = "embedded_json")]
+            embedded_schema,
+            other_aggregators: _,
+        } = context;
+        Self {
+            context: StringContext {
+                count: *count,
+                samples: Default::default(),
+                suspicious_strings: suspicious_strings.clone(),
+                semantic_extractor: semantic_extractor.clone(),
+                unit_extractor: unit_extractor.clone(),
+                binary_encodings: binary_encodings.clone(),
+                pattern_template: pattern_template.clone(),
+                #[cfg(feature = "script_detection")]
+                scripts: scripts.clone(),
+                suspicious_spellings: suspicious_spellings.clone(),
+                min_max_length: min_max_length.clone(),
+                is_datetime: *is_datetime,
+                #[cfg(feature = "embedded_json")]
+                embedded_json: *embedded_json,
+                #[cfg(feature = "embedded_json")]
+                embedded_schema: None,
+                other_aggregators: Default::default(),
+            },
+            #[cfg(feature = "embedded_json")]
+            embedded_schema: embedded_schema
+                .as_deref()
+                .map(|schema| Box::new(Summary::new(schema))),
+            #[cfg(not(feature = "embedded_json"))]
+            embedded_schema: std::marker::PhantomData,
+        }
+    }
+}
+
+/// A [NumberContext] without samples.
+fn number_summary<T: Orderly>(context: &NumberContext<T>) -> NumberContext<T>
+where
+    MinMax<T>: Clone,
+{
+    let NumberContext {
+        count,
+        samples: _,
+        min_max,
+        exceeds_js_safe_integer,
+        saw_values_above_i64_max,
+        skip_samples,
+        other_aggregators: _,
+    } = context;
+    NumberContext {
+        count: *count,
+        samples: Default::default(),
+        min_max: min_max.clone(),
+        exceeds_js_safe_integer: *exceeds_js_safe_integer,
+        saw_values_above_i64_max: *saw_values_above_i64_max,
+        skip_samples: *skip_samples,
+        other_aggregators: Default::default(),
+    }
+}
+
+/// A [MapStructContext] without key sets.
+fn map_struct_summary(context: &MapStructContext) -> MapStructContext {
+    let MapStructContext {
+        count,
+        key_count,
+        duplicate_keys,
+        may_have_non_string_keys,
+        key_spellings,
+        key_sets: _,
+        key_order_consistent,
+        key_orders: _,
+        other_aggregators: _,
+    } = context;
+    MapStructContext {
+        count: *count,
+        key_count: key_count.clone(),
+        duplicate_keys: duplicate_keys.clone(),
+        may_have_non_string_keys: *may_have_non_string_keys,
+        key_spellings: key_spellings.clone(),
+        key_sets: Default::default(),
+        key_order_consistent: *key_order_consistent,
+        key_orders: Default::default(),
+        other_aggregators: Default::default(),
+    }
+}
+
+/// Serializes a [Schema] without its contexts, for [SerializeMode::ShapeOnly].
+struct ShapeOnly<'a>(&'a Schema);
+impl Serialize for ShapeOnly<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = match self.0 {
+            Schema::Sequence { .. } | Schema::Struct { .. } | Schema::Union { .. } => 2,
+            _ => 1,
+        };
+        let mut state = serializer.serialize_struct("Schema", len)?;
+        state.serialize_field("type", self.0.variant_name())?;
+        match self.0 {
+            Schema::Sequence { field, .. } => {
+                state.serialize_field("field", &ShapeOnlyField(field))?;
+            }
+            Schema::Struct { fields, .. } => {
+                let fields: BTreeMap<&str, ShapeOnlyField> = fields
+                    .iter()
+                    .map(|(key, field)| (key.as_ref(), ShapeOnlyField(field)))
+                    .collect();
+                state.serialize_field("fields", &fields)?;
+            }
+            Schema::Union { variants } => {
+                let variants: Vec<ShapeOnly> = variants.iter().map(ShapeOnly).collect();
+                state.serialize_field("variants", &variants)?;
+            }
+            _ => {}
+        }
+        state.end()
+    }
+}
+
+/// Serializes a [Field] whose [Schema] has no contexts, for [SerializeMode::ShapeOnly].
+struct ShapeOnlyField<'a>(&'a Field);
+impl Serialize for ShapeOnlyField<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Field", 2)?;
+        state.serialize_field("status", &self.0.status)?;
+        match &self.0.schema {
+            Some(schema) => state.serialize_field("schema", &ShapeOnly(schema))?,
+            None => state.skip_field("schema")?,
+        }
+        state.end()
+    }
+}
@@ -0,0 +1,190 @@
+//! Checks that the hot loops do not allocate more than needed, and that the memory held by a
+//! [Schema](schema_analysis::Schema) depends on the shape of the data, not on how many
+//! documents were analyzed.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use serde::de::DeserializeSeed;
+
+use schema_analysis::InferredSchema;
+
+struct CountingAllocator;
+
+thread_local! {
+    // Counted per thread, as the tests run in parallel.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        let _ = LIVE_BYTES.try_with(|bytes| bytes.set(bytes.get() + layout.size() as isize));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = LIVE_BYTES.try_with(|bytes| bytes.set(bytes.get() - layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The allocations made by the current thread so far.
+fn allocated() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// The bytes still allocated by the inferred schema of `data`.
+fn retained_bytes(data: &str) -> isize {
+    let before = LIVE_BYTES.with(Cell::get);
+    let inferred: InferredSchema = serde_json::from_str(data).unwrap();
+    let retained = LIVE_BYTES.with(Cell::get) - before;
+    drop(inferred);
+    retained
+}
+
+#[test]
+fn borrowed_keys_of_known_fields_are_not_allocated() {
+    const RECORDS: usize = 1_000;
+    const KEYS: usize = 20;
+
+    let record = (0..KEYS)
+        .map(|i| format!(r#""field_{}": {}"#, i, i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let data = format!(
+        "[{}]",
+        vec![format!("{{ {} }}", record); RECORDS].join(", ")
+    );
+
+    let before = allocated();
+    let inferred: InferredSchema = serde_json::from_str(&data).unwrap();
+    let allocations = allocated() - before;
+    drop(inferred);
+
+    // Records may still need a few allocations (like new samples), but there should be far
+    // fewer than one per key.
+    assert!(
+        allocations < RECORDS * KEYS / 2,
+        "{} allocations for {} keys",
+        allocations,
+        RECORDS * KEYS
+    );
+}
+
+/// Small objects with the same keys, and values that the contexts can record without
+/// allocating once they have been seen.
+fn small_objects(count: usize) -> String {
+    let object = |i: usize| {
+        format!(
+            r#"{{ "id": {}, "ok": true, "code": "AB-{}", "inner": {{ "x": 1 }} }}"#,
+            i % 3,
+            i % 3
+        )
+    };
+    format!(
+        "[{}]",
+        (0..count).map(object).collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// The allocations made while extending `inferred` with `data`.
+fn allocations(inferred: &mut InferredSchema, data: &str) -> usize {
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let before = allocated();
+    inferred.deserialize(&mut deserializer).unwrap();
+    allocated() - before
+}
+
+#[test]
+fn extending_does_not_allocate_per_object() {
+    let few = small_objects(10);
+    let many = small_objects(10_000);
+
+    let mut inferred: InferredSchema = serde_json::from_str(&few).unwrap();
+    // Fill the samplers.
+    allocations(&mut inferred, &few);
+
+    let few = allocations(&mut inferred, &few);
+    let many = allocations(&mut inferred, &many);
+    assert_eq!(few, many);
+}
+
+/// Wide records with nested structs that repeat the same keys at different paths.
+fn nested_records(records: usize) -> String {
+    let nested = (0..20)
+        .map(|i| format!(r#""field_{}": {}"#, i, i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let record = |i: usize| {
+        format!(
+            r#"{{ "id": {i}, "name": "record", {nested}, "inner": {{ "id": {i}, {nested}, "inner": {{ {nested} }} }}, "items": [ {{ "id": {i}, {nested} }} ] }}"#,
+            i = i % 100,
+            nested = nested
+        )
+    };
+    format!(
+        "[{}]",
+        (0..records).map(record).collect::<Vec<_>>().join(", ")
+    )
+}
+
+#[test]
+fn schema_memory_does_not_grow_with_documents() {
+    let small = nested_records(10);
+    let large = nested_records(10_000);
+    // Global data (like the compiled regexes) is allocated on first use.
+    retained_bytes(&small);
+
+    let small = retained_bytes(&small);
+    let large = retained_bytes(&large);
+
+    assert!(
+        large <= small + small / 10,
+        "{} bytes for 10 records, {} bytes for 10_000",
+        small,
+        large
+    );
+}
+
+/// Ten nested structs with the same long keys, or with keys of the same length that are
+/// different at each level.
+fn nested_keys(repeated: bool) -> String {
+    let mut data = String::from("1");
+    for level in 0..10 {
+        let prefix = if repeated { 0 } else { level };
+        let fields = (0..20)
+            .map(|i| format!(r#""{}_a_rather_long_key_{:02}": 1"#, prefix, i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        data = format!(r#"{{ {}, "inner": {} }}"#, fields, data);
+    }
+    data
+}
+
+#[test]
+fn repeated_keys_memory() {
+    // Global data (like the compiled regexes) is allocated on first use.
+    retained_bytes(&nested_keys(true));
+
+    let repeated = retained_bytes(&nested_keys(true));
+    let distinct = retained_bytes(&nested_keys(false));
+    // The keys of nine of the levels, which are shared when interned.
+    let key_bytes = 9 * 20 * r#"0_a_rather_long_key_00"#.len() as isize;
+
+    if cfg!(feature = "intern_keys") {
+        assert!(
+            repeated <= distinct - key_bytes / 2,
+            "{} bytes for repeated keys, {} bytes for distinct keys",
+            repeated,
+            distinct
+        );
+    } else {
+        assert_eq!(repeated, distinct);
+    }
+}
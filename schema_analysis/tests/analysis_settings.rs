@@ -0,0 +1,397 @@
+use serde::{
+    de::{value, DeserializeSeed, SeqAccess, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+
+use maplit::btreemap;
+
+use schema_analysis::{
+    context::{MapStructContext, Monotonicity},
+    Coalesce, Context, Field, InferredSchema, KeyNormalizer, Schema, StructuralEq,
+};
+
+#[test]
+fn existing_schema_with_context() {
+    let inferred: InferredSchema = serde_json::from_str(r#"{ "id": 1, "tags": ["a"] }"#).unwrap();
+
+    let mut context = Context::lean();
+    context.settings.ignore_path(&["tags"]);
+    let mut inferred = inferred.with_context(context);
+
+    let mut deserializer =
+        serde_json::Deserializer::from_str(r#"{ "id": 2, "tags": [1], "count": 3 }"#);
+    inferred.deserialize(&mut deserializer).unwrap();
+
+    let InferredSchema { schema } = inferred.into();
+    let fields = match schema {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    // The settings apply to the whole document.
+    assert!(fields["tags"].status.is_ignored);
+    match &fields["tags"].schema {
+        Some(Schema::Sequence { field, .. }) => {
+            assert!(matches!(field.schema, Some(Schema::String(_))))
+        }
+        _ => unreachable!(),
+    }
+    // New nodes are created from the context.
+    match &fields["count"].schema {
+        Some(Schema::Integer(context)) => {
+            assert!(context.skip_samples);
+            assert!(context.samples.values().is_empty());
+        }
+        _ => unreachable!(),
+    }
+    // While existing ones keep their configuration.
+    match &fields["id"].schema {
+        Some(Schema::Integer(context)) => assert_eq!(context.samples.values().len(), 2),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn ignored_paths_are_not_analyzed() {
+    let mut context = Context::default();
+    context.settings.ignore_path(&["text"]);
+    context.settings.ignore_path(&["nested", "skipped"]);
+
+    let data = r#"{
+        "id": 1,
+        "text": { "huge": [ "formatted", { "text": true } ] },
+        "nested": [ { "kept": "x", "skipped": [1, 2, 3] }, { "kept": "y" } ]
+    }"#;
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let mut inferred = context.deserialize_schema(&mut deserializer).unwrap();
+
+    // The same settings are used for further documents.
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "id": 2, "text": null }"#);
+    inferred.deserialize(&mut deserializer).unwrap();
+
+    let mut id = Field::with_schema(Schema::Integer(Default::default()));
+    id.status.may_be_normal = true;
+
+    let mut text = Field::default();
+    text.status.is_ignored = true;
+
+    let mut kept = Field::with_schema(Schema::String(Default::default()));
+    kept.status.may_be_normal = true;
+    let mut skipped = Field::default();
+    skipped.status.is_ignored = true;
+    skipped.status.may_be_missing = true;
+    let mut element = Field::with_schema(Schema::Struct {
+        fields: btreemap! {
+            "kept".into() => kept,
+            "skipped".into() => skipped,
+        },
+        context: Default::default(),
+    });
+    element.status.may_be_normal = true;
+    let mut nested = Field::with_schema(Schema::Sequence {
+        field: Box::new(element),
+        context: Default::default(),
+    });
+    nested.status.may_be_normal = true;
+    nested.status.may_be_missing = true;
+
+    let expected = Schema::Struct {
+        fields: btreemap! {
+            "id".into() => id,
+            "text".into() => text,
+            "nested".into() => nested,
+        },
+        context: Default::default(),
+    };
+    assert!(inferred.schema.structural_eq(&expected));
+}
+
+/// A format-less deserializer for arbitrarily deeply nested sequences (`[[[..[null]..]]]`),
+/// the usual formats have recursion limits of their own that would kick in first.
+struct Nested(usize);
+
+impl<'de> Deserializer<'de> for Nested {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            0 => visitor.visit_unit(),
+            depth => visitor.visit_seq(NestedSeq(Some(depth - 1))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct NestedSeq(Option<usize>);
+
+impl<'de> SeqAccess<'de> for NestedSeq {
+    type Error = value::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.0
+            .take()
+            .map(|depth| seed.deserialize(Nested(depth)))
+            .transpose()
+    }
+}
+
+#[test]
+fn max_depth() {
+    let error = InferredSchema::deserialize(Nested(10_000)).unwrap_err();
+    assert!(error.to_string().contains("maximum nesting depth"));
+
+    // Extending an existing schema is guarded too.
+    let mut inferred = InferredSchema::deserialize(Nested(100)).unwrap();
+    assert!(inferred.deserialize(Nested(10_000)).is_err());
+    assert!(inferred.deserialize(Nested(100)).is_ok());
+
+    let mut context = Context::default();
+    context.settings.max_depth = Some(3);
+    assert!(context.clone().deserialize_schema(Nested(3)).is_ok());
+    assert!(context.deserialize_schema(Nested(4)).is_err());
+}
+
+#[test]
+fn normalized_keys_share_a_field() {
+    let mut context = Context::default();
+    context.settings.key_normalizer = Some(KeyNormalizer::Lowercase);
+
+    let data = r#"[
+        { "UserId": 1 },
+        { "userId": 2 },
+        { "userid": 3, "Name": "x", "name": "y" }
+    ]"#;
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let inferred = context.deserialize_schema(&mut deserializer).unwrap();
+
+    let (fields, context) = match inferred.schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Struct { fields, context }) => (fields, context),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    assert_eq!(
+        fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+        ["name", "userid"]
+    );
+    match &fields["userid"].schema {
+        Some(Schema::Integer(context)) => assert_eq!(context.count.0, 3),
+        other => panic!("{:?}", other),
+    }
+    assert!(!fields["userid"].status.may_be_missing);
+    assert!(fields["name"].status.may_be_missing);
+
+    let spellings = |key: &str| {
+        context.key_spellings[key]
+            .0
+            .iter()
+            .map(|(spelling, count)| (spelling.as_str(), *count))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        spellings("userid"),
+        [("UserId", 1), ("userId", 1), ("userid", 1)]
+    );
+    // Different keys that collide in the same struct are merged and marked as duplicates.
+    assert_eq!(spellings("name"), [("Name", 1), ("name", 1)]);
+    assert!(fields["name"].status.may_be_duplicate);
+    assert_eq!(context.duplicate_keys.0.get("name"), Some(&1));
+}
+
+#[test]
+fn key_normalizers() {
+    assert_eq!(KeyNormalizer::Lowercase.normalize("userid"), None);
+    assert_eq!(
+        KeyNormalizer::Lowercase.normalize("User_Id").as_deref(),
+        Some("user_id")
+    );
+    assert_eq!(KeyNormalizer::Fold.normalize("userid"), None);
+    assert_eq!(
+        KeyNormalizer::Fold.normalize("User_Id").as_deref(),
+        Some("userid")
+    );
+    assert_eq!(
+        KeyNormalizer::Fold.normalize("user-id ").as_deref(),
+        Some("userid")
+    );
+}
+
+#[test]
+fn monotonicity() {
+    let mut context = Context::default();
+    context.settings.track_monotonicity = true;
+
+    let monotonicity = |data: &str| {
+        let mut deserializer = serde_json::Deserializer::from_str(data);
+        let inferred = context
+            .clone()
+            .deserialize_schema(&mut deserializer)
+            .unwrap();
+        match inferred.schema {
+            // The outer sequence holds sequences, so only the inner one is monotonic.
+            Schema::Sequence { field, context } => {
+                assert_eq!(
+                    context.monotonicity,
+                    Some(Monotonicity {
+                        is_increasing: false,
+                        is_decreasing: false
+                    })
+                );
+                match field.schema {
+                    Some(Schema::Sequence { context, .. }) => context.monotonicity.unwrap(),
+                    other => panic!("{:?}", other),
+                }
+            }
+            other => panic!("{:?}", other),
+        }
+    };
+
+    assert_eq!(
+        monotonicity("[[1, 2, 3], [4, 4.5, 5], []]"),
+        Monotonicity {
+            is_increasing: true,
+            is_decreasing: false
+        }
+    );
+    assert_eq!(
+        monotonicity("[[3, 2, 1], [1.5, 1]]"),
+        Monotonicity {
+            is_increasing: false,
+            is_decreasing: true
+        }
+    );
+    assert_eq!(
+        monotonicity("[[1, 2, 3], [3, 2, 1]]"),
+        Monotonicity {
+            is_increasing: false,
+            is_decreasing: false
+        }
+    );
+    assert_eq!(
+        monotonicity("[[1, 2, null]]"),
+        Monotonicity {
+            is_increasing: false,
+            is_decreasing: false
+        }
+    );
+
+    // Nothing is recorded by default.
+    let inferred: InferredSchema = serde_json::from_str("[1, 2, 3]").unwrap();
+    match inferred.schema {
+        Schema::Sequence { context, .. } => assert_eq!(context.monotonicity, None),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn normalize_suspicious_strings() {
+    let suspicious = |normalize: bool| {
+        let mut context = Context::default();
+        context.settings.normalize_suspicious_strings = normalize;
+        let mut deserializer = serde_json::Deserializer::from_str(r#"["N/A", "n/a", "NA", "ok"]"#);
+        let inferred = context.deserialize_schema(&mut deserializer).unwrap();
+        match inferred.schema {
+            Schema::Sequence { field, .. } => match field.schema {
+                Some(Schema::String(context)) => context,
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        }
+    };
+
+    // By default each spelling is counted separately.
+    let separate = suspicious(false);
+    assert_eq!(
+        separate.suspicious_strings.0 .0,
+        btreemap! { "N/A".into() => 1, "n/a".into() => 1, "NA".into() => 1 }
+    );
+    assert!(separate.suspicious_spellings.is_empty());
+
+    let grouped = suspicious(true);
+    assert_eq!(
+        grouped.suspicious_strings.0 .0,
+        btreemap! { "n/a".into() => 2, "na".into() => 1 }
+    );
+    assert_eq!(
+        grouped.suspicious_spellings["n/a"].0,
+        btreemap! { "N/A".into() => 1, "n/a".into() => 1 }
+    );
+    assert_eq!(
+        grouped.suspicious_spellings["na"].0,
+        btreemap! { "NA".into() => 1 }
+    );
+}
+
+#[test]
+fn key_sets() {
+    let mut context = Context::default();
+    context.settings.track_key_sets = true;
+
+    let infer = |data: &str| {
+        let mut deserializer = serde_json::Deserializer::from_str(data);
+        context
+            .clone()
+            .deserialize_schema(&mut deserializer)
+            .unwrap()
+    };
+    let key_sets = |schema: &Schema| match schema {
+        Schema::Sequence { field, .. } => match &field.schema {
+            Some(Schema::Struct { context, .. }) => context.key_sets.counts().clone(),
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    };
+
+    // `discount` is only present along with `coupon`.
+    let mut inferred = infer(
+        r#"[
+            { "id": 1 },
+            { "id": 2, "coupon": "A", "discount": 5 },
+            { "discount": 10, "id": 3, "coupon": "B" },
+            { "id": 4, "note": "x" }
+        ]"#,
+    );
+    assert_eq!(
+        key_sets(&inferred.schema),
+        btreemap! {
+            r#"["id"]"#.to_string() => 1,
+            r#"["coupon", "discount", "id"]"#.to_string() => 2,
+            r#"["id", "note"]"#.to_string() => 1,
+        }
+    );
+
+    // Extending and merging keep counting.
+    let mut deserializer = serde_json::Deserializer::from_str(r#"[{ "id": 5 }]"#);
+    (&mut inferred).deserialize(&mut deserializer).unwrap();
+    inferred.coalesce(infer(r#"[{ "id": 6, "note": "y" }]"#));
+    let expected = btreemap! {
+        r#"["id"]"#.to_string() => 2,
+        r#"["coupon", "discount", "id"]"#.to_string() => 2,
+        r#"["id", "note"]"#.to_string() => 2,
+    };
+    assert_eq!(key_sets(&inferred.schema), expected);
+
+    // Not tracked by default.
+    let InferredSchema { schema } = serde_json::from_str(r#"[{ "id": 1 }]"#).unwrap();
+    assert!(key_sets(&schema).is_empty());
+}
+
+#[test]
+fn key_set_names_are_json() {
+    assert_eq!(
+        MapStructContext::key_set_name(&["b\"c", "a\\", "é\n", "\u{1}"]),
+        r#"["\u0001", "a\\", "b\"c", "é\n"]"#
+    );
+    let name = MapStructContext::key_set_name(&["id", "naïve", "x\ty"]);
+    let keys: Vec<String> = serde_json::from_str(&name).unwrap();
+    assert_eq!(keys, vec!["id", "naïve", "x\ty"]);
+}
@@ -0,0 +1,230 @@
+//! Randomized tests of the invariants of [Coalesce] for [Schema].
+
+use proptest::prelude::*;
+use serde::de::DeserializeSeed;
+
+use schema_analysis::{
+    traits::walk_field, Aggregate, Coalesce, Field, InferredSchema, Schema, SchemaVisitorMut,
+    StructuralEq,
+};
+
+fn leaf() -> impl Strategy<Value = Schema> {
+    prop_oneof![
+        Just(Schema::Null(Default::default())),
+        any::<bool>().prop_map(|value| {
+            let mut context = schema_analysis::context::BooleanContext::default();
+            context.aggregate(&value);
+            Schema::Boolean(context)
+        }),
+        any::<i64>().prop_map(|value| {
+            let mut context = schema_analysis::context::NumberContext::default();
+            context.aggregate(&i128::from(value));
+            Schema::Integer(context)
+        }),
+        (-1e6..1e6).prop_map(|value: f64| {
+            let mut context = schema_analysis::context::NumberContext::default();
+            context.aggregate(&value);
+            Schema::Float(context)
+        }),
+        "[a-z]{0,3}".prop_map(|value| {
+            let mut context = schema_analysis::context::StringContext::default();
+            context.aggregate(value.as_str());
+            Schema::String(context)
+        }),
+        Just(Schema::Bytes(Default::default())),
+    ]
+}
+
+fn field(schema: impl Strategy<Value = Schema>) -> impl Strategy<Value = Field> {
+    (
+        proptest::option::weighted(0.8, schema),
+        any::<(bool, bool, bool)>(),
+        any::<(Option<u8>, Option<u8>)>(),
+    )
+        .prop_map(
+            |(schema, (may_be_null, may_be_missing, may_be_duplicate), (first, last))| {
+                let mut field = Field {
+                    schema,
+                    ..Default::default()
+                };
+                field.status.may_be_normal = field.schema.is_some();
+                field.status.may_be_null = may_be_null;
+                field.status.may_be_missing = may_be_missing;
+                field.status.may_be_duplicate = may_be_duplicate;
+                field.status.first_seen = first.map(usize::from);
+                field.status.last_seen = last.map(usize::from);
+                field
+            },
+        )
+}
+
+/// Arbitrary normalized schemas, unions are only built by coalescing.
+fn schema() -> impl Strategy<Value = Schema> {
+    leaf().prop_recursive(4, 32, 4, |inner| {
+        prop_oneof![
+            field(inner.clone()).prop_map(|field| Schema::Sequence {
+                field: Box::new(field),
+                context: Default::default(),
+            }),
+            proptest::collection::btree_map("[a-c]", field(inner.clone()), 0..4).prop_map(
+                |fields| Schema::Struct {
+                    fields: fields
+                        .into_iter()
+                        .map(|(k, v)| (k.as_str().into(), v))
+                        .collect(),
+                    context: Default::default(),
+                }
+            ),
+            proptest::collection::vec(inner, 2..4)
+                .prop_map(|schemas| schemas.into_iter().reduce(Coalesce::coalesced).unwrap()),
+        ]
+    })
+}
+
+/// Clears the documents in which the fields were seen.
+struct ForgetSeen;
+impl SchemaVisitorMut for ForgetSeen {
+    fn visit_field(&mut self, field: &mut Field) {
+        field.status.first_seen = None;
+        field.status.last_seen = None;
+        walk_field(self, field);
+    }
+}
+
+proptest! {
+    #[test]
+    fn generated_schemas_are_normalized(schema in schema()) {
+        prop_assert!(schema.is_normalized(), "{:#?}", schema);
+    }
+
+    #[test]
+    fn seen_documents_do_not_change_the_shape(schema in schema()) {
+        let mut forgotten = schema.clone();
+        ForgetSeen.visit_schema(&mut forgotten);
+        prop_assert!(schema.structural_eq(&forgotten), "{:#?}", schema);
+        prop_assert_eq!(schema.shape_fingerprint(), forgotten.shape_fingerprint());
+    }
+
+    #[test]
+    fn coalesce_preserves_normalization(a in schema(), b in schema()) {
+        let coalesced = a.coalesced(b);
+        prop_assert!(coalesced.is_normalized(), "{:#?}", coalesced);
+    }
+
+    #[test]
+    fn coalesce_is_commutative(a in schema(), b in schema()) {
+        let ab = a.clone().coalesced(b.clone());
+        let ba = b.coalesced(a);
+        prop_assert!(ab.structural_eq(&ba), "{:#?}\n{:#?}", ab, ba);
+    }
+
+    #[test]
+    fn coalesce_is_associative(a in schema(), b in schema(), c in schema()) {
+        let ab_c = a.clone().coalesced(b.clone()).coalesced(c.clone());
+        let a_bc = a.coalesced(b.coalesced(c));
+        prop_assert!(ab_c.structural_eq(&a_bc), "{:#?}\n{:#?}", ab_c, a_bc);
+    }
+
+    #[test]
+    fn coalesce_order_does_not_matter(
+        schemas in proptest::collection::vec(schema(), 1..6),
+        seed in any::<u64>(),
+    ) {
+        let forward = schemas.clone().into_iter().reduce(Coalesce::coalesced).unwrap();
+
+        // A cheap deterministic shuffle.
+        let mut shuffled: Vec<(u64, Schema)> = schemas
+            .into_iter()
+            .enumerate()
+            .map(|(i, schema)| ((i as u64 + 1).wrapping_mul(seed | 1).rotate_left(17), schema))
+            .collect();
+        shuffled.sort_by_key(|(key, _)| *key);
+        let shuffled = shuffled
+            .into_iter()
+            .map(|(_, schema)| schema)
+            .reduce(Coalesce::coalesced)
+            .unwrap();
+
+        prop_assert!(shuffled.is_normalized());
+        prop_assert!(forward.structural_eq(&shuffled), "{:#?}\n{:#?}", forward, shuffled);
+    }
+}
+
+#[test]
+fn unnormalized_schemas_are_detected() {
+    let nested = Schema::Union {
+        variants: vec![
+            Schema::Boolean(Default::default()),
+            Schema::Union {
+                variants: vec![Schema::Null(Default::default())],
+            },
+        ],
+    };
+    assert!(!nested.is_normalized());
+
+    let duplicate = Schema::Sequence {
+        field: Box::new(Field::with_schema(Schema::Union {
+            variants: vec![
+                Schema::Integer(Default::default()),
+                Schema::Integer(Default::default()),
+            ],
+        })),
+        context: Default::default(),
+    };
+    assert!(!duplicate.is_normalized());
+}
+
+/// A field found in only one of two coalesced structs may be missing, like when the schema is
+/// extended with a document, whether the structs are on their own or inside unions.
+#[test]
+fn one_sided_struct_fields_may_be_missing() {
+    for (first, second) in [
+        (r#"{ "id": 1, "name": "a" }"#, r#"{ "id": 2, "tags": [] }"#),
+        (
+            r#"[{ "id": 1, "name": "a" }, 5]"#,
+            r#"[{ "id": 2, "tags": [] }]"#,
+        ),
+        (
+            r#"[{ "id": 1, "name": "a" }]"#,
+            r#"[{ "id": 2, "tags": [] }, 5]"#,
+        ),
+    ] {
+        let mut coalesced = serde_json::from_str::<InferredSchema>(first)
+            .unwrap()
+            .schema;
+        coalesced.coalesce(
+            serde_json::from_str::<InferredSchema>(second)
+                .unwrap()
+                .schema,
+        );
+
+        let mut extended: InferredSchema = serde_json::from_str(first).unwrap();
+        extended
+            .deserialize(&mut serde_json::Deserializer::from_str(second))
+            .unwrap();
+
+        assert!(
+            coalesced.structural_eq(&extended.schema),
+            "{:#?}\n{:#?}",
+            coalesced,
+            extended.schema
+        );
+    }
+
+    let mut coalesced = serde_json::from_str::<InferredSchema>(r#"{ "id": 1, "name": "a" }"#)
+        .unwrap()
+        .schema;
+    coalesced.coalesce(
+        serde_json::from_str::<InferredSchema>(r#"{ "id": 2, "tags": [] }"#)
+            .unwrap()
+            .schema,
+    );
+    match coalesced {
+        Schema::Struct { fields, .. } => {
+            assert!(!fields["id"].status.may_be_missing);
+            assert!(fields["name"].status.may_be_missing);
+            assert!(fields["tags"].status.may_be_missing);
+        }
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,713 @@
+use schema_analysis::{
+    context::{
+        BinaryEncodings, Context, Counter, CountingSampler, NumberContext, PostalCodeCountry,
+        UnitExtractor,
+    },
+    helpers, Aggregate, Coalesce, InferredSchema, Schema,
+};
+
+#[test]
+fn map_struct_duplicate_keys() {
+    let data = r#"[
+        { "a": 1, "a": 2, "b": 3, "b": 4, "b": 5, "c": 6 },
+        { "a": 1, "c": 2 },
+        { "a": 1, "a": 2 }
+    ]"#;
+    let inferred: InferredSchema = serde_json::from_str(data).unwrap();
+
+    let context = match inferred.schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Struct { context, .. }) => context,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    assert_eq!(context.count.0, 3);
+    // Duplicates are counted once per struct.
+    assert_eq!(context.duplicate_keys.0.get("a"), Some(&2));
+    assert_eq!(context.duplicate_keys.0.get("b"), Some(&1));
+    assert_eq!(context.duplicate_keys.0.get("c"), None);
+    assert_eq!(context.duplicate_keys.len(), 2);
+}
+
+#[test]
+fn map_struct_key_count() {
+    use serde::de::DeserializeSeed;
+
+    let struct_context = |schema: &Schema| match schema {
+        Schema::Struct { context, .. } => context.clone(),
+        _ => unreachable!(),
+    };
+
+    let documents = [
+        r#"{ "a": 1, "b": 2, "c": 3 }"#,
+        r#"{ "a": 1 }"#,
+        r#"{ "a": 1, "b": 2, "c": 3, "d": 4, "e": 5, "f": 6 }"#,
+    ];
+    let mut schemas = documents.iter().map(|document| {
+        serde_json::from_str::<InferredSchema>(document)
+            .unwrap()
+            .schema
+    });
+    let mut schema = schemas.next().unwrap();
+    assert_eq!(struct_context(&schema).key_count.min, Some(3));
+    assert_eq!(struct_context(&schema).key_count.max, Some(3));
+    for other in schemas {
+        schema.coalesce(other);
+    }
+    let context = struct_context(&schema);
+    assert_eq!(context.count.0, 3);
+    assert_eq!(context.key_count.min, Some(1));
+    assert_eq!(context.key_count.max, Some(6));
+
+    // Extending an existing schema, duplicate keys are counted.
+    let mut inferred = InferredSchema { schema };
+    let mut deserializer = serde_json::Deserializer::from_str(
+        r#"{ "a": 1, "a": 2, "a": 3, "b": 1, "b": 2, "c": 1, "c": 2 }"#,
+    );
+    (&mut inferred).deserialize(&mut deserializer).unwrap();
+    let context = struct_context(&inferred.schema);
+    assert_eq!(context.key_count.min, Some(1));
+    assert_eq!(context.key_count.max, Some(7));
+}
+
+#[test]
+fn map_struct_key_order_consistent() {
+    let consistent = |data: &str| match serde_json::from_str::<InferredSchema>(data).unwrap().schema
+    {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Struct { context, .. }) => context.key_order_consistent,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    assert!(consistent(
+        r#"[{ "a": 1, "b": 2 }, { "a": 1, "b": 2 }, { "a": 1 }]"#
+    ));
+    // Different sets of keys are not compared.
+    assert!(consistent(
+        r#"[{ "a": 1, "b": 2 }, { "b": 1, "c": 2, "a": 3 }]"#
+    ));
+    assert!(!consistent(r#"[{ "a": 1, "b": 2 }, { "b": 2, "a": 1 }]"#));
+
+    // Merging also compares the orders, and a cleared flag wins.
+    let struct_schema = |data: &str| serde_json::from_str::<InferredSchema>(data).unwrap().schema;
+    let flag = |schema: &Schema| match schema {
+        Schema::Struct { context, .. } => context.key_order_consistent,
+        _ => unreachable!(),
+    };
+    let mut schema = struct_schema(r#"{ "x": 1, "y": 2 }"#);
+    schema.coalesce(struct_schema(r#"{ "x": 1, "y": 2 }"#));
+    assert!(flag(&schema));
+    schema.coalesce(struct_schema(r#"{ "y": 1, "x": 2 }"#));
+    assert!(!flag(&schema));
+    schema.coalesce(struct_schema(r#"{ "x": 1, "y": 2 }"#));
+    assert!(!flag(&schema));
+
+    // The flag is only serialized when cleared.
+    let serialized = serde_json::to_value(&schema).unwrap();
+    assert_eq!(serialized["context"]["key_order_consistent"], false);
+    let reloaded: Schema = serde_json::from_value(serialized).unwrap();
+    assert!(!flag(&reloaded));
+    let serialized = serde_json::to_value(struct_schema(r#"{ "x": 1 }"#)).unwrap();
+    assert!(serialized["context"].get("key_order_consistent").is_none());
+}
+
+#[test]
+fn integer_exceeds_js_safe_integer() {
+    let flag = |data: &str| match serde_json::from_str::<InferredSchema>(data).unwrap().schema {
+        Schema::Integer(context) => context.exceeds_js_safe_integer,
+        _ => unreachable!(),
+    };
+
+    assert!(!flag("9007199254740991"));
+    assert!(!flag("-9007199254740991"));
+    assert!(flag("9007199254740993"));
+    assert!(flag("-9007199254740993"));
+
+    // The flag survives merging.
+    let mut inferred: InferredSchema = serde_json::from_str("1").unwrap();
+    let big: InferredSchema = serde_json::from_str("9007199254740993").unwrap();
+    inferred.coalesce(big);
+    match inferred.schema {
+        Schema::Integer(context) => assert!(context.exceeds_js_safe_integer),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn integer_above_i64_max() {
+    let context = |data: &str| match serde_json::from_str::<InferredSchema>(data).unwrap().schema {
+        Schema::Integer(context) => context,
+        _ => unreachable!(),
+    };
+
+    let max = context(&u64::MAX.to_string());
+    assert!(max.saw_values_above_i64_max);
+    assert!(max.is_u64());
+
+    let signed = context(&i64::MAX.to_string());
+    assert!(!signed.saw_values_above_i64_max);
+    assert!(!signed.is_u64());
+
+    // The flag survives merging, but negative values need a wider type than u64.
+    let mut inferred: InferredSchema = serde_json::from_str(&u64::MAX.to_string()).unwrap();
+    inferred.coalesce(serde_json::from_str::<InferredSchema>("-1").unwrap());
+    match inferred.schema {
+        Schema::Integer(context) => {
+            assert!(context.saw_values_above_i64_max);
+            assert!(!context.is_u64());
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn semantic_network_addresses() {
+    let matches = |value: &str| {
+        let context = match serde_json::from_value::<InferredSchema>(value.into())
+            .unwrap()
+            .schema
+        {
+            Schema::String(context) => context.semantic_extractor,
+            _ => unreachable!(),
+        };
+        ["IPv4", "IPv6", "Hostname"]
+            .iter()
+            .copied()
+            .filter(|target| context.count(target) == 1)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(matches("192.168.0.1"), ["IPv4"]);
+    assert_eq!(matches("0.0.0.0"), ["IPv4"]);
+    assert_eq!(matches("255.255.255.255"), ["IPv4"]);
+    assert!(matches("256.1.1.1").is_empty());
+    assert!(matches("1.2.3").is_empty());
+    assert!(matches("01.2.3.4").is_empty());
+
+    assert_eq!(matches("::1"), ["IPv6"]);
+    assert_eq!(matches("::"), ["IPv6"]);
+    assert_eq!(matches("2001:db8::8a2e:370:7334"), ["IPv6"]);
+    assert_eq!(matches("2001:0db8:0000:0000:0000:ff00:0042:8329"), ["IPv6"]);
+    assert_eq!(matches("::ffff:192.0.2.128"), ["IPv6"]);
+    assert_eq!(matches("fe80::1%eth0"), ["IPv6"]);
+    assert!(matches("2001:db8::1::1").is_empty());
+    assert!(matches("12345::1").is_empty());
+    assert!(matches("1:2:3:4:5:6:7:8:9").is_empty());
+    assert!(matches(":::").is_empty());
+    assert!(matches("g::1").is_empty());
+
+    assert_eq!(matches("example.com"), ["Hostname"]);
+    assert_eq!(matches("api-1.eu.example.org"), ["Hostname"]);
+    assert!(matches("localhost").is_empty());
+    assert!(matches("-bad.example.com").is_empty());
+    assert!(matches("bad-.example.com").is_empty());
+    assert!(matches("example..com").is_empty());
+    assert!(matches("under_score.com").is_empty());
+    // Dotted names that are not hosts.
+    assert!(matches("photo.jpg").is_empty());
+    assert!(matches("config.yaml").is_empty());
+    assert!(matches("main.rs").is_empty());
+    assert!(matches("README.MD").is_empty());
+    assert!(matches("example.c0m").is_empty());
+    assert_eq!(matches("www.example.co.uk"), ["Hostname"]);
+    assert_eq!(matches("db.internal"), ["Hostname"]);
+    assert_eq!(matches("store.shop"), ["Hostname"]);
+    assert_eq!(matches("wikipedia.pl"), ["Hostname"]);
+    // Any alphabetic top-level domain is accepted, so some names still match.
+    assert_eq!(matches("john.doe"), ["Hostname"]);
+}
+
+#[test]
+fn semantic_json_schema_formats() {
+    let format = |values: &[&str]| {
+        let InferredSchema { schema } = serde_json::from_value(values.into()).unwrap();
+        match schema {
+            Schema::Sequence { field, .. } => match field.schema {
+                Some(Schema::String(context)) => context.json_schema_format(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    };
+
+    assert_eq!(format(&["a@example.com", "b.c@d.org"]), Some("email"));
+    assert_eq!(
+        format(&["123E4567-E89B-12D3-A456-426614174000"]),
+        Some("uuid")
+    );
+    assert_eq!(
+        format(&["https://example.com/a?b=c", "ftp://files.example.org"]),
+        Some("uri")
+    );
+    assert_eq!(format(&["2001-12-31"]), Some("date"));
+    assert_eq!(format(&["192.168.0.1", "::1"]), None);
+    assert_eq!(format(&["a@example.com", "example.com"]), None);
+    assert_eq!(format(&["a@b", "key:value", "example.com"]), None);
+
+    // Only values that are valid for the format.
+    assert_eq!(format(&["2000-02-29", "2001-04-30"]), Some("date"));
+    assert_eq!(format(&["2001-12-31", "2001-02-29"]), None);
+    assert_eq!(format(&["2001-12-31", "2001-13-01"]), None);
+    assert_eq!(format(&["2001-12-31", "1900-02-29"]), None);
+    assert_eq!(format(&["a@example.com", "a..b@example.com"]), None);
+    assert_eq!(format(&["a@example.com", "a@-example.com"]), None);
+    assert_eq!(format(&["a@example.com", "a\"b@example.com"]), None);
+    assert_eq!(
+        format(&["https://example.com", "https://example.com/%zz"]),
+        None
+    );
+    assert_eq!(
+        format(&["https://example.com", "https://example.com/<a>"]),
+        None
+    );
+}
+
+#[test]
+fn counter_conversions() {
+    let mut counter = Counter::from(2);
+    assert_eq!(counter.get(), 2);
+    assert_eq!(usize::from(counter), 2);
+    assert_eq!(counter.to_string(), "2");
+
+    assert_eq!(counter + Counter(3), Counter(5));
+    counter += Counter(1);
+    assert_eq!(counter, Counter(3));
+
+    counter.aggregate("anything");
+    counter.coalesce(Counter(4));
+    assert_eq!(counter.get(), 8);
+}
+
+#[test]
+fn counting_sampler() {
+    let mut sampler: CountingSampler<String> = Default::default();
+    for value in ["a", "a", "b"].iter() {
+        sampler.aggregate(*value);
+    }
+    assert_eq!(
+        sampler.counts(),
+        &maplit::btreemap! { "a".to_string() => 2, "b".to_string() => 1 }
+    );
+    assert_eq!(sampler.mode().map(String::as_str), Some("a"));
+    assert!(sampler.is_exaustive());
+
+    // New values beyond the cap are not sampled, known ones are still counted.
+    for value in ["c", "d", "e", "f", "b"].iter() {
+        sampler.aggregate(*value);
+    }
+    assert_eq!(sampler.counts().len(), 5);
+    assert_eq!(sampler.counts()["b"], 2);
+    assert!(!sampler.counts().contains_key("f"));
+    assert!(!sampler.is_exaustive());
+
+    // Merging keeps the most frequent values.
+    let mut other: CountingSampler<String> = Default::default();
+    for value in ["f", "f", "f", "a"].iter() {
+        other.aggregate(*value);
+    }
+    sampler.coalesce(other);
+    assert_eq!(sampler.counts().len(), 5);
+    assert_eq!(sampler.counts()["a"], 3);
+    assert_eq!(sampler.counts()["f"], 3);
+    assert_eq!(sampler.mode().map(String::as_str), Some("a"));
+}
+
+#[test]
+fn semantic_scientific_notation() {
+    // XML hands numbers over as text, so the notation is still visible.
+    let data = "<root><a>1.5e10</a><b>-2E-3</b><c>15000000000.0</c><d>1e</d></root>";
+    let mut inferred: InferredSchema = quick_xml::de::from_str(data).unwrap();
+    helpers::xml::cleanup_xml_schema(&mut inferred.schema);
+
+    let fields = match inferred.schema {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    let is_scientific = |key: &str| match &fields[key].schema {
+        Some(Schema::String(context)) => context.semantic_extractor.count("Scientific Float") == 1,
+        _ => unreachable!(),
+    };
+
+    assert!(is_scientific("a"));
+    assert!(is_scientific("b"));
+    assert!(!is_scientific("c"));
+    assert!(!is_scientific("d"));
+
+    // Formats that parse numbers themselves give no way to tell.
+    let inferred: InferredSchema = serde_json::from_str("1.5e10").unwrap();
+    assert!(matches!(inferred.schema, Schema::Float(_)));
+}
+
+#[test]
+fn numbers_with_units() {
+    let string_context =
+        |data: &str| match serde_json::from_str::<InferredSchema>(data).unwrap().schema {
+            Schema::Sequence { field, .. } => match field.schema {
+                Some(Schema::String(context)) => context,
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        };
+
+    let percent = string_context(r#"["45%", "-3.5%", "100 %", ".5%"]"#);
+    assert_eq!(percent.single_unit(), Some("%"));
+    assert_eq!(percent.unit_extractor.0.counts()["%"], 4);
+
+    let weights = string_context(r#"["12kg", "0,5 kg", "300g", "-1 kg"]"#);
+    assert_eq!(weights.single_unit(), None);
+    assert_eq!(weights.unit_extractor.0.counts()["kg"], 3);
+    assert_eq!(weights.unit_extractor.0.counts()["g"], 1);
+
+    // A single value without a unit is enough to not share one.
+    let mixed = string_context(r#"["12kg", "12"]"#);
+    assert_eq!(mixed.single_unit(), None);
+
+    let others = string_context(r#"["kg", "12", "1.2.3kg", "12 kg kg", "", "3 km/h", "20°C"]"#);
+    assert_eq!(
+        others.unit_extractor.0.counts().keys().collect::<Vec<_>>(),
+        ["km/h", "°C"]
+    );
+
+    assert_eq!(UnitExtractor::unit("3.5ms"), Some("ms"));
+    assert_eq!(UnitExtractor::unit("4 m²"), Some("m²"));
+}
+
+#[test]
+fn map_struct_duplicate_keys_when_extending() {
+    use serde::de::DeserializeSeed;
+
+    let mut inferred: InferredSchema = serde_json::from_str(r#"[{ "a": 1, "b": 2 }]"#).unwrap();
+    let data = r#"[
+        { "a": 1, "a": 2, "b": 3, "b": 4 },
+        { "a": 1, "c": { "a": 1, "a": 2 } },
+        { "b": 1, "b": 2 }
+    ]"#;
+    // Twice, so that the nested maps reuse the key buffers of previous ones.
+    for _ in 0..2 {
+        let mut deserializer = serde_json::Deserializer::from_str(data);
+        (&mut inferred).deserialize(&mut deserializer).unwrap();
+    }
+
+    let (fields, context) = match inferred.schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Struct { fields, context }) => (fields, context),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    assert_eq!(context.count.0, 7);
+    assert_eq!(context.duplicate_keys.0.get("a"), Some(&2));
+    assert_eq!(context.duplicate_keys.0.get("b"), Some(&4));
+    assert_eq!(context.duplicate_keys.0.get("c"), None);
+    assert!(fields["a"].status.may_be_duplicate);
+    assert!(!fields["c"].status.may_be_duplicate);
+
+    match &fields["c"].schema {
+        Some(Schema::Struct { context, .. }) => {
+            assert_eq!(context.duplicate_keys.0.get("a"), Some(&2))
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn lean_context() {
+    let data = r#"{ "integers": [3, -1, 7, 7], "floats": [0.5, 2.5] }"#;
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let inferred = Context::lean()
+        .deserialize_schema(&mut deserializer)
+        .unwrap();
+
+    let element = |key: &str| match &inferred.schema {
+        Schema::Struct { fields, .. } => match &fields[key].schema {
+            Some(Schema::Sequence { field, .. }) => field.schema.clone().unwrap(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    match element("integers") {
+        Schema::Integer(context) => {
+            assert!(context.samples.values().is_empty());
+            assert_eq!(context.count.0, 4);
+            assert_eq!(context.min_max.min, Some(-1));
+            assert_eq!(context.min_max.max, Some(7));
+        }
+        _ => unreachable!(),
+    }
+    match element("floats") {
+        Schema::Float(context) => {
+            assert!(context.samples.values().is_empty());
+            assert_eq!(context.count.0, 2);
+            assert_eq!(context.min_max.min, Some(0.5));
+            assert_eq!(context.min_max.max, Some(2.5));
+        }
+        _ => unreachable!(),
+    }
+
+    // The schema can still be stored and loaded without samples.
+    let serialized = serde_json::to_string(&inferred.schema).unwrap();
+    let deserialized: Schema = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, inferred.schema);
+    match &deserialized {
+        Schema::Struct { fields, .. } => match &fields["integers"].schema {
+            Some(Schema::Sequence { field, .. }) => match &field.schema {
+                Some(Schema::Integer(context)) => assert!(context.skip_samples),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn lean_context_coalesced_with_samples() {
+    let sampled: InferredSchema = serde_json::from_str("[1, 1]").unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str("[2]");
+    let lean = Context::lean()
+        .deserialize_schema(&mut deserializer)
+        .unwrap();
+    assert_ne!(
+        NumberContext::<i128>::default(),
+        NumberContext::without_samples()
+    );
+
+    let merged = sampled.schema.coalesced(lean.schema);
+    match &merged {
+        Schema::Sequence { field, .. } => {
+            match &field.schema {
+                Some(Schema::Integer(context)) => {
+                    assert!(context.skip_samples);
+                    assert!(context.samples.values().is_empty());
+                    assert!(!context.samples.is_exaustive());
+                }
+                _ => unreachable!(),
+            }
+            // The 2 was not sampled, so 1 is not known to be a constant.
+            assert_eq!(field.constant_value(), None);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn binary_encodings() {
+    let data = r#"[
+        { "base64": "aGVsbG8gd29ybGQ=", "url": "-_7dzLuqmYh3ZlVE", "hex": "deadbeef", "text": "Hello world" },
+        { "base64": "U2NoZW1hIGFuYWx5c2lzIQ==", "url": "c2NoZW1h_YW5hbHlzaXM", "hex": "0123456789abcdef", "text": "Password" },
+        { "base64": "AAECAwQFBgcICQ==", "url": "AAECAwQF-gcICQ==", "hex": "CAFEBABE00FF", "text": "Description" },
+        { "base64": "+/7dzLuqmYh3ZlVE", "url": "_-7dzLuqmYh3ZlVE", "hex": "a1b2c3d4", "text": "deadline" }
+    ]"#;
+    let inferred: InferredSchema = serde_json::from_str(data).unwrap();
+
+    let fields = match inferred.schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Struct { fields, .. }) => fields,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+    let string = |key: &str| match &fields[key].schema {
+        Some(Schema::String(context)) => context.clone(),
+        _ => unreachable!(),
+    };
+
+    let base64 = string("base64");
+    assert_eq!(base64.binary_encodings.base64.0, 4);
+    assert!(base64.is_base64());
+    assert!(!base64.is_base64_url());
+
+    let url = string("url");
+    assert_eq!(url.binary_encodings.base64_url.0, 4);
+    assert!(url.is_base64_url());
+    assert!(!url.is_base64());
+    assert!(!base64.is_hex());
+
+    let hex = string("hex");
+    assert_eq!(hex.binary_encodings.hex.0, 4);
+    assert!(hex.is_hex());
+    assert!(!hex.is_base64());
+
+    let text = string("text");
+    assert!(text.binary_encodings.is_empty());
+    assert!(!text.is_base64());
+    assert!(!text.is_hex());
+
+    // Mixing the standard and url-safe alphabets, or too much padding, is not base64.
+    assert!(!BinaryEncodings::is_base64("ab+_cdef"));
+    assert!(!BinaryEncodings::is_base64("abcd===="));
+    // Identifiers are valid base64 too, but without padding or a symbol they are not counted.
+    for id in ["SKU12345", "user1234", "AbCdEfGh"] {
+        assert!(!BinaryEncodings::is_base64(id), "{}", id);
+        assert!(!BinaryEncodings::is_base64_url(id), "{}", id);
+    }
+    // The url-safe alphabet is counted separately, and may be unpadded.
+    assert!(!BinaryEncodings::is_base64("-_7dzLuqmYh3ZlVE"));
+    assert!(BinaryEncodings::is_base64_url("-_7dzLuqmYh3ZlVE"));
+    assert!(!BinaryEncodings::is_base64_url("user_name_field"));
+    // Numbers are not hex, nor are mixed-case letters.
+    assert!(!BinaryEncodings::is_hex("12345678"));
+    assert!(!BinaryEncodings::is_hex("deadBEEF"));
+}
+
+#[test]
+fn phone_numbers_and_postal_codes() {
+    let string = |values: &[&str]| {
+        let json = serde_json::to_string(values).unwrap();
+        match serde_json::from_str::<InferredSchema>(&json)
+            .unwrap()
+            .schema
+        {
+            Schema::Sequence { field, .. } => match field.schema {
+                Some(Schema::String(context)) => context,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    };
+
+    let phones = string(&["+14155552671", "+442079460958", "+33 1 23 45 67 89"]);
+    assert_eq!(phones.semantic_extractor.count("Phone Number"), 3);
+    assert!(phones.is_phone_number());
+    assert!(!phones.is_postal_code(None));
+
+    let zips = string(&["94105", "10001-1234", "02134"]);
+    assert_eq!(zips.semantic_extractor.count("Postal Code US"), 3);
+    assert!(zips.is_postal_code(None));
+    assert!(zips.is_postal_code(Some(PostalCodeCountry::US)));
+    assert!(!zips.is_postal_code(Some(PostalCodeCountry::GB)));
+    assert!(!zips.is_phone_number());
+
+    let uk = string(&["SW1A 1AA", "M1 1AE"]);
+    assert!(uk.is_postal_code(None));
+    assert!(uk.is_postal_code(Some(PostalCodeCountry::GB)));
+    assert!(!uk.is_postal_code(Some(PostalCodeCountry::US)));
+
+    let mixed = string(&["1234 AB", "01310-100", "K1A 0B1"]);
+    assert_eq!(mixed.semantic_extractor.count("Postal Code"), 3);
+    assert!(mixed.is_postal_code(None));
+    assert!(!mixed.is_postal_code(Some(PostalCodeCountry::NL)));
+
+    // Small numbers are neither.
+    let counter: Vec<String> = (1..=200).map(|n| n.to_string()).collect();
+    let counter: Vec<&str> = counter.iter().map(String::as_str).collect();
+    let counter = string(&counter);
+    assert_eq!(counter.semantic_extractor.count("Phone Number"), 0);
+    assert_eq!(counter.semantic_extractor.count("Postal Code"), 0);
+    assert!(!counter.is_phone_number());
+    assert!(!counter.is_postal_code(None));
+
+    // But five digit ids can't be told apart from ZIP codes, see PII_FRACTION.
+    let ids: Vec<String> = (10_000..=10_200).map(|n| n.to_string()).collect();
+    let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+    let ids = string(&ids);
+    assert!(!ids.is_phone_number());
+    assert!(ids.is_postal_code(None));
+    assert!(ids.is_postal_code(Some(PostalCodeCountry::US)));
+
+    // A few matches are not enough.
+    let mixed = string(&["+14155552671", "call me", "later", "maybe"]);
+    assert_eq!(mixed.semantic_extractor.count("Phone Number"), 1);
+    assert!(!mixed.is_phone_number());
+}
+
+#[test]
+fn pattern_template() {
+    let template = |values: &[&str]| {
+        let json = serde_json::to_string(values).unwrap();
+        match serde_json::from_str::<InferredSchema>(&json)
+            .unwrap()
+            .schema
+        {
+            Schema::Sequence { field, .. } => match field.schema {
+                Some(Schema::String(context)) => context.pattern_template.regex().unwrap(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    };
+
+    assert_eq!(
+        template(&["AB-1234-XY", "CD-5678-ZW", "EF-9012-QR"]),
+        r"[A-Z]{2}-\d{4}-[A-Z]{2}"
+    );
+    // Repetitions and classes are widened run by run.
+    assert_eq!(
+        template(&["AB-1234-XY", "Cd-56-ZW", "ab-123456-q"]),
+        r"[A-Za-z]{2}-\d{2,6}-[A-Za-z]{1,2}"
+    );
+    assert_eq!(template(&["item_7", "item_a1"]), r"[a-z0-9_]{6,7}");
+    // Values that don't line up fall back to their characters and lengths.
+    assert_eq!(template(&["AB-1234", "1234"]), r"[A-Z0-9\-]{4,7}");
+    assert_eq!(template(&["a.b", "a-b"]), r"[a-z\-\.]{3}");
+
+    // Merging schemas merges the templates.
+    let mut first: InferredSchema = serde_json::from_str(r#""AB-12""#).unwrap();
+    first.coalesce(serde_json::from_str::<InferredSchema>(r#""CD-345""#).unwrap());
+    match first.schema {
+        Schema::String(context) => {
+            assert_eq!(
+                context.pattern_template.regex().unwrap(),
+                r"[A-Z]{2}-\d{2,3}"
+            )
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn keeping_all_values_below() {
+    let integers = |count: i32| {
+        let values: Vec<i32> = (0..count).collect();
+        let json = serde_json::to_string(&values).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let inferred = Context::keeping_all_values_below(20)
+            .deserialize_schema(&mut deserializer)
+            .unwrap();
+        match inferred.schema {
+            Schema::Sequence { field, .. } => match field.schema {
+                Some(Schema::Integer(context)) => context.samples,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    };
+
+    // A tiny dataset is kept in full.
+    let tiny = integers(12);
+    assert_eq!(tiny.values().len(), 12);
+    assert!(tiny.is_exaustive());
+    assert!(tiny.is_keeping_all());
+
+    // A larger one falls back to sampling.
+    let larger = integers(50);
+    assert!(larger.values().len() < 12);
+    assert!(!larger.is_exaustive());
+    assert!(!larger.is_keeping_all());
+
+    // Merging counts the values of both.
+    let mut merged = integers(12);
+    merged.coalesce(integers(6));
+    assert!(merged.is_keeping_all());
+    assert_eq!(merged.values().len(), 12);
+    merged.coalesce(integers(6));
+    assert!(!merged.is_keeping_all());
+    assert!(!merged.is_exaustive());
+    assert_eq!(merged.values().len(), 5);
+
+    // The default samplers are unaffected.
+    let inferred: InferredSchema = serde_json::from_str("[1, 2, 3, 4, 5, 6, 7]").unwrap();
+    match inferred.schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Integer(context)) => assert!(!context.samples.is_keeping_all()),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
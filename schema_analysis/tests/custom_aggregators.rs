@@ -0,0 +1,233 @@
+use serde::de::DeserializeSeed;
+
+use std::time::Duration;
+
+use schema_analysis::{
+    context::{Context, DurationAnalysis, DurationSummary, TimeUnit},
+    traits::CoalescingAggregator,
+    Aggregate, Coalesce, Schema,
+};
+
+/// Records each integer along with the key it was found under.
+#[derive(Debug, Clone, Default)]
+struct KeyedIntegers(Vec<(Option<String>, i128)>);
+impl Aggregate<i128> for KeyedIntegers {
+    fn aggregate(&mut self, value: &i128) {
+        self.0.push((None, *value));
+    }
+    fn aggregate_with_key(&mut self, key: &str, value: &i128) {
+        self.0.push((Some(key.to_string()), *value));
+    }
+}
+impl Coalesce for KeyedIntegers {
+    fn coalesce(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+}
+impl CoalescingAggregator<i128> for KeyedIntegers {}
+
+fn recorded(schema: &Schema) -> Vec<(Option<&str>, i128)> {
+    let context = match schema {
+        Schema::Integer(context) => context,
+        _ => unreachable!(),
+    };
+    let aggregator = context.other_aggregators.0[0]
+        .as_any()
+        .downcast_ref::<KeyedIntegers>()
+        .unwrap();
+    aggregator
+        .0
+        .iter()
+        .map(|(key, value)| (key.as_deref(), *value))
+        .collect()
+}
+
+#[test]
+fn aggregators_see_the_field_key() {
+    let mut context = Context::default();
+    context
+        .integer
+        .other_aggregators
+        .0
+        .push(Box::new(KeyedIntegers::default()));
+
+    let mut deserializer =
+        serde_json::Deserializer::from_str(r#"{ "id": 1, "inner": { "id": 2 }, "list": [3] }"#);
+    let mut inferred = context
+        .clone()
+        .deserialize_schema(&mut deserializer)
+        .unwrap();
+    // Extending the schema goes through a different visitor.
+    let mut deserializer =
+        serde_json::Deserializer::from_str(r#"{ "id": 4, "inner": { "id": 5 }, "list": [6, 7] }"#);
+    (&mut inferred).deserialize(&mut deserializer).unwrap();
+
+    let fields = match &inferred.schema {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        recorded(fields["id"].schema.as_ref().unwrap()),
+        vec![(Some("id"), 1), (Some("id"), 4)]
+    );
+    match fields["inner"].schema.as_ref().unwrap() {
+        Schema::Struct { fields, .. } => assert_eq!(
+            recorded(fields["id"].schema.as_ref().unwrap()),
+            vec![(Some("id"), 2), (Some("id"), 5)]
+        ),
+        _ => unreachable!(),
+    }
+    // Values nested in sequences see the key of the sequence.
+    match fields["list"].schema.as_ref().unwrap() {
+        Schema::Sequence { field, .. } => assert_eq!(
+            recorded(field.schema.as_ref().unwrap()),
+            vec![(Some("list"), 3), (Some("list"), 6), (Some("list"), 7)]
+        ),
+        _ => unreachable!(),
+    }
+
+    // Values at the root have no key.
+    let mut deserializer = serde_json::Deserializer::from_str("8");
+    let inferred = context.deserialize_schema(&mut deserializer).unwrap();
+    assert_eq!(recorded(&inferred.schema), vec![(None, 8)]);
+}
+
+/// Infers the schema of `data` with `durations` on the integers and returns the aggregator of
+/// each field of the root struct.
+fn durations(durations: DurationAnalysis, data: &str) -> Vec<(String, DurationAnalysis)> {
+    let mut context = Context::default();
+    context
+        .integer
+        .other_aggregators
+        .0
+        .push(Box::new(durations));
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let inferred = context.deserialize_schema(&mut deserializer).unwrap();
+
+    let fields = match inferred.schema {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    fields
+        .into_iter()
+        .map(|(key, field)| match field.schema {
+            Some(Schema::Sequence { field, .. }) => match field.schema {
+                Some(Schema::Integer(context)) => {
+                    let aggregator = context.other_aggregators.0[0]
+                        .as_any()
+                        .downcast_ref::<DurationAnalysis>()
+                        .unwrap()
+                        .clone();
+                    (key.to_string(), aggregator)
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[test]
+fn millisecond_durations_are_summarized() {
+    let analysis = DurationAnalysis::new(TimeUnit::Seconds, &["latency"]);
+    let fields = durations(
+        analysis,
+        r#"{
+            "latency_ms": [250, 40, 1200, 40, 95000, 250, 250],
+            "count": [250, 40, 1200]
+        }"#,
+    );
+
+    let (key, count) = &fields[0];
+    assert_eq!(key, "count");
+    assert!(!count.is_duration());
+    assert_eq!(count.summary(), None);
+
+    let (key, latency) = &fields[1];
+    assert_eq!(key, "latency_ms");
+    let summary = latency.summary().unwrap();
+    assert_eq!(
+        summary,
+        DurationSummary {
+            count: 7,
+            min: Duration::from_millis(40),
+            median: Duration::from_millis(250),
+            max: Duration::from_secs(95),
+        }
+    );
+    assert_eq!(
+        summary.to_string(),
+        "40ms to 1.58min, median 250ms (7 values)"
+    );
+}
+
+#[test]
+fn durations_are_detected_by_range() {
+    // Without a key hint, only the range tells the durations apart.
+    let analysis =
+        DurationAnalysis::new(TimeUnit::Seconds, &[]).with_plausible_range(0.0..=86_400.0);
+    let fields = durations(
+        analysis,
+        r#"{ "ttl": [60, 3600, 7200], "offset": [-5, 10], "epoch": [1700000000] }"#,
+    );
+    let summaries: Vec<_> = fields
+        .iter()
+        .map(|(key, analysis)| (key.as_str(), analysis.summary().map(|s| s.to_string())))
+        .collect();
+    assert_eq!(
+        summaries,
+        vec![
+            ("epoch", None),
+            ("offset", None),
+            ("ttl", Some("1min to 2h, median 1h (3 values)".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn durations_too_long_have_no_summary() {
+    let mut context = Context::default();
+    let analysis = DurationAnalysis::new(TimeUnit::Seconds, &["timeout"]);
+    context.float.other_aggregators.0.push(Box::new(analysis));
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "timeout_ms": 1e23 }"#);
+    let inferred = context.deserialize_schema(&mut deserializer).unwrap();
+
+    let timeout = match inferred.schema {
+        Schema::Struct { mut fields, .. } => fields.remove("timeout_ms").unwrap().schema,
+        _ => unreachable!(),
+    };
+    let analysis = match timeout {
+        Some(Schema::Float(context)) => context.other_aggregators.0[0]
+            .as_any()
+            .downcast_ref::<DurationAnalysis>()
+            .unwrap()
+            .clone(),
+        _ => unreachable!(),
+    };
+    assert!(analysis.is_duration());
+    assert_eq!(analysis.summary(), None);
+}
+
+#[test]
+fn time_units_need_a_word_boundary() {
+    for key in [
+        "timeout_ms",
+        "timeoutMs",
+        "TIMEOUT_MS",
+        "elapsed-millis",
+        "ms",
+    ] {
+        assert_eq!(
+            TimeUnit::from_key(key),
+            Some(TimeUnit::Milliseconds),
+            "{}",
+            key
+        );
+    }
+    for key in ["elapsed_s", "elapsedSecs", "ttl_seconds"] {
+        assert_eq!(TimeUnit::from_key(key), Some(TimeUnit::Seconds), "{}", key);
+    }
+    for key in ["items", "terms", "platforms", "status", "seconds_left"] {
+        assert_eq!(TimeUnit::from_key(key), None, "{}", key);
+    }
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "embedded_json")]
+
+use serde::de::DeserializeSeed;
+
+use schema_analysis::{context::StringContext, Context, InferredSchema, Schema};
+
+const DATA: &str = r#"[
+    { "payload": "{\"a\": 1, \"b\": [true]}" },
+    { "payload": "{\"a\": 2, \"c\": \"[1, 2]\"}" },
+    { "payload": " [1] " }
+]"#;
+
+fn payload(schema: &Schema) -> StringContext {
+    match schema {
+        Schema::Sequence { field, .. } => match &field.schema {
+            Some(Schema::Struct { fields, .. }) => match &fields["payload"].schema {
+                Some(Schema::String(context)) => context.clone(),
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn embedded_json_is_detected() {
+    let inferred: InferredSchema = serde_json::from_str(DATA).unwrap();
+    let context = payload(&inferred.schema);
+
+    assert_eq!(context.embedded_json.0, 3);
+    assert!(context.is_embedded_json());
+    // Inference is opt-in.
+    assert!(context.embedded_schema.is_none());
+
+    let inferred: InferredSchema =
+        serde_json::from_str(r#"["{\"a\": 1}", "{ not json", "plain"]"#).unwrap();
+    match inferred.schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::String(context)) => {
+                assert_eq!(context.embedded_json.0, 1);
+                assert!(!context.is_embedded_json());
+            }
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn embedded_json_is_inferred() {
+    let mut context = Context::default();
+    context.settings.embedded_json_depth = 1;
+
+    let mut deserializer = serde_json::Deserializer::from_str(DATA);
+    let mut inferred = context.deserialize_schema(&mut deserializer).unwrap();
+    // Further documents extend the embedded schema.
+    let mut deserializer = serde_json::Deserializer::from_str(r#"[{ "payload": "{\"a\": 3.5}" }]"#);
+    inferred.deserialize(&mut deserializer).unwrap();
+
+    let embedded = *payload(&inferred.schema).embedded_schema.unwrap();
+    let (objects, arrays) = match embedded {
+        Schema::Union { variants } => match variants.as_slice() {
+            [objects @ Schema::Struct { .. }, arrays @ Schema::Sequence { .. }] => {
+                (objects.clone(), arrays.clone())
+            }
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    };
+
+    let fields = match objects {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+        ["a", "b", "c"]
+    );
+    assert!(matches!(
+        fields["a"].schema,
+        Some(Schema::Union { ref variants }) if variants.len() == 2
+    ));
+    // Only one level is analyzed.
+    match &fields["c"].schema {
+        Some(Schema::String(context)) => {
+            assert_eq!(context.embedded_json.0, 1);
+            assert!(context.embedded_schema.is_none());
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(matches!(arrays, Schema::Sequence { .. }));
+}
+
+#[test]
+fn invalid_embedded_json_is_skipped() {
+    let mut context = Context::default();
+    context.settings.embedded_json_depth = 1;
+
+    let data = r#"[
+        { "payload": "{\"a\": 1}" },
+        { "payload": "{\"a\": 2, \"b\": " },
+        { "payload": "[1] trailing" }
+    ]"#;
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let inferred = context.deserialize_schema(&mut deserializer).unwrap();
+
+    let context = payload(&inferred.schema);
+    assert_eq!(context.embedded_json.0, 1);
+    // Nothing is left behind by the values that are not JSON.
+    let InferredSchema { schema: expected } = serde_json::from_str(r#"{ "a": 1 }"#).unwrap();
+    assert_eq!(*context.embedded_schema.unwrap(), expected);
+}
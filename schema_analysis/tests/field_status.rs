@@ -0,0 +1,80 @@
+use schema_analysis::{Coalesce, FieldStatus, InferredSchema, Nullability, Schema};
+
+fn status(may_be_null: bool, may_be_normal: bool, may_be_missing: bool) -> FieldStatus {
+    FieldStatus {
+        may_be_null,
+        may_be_normal,
+        may_be_missing,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn nullability_of_every_flag_combination() {
+    use Nullability::*;
+
+    // (null, normal, missing) -> expected
+    let cases = [
+        ((false, false, false), NeverSeen),
+        ((false, true, false), Required),
+        ((true, false, false), Optional),
+        ((true, true, false), Optional),
+        ((false, false, true), Sometimes),
+        ((false, true, true), Sometimes),
+        ((true, false, true), OptionalAndSometimes),
+        ((true, true, true), OptionalAndSometimes),
+    ];
+
+    for ((null, normal, missing), expected) in cases {
+        let mut status = status(null, normal, missing);
+        assert_eq!(status.nullability(), expected);
+        assert_eq!(status.is_option(), null || missing);
+
+        // Duplicates should not affect the category.
+        status.may_be_duplicate = true;
+        assert_eq!(status.nullability(), expected);
+    }
+}
+
+#[test]
+fn nullability_helpers() {
+    use Nullability::*;
+
+    assert!(!Required.may_be_null() && !Required.may_be_missing());
+    assert!(!NeverSeen.may_be_null() && !NeverSeen.may_be_missing());
+    assert!(Optional.may_be_null() && !Optional.may_be_missing());
+    assert!(!Sometimes.may_be_null() && Sometimes.may_be_missing());
+    assert!(OptionalAndSometimes.may_be_null() && OptionalAndSometimes.may_be_missing());
+}
+
+#[test]
+fn empty_collections() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "tags": [], "always": [1], "object": {}, "nested": [[], [1]] },
+            { "tags": [1, 2], "always": [2], "object": { "a": 1 }, "nested": [[2]] }
+        ]"#,
+    )
+    .unwrap();
+
+    let fields = match schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Struct { fields, .. }) => fields,
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    };
+    assert!(fields["tags"].status.may_be_empty_collection);
+    assert!(!fields["tags"].status.may_be_missing);
+    assert!(!fields["always"].status.may_be_empty_collection);
+    assert!(fields["object"].status.may_be_empty_collection);
+    assert!(!fields["nested"].status.may_be_empty_collection);
+    match &fields["nested"].schema {
+        Some(Schema::Sequence { field, .. }) => assert!(field.status.may_be_empty_collection),
+        other => panic!("{:?}", other),
+    }
+
+    let mut status = FieldStatus::default();
+    status.coalesce(fields["tags"].status.clone());
+    assert!(status.may_be_empty_collection);
+}
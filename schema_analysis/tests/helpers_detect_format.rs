@@ -0,0 +1,105 @@
+#![cfg(feature = "detect_format")]
+
+use serde_json::json;
+
+use schema_analysis::{
+    helpers::detect_format::{detect_and_infer, DetectedFormat},
+    InferredSchema, Schema, StructuralEq,
+};
+
+fn expected() -> Schema {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "name": "schema", "version": 1 }"#).unwrap();
+    schema
+}
+
+fn assert_detected(data: &[u8], format: DetectedFormat) {
+    let (detected, inferred) = detect_and_infer(data).unwrap();
+    assert_eq!(detected, format);
+    assert!(
+        inferred.schema.structural_eq(&expected()),
+        "{:?}",
+        inferred.schema
+    );
+}
+
+#[test]
+fn json() {
+    assert_detected(
+        br#"{ "name": "schema", "version": 1 }"#,
+        DetectedFormat::Json,
+    );
+}
+
+#[test]
+fn yaml() {
+    assert_detected(b"name: schema\nversion: 1\n", DetectedFormat::Yaml);
+    assert_detected(
+        b"name: schema\nversion: 1\n---\nname: analysis\nversion: 2\n",
+        DetectedFormat::Yaml,
+    );
+}
+
+#[test]
+fn tagged_yaml() {
+    // YAML tags reach the analysis as enums, the tag is dropped and the value analyzed.
+    assert_detected(
+        b"name: !Name schema\nversion: !Version 1\n",
+        DetectedFormat::Yaml,
+    );
+
+    let (detected, inferred) = detect_and_infer(b"a: !Foo 1\n").unwrap();
+    assert_eq!(detected, DetectedFormat::Yaml);
+    let InferredSchema { schema } = serde_json::from_str(r#"{ "a": 1 }"#).unwrap();
+    assert!(inferred.schema.structural_eq(&schema));
+}
+
+#[test]
+fn toml() {
+    assert_detected(b"name = \"schema\"\nversion = 1\n", DetectedFormat::Toml);
+}
+
+#[test]
+fn cbor() {
+    let data = serde_cbor::to_vec(&json!({ "name": "schema", "version": 1 })).unwrap();
+    assert_detected(&data, DetectedFormat::Cbor);
+}
+
+#[test]
+fn bson() {
+    let data = bson::to_vec(&bson::doc! { "name": "schema", "version": 1 }).unwrap();
+    assert_detected(&data, DetectedFormat::Bson);
+}
+
+#[test]
+fn xml() {
+    let (detected, inferred) =
+        detect_and_infer(b"<root><name>schema</name><version>1</version></root>").unwrap();
+    assert_eq!(detected, DetectedFormat::Xml);
+    match inferred.schema {
+        Schema::Struct { fields, .. } => {
+            assert_eq!(
+                fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+                ["name", "version"]
+            )
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn json_takes_priority_over_yaml() {
+    // Valid as both.
+    let (detected, _) = detect_and_infer(b"[1, 2, 3]").unwrap();
+    assert_eq!(detected, DetectedFormat::Json);
+}
+
+#[test]
+fn failures() {
+    let error = detect_and_infer(b"  \n").unwrap_err();
+    assert!(error.attempts.is_empty());
+
+    let error = detect_and_infer(&[0xff, 0xfe, 0xfd]).unwrap_err();
+    let formats: Vec<_> = error.attempts.iter().map(|(format, _)| *format).collect();
+    assert_eq!(formats, [DetectedFormat::Cbor]);
+}
@@ -0,0 +1,121 @@
+use schema_analysis::{
+    helpers::jsonpath_filter::{infer_selected, infer_selected_with_context, Segment, Selector},
+    Context, InferredSchema, Schema, StructuralEq,
+};
+
+const DOCUMENT: &str = r#"{
+    "meta": { "page": 1, "tags": ["a", "b"] },
+    "data": {
+        "total": 2,
+        "results": [
+            { "id": 1, "name": "first" },
+            { "id": 2, "name": "second", "extra": true }
+        ]
+    }
+}"#;
+
+fn select(selector: &str) -> Option<InferredSchema> {
+    let selector: Selector = selector.parse().unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(DOCUMENT);
+    infer_selected(&selector, &mut deserializer).unwrap()
+}
+
+#[test]
+fn nested_array() {
+    let InferredSchema { schema } = select("$.data.results").unwrap();
+
+    let InferredSchema { schema: expected } = serde_json::from_str(
+        r#"[{ "id": 1, "name": "first" }, { "id": 2, "name": "second", "extra": true }]"#,
+    )
+    .unwrap();
+    assert!(schema.structural_eq(&expected));
+}
+
+#[test]
+fn nested_array_elements() {
+    let InferredSchema { schema } = select("$.data.results[*]").unwrap();
+
+    let fields = match &schema {
+        Schema::Struct { fields, context } => {
+            assert_eq!(context.count.0, 2);
+            fields
+        }
+        other => panic!("expected a struct, got {:?}", other),
+    };
+    let keys: Vec<&str> = fields.keys().map(|key| &**key).collect();
+    assert_eq!(keys, vec!["extra", "id", "name"]);
+    assert!(fields["extra"].status.may_be_missing);
+}
+
+#[test]
+fn fields_of_elements() {
+    let InferredSchema { schema } = select("data.results[*].id").unwrap();
+    match schema {
+        Schema::Integer(context) => assert_eq!(context.count.0, 2),
+        other => panic!("expected an integer, got {:?}", other),
+    }
+}
+
+#[test]
+fn no_match() {
+    assert!(select("$.data.missing").is_none());
+    assert!(select("$.meta.page[*]").is_none());
+    assert!(select("$.data[*]").is_none());
+}
+
+#[test]
+fn selected_with_context() {
+    let selector: Selector = "$.data.results[*]".parse().unwrap();
+    let mut context = Context::default();
+    // Paths are relative to the selected values.
+    context.settings.ignore_path(&["name"]);
+
+    let mut deserializer = serde_json::Deserializer::from_str(DOCUMENT);
+    let inferred = infer_selected_with_context(&selector, context, &mut deserializer)
+        .unwrap()
+        .unwrap();
+    match &inferred.schema {
+        Schema::Struct { fields, .. } => {
+            assert!(fields["name"].status.is_ignored);
+            assert!(!fields["id"].status.is_ignored);
+        }
+        other => panic!("expected a struct, got {:?}", other),
+    }
+}
+
+#[test]
+fn non_string_keys() {
+    let selector: Selector = "$.1".parse().unwrap();
+    let data = serde_cbor::to_vec(&maplit::btreemap! { 1 => "a", 2 => "b" }).unwrap();
+
+    let mut deserializer = serde_cbor::Deserializer::from_slice(&data);
+    match infer_selected(&selector, &mut deserializer).unwrap() {
+        Some(InferredSchema {
+            schema: Schema::String(context),
+        }) => assert_eq!(context.count.0, 1),
+        other => panic!("expected a string, got {:?}", other),
+    }
+}
+
+#[test]
+fn parse_selectors() {
+    let parsed: Selector = "$.a[*].b".parse().unwrap();
+    assert_eq!(
+        parsed,
+        Selector(vec![
+            Segment::Key("a".to_string()),
+            Segment::AnyElement,
+            Segment::Key("b".to_string()),
+        ])
+    );
+    assert_eq!("$".parse::<Selector>().unwrap(), Selector::default());
+    assert_eq!("[*][*]".parse::<Selector>().unwrap().0.len(), 2);
+
+    for invalid in &["$.", "$..a", "$a", "$.a[0]", "$.a[*", "$.a.[*]"] {
+        assert!(
+            invalid.parse::<Selector>().is_err(),
+            "{} should be invalid",
+            invalid
+        );
+    }
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "serde_json")]
+
+use schema_analysis::{helpers, Coalesce, Schema};
+
+mod shared;
+use shared::infer;
+
+#[test]
+fn merging_stored_schemas() {
+    let shard_1 = infer(r#"[{ "id": 1, "name": "a" }, { "id": 2, "name": null }]"#);
+    let shard_2 = infer(r#"[{ "id": 3.5, "tags": ["x"] }]"#);
+    let stored_1 = serde_json::to_string(&shard_1).unwrap();
+    let stored_2 = serde_json::to_string(&shard_2).unwrap();
+
+    let merged = helpers::merge_schema_json(&stored_1, &stored_2).unwrap();
+
+    let direct = shard_1.coalesced(shard_2);
+    assert_eq!(serde_json::from_str::<Schema>(&merged).unwrap(), direct);
+    assert_eq!(merged, serde_json::to_string(&direct).unwrap());
+}
+
+#[test]
+fn invalid_schema() {
+    let stored = serde_json::to_string(&infer("1")).unwrap();
+    assert!(helpers::merge_schema_json(&stored, r#"{ "type": "Nope" }"#).is_err());
+    assert!(helpers::merge_schema_json("[1, 2]", &stored).is_err());
+}
+
+#[test]
+fn unnormalized_schema() {
+    let stored = serde_json::to_string(&infer("1")).unwrap();
+    let nested = Schema::Union {
+        variants: vec![
+            Schema::Boolean(Default::default()),
+            Schema::Union {
+                variants: vec![
+                    Schema::Integer(Default::default()),
+                    Schema::String(Default::default()),
+                ],
+            },
+        ],
+    };
+    let nested = serde_json::to_string(&nested).unwrap();
+    assert!(helpers::merge_schema_json(&stored, &nested).is_err());
+    assert!(helpers::merge_schema_json(&nested, &stored).is_err());
+}
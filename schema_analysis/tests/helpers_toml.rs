@@ -0,0 +1,68 @@
+use schema_analysis::{helpers, InferredSchema, Schema};
+
+fn infer_toml(toml: &str) -> Schema {
+    let InferredSchema { mut schema } = toml::from_str(toml).unwrap();
+    helpers::toml::cleanup_toml_schema(&mut schema);
+    schema
+}
+
+fn is_datetime(schema: &Schema, name: &str) -> bool {
+    match schema {
+        Schema::Struct { fields, .. } => match &fields[name].schema {
+            Some(Schema::String(context)) => context.is_datetime,
+            other => panic!("{:?}", other),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn datetimes() {
+    let schema = infer_toml(
+        r#"
+        date = 2021-12-31T00:00:00Z
+        local_date_time = 2021-12-31T23:59:59
+        local_date = 2021-12-31
+        local_time = 07:32:00
+        text = "2021-12-31T00:00:00Z"
+        "#,
+    );
+
+    assert!(is_datetime(&schema, "date"));
+    assert!(is_datetime(&schema, "local_date_time"));
+    assert!(is_datetime(&schema, "local_date"));
+    assert!(is_datetime(&schema, "local_time"));
+    assert!(!is_datetime(&schema, "text"));
+}
+
+#[test]
+fn datetimes_in_arrays_merge_with_strings() {
+    let schema = infer_toml(
+        r#"
+        [[events]]
+        at = 2021-12-31T00:00:00Z
+
+        [[events]]
+        at = "unknown"
+        "#,
+    );
+
+    let events = match &schema {
+        Schema::Struct { fields, .. } => fields["events"].schema.clone().unwrap(),
+        _ => unreachable!(),
+    };
+    let event = match events {
+        Schema::Sequence { field, .. } => field.schema.unwrap(),
+        _ => unreachable!(),
+    };
+    match &event {
+        Schema::Struct { fields, .. } => match &fields["at"].schema {
+            Some(Schema::String(context)) => {
+                assert!(context.is_datetime);
+                assert_eq!(context.count.0, 2);
+            }
+            other => panic!("{:?}", other),
+        },
+        _ => unreachable!(),
+    }
+}
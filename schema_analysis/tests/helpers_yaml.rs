@@ -0,0 +1,106 @@
+#![cfg(feature = "yaml")]
+
+use maplit::btreemap;
+
+use schema_analysis::{helpers, Field, Schema, StructuralEq};
+
+#[test]
+fn multi_document_stream() {
+    let input = "
+---
+id: 1
+name: first
+---
+id: 2
+---
+id: 3
+name: third
+tags: [ a, b ]
+";
+    let inferred = helpers::yaml::infer_multi_document(input).unwrap();
+
+    let mut id = Field::with_schema(Schema::Integer(Default::default()));
+    id.status.may_be_normal = true;
+    let mut name = Field::with_schema(Schema::String(Default::default()));
+    name.status.may_be_normal = true;
+    name.status.may_be_missing = true;
+    let mut tags = {
+        let mut element = Field::with_schema(Schema::String(Default::default()));
+        element.status.may_be_normal = true;
+        Field::with_schema(Schema::Sequence {
+            field: Box::new(element),
+            context: Default::default(),
+        })
+    };
+    tags.status.may_be_normal = true;
+    tags.status.may_be_missing = true;
+
+    let expected = Schema::Struct {
+        fields: btreemap! {
+            "id".into() => id,
+            "name".into() => name,
+            "tags".into() => tags,
+        },
+        context: Default::default(),
+    };
+    assert!(inferred.schema.structural_eq(&expected));
+
+    if let Schema::Struct { context, .. } = &inferred.schema {
+        assert_eq!(context.count.0, 3);
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn single_document_without_separator() {
+    let inferred = helpers::yaml::infer_multi_document("true").unwrap();
+    assert!(inferred
+        .schema
+        .structural_eq(&Schema::Boolean(Default::default())));
+}
+
+#[test]
+fn differing_documents_and_empty_documents() {
+    // The empty document is considered null.
+    let inferred = helpers::yaml::infer_multi_document("1\n---\nhello\n---\n").unwrap();
+    let expected = Schema::Union {
+        variants: vec![
+            Schema::Null(Default::default()),
+            Schema::Integer(Default::default()),
+            Schema::String(Default::default()),
+        ],
+    };
+    assert!(inferred.schema.structural_eq(&expected));
+}
+
+#[test]
+fn tagged_documents() {
+    // Tags are dropped, both on the root of a document and on nested values, whether they
+    // create the schema or extend it.
+    let inferred = helpers::yaml::infer_multi_document("a: !Foo 1\n").unwrap();
+    let untagged = helpers::yaml::infer_multi_document("a: 1\n").unwrap();
+    assert!(inferred.schema.structural_eq(&untagged.schema));
+
+    let input = "
+--- !Point
+x: !Int 1
+tags: !Tags [ !Tag a, b ]
+--- !Point
+x: !Int 2
+tags: [ a, !Tag b ]
+";
+    let inferred = helpers::yaml::infer_multi_document(input).unwrap();
+    let untagged = helpers::yaml::infer_multi_document(
+        "
+---
+x: 1
+tags: [ a, b ]
+---
+x: 2
+tags: [ a, b ]
+",
+    )
+    .unwrap();
+    assert!(inferred.schema.structural_eq(&untagged.schema));
+}
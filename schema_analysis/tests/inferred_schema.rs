@@ -0,0 +1,544 @@
+use serde::de::{
+    self,
+    value::{Error as ValueError, MapDeserializer, SeqDeserializer},
+    DeserializeSeed, Deserializer, IntoDeserializer, Visitor,
+};
+
+use schema_analysis::{
+    Coalesce, CoalesceRef, ConstantValue, Context, Field, InferredSchema, KeyNormalizer, Schema,
+    SchemaAccumulator, StructuralEq,
+};
+
+fn infer_stream(documents: &[&str]) -> InferredSchema {
+    let mut iter = documents.iter();
+    let mut inferred: InferredSchema = serde_json::from_str(iter.next().unwrap()).unwrap();
+    for document in iter {
+        let mut deserializer = serde_json::Deserializer::from_str(document);
+        inferred.deserialize(&mut deserializer).unwrap();
+    }
+    inferred
+}
+
+#[test]
+fn root_shape_summary_of_heterogeneous_stream() {
+    let inferred = infer_stream(&[
+        r#"{ "a": 1 }"#,
+        r#"[1, 2]"#,
+        r#"{ "b": 2 }"#,
+        r#"[]"#,
+        r#"{ "c": 3 }"#,
+        r#""scalar""#,
+    ]);
+    assert_eq!(
+        inferred.root_shape_summary(),
+        vec![
+            ("String".to_string(), 1),
+            ("Sequence".to_string(), 2),
+            ("Struct".to_string(), 3),
+        ]
+    );
+}
+
+#[test]
+fn root_shape_summary_of_homogeneous_stream() {
+    let inferred = infer_stream(&[r#"{ "a": 1 }"#, r#"{ "a": 2 }"#]);
+    assert_eq!(
+        inferred.root_shape_summary(),
+        vec![("Struct".to_string(), 2)]
+    );
+}
+
+#[test]
+fn documents_seen() {
+    let inferred = infer_stream(&[
+        r#"{ "a": 1 }"#,
+        r#"{ "a": 2 }"#,
+        r#"{ "a": null }"#,
+        r#"{ "b": 2 }"#,
+        r#"{}"#,
+    ]);
+    assert_eq!(inferred.documents_seen(), 5);
+
+    let mut documents = [r#"{ "a": 1 }"#, "[1, 2]", "null", r#""x""#, "[]"]
+        .iter()
+        .map(|document| serde_json::from_str::<InferredSchema>(document).unwrap());
+    let mut coalesced = documents.next().unwrap();
+    for document in documents {
+        coalesced.schema.coalesce(document.schema);
+    }
+    assert!(matches!(coalesced.schema, Schema::Union { .. }));
+    assert_eq!(coalesced.documents_seen(), 5);
+}
+
+#[test]
+fn first_seen_document() {
+    let documents = [
+        r#"{ "a": 1 }"#,
+        r#"{ "a": 2, "b": { "c": 1 } }"#,
+        r#"{ "a": 3 }"#,
+        r#"{ "a": 4, "b": { "c": 2, "d": [ { "e": true } ] }, "f": null }"#,
+    ];
+
+    let mut deserializer = serde_json::Deserializer::from_str(documents[0]);
+    let mut inferred = InferredSchema::deserialize_document(0, &mut deserializer).unwrap();
+    for (index, document) in documents.iter().enumerate().skip(1) {
+        let mut deserializer = serde_json::Deserializer::from_str(document);
+        inferred
+            .extend_with_document(index, &mut deserializer)
+            .unwrap();
+    }
+
+    let first_seen = |path: &[&str]| {
+        let mut schema = &inferred.schema;
+        let mut field = None;
+        for key in path {
+            let fields = match schema {
+                Schema::Struct { fields, .. } => fields,
+                Schema::Sequence { field, .. } => match &field.schema {
+                    Some(Schema::Struct { fields, .. }) => fields,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            };
+            field = Some(&fields[*key]);
+            schema = fields[*key].schema.as_ref().unwrap_or(schema);
+        }
+        field.unwrap().status.first_seen
+    };
+
+    assert_eq!(first_seen(&["a"]), Some(0));
+    assert_eq!(first_seen(&["b"]), Some(1));
+    assert_eq!(first_seen(&["b", "c"]), Some(1));
+    assert_eq!(first_seen(&["b", "d"]), Some(3));
+    assert_eq!(first_seen(&["b", "d", "e"]), Some(3));
+    assert_eq!(first_seen(&["f"]), Some(3));
+
+    // Plain deserialization does not record anything.
+    let plain: InferredSchema = serde_json::from_str(documents[3]).unwrap();
+    match plain.schema {
+        Schema::Struct { fields, .. } => assert_eq!(fields["a"].status.first_seen, None),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn last_seen_document() {
+    // The ticks can be anything that grows, like timestamps.
+    let documents = [
+        (
+            1_600_000_000,
+            r#"{ "a": 1, "old": true, "b": [ { "c": 1 } ] }"#,
+        ),
+        (1_600_000_060, r#"{ "a": 2, "old": null, "b": [] }"#),
+        (1_600_000_120, r#"{ "a": 3, "b": [ { "d": 1 } ] }"#),
+        (1_600_000_180, r#"{ "a": 4 }"#),
+    ];
+
+    let mut inferred: Option<InferredSchema> = None;
+    for (tick, document) in documents.iter() {
+        let mut deserializer = serde_json::Deserializer::from_str(document);
+        match &mut inferred {
+            Some(inferred) => inferred
+                .extend_with_document(*tick, &mut deserializer)
+                .unwrap(),
+            None => {
+                inferred =
+                    Some(InferredSchema::deserialize_document(*tick, &mut deserializer).unwrap())
+            }
+        }
+    }
+
+    let fields = match inferred.unwrap().schema {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    let seen = |field: &Field| (field.status.first_seen, field.status.last_seen);
+    assert_eq!(
+        seen(&fields["a"]),
+        (Some(1_600_000_000), Some(1_600_000_180))
+    );
+    // Null values count as seen.
+    assert_eq!(
+        seen(&fields["old"]),
+        (Some(1_600_000_000), Some(1_600_000_060))
+    );
+    assert_eq!(
+        seen(&fields["b"]),
+        (Some(1_600_000_000), Some(1_600_000_120))
+    );
+    let inner = match &fields["b"].schema {
+        Some(Schema::Sequence { field, .. }) => match &field.schema {
+            Some(Schema::Struct { fields, .. }) => fields,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        seen(&inner["c"]),
+        (Some(1_600_000_000), Some(1_600_000_000))
+    );
+    assert_eq!(
+        seen(&inner["d"]),
+        (Some(1_600_000_120), Some(1_600_000_120))
+    );
+
+    // Coalescing keeps the widest range.
+    let mut early = fields["old"].status.clone();
+    early.coalesce(fields["a"].status.clone());
+    assert_eq!(
+        (early.first_seen, early.last_seen),
+        (Some(1_600_000_000), Some(1_600_000_180))
+    );
+}
+
+#[test]
+fn documents_with_context() {
+    let mut context = Context::default();
+    context.settings.key_normalizer = Some(KeyNormalizer::Lowercase);
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "UserId": 1 }"#);
+    let mut inferred = context.deserialize_document(0, &mut deserializer).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "userid": 2, "Name": "a" }"#);
+    inferred.extend_with_document(1, &mut deserializer).unwrap();
+
+    let fields = match &inferred.schema {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    let seen = |field: &Field| (field.status.first_seen, field.status.last_seen);
+    assert_eq!(fields.len(), 2);
+    assert_eq!(seen(&fields["userid"]), (Some(0), Some(1)));
+    assert_eq!(seen(&fields["name"]), (Some(1), Some(1)));
+}
+
+#[test]
+fn constant_values() {
+    let inferred = infer_stream(&[
+        r#"{ "version": "v2", "kind": "a", "flag": true, "ratio": 0.5 }"#,
+        r#"{ "version": "v2", "kind": "b", "flag": true, "ratio": 0.5 }"#,
+        r#"{ "version": "v2", "kind": "a", "flag": false }"#,
+    ]);
+
+    let fields = match &inferred.schema {
+        Schema::Struct { fields, .. } => fields,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        fields["version"].constant_value(),
+        Some(ConstantValue::String("v2"))
+    );
+    assert_eq!(fields["kind"].constant_value(), None);
+    assert_eq!(fields["flag"].constant_value(), None);
+    // Missing values are not taken into account.
+    assert_eq!(
+        fields["ratio"].constant_value(),
+        Some(ConstantValue::Float(0.5))
+    );
+}
+
+#[test]
+fn union_discriminator() {
+    let circle = infer_stream(&[
+        r#"{ "kind": "circle", "radius": 1.5, "label": "a" }"#,
+        r#"{ "kind": "circle", "radius": 2, "label": "a" }"#,
+    ]);
+    let square = infer_stream(&[r#"{ "kind": "square", "side": 3, "label": "a" }"#]);
+    let union = Schema::Union {
+        variants: vec![circle.schema.clone(), square.schema],
+    };
+    // `label` is constant too, but the same in both variants.
+    assert_eq!(union.union_discriminator(), Some("kind".to_string()));
+
+    // The same value in two variants, or a variant that is not a struct, is not a union of
+    // tagged structs.
+    let other_circle = infer_stream(&[r#"{ "kind": "circle", "center": [0, 0] }"#]);
+    let union = Schema::Union {
+        variants: vec![circle.schema.clone(), other_circle.schema],
+    };
+    assert_eq!(union.union_discriminator(), None);
+    let union = Schema::Union {
+        variants: vec![circle.schema, infer_stream(&["1"]).schema],
+    };
+    assert_eq!(union.union_discriminator(), None);
+}
+
+#[test]
+fn coalesce_from_reference() {
+    let base = || infer_stream(&[r#"{ "a": 1, "b": "x" }"#]).schema;
+    let cached = infer_stream(&[r#"{ "a": 2.5, "c": [true] }"#, r#"{ "a": null }"#]).schema;
+
+    let mut borrowed = base();
+    let mut owned = base();
+    for _ in 0..3 {
+        borrowed.coalesce_ref(&cached);
+        owned.coalesce(cached.clone());
+    }
+
+    assert_eq!(borrowed, owned);
+    // The cached schema is left untouched.
+    assert_eq!(
+        cached,
+        infer_stream(&[r#"{ "a": 2.5, "c": [true] }"#, r#"{ "a": null }"#]).schema
+    );
+}
+
+#[test]
+fn from_deserializers_matches_manual_loop() {
+    let documents = [
+        r#"{ "a": 1, "b": [true] }"#,
+        r#"{ "a": "x" }"#,
+        r#"[{ "c": null }]"#,
+    ];
+
+    let mut deserializers: Vec<_> = documents
+        .iter()
+        .map(|document| serde_json::Deserializer::from_str(document))
+        .collect();
+    let inferred = InferredSchema::from_deserializers(deserializers.iter_mut()).unwrap();
+
+    assert_eq!(inferred, infer_stream(&documents));
+}
+
+#[test]
+fn from_deserializers_without_documents() {
+    let mut deserializers: Vec<serde_json::Deserializer<serde_json::de::StrRead>> = Vec::new();
+    let error = InferredSchema::from_deserializers(deserializers.iter_mut()).unwrap_err();
+    assert!(error.to_string().contains("no documents found"));
+}
+
+/// Produces `[record, record, ...]` on demand, so the array is never held in memory.
+#[cfg(feature = "serde_json")]
+struct RecordStream {
+    next: usize,
+    records: usize,
+    pending: std::collections::VecDeque<u8>,
+}
+#[cfg(feature = "serde_json")]
+impl RecordStream {
+    fn new(records: usize) -> Self {
+        Self {
+            next: 0,
+            records,
+            pending: b"[".iter().copied().collect(),
+        }
+    }
+}
+#[cfg(feature = "serde_json")]
+impl std::io::Read for RecordStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() && self.next <= self.records {
+            let chunk = if self.next == self.records {
+                "]".to_string()
+            } else {
+                let separator = if self.next == 0 { "" } else { ", " };
+                format!(
+                    r#"{}{{ "id": {}, "name": "record {}", "tags": [{}], "inner": {{ "even": {} }} }}"#,
+                    separator,
+                    self.next,
+                    self.next,
+                    self.next % 3,
+                    self.next.is_multiple_of(2)
+                )
+            };
+            self.pending.extend(chunk.bytes());
+            self.next += 1;
+        }
+        let n = buf.len().min(self.pending.len());
+        for (slot, byte) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *slot = byte;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn from_json_array_stream_matches_elements() {
+    let documents = [
+        r#"{ "a": 1, "b": [true] }"#,
+        r#"{ "a": "x" }"#,
+        r#"[{ "c": null }]"#,
+    ];
+    let array = format!("[{}]", documents.join(", "));
+
+    let inferred = InferredSchema::from_json_array_stream(array.as_bytes()).unwrap();
+
+    assert_eq!(inferred, infer_stream(&documents));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn from_json_array_stream_has_bounded_size() {
+    let small = InferredSchema::from_json_array_stream(RecordStream::new(10)).unwrap();
+    let large = InferredSchema::from_json_array_stream(RecordStream::new(20_000)).unwrap();
+
+    match &large.schema {
+        Schema::Struct { context, .. } => assert_eq!(context.count.0, 20_000),
+        schema => panic!("expected a struct, found {:?}", schema),
+    }
+    assert_eq!(
+        small.schema.json_pointers().len(),
+        large.schema.json_pointers().len()
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn from_json_array_stream_errors() {
+    let not_an_array = InferredSchema::from_json_array_stream(&b"{}"[..]).unwrap_err();
+    assert!(not_an_array.to_string().contains("expected an array"));
+
+    let empty = InferredSchema::from_json_array_stream(&b"[]"[..]).unwrap_err();
+    assert!(empty.to_string().contains("no elements found"));
+
+    let trailing = InferredSchema::from_json_array_stream(&b"[1] 2"[..]).unwrap_err();
+    assert!(trailing.to_string().contains("trailing characters"));
+}
+
+#[test]
+fn transcode_json_to_json() {
+    let document = r#"{"id":7,"name":"Ada","score":-1.5,"tags":["a","b"],"parent":null,"meta":{"ok":true,"nested":[[1],[]]}}"#;
+    let pretty =
+        serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(document).unwrap())
+            .unwrap();
+
+    let mut output = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_str(&pretty);
+    let mut serializer = serde_json::Serializer::new(&mut output);
+    let transcoded = InferredSchema::transcode(&mut deserializer, &mut serializer).unwrap();
+    deserializer.end().unwrap();
+
+    let standalone: InferredSchema = serde_json::from_str(document).unwrap();
+    assert_eq!(transcoded, standalone);
+    assert_eq!(String::from_utf8(output).unwrap(), document);
+
+    let mut output = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{"id":7,"#);
+    let mut serializer = serde_json::Serializer::new(&mut output);
+    assert!(InferredSchema::transcode(&mut deserializer, &mut serializer).is_err());
+}
+
+#[test]
+fn schema_accumulator() {
+    fn observe(accumulator: &mut SchemaAccumulator, document: &str) {
+        let mut deserializer = serde_json::Deserializer::from_str(document);
+        accumulator.observe(&mut deserializer).unwrap();
+    }
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+    // The documents are indexed in the order they are observed.
+    fn infer_documents(documents: &[&str]) -> Schema {
+        let mut deserializer = serde_json::Deserializer::from_str(documents[0]);
+        let mut inferred = InferredSchema::deserialize_document(0, &mut deserializer).unwrap();
+        for (index, document) in documents.iter().enumerate().skip(1) {
+            let mut deserializer = serde_json::Deserializer::from_str(document);
+            inferred
+                .extend_with_document(index, &mut deserializer)
+                .unwrap();
+        }
+        inferred.schema
+    }
+
+    let mut accumulator = SchemaAccumulator::new();
+    assert_send_sync(&accumulator);
+    assert_eq!(accumulator.snapshot(), None);
+
+    observe(&mut accumulator, r#"{ "id": 1 }"#);
+    let first = accumulator.snapshot().unwrap();
+    assert_eq!(first, infer_documents(&[r#"{ "id": 1 }"#]));
+
+    observe(&mut accumulator, r#"{ "id": 2, "name": "a" }"#);
+    let second = accumulator.snapshot().unwrap();
+    assert_eq!(
+        second,
+        infer_documents(&[r#"{ "id": 1 }"#, r#"{ "id": 2, "name": "a" }"#])
+    );
+    // Earlier snapshots are not affected.
+    assert_ne!(first, second);
+    assert_eq!(accumulator.documents(), 2);
+
+    // Failed documents are neither counted nor merged.
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "id": 3, "other": 1, "#);
+    assert!(accumulator.observe(&mut deserializer).is_err());
+    assert_eq!(accumulator.documents(), 2);
+    assert_eq!(accumulator.snapshot().unwrap(), second);
+
+    // Documents analyzed elsewhere can be merged in.
+    let mut other = SchemaAccumulator::new();
+    observe(&mut other, r#"{ "id": 3 }"#);
+    accumulator.coalesce(other);
+    accumulator.coalesce(SchemaAccumulator::new());
+    assert_eq!(accumulator.documents(), 3);
+    assert!(accumulator.into_inferred().unwrap().schema.structural_eq(
+        &infer_stream(&[
+            r#"{ "id": 1 }"#,
+            r#"{ "id": 2, "name": "a" }"#,
+            r#"{ "id": 3 }"#
+        ])
+        .schema
+    ));
+}
+
+/// Wraps a value in a newtype struct, like a Rust-side wrapper type would.
+struct Newtype<T>(T);
+impl<'de, T: IntoDeserializer<'de, ValueError>> Deserializer<'de> for Newtype<T> {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_newtype_struct(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+impl<'de, T: IntoDeserializer<'de, ValueError>> IntoDeserializer<'de, ValueError> for Newtype<T> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Wraps a value in an explicit `Some`, like a deserializer wrapping an [Option] would.
+struct Present<T>(T);
+impl<'de, T: IntoDeserializer<'de, ValueError>> Deserializer<'de> for Present<T> {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_some(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[test]
+fn root_some_is_transparent() {
+    let mut inferred: InferredSchema = de::Deserialize::deserialize(Present(7)).unwrap();
+    assert_eq!(inferred, infer_stream(&["7"]));
+    inferred.deserialize(Present(8)).unwrap();
+    assert_eq!(inferred, infer_stream(&["7", "8"]));
+}
+
+#[test]
+fn newtype_structs_are_transparent() {
+    let mut inferred: InferredSchema = de::Deserialize::deserialize(Newtype(7)).unwrap();
+    assert!(matches!(inferred.schema, Schema::Integer(_)));
+    inferred.deserialize(Newtype(7.5)).unwrap();
+    assert!(matches!(&inferred.schema, Schema::Union { variants } if variants.len() == 2));
+
+    let map = MapDeserializer::<_, ValueError>::new(
+        vec![("a", Newtype(1)), ("b", Newtype(2))].into_iter(),
+    );
+    let inferred: InferredSchema = de::Deserialize::deserialize(map).unwrap();
+    assert_eq!(inferred, infer_stream(&[r#"{ "a": 1, "b": 2 }"#]));
+
+    let seq = SeqDeserializer::<_, ValueError>::new(vec![Newtype("x"), Newtype("y")].into_iter());
+    let inferred: InferredSchema = de::Deserialize::deserialize(seq).unwrap();
+    assert_eq!(inferred, infer_stream(&[r#"["x", "y"]"#]));
+}
@@ -0,0 +1,81 @@
+//! Checks the size-bounded [DebugCompact] view of schemas.
+
+use schema_analysis::{
+    context::{CountingSet, StringContext},
+    Aggregate, DebugCompact, Field, InferredSchema, Schema,
+};
+
+#[test]
+fn compact_debug_is_bounded() {
+    let mut context = StringContext::default();
+    for i in 0..1000 {
+        let value = format!("{:04} {}", i, "a long sample value ".repeat(10));
+        context.aggregate(&value);
+        // Spellings are not capped like samples.
+        let spellings = context
+            .suspicious_spellings
+            .entry(value.clone())
+            .or_insert_with(CountingSet::default);
+        spellings.insert(&value);
+    }
+    let schema = Schema::Sequence {
+        field: Box::new(Field::with_schema(Schema::String(context))),
+        context: Default::default(),
+    };
+
+    let full = format!("{:?}", schema);
+    let compact = format!("{:?}", schema.debug_compact());
+    assert!(full.len() > 100_000, "{}", full.len());
+    assert!(compact.len() < 1_000, "{}", compact);
+
+    // Truncated entries are counted.
+    assert!(compact.contains("… 997 more"), "{}", compact);
+    // Samples are truncated in both number and length.
+    assert!(
+        compact.contains(r#""0000 a long sample value a long "…"#),
+        "{}",
+        compact
+    );
+    // Empty fields are left out.
+    assert!(!compact.contains("may_be_null"), "{}", compact);
+    assert!(!compact.contains("is_datetime"), "{}", compact);
+}
+
+#[test]
+fn compact_debug_keeps_the_structure() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[{ "a": 1, "b": "x" }, { "a": 1.5, "c": [true, false, true, false] }]"#,
+    )
+    .unwrap();
+
+    let compact = format!("{:?}", DebugCompact(&schema));
+    for expected in [
+        "Sequence { field: Field {",
+        r#""a": Field { status: FieldStatus { may_be_normal: true }, schema: Union { variants: [Integer {"#,
+        r#""b": Field { status: FieldStatus { may_be_normal: true, may_be_missing: true }, schema: String {"#,
+        "schema: Boolean { count: 4, trues: 2, falses: 2 }",
+    ]
+    .iter()
+    {
+        assert!(compact.contains(expected), "{}\n{}", expected, compact);
+    }
+
+    // The full view and serialization are unaffected.
+    assert!(format!("{:?}", schema).contains("may_be_duplicate: false"));
+    let serialized = serde_json::to_string(&schema).unwrap();
+    assert!(serialized.contains(r#""may_be_duplicate":false"#));
+}
+
+#[test]
+fn compact_debug_keeps_zero_values() {
+    let InferredSchema { schema } = serde_json::from_str(r#"[0, 0, [true]]"#).unwrap();
+
+    let compact = format!("{:?}", schema.debug_compact());
+    // A zero minimum or maximum is a value, a zero count is not.
+    assert!(compact.contains("min: 0, max: 0 }"), "{}", compact);
+    assert!(
+        compact.contains("Boolean { count: 1, trues: 1 }"),
+        "{}",
+        compact
+    );
+}
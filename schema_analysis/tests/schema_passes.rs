@@ -0,0 +1,652 @@
+use maplit::btreemap;
+
+use schema_analysis::{
+    context::{
+        BooleanContext, BytesContext, MapStructContext, NullContext, NumberContext,
+        SequenceContext, StringContext,
+    },
+    Coalesce, CoalesceConfig, ContextMapper, Field, InferredSchema, Schema, StructuralEq,
+    UnionOverflow,
+};
+
+mod shared;
+use shared::infer;
+
+fn field_of(schema: &Schema, name: &str) -> Field {
+    match schema {
+        Schema::Sequence { field, .. } => match &field.schema {
+            Some(Schema::Struct { fields, .. }) => fields[name].clone(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn unify_string_and_string_sequence() {
+    let mut schema = infer(r#"[ { "tags": "x" }, { "tags": ["x", "y"] } ]"#);
+    schema.unify_scalar_and_sequence();
+
+    let mut element = Field::with_schema(Schema::String(Default::default()));
+    element.status.may_be_normal = true;
+    let expected = Schema::Sequence {
+        field: Box::new(element),
+        context: Default::default(),
+    };
+
+    let tags = field_of(&schema, "tags");
+    let tags_schema = tags.schema.unwrap();
+    assert!(tags_schema.structural_eq(&expected));
+    match tags_schema {
+        Schema::Sequence { field, context } => {
+            assert!(context.may_be_scalar);
+            // The scalar counts as a sequence of one element.
+            assert_eq!(context.count.0, 2);
+            assert_eq!((context.length.min, context.length.max), (Some(1), Some(2)));
+            // The scalar was merged into the element's context.
+            match field.schema {
+                Some(Schema::String(context)) => assert_eq!(context.count.0, 3),
+                _ => unreachable!(),
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn unify_struct_and_struct_sequence() {
+    let mut schema = infer(r#"[ { "a": { "b": 1 } }, { "a": [ { "b": 2 }, { "c": 3 } ] } ]"#);
+    schema.unify_scalar_and_sequence();
+
+    let mut b = Field::with_schema(Schema::Integer(Default::default()));
+    b.status.may_be_normal = true;
+    b.status.may_be_missing = true;
+    let mut c = Field::with_schema(Schema::Integer(Default::default()));
+    c.status.may_be_normal = true;
+    c.status.may_be_missing = true;
+    let mut element = Field::with_schema(Schema::Struct {
+        fields: btreemap! {
+            "b".into() => b,
+            "c".into() => c,
+        },
+        context: Default::default(),
+    });
+    element.status.may_be_normal = true;
+    let expected = Schema::Sequence {
+        field: Box::new(element),
+        context: Default::default(),
+    };
+
+    let a = field_of(&schema, "a");
+    let a_schema = a.schema.unwrap();
+    assert!(a_schema.structural_eq(&expected));
+    match a_schema {
+        Schema::Sequence { field, context } => {
+            assert_eq!(context.count.0, 2);
+            match field.schema {
+                Some(Schema::Struct { context, .. }) => assert_eq!(context.count.0, 3),
+                _ => unreachable!(),
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn unify_keeps_unrelated_variants() {
+    let mut schema = infer(r#"[ { "a": "x" }, { "a": 1 }, { "a": ["y"] } ]"#);
+    schema.unify_scalar_and_sequence();
+
+    let mut element = Field::with_schema(Schema::String(Default::default()));
+    element.status.may_be_normal = true;
+    let expected = Schema::Union {
+        variants: vec![
+            Schema::Integer(Default::default()),
+            Schema::Sequence {
+                field: Box::new(element),
+                context: Default::default(),
+            },
+        ],
+    };
+
+    let a = field_of(&schema, "a");
+    assert!(a.schema.unwrap().structural_eq(&expected));
+
+    // The sequence keeps its place among the other variants.
+    let mut schema = infer(r#"[ { "a": ["y"] }, { "a": "x" }, { "a": 1 }, { "a": true } ]"#);
+    schema.unify_scalar_and_sequence();
+    match field_of(&schema, "a").schema {
+        Some(Schema::Union { variants }) => match variants.as_slice() {
+            [Schema::Sequence { context, .. }, Schema::Integer(integer), Schema::Boolean(boolean)] =>
+            {
+                assert_eq!(context.count.0, 2);
+                assert_eq!((integer.count.0, boolean.count.0), (1, 1));
+            }
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn retain_fields() {
+    let InferredSchema { mut schema } = serde_json::from_str(
+        r#"{
+            "name": "a",
+            "user_ssn": "b",
+            "users": [ { "id": 1, "spouse_ssn": "c", "password": "d" } ],
+            "secrets": { "password": "e" }
+        }"#,
+    )
+    .unwrap();
+
+    let mut paths = Vec::new();
+    schema.retain_fields(|path, _| {
+        paths.push(path.join("."));
+        let key = path.last().unwrap();
+        !key.ends_with("_ssn") && key != "password"
+    });
+
+    // Removed fields are not visited further.
+    assert_eq!(
+        paths,
+        [
+            "name",
+            "secrets",
+            "user_ssn",
+            "users",
+            "secrets.password",
+            "users.id",
+            "users.password",
+            "users.spouse_ssn",
+        ]
+    );
+
+    let expected: InferredSchema =
+        serde_json::from_str(r#"{ "name": "a", "users": [ { "id": 1 } ], "secrets": {} }"#)
+            .unwrap();
+    assert!(schema.structural_eq(&expected.schema));
+}
+
+#[test]
+fn resolve_dominant_collapses_mostly_integer_union() {
+    let values = (0..95)
+        .map(|i| format!(r#"{{ "id": {} }}"#, i))
+        .chain((0..5).map(|i| format!(r#"{{ "id": "{}" }}"#, i)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut schema = infer(&format!("[{}]", values));
+    schema.resolve_dominant(0.9);
+
+    let field = field_of(&schema, "id");
+    match field.schema {
+        Some(Schema::Integer(context)) => assert_eq!(context.count.0, 95),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn resolve_dominant_updates_the_status() {
+    let values = |kept: &str, dropped: &str| {
+        let values = (0..9)
+            .map(|_| kept)
+            .chain(std::iter::once(dropped))
+            .map(|value| format!(r#"{{ "id": {} }}"#, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("[{}]", values)
+    };
+
+    // The empty sequences were dropped with their variant.
+    let mut schema = infer(&values("1", "[]"));
+    assert!(field_of(&schema, "id").status.may_be_empty_collection);
+    schema.resolve_dominant(0.9);
+    let field = field_of(&schema, "id");
+    assert!(matches!(field.schema, Some(Schema::Integer(_))));
+    assert!(!field.status.may_be_empty_collection);
+
+    // But not when they are in the kept one.
+    let mut schema = infer(&values("[]", "1"));
+    schema.resolve_dominant(0.9);
+    let field = field_of(&schema, "id");
+    assert!(matches!(field.schema, Some(Schema::Sequence { .. })));
+    assert!(field.status.may_be_empty_collection);
+
+    let mut schema = infer(&values("{}", "[]"));
+    schema.resolve_dominant(0.9);
+    let field = field_of(&schema, "id");
+    assert!(matches!(field.schema, Some(Schema::Struct { .. })));
+    assert!(field.status.may_be_empty_collection);
+}
+
+#[test]
+fn resolve_dominant_keeps_balanced_union() {
+    let mut schema = infer(r#"[{ "id": 1 }, { "id": "2" }, { "id": 3 }, { "id": "4" }]"#);
+    let before = schema.clone();
+    schema.resolve_dominant(0.9);
+    assert_eq!(schema, before);
+
+    // Unless the threshold allows it.
+    schema.resolve_dominant(0.5);
+    let field = field_of(&schema, "id");
+    assert!(!matches!(field.schema, Some(Schema::Union { .. })));
+}
+
+#[test]
+fn cap_union_keeps_most_common_variants() {
+    // Six kinds of values, as nulls are recorded on the field.
+    let values = [
+        ("1", 6),
+        (r#""a""#, 5),
+        ("1.5", 4),
+        ("true", 3),
+        ("[1]", 2),
+        (r#"{ "inner": 1 }"#, 1),
+    ]
+    .iter()
+    .flat_map(|(value, count)| (0..*count).map(move |_| format!(r#"{{ "v": {} }}"#, value)))
+    .collect::<Vec<_>>()
+    .join(", ");
+    let mut schema = infer(&format!("[{}]", values));
+
+    let overflows = schema.cap_union(3);
+    assert_eq!(
+        overflows,
+        vec![UnionOverflow {
+            path: vec!["v".into()],
+            variants: vec!["Boolean", "Sequence", "Struct"],
+            count: 6,
+        }]
+    );
+    match field_of(&schema, "v").schema {
+        Some(Schema::Union { variants }) => {
+            let mut names = variants
+                .iter()
+                .map(|variant| match variant {
+                    Schema::Integer(context) => ("Integer", context.count.0),
+                    Schema::Float(context) => ("Float", context.count.0),
+                    Schema::String(context) => ("String", context.count.0),
+                    other => panic!("{:?}", other),
+                })
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            assert_eq!(names, vec![("Float", 4), ("Integer", 6), ("String", 5)]);
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // Capping again changes nothing.
+    let before = schema.clone();
+    assert!(schema.cap_union(3).is_empty());
+    assert_eq!(schema, before);
+
+    // A single remaining variant replaces the union.
+    schema.cap_union(1);
+    assert!(matches!(
+        field_of(&schema, "v").schema,
+        Some(Schema::Integer(_))
+    ));
+}
+
+#[test]
+fn coalesce_with_default_keeps_numbers_apart() {
+    let mut schema = infer(r#"{ "x": 1 }"#);
+    let mut coalesced = schema.clone();
+    schema.coalesce_with(infer(r#"{ "x": 1.5 }"#), &CoalesceConfig::default());
+    coalesced.coalesce(infer(r#"{ "x": 1.5 }"#));
+    assert_eq!(schema, coalesced);
+
+    match schema {
+        Schema::Struct { fields, .. } => {
+            assert!(matches!(fields["x"].schema, Some(Schema::Union { .. })))
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn coalesce_with_numeric_widening() {
+    let config = CoalesceConfig {
+        numeric_widening: true,
+    };
+
+    let mut root = infer("1");
+    root.coalesce_with(infer("2.5"), &config);
+    match root {
+        Schema::Float(context) => {
+            assert_eq!(context.count.0, 2);
+            assert_eq!(context.min_max.min, Some(1.0));
+            assert_eq!(context.min_max.max, Some(2.5));
+            assert!(!context.exceeds_js_safe_integer);
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // Integers outside of the JavaScript safe range are still flagged once widened.
+    let mut large = infer("9007199254740993");
+    large.coalesce_with(infer("2.5"), &config);
+    match large {
+        Schema::Float(context) => assert!(context.exceeds_js_safe_integer),
+        other => panic!("{:?}", other),
+    }
+
+    let mut nested = infer(r#"[{ "x": 1 }, { "x": "a" }]"#);
+    nested.coalesce_with(infer(r#"[{ "x": 1.5 }, { "x": 2 }]"#), &config);
+    let field = field_of(&nested, "x");
+    match field.schema {
+        Some(Schema::Union { variants }) => {
+            assert_eq!(variants.len(), 2);
+            assert!(variants.iter().any(|v| matches!(v, Schema::String(_))));
+            let float = variants.iter().find_map(|v| match v {
+                Schema::Float(context) => Some(context),
+                _ => None,
+            });
+            assert_eq!(float.unwrap().count.0, 3);
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn widen_numeric_sequence() {
+    let mut integers = infer("[1, 2, 3]");
+    assert!(!integers.is_numeric_mixed());
+    assert!(!integers.widen_numeric_sequence());
+    match &integers {
+        Schema::Sequence { field, .. } => {
+            assert!(matches!(field.schema, Some(Schema::Integer(_))))
+        }
+        other => panic!("{:?}", other),
+    }
+
+    let mut mixed = infer("[1, 2.5]");
+    assert!(mixed.is_numeric_mixed());
+    assert!(mixed.widen_numeric_sequence());
+    assert!(!mixed.is_numeric_mixed());
+    match &mixed {
+        Schema::Sequence { field, .. } => match &field.schema {
+            Some(Schema::Float(context)) => {
+                assert_eq!(context.count.0, 2);
+                assert_eq!(context.min_max.min, Some(1.0));
+                assert_eq!(context.min_max.max, Some(2.5));
+            }
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    }
+
+    assert!(!infer(r#"[1, 2.5, "a"]"#).is_numeric_mixed());
+}
+
+#[test]
+fn strip_contexts() {
+    let schema = infer(
+        r#"[
+            { "id": 1, "name": "a", "tags": ["x", "y"], "score": 0.5 },
+            { "id": "2", "name": null, "tags": [] }
+        ]"#,
+    );
+    let stripped = schema.clone().strip_contexts();
+
+    assert!(stripped.structural_eq(&schema));
+    assert_ne!(stripped, schema);
+    assert!(
+        serde_json::to_string(&stripped).unwrap().len()
+            < serde_json::to_string(&schema).unwrap().len()
+    );
+
+    // The statuses are kept, the contexts are emptied.
+    let name = field_of(&stripped, "name");
+    assert_eq!(name.status, field_of(&schema, "name").status);
+    match name.schema {
+        Some(Schema::String(context)) => assert_eq!(context, Default::default()),
+        other => panic!("{:?}", other),
+    }
+    match field_of(&stripped, "tags").schema {
+        Some(Schema::Sequence { context, .. }) => assert_eq!(context, Default::default()),
+        other => panic!("{:?}", other),
+    }
+    match field_of(&stripped, "id").schema {
+        Some(Schema::Union { variants }) => assert!(variants
+            .iter()
+            .all(|variant| *variant == variant.clone().strip_contexts())),
+        other => panic!("{:?}", other),
+    }
+
+    let field = field_of(&schema, "name");
+    assert!(field.clone().strip_contexts().structural_eq(&field));
+}
+
+#[test]
+fn clone_shape_only() {
+    let schema = infer(
+        r#"[
+            { "id": 1, "name": "a", "tags": ["x", "y"], "score": 0.5 },
+            { "id": "2", "name": null, "tags": [], "nested": { "ok": true } }
+        ]"#,
+    );
+    let shape = schema.clone_shape_only();
+
+    assert!(shape.structural_eq(&schema));
+    assert_eq!(shape, schema.clone().strip_contexts());
+
+    let field = field_of(&schema, "nested");
+    assert_eq!(field.clone_shape_only(), field.clone().strip_contexts());
+}
+
+/// Keeps only the counts.
+struct CountOnly {
+    mapped: usize,
+}
+impl ContextMapper for CountOnly {
+    fn map_null(&mut self, context: NullContext) -> NullContext {
+        self.mapped += 1;
+        NullContext {
+            count: context.count,
+        }
+    }
+    fn map_boolean(&mut self, context: BooleanContext) -> BooleanContext {
+        self.mapped += 1;
+        BooleanContext {
+            count: context.count,
+            ..Default::default()
+        }
+    }
+    fn map_integer(&mut self, context: NumberContext<i128>) -> NumberContext<i128> {
+        self.mapped += 1;
+        NumberContext {
+            count: context.count,
+            ..Default::default()
+        }
+    }
+    fn map_float(&mut self, context: NumberContext<f64>) -> NumberContext<f64> {
+        self.mapped += 1;
+        NumberContext {
+            count: context.count,
+            ..Default::default()
+        }
+    }
+    fn map_string(&mut self, context: StringContext) -> StringContext {
+        self.mapped += 1;
+        StringContext {
+            count: context.count,
+            ..Default::default()
+        }
+    }
+    fn map_bytes(&mut self, context: BytesContext) -> BytesContext {
+        self.mapped += 1;
+        BytesContext {
+            count: context.count,
+            ..Default::default()
+        }
+    }
+    fn map_sequence(&mut self, context: SequenceContext) -> SequenceContext {
+        self.mapped += 1;
+        SequenceContext {
+            count: context.count,
+            ..Default::default()
+        }
+    }
+    fn map_struct(&mut self, context: MapStructContext) -> MapStructContext {
+        self.mapped += 1;
+        let mut reset = MapStructContext::default();
+        reset.count = context.count;
+        reset
+    }
+}
+
+#[test]
+fn map_contexts() {
+    let schema = infer(r#"[{ "id": 1, "name": "a" }, { "id": 2.5, "name": "b" }, { "id": 3 }]"#);
+
+    let mut mapper = CountOnly { mapped: 0 };
+    let mapped = schema.clone().map_contexts(&mut mapper);
+    assert!(mapped.structural_eq(&schema));
+    // The sequence, the struct, the two variants of the union and the string.
+    assert_eq!(mapper.mapped, 5);
+
+    match field_of(&mapped, "name").schema {
+        Some(Schema::String(context)) => {
+            assert_eq!(context.count.0, 2);
+            assert_eq!(context.samples, Default::default());
+        }
+        other => panic!("{:?}", other),
+    }
+    match field_of(&mapped, "id").schema {
+        Some(Schema::Union { variants }) => {
+            let counts: Vec<_> = variants
+                .iter()
+                .map(|variant| match variant {
+                    Schema::Integer(context) => (context.count.0, context.min_max.max.is_some()),
+                    Schema::Float(context) => (context.count.0, context.min_max.max.is_some()),
+                    other => panic!("{:?}", other),
+                })
+                .collect();
+            assert_eq!(counts, [(2, false), (1, false)]);
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // The defaults leave everything as is.
+    struct Identity;
+    impl ContextMapper for Identity {}
+    assert_eq!(schema.clone().map_contexts(&mut Identity), schema);
+}
+
+#[test]
+fn required_and_optional_field_names() {
+    let schema = infer(
+        r#"[
+            { "hello": 1, "possibly_null": "!", "possibly_missing": 1.1, "null_or_missing": null },
+            { "hello": 2, "possibly_null": null }
+        ]"#,
+    );
+    let inner = match &schema {
+        Schema::Sequence { field, .. } => field.schema.as_ref().unwrap(),
+        _ => unreachable!(),
+    };
+
+    assert_eq!(inner.required_field_names(), ["hello", "possibly_null"]);
+    assert_eq!(
+        inner.optional_field_names(),
+        ["null_or_missing", "possibly_missing"]
+    );
+
+    assert!(schema.required_field_names().is_empty());
+    assert!(schema.optional_field_names().is_empty());
+}
+
+#[test]
+fn merge_sequences_of_unions() {
+    let shape = |json: &str| match infer(json) {
+        Schema::Sequence { field, .. } => field.schema.unwrap(),
+        _ => unreachable!(),
+    };
+    let sequence_of = |variants: Vec<Schema>| {
+        let mut element = Field::with_schema(Schema::Union { variants });
+        element.status.may_be_normal = true;
+        Schema::Sequence {
+            field: Box::new(element),
+            context: Default::default(),
+        }
+    };
+
+    // A union with a struct per shape, like other tools produce.
+    let mut schema = sequence_of(vec![
+        shape(r#"[{ "a": 1, "b": "x" }]"#),
+        shape(r#"[{ "a": 2, "c": true }]"#),
+    ]);
+    assert!(!schema.is_normalized());
+    schema.merge_sequences_of_unions();
+    assert!(schema.is_normalized());
+    let expected = infer(r#"[{ "a": 1, "b": "x" }, { "a": 2, "c": true }]"#);
+    assert!(schema.structural_eq(&expected), "{:#?}", schema);
+
+    // Other variants are kept, and nested sequences are merged too.
+    let mut items = Field::with_schema(sequence_of(vec![
+        shape(r#"[{ "d": 1 }]"#),
+        shape(r#"[{ "e": 1.5 }]"#),
+    ]));
+    items.status.may_be_normal = true;
+    let mut schema = sequence_of(vec![
+        Schema::Integer(Default::default()),
+        Schema::Struct {
+            fields: btreemap! { "items".into() => items },
+            context: Default::default(),
+        },
+        shape(r#"[{ "f": null }]"#),
+    ]);
+    schema.merge_sequences_of_unions();
+    assert!(schema.is_normalized());
+
+    let expected = shape(r#"[{ "items": [{ "d": 1 }, { "e": 1.5 }] }, { "f": null }]"#);
+    match schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::Union { variants }) => match variants.as_slice() {
+                [Schema::Integer(_), merged] => {
+                    assert!(merged.structural_eq(&expected), "{:#?}", merged)
+                }
+                _ => panic!("{:#?}", variants),
+            },
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn promote_int_booleans() {
+    let mut schema =
+        infer(r#"[{ "flag": 0, "n": 0 }, { "flag": 1, "n": 1 }, { "flag": 1, "n": 2 }]"#);
+    schema.promote_int_booleans();
+
+    let flag = field_of(&schema, "flag");
+    match &flag.schema {
+        Some(Schema::Boolean(context)) => assert_eq!(context.count.0, 3),
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(flag.constant_value(), None);
+    assert!(matches!(
+        field_of(&schema, "n").schema,
+        Some(Schema::Integer(_))
+    ));
+
+    // Joins the booleans already there.
+    let mut schema = infer(r#"[{ "flag": 0 }, { "flag": true }, { "flag": 1 }]"#);
+    schema.promote_int_booleans();
+    let flag = field_of(&schema, "flag");
+    match &flag.schema {
+        Some(Schema::Boolean(context)) => assert_eq!(context.count.0, 3),
+        other => panic!("{:?}", other),
+    }
+    // Both values were found, even if their exact counts are unknown.
+    assert_eq!(flag.constant_value(), None);
+
+    // A single value is not enough.
+    let mut constant = infer("[1, 1]");
+    constant.promote_int_booleans();
+    assert!(matches!(
+        constant,
+        Schema::Sequence { field, .. } if matches!(field.schema, Some(Schema::Integer(_)))
+    ));
+}
@@ -0,0 +1,43 @@
+use schema_analysis::{InferredSchema, Schema};
+
+#[test]
+fn json_pointers() {
+    // Same shape as the `map_struct_mixed_sequence` shared test.
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{ "hello": 1, "world": "!", "sequence": ["one", "two", "three"] }"#,
+    )
+    .unwrap();
+
+    let pointers = schema.json_pointers();
+    let names: Vec<&str> = pointers.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "",
+            "/properties/hello",
+            "/properties/sequence",
+            "/properties/sequence/items",
+            "/properties/world",
+        ]
+    );
+
+    let (_, element) = &pointers[3];
+    assert!(matches!(element, Schema::String(_)));
+}
+
+#[test]
+fn json_pointers_escaping_and_unions() {
+    let InferredSchema { schema } = serde_json::from_str(r#"[ { "a/b~c": [] }, 1 ]"#).unwrap();
+
+    let names: Vec<String> = schema.json_pointers().into_iter().map(|(p, _)| p).collect();
+    assert_eq!(
+        names,
+        [
+            "",
+            "/items",
+            "/items/anyOf/0",
+            "/items/anyOf/0/properties/a~1b~0c",
+            "/items/anyOf/1"
+        ]
+    );
+}
@@ -0,0 +1,519 @@
+//! Checks that schemas survive being stored, both when inferred and when built by hand with
+//! empty contexts.
+
+use serde_json::{json, Value};
+
+use schema_analysis::{
+    context::{Context, MapStructContext, NumberContext, StringContext},
+    Coalesce, ContextMapper, Field, InferredSchema, Schema, SerializeMode, StructuralEq,
+};
+
+mod shared;
+use shared::FormatTests;
+
+struct RoundTrip;
+
+test_format!(RoundTrip);
+
+fn round_trip(schema: &Schema) -> Schema {
+    let serialized = serde_json::to_string(schema).unwrap();
+    let deserialized: Schema = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(&deserialized, schema, "{}", serialized);
+    // Serializing again yields the same output.
+    assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    deserialized
+}
+
+impl FormatTests<Value> for RoundTrip {
+    fn convert_to_inferred_schema(value: Value) -> InferredSchema {
+        let inferred: InferredSchema = serde_json::from_value(value).unwrap();
+        InferredSchema {
+            schema: round_trip(&inferred.schema),
+        }
+    }
+    fn compare(value: Value, target_schema: Schema) {
+        let InferredSchema { schema } = Self::convert_to_inferred_schema(value);
+        assert_eq!(schema, round_trip(&schema));
+        // The targets are built with default contexts, which mostly serialize as nothing.
+        round_trip(&target_schema);
+    }
+
+    fn null() -> Option<Value> {
+        Some(json!(null))
+    }
+    fn boolean() -> Option<Value> {
+        Some(json!(true))
+    }
+    fn integer() -> Option<Value> {
+        Some(json!(123))
+    }
+    fn float() -> Option<Value> {
+        Some(json!(123.123))
+    }
+    fn string() -> Option<Value> {
+        Some(json!("hello there!"))
+    }
+
+    fn empty_sequence() -> Option<Value> {
+        Some(json!([]))
+    }
+    fn string_sequence() -> Option<Value> {
+        Some(json!(["one", "two", "three"]))
+    }
+    fn integer_sequence() -> Option<Value> {
+        Some(json!([1, 2, 3]))
+    }
+    fn mixed_sequence() -> Option<Value> {
+        Some(json!([1, "two", 3]))
+    }
+    fn optional_mixed_sequence() -> Option<Value> {
+        Some(json!([1, "two", 3, null]))
+    }
+
+    fn empty_map_struct() -> Option<Value> {
+        Some(json!({}))
+    }
+    fn map_struct_single() -> Option<Value> {
+        Some(json!({
+            "hello": 1
+        }))
+    }
+    fn map_struct_double() -> Option<Value> {
+        Some(json!({
+            "hello": 1,
+            "world": "!"
+        }))
+    }
+    fn sequence_map_struct_mixed() -> Option<Value> {
+        Some(json!([
+            {
+                "hello": 1,
+                "world": "!",
+                "mixed": 1.1,
+            },
+            {
+                "hello": 1,
+                "world": "!",
+                "mixed": "1.1",
+            }
+        ]))
+    }
+    fn sequence_map_struct_optional_or_missing() -> Option<Value> {
+        Some(json!([
+            {
+                "hello": 1,
+                "possibly_null": "!",
+                "possibly_missing": 1.1,
+                "null_or_missing": null,
+            },
+            {
+                "hello": 2,
+                "possibly_null": null,
+            }
+        ]))
+    }
+    fn map_struct_mixed_sequence() -> Option<Value> {
+        Some(json!({
+            "hello": 1,
+            "world": "!",
+            "sequence": ["one", "two", "three"]
+        }))
+    }
+    fn map_struct_mixed_sequence_optional() -> Option<Value> {
+        Some(json!({
+            "hello": 1,
+            "world": "!",
+            "optional": null,
+            "sequence": ["one", "two", "three", null]
+        }))
+    }
+}
+
+fn inferred_field() -> Field {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "id": 1, "ratio": 0.5, "name": "a", "tags": ["x"] },
+            { "id": -5, "ratio": 2, "name": null, "tags": [] },
+            { "id": 9007199254740993, "nested": { "a": 1.5 } }
+        ]"#,
+    )
+    .unwrap();
+    match schema {
+        Schema::Sequence { field, .. } => *field,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn field_round_trips_through_json() {
+    let field = inferred_field();
+    let serialized = serde_json::to_string(&field).unwrap();
+    let deserialized: Field = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, field, "{}", serialized);
+}
+
+#[test]
+fn field_round_trips_through_yaml() {
+    let field = inferred_field();
+    let serialized = serde_yaml::to_string(&field).unwrap();
+    let deserialized: Field = serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, field, "{}", serialized);
+}
+
+#[test]
+fn field_without_schema() {
+    let mut field = Field::default();
+    field.status.may_be_null = true;
+
+    let serialized = serde_json::to_value(&field).unwrap();
+    assert!(serialized.get("schema").is_none());
+    assert_eq!(serialized["status"]["may_be_null"], true);
+    assert_eq!(serde_json::from_value::<Field>(serialized).unwrap(), field);
+}
+
+#[test]
+fn rounded_float_ranges_are_byte_stable() {
+    let range = |schema: &Schema| match schema {
+        Schema::Float(context) => context.min_max.clone(),
+        _ => unreachable!(),
+    };
+    let run = |document: &str| {
+        let InferredSchema { mut schema } = serde_json::from_str(document).unwrap();
+        schema.round_float_ranges(6);
+        schema
+    };
+
+    // The same data, with some floating point noise.
+    let noisy = run("0.30000000000000004");
+    let clean = run("0.3");
+    assert_eq!(range(&noisy), range(&clean));
+    assert_eq!(range(&noisy).min, Some(0.3));
+
+    // Storing, reloading, merging and rounding again gives the same bytes.
+    let serialized = serde_json::to_string(&range(&noisy)).unwrap();
+    let mut reloaded: Schema =
+        serde_json::from_str(&serde_json::to_string(&noisy).unwrap()).unwrap();
+    reloaded.coalesce(clean);
+    reloaded.round_float_ranges(6);
+    assert_eq!(
+        serde_json::to_string(&range(&reloaded)).unwrap(),
+        serialized
+    );
+    assert_eq!(serialized, r#"{"min":0.3,"max":0.3}"#);
+
+    // Significant digits, not decimal places.
+    let mut schema = run("[123456.789, 0.000123456789]");
+    schema.round_float_ranges(3);
+    let element = match &schema {
+        Schema::Sequence { field, .. } => field.schema.as_ref().unwrap(),
+        _ => unreachable!(),
+    };
+    let rounded = range(element);
+    assert_eq!((rounded.min, rounded.max), (Some(0.000123), Some(123000.0)));
+}
+
+#[test]
+fn serialize_modes() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "id": 7, "tags": ["a", "b"] }"#).unwrap();
+    let serialize = |mode| serde_json::to_value(schema.view(mode)).unwrap();
+
+    // Full is the same as serializing the schema.
+    let full = serialize(SerializeMode::Full);
+    assert_eq!(full, serde_json::to_value(&schema).unwrap());
+    assert_eq!(
+        full["fields"]["id"]["schema"]["samples"]["values"],
+        json!([7])
+    );
+
+    // Summary keeps the statistics, but not the samples.
+    let summary = serialize(SerializeMode::Summary);
+    let id = &summary["fields"]["id"]["schema"];
+    assert_eq!(
+        (&id["count"], &id["min"], &id["max"]),
+        (&json!(1), &json!(7), &json!(7))
+    );
+    assert_eq!(id["samples"]["values"], json!([]));
+    let tags = &summary["fields"]["tags"]["schema"]["field"]["schema"];
+    assert_eq!(tags["count"], 2);
+    assert_eq!(tags["samples"]["values"], json!([]));
+    assert_eq!(summary["context"]["count"], 1);
+
+    // ShapeOnly only has the types, the fields and their status.
+    let status = json!({
+        "may_be_null": false,
+        "may_be_normal": true,
+        "may_be_missing": false,
+        "may_be_duplicate": false,
+    });
+    assert_eq!(
+        serialize(SerializeMode::ShapeOnly),
+        json!({
+            "type": "Struct",
+            "fields": {
+                "id": { "status": status, "schema": { "type": "Integer" } },
+                "tags": {
+                    "status": status,
+                    "schema": {
+                        "type": "Sequence",
+                        "field": { "status": status, "schema": { "type": "String" } },
+                    },
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn summary_matches_dropping_the_samples() {
+    struct DropSamples;
+    impl ContextMapper for DropSamples {
+        fn map_integer(&mut self, mut context: NumberContext<i128>) -> NumberContext<i128> {
+            context.samples = Default::default();
+            context
+        }
+        fn map_float(&mut self, mut context: NumberContext<f64>) -> NumberContext<f64> {
+            context.samples = Default::default();
+            context
+        }
+        fn map_string(&mut self, mut context: StringContext) -> StringContext {
+            context.samples = Default::default();
+            #[cfg(feature = "embedded_json")]
+            {
+                context.embedded_schema = context
+                    .embedded_schema
+                    .map(|schema| Box::new(schema.map_contexts(self)));
+            }
+            context
+        }
+        fn map_struct(&mut self, mut context: MapStructContext) -> MapStructContext {
+            context.key_sets = Default::default();
+            context
+        }
+    }
+
+    let mut context = Context::default();
+    context.settings.track_key_sets = true;
+    #[cfg(feature = "embedded_json")]
+    {
+        context.settings.embedded_json_depth = 1;
+    }
+    let mut deserializer = serde_json::Deserializer::from_str(
+        r#"[
+            { "id": 1, "price": 2.5, "raw": "{\"a\": [1, 2]}", "tags": ["x"] },
+            { "id": "2", "price": null, "flag": true, "blob": null }
+        ]"#,
+    );
+    let schema = context
+        .deserialize_schema(&mut deserializer)
+        .unwrap()
+        .schema;
+
+    assert_eq!(
+        serde_json::to_value(schema.view(SerializeMode::Summary)).unwrap(),
+        serde_json::to_value(schema.clone().map_contexts(&mut DropSamples)).unwrap()
+    );
+}
+
+/// The documents below, as stored before [Field] stopped flattening its status and schema.
+const LEGACY_DOCUMENTS: &str =
+    r#"[{"id":1,"name":"a","tags":["x"]},{"id":2,"name":null,"tags":[]},{"id":3}]"#;
+const LEGACY_SCHEMA: &str = r#"
+    {
+        "type": "Sequence",
+        "field": {
+            "may_be_null": false,
+            "may_be_normal": true,
+            "may_be_missing": false,
+            "may_be_duplicate": false,
+            "type": "Struct",
+            "fields": {
+                "id": {
+                    "may_be_null": false,
+                    "may_be_normal": true,
+                    "may_be_missing": false,
+                    "may_be_duplicate": false,
+                    "type": "Integer",
+                    "count": 3,
+                    "samples": {
+                        "values": [
+                            1,
+                            2,
+                            3
+                        ],
+                        "is_exaustive": true
+                    },
+                    "min": 1,
+                    "max": 3
+                },
+                "name": {
+                    "may_be_null": true,
+                    "may_be_normal": true,
+                    "may_be_missing": true,
+                    "may_be_duplicate": false,
+                    "type": "String",
+                    "count": 1,
+                    "samples": {
+                        "values": [
+                            "a"
+                        ],
+                        "is_exaustive": true
+                    },
+                    "min_max_length": {
+                        "min": 1,
+                        "max": 1
+                    }
+                },
+                "tags": {
+                    "may_be_null": false,
+                    "may_be_normal": true,
+                    "may_be_missing": true,
+                    "may_be_duplicate": false,
+                    "may_be_empty_collection": true,
+                    "type": "Sequence",
+                    "field": {
+                        "may_be_null": false,
+                        "may_be_normal": true,
+                        "may_be_missing": true,
+                        "may_be_duplicate": false,
+                        "type": "String",
+                        "count": 1,
+                        "samples": {
+                            "values": [
+                                "x"
+                            ],
+                            "is_exaustive": true
+                        },
+                        "min_max_length": {
+                            "min": 1,
+                            "max": 1
+                        }
+                    },
+                    "context": {
+                        "count": 2,
+                        "length": {
+                            "min": 0,
+                            "max": 1
+                        }
+                    }
+                }
+            },
+            "context": {
+                "count": 3
+            }
+        },
+        "context": {
+            "count": 1,
+            "length": {
+                "min": 3,
+                "max": 3
+            }
+        }
+    }
+"#;
+
+#[test]
+fn legacy_flattened_fields() {
+    let legacy: Schema = serde_json::from_str(LEGACY_SCHEMA).unwrap();
+    let InferredSchema { schema } = serde_json::from_str(LEGACY_DOCUMENTS).unwrap();
+    assert!(legacy.structural_eq(&schema), "{:#?}", legacy);
+
+    let Schema::Sequence { field, .. } = &legacy else {
+        unreachable!()
+    };
+    let Some(Schema::Struct { fields, .. }) = &field.schema else {
+        unreachable!()
+    };
+    assert!(fields["name"].status.may_be_null);
+    assert!(matches!(
+        &fields["id"].schema,
+        Some(Schema::Integer(context)) if context.count.get() == 3
+    ));
+}
+
+#[test]
+fn fields_with_lost_data_are_rejected() {
+    // Incomplete legacy status.
+    let incomplete = r#"{ "type": "Integer", "may_be_null": true, "may_be_missing": true }"#;
+    assert!(serde_json::from_str::<Field>(incomplete).is_err());
+    // Unknown keys next to the current ones.
+    let field = serde_json::to_value(Field::with_schema(Schema::Integer(Default::default())));
+    let mut field = field.unwrap();
+    assert!(serde_json::from_value::<Field>(field.clone()).is_ok());
+    field["extra"] = json!(1);
+    assert!(serde_json::from_value::<Field>(field).is_err());
+
+    let legacy = |extra: Value| {
+        let mut field = json!({
+            "may_be_null": false,
+            "may_be_normal": true,
+            "may_be_missing": false,
+            "may_be_duplicate": false,
+            "type": "Integer",
+            "count": 1,
+        });
+        field
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value::<Field>(field)
+    };
+    assert!(legacy(json!({})).is_ok());
+    // Unknown schema type.
+    let error = legacy(json!({ "type": "Nope" })).unwrap_err();
+    assert!(
+        error.to_string().contains("unknown variant `Nope`"),
+        "{}",
+        error
+    );
+    // Unknown keys, both for the schema and in place of it.
+    let error = legacy(json!({ "bogus": 5 })).unwrap_err();
+    assert!(
+        error.to_string().contains("unknown field `bogus`"),
+        "{}",
+        error
+    );
+    let error = legacy(json!({ "fields": {} })).unwrap_err();
+    assert!(
+        error.to_string().contains("unknown field `fields`"),
+        "{}",
+        error
+    );
+    let no_schema = json!({
+        "may_be_null": false,
+        "may_be_normal": false,
+        "may_be_missing": true,
+        "may_be_duplicate": false,
+        "bogus": 5,
+    });
+    // Without a type, it can only be read as a flattened schema missing its type.
+    let error = serde_json::from_value::<Field>(no_schema).unwrap_err();
+    assert!(
+        error.to_string().contains("missing field `type`"),
+        "{}",
+        error
+    );
+    // The two forms can't be mixed.
+    assert!(legacy(json!({ "status": {} })).is_err());
+
+    // The current form reports the key, not a failed match of the forms.
+    let error = serde_json::from_str::<Field>(r#"{ "schema": null, "extra": 1 }"#).unwrap_err();
+    assert!(
+        error.to_string().contains("unknown field `extra`"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn legacy_fields_without_schema() {
+    let field = r#"{
+        "may_be_null": false,
+        "may_be_normal": false,
+        "may_be_missing": true,
+        "may_be_duplicate": false
+    }"#;
+    let field: Field = serde_json::from_str(field).unwrap();
+    assert!(field.status.may_be_missing);
+    assert!(field.schema.is_none());
+}
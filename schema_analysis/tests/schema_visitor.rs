@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use maplit::btreemap;
+
+use schema_analysis::{
+    context::MapStructContext, Field, InferredSchema, Schema, SchemaVisitorMut, StructKey,
+    StructuralEq,
+};
+
+struct UppercaseKeys;
+impl SchemaVisitorMut for UppercaseKeys {
+    fn visit_struct(&mut self, fields: &mut BTreeMap<StructKey, Field>, _: &mut MapStructContext) {
+        *fields = std::mem::take(fields)
+            .into_iter()
+            .map(|(k, v)| (k.to_uppercase().as_str().into(), v))
+            .collect();
+        for field in fields.values_mut() {
+            self.visit_field(field);
+        }
+    }
+}
+
+#[test]
+fn uppercase_all_struct_keys() {
+    let data = r#"{
+        "hello": 1,
+        "nested": [ { "world": "!" }, 1 ]
+    }"#;
+    let InferredSchema { mut schema } = serde_json::from_str(data).unwrap();
+    UppercaseKeys.visit_schema(&mut schema);
+
+    let mut world = Field::with_schema(Schema::String(Default::default()));
+    world.status.may_be_normal = true;
+    let mut element = Field::with_schema(Schema::Union {
+        variants: vec![
+            Schema::Struct {
+                fields: btreemap! { "WORLD".into() => world },
+                context: Default::default(),
+            },
+            Schema::Integer(Default::default()),
+        ],
+    });
+    element.status.may_be_normal = true;
+    let mut nested = Field::with_schema(Schema::Sequence {
+        field: Box::new(element),
+        context: Default::default(),
+    });
+    nested.status.may_be_normal = true;
+    let mut hello = Field::with_schema(Schema::Integer(Default::default()));
+    hello.status.may_be_normal = true;
+
+    let expected = Schema::Struct {
+        fields: btreemap! {
+            "HELLO".into() => hello,
+            "NESTED".into() => nested,
+        },
+        context: Default::default(),
+    };
+    assert!(schema.structural_eq(&expected));
+}
+
+#[test]
+fn leaves_are_visited() {
+    #[derive(Default)]
+    struct CountLeaves(usize);
+    impl SchemaVisitorMut for CountLeaves {
+        fn visit_leaf(&mut self, _schema: &mut Schema) {
+            self.0 += 1;
+        }
+    }
+
+    let data = r#"{ "a": 1, "b": [ "x", true ], "c": { "d": null, "e": 1.5 } }"#;
+    let InferredSchema { mut schema } = serde_json::from_str(data).unwrap();
+    let mut counter = CountLeaves::default();
+    counter.visit_schema(&mut schema);
+
+    // a, b (string + boolean), e. The null field has no schema.
+    assert_eq!(counter.0, 4);
+}
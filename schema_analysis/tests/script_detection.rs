@@ -0,0 +1,58 @@
+#![cfg(feature = "script_detection")]
+
+use schema_analysis::{context::ScriptCounter, Coalesce, InferredSchema, Schema};
+
+fn scripts(values: &[&str]) -> ScriptCounter {
+    let InferredSchema { schema } = serde_json::from_value(values.into()).unwrap();
+    match schema {
+        Schema::Sequence { field, .. } => match field.schema {
+            Some(Schema::String(context)) => context.scripts,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn latin_only() {
+    let scripts = scripts(&["Hello, world!", "Ça va? 123", "Straße"]);
+    assert_eq!(scripts.dominant(), Some("Latin"));
+    assert!(!scripts.is_mixed());
+    assert_eq!(scripts.0["Latin"], 10 + 4 + 6);
+}
+
+#[test]
+fn cjk_only() {
+    // Japanese mixes kanji, hiragana and katakana, Korean is written in hangul.
+    let scripts = scripts(&["東京タワーへ行きます。", "中文", "안녕하세요"]);
+    assert_eq!(scripts.dominant(), Some("CJK"));
+    assert!(!scripts.is_mixed());
+}
+
+#[test]
+fn mixed_scripts() {
+    let scripts = scripts(&["Привет", "мир", "hello", "مرحبا"]);
+    assert_eq!(scripts.dominant(), Some("Cyrillic"));
+    assert!(scripts.is_mixed());
+    assert_eq!(scripts.0.len(), 3);
+    assert_eq!(scripts.0["Cyrillic"], 9);
+    assert_eq!(scripts.0["Latin"], 5);
+    assert_eq!(scripts.0["Arabic"], 5);
+}
+
+#[test]
+fn no_script() {
+    let scripts = scripts(&["123", "-- !?", ""]);
+    assert!(scripts.is_empty());
+    assert_eq!(scripts.dominant(), None);
+    assert!(!scripts.is_mixed());
+}
+
+#[test]
+fn coalesce() {
+    let mut first = scripts(&["abc"]);
+    first.coalesce(scripts(&["Ελλάδα", "de"]));
+    assert_eq!(first.0["Latin"], 5);
+    assert_eq!(first.0["Greek"], 6);
+    assert!(first.is_mixed());
+}
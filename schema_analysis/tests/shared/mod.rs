@@ -1,8 +1,17 @@
+// Each test crate only uses some of these helpers.
+#![allow(dead_code)]
+
 use std::collections::BTreeMap;
 
 use maplit::btreemap;
 
-use schema_analysis::{Field, FieldStatus, InferredSchema, Schema, StructuralEq};
+use schema_analysis::{Field, FieldStatus, InferredSchema, Schema, StructKey, StructuralEq};
+
+/// Runs the analysis on a JSON document.
+pub fn infer(json: &str) -> Schema {
+    let InferredSchema { schema } = serde_json::from_str(json).unwrap();
+    schema
+}
 
 /// This provides a way for formats to quickly implement some basic tests.
 ///
@@ -35,7 +44,7 @@ pub trait FormatTests<T> {
             },
         )
     }
-    fn _compare_map_struct(value: Option<T>, fields: BTreeMap<String, Field>) {
+    fn _compare_map_struct(value: Option<T>, fields: BTreeMap<StructKey, Field>) {
         Self::_compare_option(
             value,
             Schema::Struct {
@@ -122,7 +131,7 @@ pub trait FormatTests<T> {
 
     fn empty_map_struct() -> Option<T>;
     fn test_empty_map_struct() {
-        let field_schemas: BTreeMap<String, Field> = BTreeMap::new();
+        let field_schemas: BTreeMap<StructKey, Field> = BTreeMap::new();
         Self::_compare_map_struct(Self::empty_map_struct(), field_schemas);
     }
     fn map_struct_single() -> Option<T>;
@@ -1,8 +1,9 @@
 use std::collections::BTreeMap;
 
+use serde::de::DeserializeSeed;
 use serde_cbor::Value;
 
-use schema_analysis::InferredSchema;
+use schema_analysis::{InferredSchema, Schema};
 
 mod shared;
 use shared::FormatTests;
@@ -140,3 +141,112 @@ impl FormatTests<Value> for Cbor {
         Some(Value::Map(mapping))
     }
 }
+
+#[test]
+fn integer_keys() {
+    let mut map = BTreeMap::new();
+    map.insert(Value::Integer(1), Value::Text("one".into()));
+    map.insert(Value::Integer(42), Value::Bool(true));
+    let InferredSchema { schema } = Cbor::convert_to_inferred_schema(Value::Map(map));
+
+    match schema {
+        Schema::Struct { fields, context } => {
+            assert_eq!(
+                fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+                vec!["1", "42"]
+            );
+            assert!(context.may_have_non_string_keys);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn non_finite_float_keys() {
+    let mut map = BTreeMap::new();
+    map.insert(Value::Float(f64::NAN), Value::Integer(1));
+    map.insert(Value::Float(f64::INFINITY), Value::Integer(3));
+    map.insert(Value::Float(f64::NEG_INFINITY), Value::Integer(4));
+    map.insert(Value::Float(1.5), Value::Integer(5));
+    let InferredSchema { schema } = Cbor::convert_to_inferred_schema(Value::Map(map));
+
+    match schema {
+        Schema::Struct { fields, context } => {
+            assert_eq!(
+                fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+                vec!["-Infinity", "1.5", "Infinity", "NaN"]
+            );
+            assert!(context.may_have_non_string_keys);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn composite_keys() {
+    let mut inner = BTreeMap::new();
+    inner.insert(Value::Text("a\"b".into()), Value::Integer(1));
+    inner.insert(Value::Integer(2), Value::Array(vec![]));
+    let mut map = BTreeMap::new();
+    map.insert(
+        Value::Array(vec![Value::Integer(1), Value::Text("x".into())]),
+        Value::Integer(1),
+    );
+    map.insert(Value::Map(inner), Value::Bool(true));
+    map.insert(Value::Text("plain".into()), Value::Null);
+    let InferredSchema { schema } = Cbor::convert_to_inferred_schema(Value::Map(map));
+
+    match schema {
+        Schema::Struct { fields, context } => {
+            assert_eq!(
+                fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+                vec![r#"[1,"x"]"#, "plain", r#"{"2":[],"a\"b":1}"#]
+            );
+            assert!(context.may_have_non_string_keys);
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Composite keys read straight from cbor bytes, both when creating and extending a schema.
+#[test]
+fn nested_composite_keys_from_bytes() {
+    #[rustfmt::skip]
+    let first: &[u8] = &[
+        0xA2, // A map with two entries.
+        // [NaN, { 1: 2.5 }]: 1
+        0x82, 0xF9, 0x7E, 0x00, 0xA1, 0x01, 0xFB, 0x40, 0x04, 0, 0, 0, 0, 0, 0,
+        0x01,
+        // { [true]: null }: 2
+        0xA1, 0x81, 0xF5, 0xF6,
+        0x02,
+    ];
+    #[rustfmt::skip]
+    let second: &[u8] = &[
+        0xA1, // A map with one entry.
+        // { [true]: null }: "x"
+        0xA1, 0x81, 0xF5, 0xF6,
+        0x61, b'x',
+    ];
+
+    let mut inferred: InferredSchema = serde_cbor::from_slice(first).unwrap();
+    inferred
+        .deserialize(&mut serde_cbor::Deserializer::from_slice(second))
+        .unwrap();
+
+    match inferred.schema {
+        Schema::Struct { fields, context } => {
+            assert_eq!(
+                fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+                vec![r#"[NaN,{"1":2.5}]"#, r#"{"[true]":null}"#]
+            );
+            assert!(fields[r#"[NaN,{"1":2.5}]"#].status.may_be_missing);
+            assert!(matches!(
+                &fields[r#"{"[true]":null}"#].schema,
+                Some(Schema::Union { variants }) if variants.len() == 2
+            ));
+            assert!(context.may_have_non_string_keys);
+        }
+        _ => unreachable!(),
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use maplit::btreemap;
 
-use schema_analysis::{helpers, Field, FieldStatus, InferredSchema, Schema};
+use schema_analysis::{helpers, Field, FieldStatus, InferredSchema, Schema, StructKey};
 
 mod shared;
 use shared::FormatTests;
@@ -62,7 +62,7 @@ impl FormatTests<String> for Xml {
     }
     fn test_map_struct_single() {
         // Xml doesn't have integer values
-        let fields: BTreeMap<String, Field> = {
+        let fields: BTreeMap<StructKey, Field> = {
             let mut hello_field = Field {
                 status: FieldStatus::default(),
                 schema: Some(Schema::String(Default::default())),
@@ -80,7 +80,7 @@ impl FormatTests<String> for Xml {
     }
     fn test_map_struct_double() {
         // Xml doesn't have integer values
-        let fields: BTreeMap<String, Field> = {
+        let fields: BTreeMap<StructKey, Field> = {
             let mut hello_field = Field {
                 status: FieldStatus::default(),
                 schema: Some(Schema::String(Default::default())),
@@ -188,7 +188,7 @@ impl FormatTests<String> for Xml {
         )
     }
     fn test_map_struct_mixed_sequence() {
-        let fields: BTreeMap<String, Field> = {
+        let fields: BTreeMap<StructKey, Field> = {
             let mut hello_field = Field::with_schema(Schema::String(Default::default())); //
             hello_field.status.may_be_normal = true;
 
@@ -221,3 +221,72 @@ impl FormatTests<String> for Xml {
         None
     }
 }
+
+fn infer_xml(xml: &str) -> Schema {
+    let InferredSchema { mut schema } = quick_xml::de::from_str(xml).unwrap();
+    helpers::xml::cleanup_xml_schema(&mut schema);
+    schema
+}
+
+#[test]
+fn positional_record() {
+    let schema = infer_xml("<root><item>1</item><item>a</item></root>");
+
+    let (field, context) = match schema {
+        Schema::Sequence { field, context } => (field, context),
+        schema => panic!("expected a sequence, got {:?}", schema),
+    };
+    assert_eq!(context.count.get(), 1);
+    assert_eq!((context.length.min, context.length.max), (Some(2), Some(2)));
+    assert!(!field.status.may_be_duplicate);
+    match field.schema {
+        Some(Schema::String(context)) => assert_eq!(context.count.get(), 2),
+        schema => panic!("expected a string, got {:?}", schema),
+    }
+}
+
+#[test]
+fn nested_positional_records() {
+    let schema = infer_xml(
+        "<table>
+            <row><item>1</item><entry>a</entry><entry>b</entry></row>
+            <row><item>2</item><item>c</item></row>
+        </table>",
+    );
+
+    let rows = match schema {
+        Schema::Struct { mut fields, .. } => fields.remove("row").unwrap(),
+        schema => panic!("expected a struct, got {:?}", schema),
+    };
+    let row = match rows.schema {
+        Some(Schema::Sequence { field, .. }) => field.schema,
+        schema => panic!("expected a sequence, got {:?}", schema),
+    };
+    match row {
+        Some(Schema::Sequence { field, context }) => {
+            assert_eq!(context.count.get(), 2);
+            assert_eq!((context.length.min, context.length.max), (Some(2), Some(3)));
+            assert!(matches!(field.schema, Some(Schema::String(_))));
+        }
+        schema => panic!("expected a sequence, got {:?}", schema),
+    }
+}
+
+#[test]
+fn named_or_single_children_are_kept() {
+    // A single generic child is not a record.
+    let schema = infer_xml("<root><item>1</item></root>");
+    assert!(matches!(schema, Schema::Struct { .. }));
+
+    // Neither are repeated generic children next to named ones.
+    let schema = infer_xml("<root><item>1</item><item>2</item><name>x</name></root>");
+    match schema {
+        Schema::Struct { fields, .. } => {
+            assert!(matches!(
+                fields["item"].schema,
+                Some(Schema::Sequence { .. })
+            ));
+        }
+        schema => panic!("expected a struct, got {:?}", schema),
+    }
+}
@@ -1,6 +1,7 @@
+use serde::de::DeserializeSeed;
 use serde_yaml::{Mapping, Value};
 
-use schema_analysis::InferredSchema;
+use schema_analysis::{InferredSchema, Schema};
 
 mod shared;
 use shared::FormatTests;
@@ -130,3 +131,69 @@ impl FormatTests<Value> for Yaml {
         Some(Value::Mapping(mapping))
     }
 }
+
+#[test]
+fn integer_and_float_across_documents() {
+    let documents = ["a: 1", "a: 1.0", "a: 2"];
+
+    let mut inferred: InferredSchema = serde_yaml::from_str(documents[0]).unwrap();
+    for document in &documents[1..] {
+        let deserializer = serde_yaml::Deserializer::from_str(document);
+        inferred.deserialize(deserializer).unwrap();
+    }
+
+    let variants = match inferred.schema {
+        Schema::Struct { mut fields, .. } => match fields.remove("a").unwrap().schema {
+            Some(Schema::Union { variants }) => variants,
+            _ => panic!("integers and floats should not be merged"),
+        },
+        _ => unreachable!(),
+    };
+    assert_eq!(variants.len(), 2);
+    for variant in variants {
+        match variant {
+            Schema::Integer(context) => assert_eq!(context.count.0, 2),
+            Schema::Float(context) => assert_eq!(context.count.0, 1),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn non_string_keys() {
+    let yaml = r#"
+plain: a
+"quoted": b
+2: c
+true: d
+~: e
+[1, x]: f
+{ a: 1 }: g
+"#;
+    let InferredSchema { schema } = serde_yaml::from_str(yaml).unwrap();
+    match schema {
+        Schema::Struct { fields, context } => {
+            assert_eq!(
+                fields.keys().map(|key| &**key).collect::<Vec<_>>(),
+                vec![
+                    "2",
+                    r#"[1,"x"]"#,
+                    "null",
+                    "plain",
+                    "quoted",
+                    "true",
+                    r#"{"a":1}"#
+                ]
+            );
+            assert!(context.may_have_non_string_keys);
+        }
+        _ => unreachable!(),
+    }
+
+    // Only string keys.
+    let InferredSchema { schema } = serde_yaml::from_str("a: 1\n'2': 2\n").unwrap();
+    match schema {
+        Schema::Struct { context, .. } => assert!(!context.may_have_non_string_keys),
+        _ => unreachable!(),
+    }
+}
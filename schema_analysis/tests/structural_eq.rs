@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use schema_analysis::{
+    context::{Counter, CountingSet, MinMax},
+    Aggregate, Schema, StructuralEq, StructuralEqOptions,
+};
+
+mod shared;
+use shared::infer;
+
+#[test]
+fn untyped_sequences() {
+    let empty = infer("[]");
+    let strings = infer(r#"["a", "b"]"#);
+
+    let relaxed = StructuralEqOptions {
+        untyped_sequences_match_any: true,
+    };
+
+    // Strict by default.
+    assert!(!empty.structural_eq(&strings));
+    assert!(!empty.structural_eq_with(&strings, &Default::default()));
+
+    assert!(empty.structural_eq_with(&strings, &relaxed));
+    assert!(strings.structural_eq_with(&empty, &relaxed));
+
+    // Also when nested.
+    let nested_empty = infer(r#"{ "tags": [] }"#);
+    let nested_strings = infer(r#"{ "tags": ["a"] }"#);
+    assert!(!nested_empty.structural_eq(&nested_strings));
+    assert!(nested_empty.structural_eq_with(&nested_strings, &relaxed));
+
+    // Typed sequences must still match.
+    let integers = infer("[1, 2]");
+    assert!(!integers.structural_eq_with(&strings, &relaxed));
+}
+
+#[test]
+fn unions_with_same_kind_variants_are_order_independent() {
+    // Unions should never hold two structs, but the comparison must still be deterministic.
+    let first = infer(r#"{ "a": 1 }"#);
+    let second = infer(r#"{ "b": "x" }"#);
+    let union = |variants: Vec<Schema>| Schema::Union { variants };
+
+    let forward = union(vec![first.clone(), second.clone()]);
+    let backward = union(vec![second.clone(), first.clone()]);
+    assert!(forward.structural_eq(&backward));
+    assert!(backward.structural_eq(&forward));
+
+    // Same keys, different field schemas.
+    let integer = infer(r#"{ "a": 1 }"#);
+    let string = infer(r#"{ "a": "x" }"#);
+    let forward = union(vec![integer.clone(), string.clone(), infer("[1]")]);
+    let backward = union(vec![infer("[1]"), string, integer]);
+    assert!(forward.structural_eq(&backward));
+
+    // Still different from a union with other structs.
+    assert!(!forward.structural_eq(&union(vec![first, second, infer("[1]")])));
+}
+
+#[test]
+fn shape_fingerprint() {
+    // Different values, same shape.
+    let first = infer(r#"{ "a": 1, "b": ["x"], "c": null }"#);
+    let second = infer(r#"{ "c": null, "b": ["y", "z"], "a": 100 }"#);
+    assert!(first.structural_eq(&second));
+    assert_eq!(first.shape_fingerprint(), second.shape_fingerprint());
+
+    let union = |variants: Vec<Schema>| Schema::Union { variants };
+    let forward = union(vec![infer("1"), infer(r#""x""#)]);
+    let backward = union(vec![infer(r#""x""#), infer("1")]);
+    assert_eq!(forward.shape_fingerprint(), backward.shape_fingerprint());
+
+    // Different keys, field schemas or field statuses.
+    let different = [
+        infer(r#"{ "a": 1, "b": ["x"], "d": null }"#),
+        infer(r#"{ "a": 1.5, "b": ["x"], "c": null }"#),
+        infer(r#"{ "a": 1, "b": ["x"], "c": 1 }"#),
+        infer(r#"{ "a": 1, "b": [], "c": null }"#),
+        infer(r#"[{ "a": 1, "b": ["x"], "c": null }, {}]"#),
+    ];
+    for schema in &different {
+        assert!(!schema.structural_eq(&first));
+        assert_ne!(schema.shape_fingerprint(), first.shape_fingerprint());
+    }
+}
+
+#[test]
+fn shape_fingerprint_is_stable_across_runs() {
+    // The hash must not depend on a random seed, so it can be stored.
+    let schema = infer(r#"{ "a": 1, "b": ["x"], "c": null }"#);
+    assert_eq!(schema.shape_fingerprint(), 13436126096042476824);
+}
+
+/// A custom context, built out of the aggregators of the crate.
+#[derive(Debug, Default)]
+struct MeasurementContext {
+    count: Counter,
+    min_max: MinMax<i64>,
+    units: CountingSet<String>,
+    /// Semantic information, which must match.
+    dimension: Option<String>,
+}
+impl MeasurementContext {
+    fn new(dimension: &str, values: &[i64]) -> Self {
+        let mut context = MeasurementContext {
+            dimension: Some(dimension.to_string()),
+            ..Default::default()
+        };
+        for value in values {
+            context.count.aggregate(value);
+            context.min_max.aggregate(value);
+            context.units.insert(&format!("unit-{}", value));
+        }
+        context
+    }
+}
+impl StructuralEq for MeasurementContext {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.count.structural_eq(&other.count)
+            && self.min_max.structural_eq(&other.min_max)
+            && self.units.structural_eq(&other.units)
+            && self.dimension.structural_eq(&other.dimension)
+    }
+}
+
+#[test]
+fn custom_context() {
+    let few = MeasurementContext::new("length", &[1, 2]);
+    let many = MeasurementContext::new("length", &[-5, 3, 100]);
+    let other = MeasurementContext::new("mass", &[1, 2]);
+
+    // The samples don't matter, the semantic information does.
+    assert!(few.structural_eq(&many));
+    assert!(few.structural_ne(&other));
+
+    // They can also be compared through the containers implementing the trait.
+    let by_field = |context| {
+        let mut map = BTreeMap::new();
+        map.insert("height".to_string(), vec![Box::new(context)]);
+        map
+    };
+    assert!(by_field(few).structural_eq(&by_field(many)));
+    assert!(by_field(MeasurementContext::new("length", &[1])).structural_ne(&by_field(other)));
+}
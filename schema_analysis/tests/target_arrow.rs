@@ -0,0 +1,113 @@
+#![cfg(feature = "arrow")]
+
+use std::sync::Arc;
+
+use arrow_schema::{DataType, Field, Fields, UnionMode};
+
+use schema_analysis::{context::NumberContext, targets::arrow::ArrowOptions, Aggregate, Schema};
+
+mod shared;
+use shared::infer;
+
+#[test]
+fn nested_records() {
+    let schema = infer(
+        r#"[
+            {
+                "id": 1,
+                "score": 0.5,
+                "name": "a",
+                "active": true,
+                "tags": ["x", null],
+                "address": { "city": "b", "zip": null }
+            },
+            {
+                "id": 9223372036854775808,
+                "score": 1.5,
+                "active": false,
+                "tags": [],
+                "address": { "city": "c", "zip": 123 }
+            }
+        ]"#,
+    );
+
+    let expected = arrow_schema::Schema::new(vec![
+        Field::new("active", DataType::Boolean, false),
+        Field::new(
+            "address",
+            DataType::Struct(Fields::from(vec![
+                Field::new("city", DataType::Utf8, false),
+                Field::new("zip", DataType::Int64, true),
+            ])),
+            false,
+        ),
+        Field::new("id", DataType::Decimal128(38, 0), false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("score", DataType::Float64, false),
+        Field::new(
+            "tags",
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        ),
+    ]);
+    assert_eq!(schema.to_arrow_schema(), expected);
+}
+
+#[test]
+fn other_roots() {
+    let schema = infer("[1, null]");
+    let expected = arrow_schema::Schema::new(vec![Field::new(
+        "value",
+        DataType::List(Arc::new(Field::new("item", DataType::Int64, true))),
+        false,
+    )]);
+    assert_eq!(schema.to_arrow_schema(), expected);
+}
+
+#[test]
+fn integers_beyond_decimals() {
+    let mut context = NumberContext::<i128>::default();
+    context.aggregate(&1);
+    let schema = Schema::Integer(context.clone());
+    assert_eq!(
+        schema.to_arrow_schema().field(0).data_type(),
+        &DataType::Int64
+    );
+
+    context.aggregate(&10_i128.pow(38));
+    let schema = Schema::Integer(context);
+    assert_eq!(
+        schema.to_arrow_schema().field(0).data_type(),
+        &DataType::Utf8
+    );
+}
+
+#[test]
+fn null_rows() {
+    let schema = infer(r#"[{ "id": 1 }, null]"#);
+    let expected = arrow_schema::Schema::new(vec![Field::new("id", DataType::Int64, true)]);
+    assert_eq!(schema.to_arrow_schema(), expected);
+}
+
+#[test]
+fn unions() {
+    let schema = infer(r#"[{ "value": 1 }, { "value": "a" }]"#);
+
+    let field = schema.to_arrow_schema().field(0).clone();
+    match field.data_type() {
+        DataType::Union(fields, UnionMode::Dense) => {
+            let types: Vec<_> = fields.iter().map(|(_, f)| f.data_type().clone()).collect();
+            assert_eq!(types, [DataType::Int64, DataType::Utf8]);
+        }
+        other => panic!("{:?}", other),
+    }
+
+    let options = ArrowOptions {
+        unions_as_strings: true,
+    };
+    let field = schema
+        .to_arrow_schema_with_options(&options)
+        .field(0)
+        .clone();
+    assert_eq!(field.data_type(), &DataType::Utf8);
+}
@@ -0,0 +1,370 @@
+use schema_analysis::{targets::csharp::CSharpOptions, InferredSchema, Schema};
+
+mod shared;
+use shared::FormatTests;
+
+struct CSharp;
+
+test_format!(CSharp);
+
+const HEADER: &str = "#nullable enable
+
+using System.Collections.Generic;
+using System.Text.Json.Serialization;
+";
+
+impl FormatTests<&str> for CSharp {
+    fn convert_to_inferred_schema(_value: &str) -> InferredSchema {
+        // Not needed for testing the target.
+        unreachable!()
+    }
+
+    // Note: here we are actually switching the source and target.
+    // The target schema from the tests before is converted to C# and compared to the
+    // classes below.
+    fn compare(target_classes: &str, tested_schema: Schema) {
+        let output = tested_schema.to_csharp("Root").unwrap();
+        assert_eq!(output, format!("{}{}", HEADER, target_classes));
+    }
+
+    // Roots that are not structs have no class of their own.
+    fn null() -> Option<&'static str> {
+        Some("")
+    }
+    fn boolean() -> Option<&'static str> {
+        Some("")
+    }
+    fn integer() -> Option<&'static str> {
+        Some("")
+    }
+    fn float() -> Option<&'static str> {
+        Some("")
+    }
+    fn string() -> Option<&'static str> {
+        Some("")
+    }
+
+    fn empty_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn string_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn integer_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn mixed_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn optional_mixed_sequence() -> Option<&'static str> {
+        Some("")
+    }
+
+    fn empty_map_struct() -> Option<&'static str> {
+        Some(
+            "
+public class Root
+{
+}
+",
+        )
+    }
+    fn map_struct_single() -> Option<&'static str> {
+        Some(
+            r#"
+public class Root
+{
+    [JsonPropertyName("hello")]
+    public long Hello { get; set; }
+}
+"#,
+        )
+    }
+    fn map_struct_double() -> Option<&'static str> {
+        Some(
+            r#"
+public class Root
+{
+    [JsonPropertyName("hello")]
+    public long Hello { get; set; }
+
+    [JsonPropertyName("world")]
+    public string World { get; set; }
+}
+"#,
+        )
+    }
+    fn sequence_map_struct_mixed() -> Option<&'static str> {
+        Some(
+            r#"
+public class Root
+{
+    [JsonPropertyName("hello")]
+    public long Hello { get; set; }
+
+    [JsonPropertyName("mixed")]
+    public object Mixed { get; set; }
+
+    [JsonPropertyName("world")]
+    public string World { get; set; }
+}
+"#,
+        )
+    }
+    fn sequence_map_struct_optional_or_missing() -> Option<&'static str> {
+        Some(
+            r#"
+public class Root
+{
+    [JsonPropertyName("hello")]
+    public long Hello { get; set; }
+
+    [JsonPropertyName("null_or_missing")]
+    public object? NullOrMissing { get; set; }
+
+    [JsonPropertyName("possibly_missing")]
+    public double? PossiblyMissing { get; set; }
+
+    [JsonPropertyName("possibly_null")]
+    public string? PossiblyNull { get; set; }
+}
+"#,
+        )
+    }
+    fn map_struct_mixed_sequence() -> Option<&'static str> {
+        Some(
+            r#"
+public class Root
+{
+    [JsonPropertyName("hello")]
+    public long Hello { get; set; }
+
+    [JsonPropertyName("sequence")]
+    public List<string> Sequence { get; set; }
+
+    [JsonPropertyName("world")]
+    public string World { get; set; }
+}
+"#,
+        )
+    }
+    fn map_struct_mixed_sequence_optional() -> Option<&'static str> {
+        Some(
+            r#"
+public class Root
+{
+    [JsonPropertyName("hello")]
+    public long Hello { get; set; }
+
+    [JsonPropertyName("optional")]
+    public object? Optional { get; set; }
+
+    [JsonPropertyName("sequence")]
+    public List<string?> Sequence { get; set; }
+
+    [JsonPropertyName("world")]
+    public string World { get; set; }
+}
+"#,
+        )
+    }
+}
+
+#[test]
+fn nested_classes_and_identifiers() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{
+            "root": true,
+            "class": 1,
+            "2fa-enabled": false,
+            "user name": "a",
+            "user_name": "b",
+            "items": [ { "id": 1, "child": { "id": 2 } } ],
+            "child": { "quote\"d": 1.5 }
+        }"#,
+    )
+    .unwrap();
+
+    let expected = r#"
+public class Root
+{
+    [JsonPropertyName("2fa-enabled")]
+    public bool _2faEnabled { get; set; }
+
+    [JsonPropertyName("child")]
+    public Child Child { get; set; }
+
+    [JsonPropertyName("class")]
+    public long Class { get; set; }
+
+    [JsonPropertyName("items")]
+    public List<Items> Items { get; set; }
+
+    [JsonPropertyName("root")]
+    public bool Root2 { get; set; }
+
+    [JsonPropertyName("user name")]
+    public string UserName { get; set; }
+
+    [JsonPropertyName("user_name")]
+    public string UserName2 { get; set; }
+}
+
+public class Child
+{
+    [JsonPropertyName("quote\"d")]
+    public double QuoteD { get; set; }
+}
+
+public class Items
+{
+    [JsonPropertyName("child")]
+    public Child2 Child { get; set; }
+
+    [JsonPropertyName("id")]
+    public long Id { get; set; }
+}
+
+public class Child2
+{
+    [JsonPropertyName("id")]
+    public long Id { get; set; }
+}
+"#;
+    assert_eq!(
+        schema.to_csharp("root").unwrap(),
+        format!("{}{}", HEADER, expected)
+    );
+}
+
+#[test]
+fn base64_strings_stay_strings() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "data": "aGVsbG8gd29ybGQ=", "hash": "deadbeef" },
+            { "data": "AAECAwQFBgcICQ==", "hash": "cafebabe" }
+        ]"#,
+    )
+    .unwrap();
+
+    let expected = r#"
+public class Root
+{
+    [JsonPropertyName("data")]
+    public string Data { get; set; }
+
+    [JsonPropertyName("hash")]
+    public string Hash { get; set; }
+}
+"#;
+    assert_eq!(
+        schema.to_csharp("root").unwrap(),
+        format!("{}{}", HEADER, expected)
+    );
+
+    // Unless asked for.
+    let options = CSharpOptions {
+        base64_as_bytes: true,
+        ..Default::default()
+    };
+    let expected = expected.replace("public string Data", "public byte[] Data");
+    assert_eq!(
+        schema.to_csharp_with_options("root", &options).unwrap(),
+        format!("{}{}", HEADER, expected)
+    );
+}
+
+#[test]
+fn unsigned_64_bit_integers() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"[{ "id": 18446744073709551615, "n": 1 }, { "id": 0, "n": -1 }]"#)
+            .unwrap();
+
+    let expected = r#"
+public class Root
+{
+    [JsonPropertyName("id")]
+    public ulong Id { get; set; }
+
+    [JsonPropertyName("n")]
+    public long N { get; set; }
+}
+"#;
+    assert_eq!(
+        schema.to_csharp("root").unwrap(),
+        format!("{}{}", HEADER, expected)
+    );
+}
+
+#[test]
+fn recursive_comment_tree() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{ "id": 1, "text": "a", "replies": [
+            { "id": 2, "text": "b", "replies": [{ "id": 3, "text": "c", "replies": [] }] },
+            { "id": 4, "text": "d" }
+        ] }"#,
+    )
+    .unwrap();
+
+    let options = CSharpOptions {
+        max_depth: Some(0),
+        ..Default::default()
+    };
+    let expected = r#"
+public class Comment
+{
+    [JsonPropertyName("id")]
+    public long Id { get; set; }
+
+    [JsonPropertyName("replies")]
+    public List<Comment> Replies { get; set; }
+
+    [JsonPropertyName("text")]
+    public string Text { get; set; }
+}
+"#;
+    assert_eq!(
+        schema.to_csharp_with_options("comment", &options).unwrap(),
+        format!("{}{}", HEADER, expected)
+    );
+
+    // Without a limit every level gets a class.
+    let output = schema.to_csharp("comment").unwrap();
+    assert!(output.contains("public List<Replies> Replies"));
+    assert!(output.contains("public List<Replies2>? Replies"));
+}
+
+#[test]
+fn max_depth_ancestor_with_other_types() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "a": 1, "child": { "a": "x", "child": { "a": "y" } } }"#)
+            .unwrap();
+
+    // The keys match, but the child can't be read into the class of the root.
+    let options = CSharpOptions {
+        max_depth: Some(0),
+        ..Default::default()
+    };
+    let output = schema.to_csharp_with_options("root", &options).unwrap();
+    assert!(
+        output.contains("public object Child { get; set; }"),
+        "{}",
+        output
+    );
+    assert!(!output.contains("public Root Child"), "{}", output);
+}
+
+#[test]
+fn max_depth_without_matching_ancestor() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "a": { "b": { "c": 1 } } }"#).unwrap();
+
+    let options = CSharpOptions {
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let output = schema.to_csharp_with_options("root", &options).unwrap();
+    assert!(output.contains("public A A { get; set; }"));
+    assert!(output.contains("public object B { get; set; }"));
+    assert!(!output.contains("class B"));
+}
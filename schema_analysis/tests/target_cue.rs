@@ -0,0 +1,161 @@
+use schema_analysis::{InferredSchema, Schema};
+
+mod shared;
+use shared::FormatTests;
+
+struct Cue;
+
+test_format!(Cue);
+
+impl FormatTests<&str> for Cue {
+    fn convert_to_inferred_schema(_value: &str) -> InferredSchema {
+        // Not needed for testing the target.
+        unreachable!()
+    }
+
+    // Note: here we are actually switching the source and target.
+    // The target schema from the tests before is converted to CUE and compared to the
+    // definitions below.
+    fn compare(target_definition: &str, tested_schema: Schema) {
+        let output = tested_schema.to_cue("Root").unwrap();
+        assert_eq!(output, target_definition);
+    }
+
+    fn null() -> Option<&'static str> {
+        Some("#Root: null\n")
+    }
+    fn boolean() -> Option<&'static str> {
+        Some("#Root: bool\n")
+    }
+    fn integer() -> Option<&'static str> {
+        Some("#Root: int\n")
+    }
+    fn float() -> Option<&'static str> {
+        Some("#Root: number\n")
+    }
+    fn string() -> Option<&'static str> {
+        Some("#Root: string\n")
+    }
+
+    fn empty_sequence() -> Option<&'static str> {
+        Some("#Root: [..._]\n")
+    }
+    fn string_sequence() -> Option<&'static str> {
+        Some("#Root: [...string]\n")
+    }
+    fn integer_sequence() -> Option<&'static str> {
+        Some("#Root: [...int]\n")
+    }
+    fn mixed_sequence() -> Option<&'static str> {
+        Some("#Root: [...int | string]\n")
+    }
+    fn optional_mixed_sequence() -> Option<&'static str> {
+        Some("#Root: [...int | string | null]\n")
+    }
+
+    fn empty_map_struct() -> Option<&'static str> {
+        Some("#Root: {}\n")
+    }
+    fn map_struct_single() -> Option<&'static str> {
+        Some(
+            "#Root: {
+\thello: int
+}
+",
+        )
+    }
+    fn map_struct_double() -> Option<&'static str> {
+        Some(
+            "#Root: {
+\thello: int
+\tworld: string
+}
+",
+        )
+    }
+    fn sequence_map_struct_mixed() -> Option<&'static str> {
+        Some(
+            "#Root: [...{
+\thello: int
+\tmixed: number | string
+\tworld: string
+}]
+",
+        )
+    }
+    fn sequence_map_struct_optional_or_missing() -> Option<&'static str> {
+        Some(
+            "#Root: [...{
+\thello: int
+\tnull_or_missing?: null
+\tpossibly_missing?: number
+\tpossibly_null: string | null
+}]
+",
+        )
+    }
+    fn map_struct_mixed_sequence() -> Option<&'static str> {
+        Some(
+            "#Root: {
+\thello: int
+\tsequence: [...string]
+\tworld: string
+}
+",
+        )
+    }
+    fn map_struct_mixed_sequence_optional() -> Option<&'static str> {
+        Some(
+            "#Root: {
+\thello: int
+\toptional: null
+\tsequence: [...string | null]
+\tworld: string
+}
+",
+        )
+    }
+}
+
+#[test]
+fn nested_structs_and_labels() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{
+            "user name": "a",
+            "_hidden": true,
+            "if": 1,
+            "items": [ { "id": 1, "child": { "ok": false } }, { "id": 2 } ],
+            "quote\"d": 1.5
+        }"#,
+    )
+    .unwrap();
+
+    let expected = "#Config: {
+\t\"_hidden\": bool
+\t\"if\": int
+\titems: [...{
+\t\tchild?: {
+\t\t\tok: bool
+\t\t}
+\t\tid: int
+\t}]
+\t\"quote\\\"d\": number
+\t\"user name\": string
+}
+";
+    assert_eq!(schema.to_cue("config").unwrap(), expected);
+}
+
+#[test]
+fn predeclared_identifiers_are_quoted() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "int": 1, "string": "a", "x": 2 }"#).unwrap();
+
+    let expected = "#Root: {
+\t\"int\": int
+\t\"string\": string
+\tx: int
+}
+";
+    assert_eq!(schema.to_cue("Root").unwrap(), expected);
+}
@@ -0,0 +1,292 @@
+use schema_analysis::{InferredSchema, Schema};
+
+mod shared;
+use shared::FormatTests;
+
+struct Elm;
+
+test_format!(Elm);
+
+const HEADER: &str = "import Json.Decode as Decode exposing (Decoder, Value)
+";
+
+const AND_MAP: &str = "
+
+andMap : Decoder a -> Decoder (a -> b) -> Decoder b
+andMap =
+    Decode.map2 (|>)
+";
+
+/// The alias and decoder of a root that is not a struct.
+fn root(elm_type: &str, decoder: &str) -> String {
+    format!(
+        "
+
+type alias Root =
+    {}
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    {}
+",
+        elm_type, decoder
+    )
+}
+
+impl FormatTests<String> for Elm {
+    fn convert_to_inferred_schema(_value: String) -> InferredSchema {
+        // Not needed for testing the target.
+        unreachable!()
+    }
+
+    // Note: here we are actually switching the source and target.
+    // The target schema from the tests before is converted to Elm and compared to the
+    // declarations below.
+    fn compare(target_declarations: String, tested_schema: Schema) {
+        let output = tested_schema.to_elm("Root").unwrap();
+        assert_eq!(output, format!("{}{}", HEADER, target_declarations));
+    }
+
+    fn null() -> Option<String> {
+        Some(root("()", "Decode.null ()"))
+    }
+    fn boolean() -> Option<String> {
+        Some(root("Bool", "Decode.bool"))
+    }
+    fn integer() -> Option<String> {
+        Some(root("Int", "Decode.int"))
+    }
+    fn float() -> Option<String> {
+        Some(root("Float", "Decode.float"))
+    }
+    fn string() -> Option<String> {
+        Some(root("String", "Decode.string"))
+    }
+
+    fn empty_sequence() -> Option<String> {
+        Some(root("List Value", "Decode.list Decode.value"))
+    }
+    fn string_sequence() -> Option<String> {
+        Some(root("List String", "Decode.list Decode.string"))
+    }
+    fn integer_sequence() -> Option<String> {
+        Some(root("List Int", "Decode.list Decode.int"))
+    }
+    fn mixed_sequence() -> Option<String> {
+        Some(root(
+            "List (Value {- Int | String -})",
+            "Decode.list Decode.value",
+        ))
+    }
+    fn optional_mixed_sequence() -> Option<String> {
+        Some(root(
+            "List (Maybe (Value {- Int | String -}))",
+            "Decode.list (Decode.nullable Decode.value)",
+        ))
+    }
+
+    fn empty_map_struct() -> Option<String> {
+        Some(
+            "
+
+type alias Root =
+    {}
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    Decode.succeed {}
+"
+            .into(),
+        )
+    }
+    fn map_struct_single() -> Option<String> {
+        Some(format!(
+            r#"
+
+type alias Root =
+    {{ hello : Int
+    }}
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    Decode.succeed Root
+        |> andMap (Decode.field "hello" Decode.int)
+{}"#,
+            AND_MAP
+        ))
+    }
+    fn map_struct_double() -> Option<String> {
+        Some(format!(
+            r#"
+
+type alias Root =
+    {{ hello : Int
+    , world : String
+    }}
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    Decode.succeed Root
+        |> andMap (Decode.field "hello" Decode.int)
+        |> andMap (Decode.field "world" Decode.string)
+{}"#,
+            AND_MAP
+        ))
+    }
+    fn sequence_map_struct_mixed() -> Option<String> {
+        Some(format!(
+            r#"{}
+
+type alias RootItem =
+    {{ hello : Int
+    , mixed : Value {{- Float | String -}}
+    , world : String
+    }}
+
+
+decodeRootItem : Decoder RootItem
+decodeRootItem =
+    Decode.succeed RootItem
+        |> andMap (Decode.field "hello" Decode.int)
+        |> andMap (Decode.field "mixed" Decode.value)
+        |> andMap (Decode.field "world" Decode.string)
+{}"#,
+            root("List RootItem", "Decode.list decodeRootItem"),
+            AND_MAP
+        ))
+    }
+    fn sequence_map_struct_optional_or_missing() -> Option<String> {
+        Some(format!(
+            r#"{}
+
+type alias RootItem =
+    {{ hello : Int
+    , nullOrMissing : Maybe Value
+    , possiblyMissing : Maybe Float
+    , possiblyNull : Maybe String
+    }}
+
+
+decodeRootItem : Decoder RootItem
+decodeRootItem =
+    Decode.succeed RootItem
+        |> andMap (Decode.field "hello" Decode.int)
+        |> andMap (Decode.oneOf [ Decode.field "null_or_missing" (Decode.nullable Decode.value), Decode.succeed Nothing ])
+        |> andMap (Decode.maybe (Decode.field "possibly_missing" Decode.float))
+        |> andMap (Decode.field "possibly_null" (Decode.nullable Decode.string))
+{}"#,
+            root("List RootItem", "Decode.list decodeRootItem"),
+            AND_MAP
+        ))
+    }
+    fn map_struct_mixed_sequence() -> Option<String> {
+        Some(format!(
+            r#"
+
+type alias Root =
+    {{ hello : Int
+    , sequence : List String
+    , world : String
+    }}
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    Decode.succeed Root
+        |> andMap (Decode.field "hello" Decode.int)
+        |> andMap (Decode.field "sequence" (Decode.list Decode.string))
+        |> andMap (Decode.field "world" Decode.string)
+{}"#,
+            AND_MAP
+        ))
+    }
+    fn map_struct_mixed_sequence_optional() -> Option<String> {
+        Some(format!(
+            r#"
+
+type alias Root =
+    {{ hello : Int
+    , optional : Maybe Value
+    , sequence : List (Maybe String)
+    , world : String
+    }}
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    Decode.succeed Root
+        |> andMap (Decode.field "hello" Decode.int)
+        |> andMap (Decode.field "optional" (Decode.nullable Decode.value))
+        |> andMap (Decode.field "sequence" (Decode.list (Decode.nullable Decode.string)))
+        |> andMap (Decode.field "world" Decode.string)
+{}"#,
+            AND_MAP
+        ))
+    }
+}
+
+#[test]
+fn nested_records_and_identifiers() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{
+            "type": true,
+            "2fa-enabled": false,
+            "user name": "a",
+            "user_name": "b",
+            "List": { "quote\"d": 1.5 },
+            "items": [ { "id": 1 } ]
+        }"#,
+    )
+    .unwrap();
+
+    let expected = r#"
+
+type alias Root =
+    { n2faEnabled : Bool
+    , list : List2
+    , items : List ItemsItem
+    , type_ : Bool
+    , userName : String
+    , userName2 : String
+    }
+
+
+decodeRoot : Decoder Root
+decodeRoot =
+    Decode.succeed Root
+        |> andMap (Decode.field "2fa-enabled" Decode.bool)
+        |> andMap (Decode.field "List" decodeList2)
+        |> andMap (Decode.field "items" (Decode.list decodeItemsItem))
+        |> andMap (Decode.field "type" Decode.bool)
+        |> andMap (Decode.field "user name" Decode.string)
+        |> andMap (Decode.field "user_name" Decode.string)
+
+
+type alias List2 =
+    { quoteD : Float
+    }
+
+
+decodeList2 : Decoder List2
+decodeList2 =
+    Decode.succeed List2
+        |> andMap (Decode.field "quote\"d" Decode.float)
+
+
+type alias ItemsItem =
+    { id : Int
+    }
+
+
+decodeItemsItem : Decoder ItemsItem
+decodeItemsItem =
+    Decode.succeed ItemsItem
+        |> andMap (Decode.field "id" Decode.int)
+"#;
+    let output = schema.to_elm("Root").unwrap();
+    assert_eq!(output, format!("{}{}{}", HEADER, expected, AND_MAP));
+}
@@ -0,0 +1,40 @@
+use std::error::Error;
+
+use schema_analysis::{targets::TargetError, InferredSchema};
+
+fn assert_error<E: Error + Send + Sync + 'static>() {}
+
+#[test]
+fn target_error_is_a_thread_safe_error() {
+    assert_error::<TargetError>();
+
+    // It can be stored in other errors.
+    let boxed: Box<dyn Error + Send + Sync> = Box::new(TargetError::Formatting(std::fmt::Error));
+    assert_eq!(
+        boxed.to_string(),
+        "formatting failed: an error occurred when formatting an argument"
+    );
+    assert!(boxed.source().is_some());
+}
+
+#[test]
+fn targets_return_target_error() {
+    let InferredSchema { schema } = serde_json::from_str(r#"{ "id": 1 }"#).unwrap();
+
+    let csharp: Result<String, TargetError> = schema.to_csharp("Root");
+    assert!(csharp.is_ok());
+
+    #[cfg(feature = "schemars_integration")]
+    {
+        let json_schema: Result<String, TargetError> = schema.to_json_schema_with_schemars();
+        assert!(json_schema.is_ok());
+    }
+
+    #[cfg(feature = "json_typegen")]
+    {
+        use schema_analysis::targets::json_typegen::OutputMode;
+
+        let rust: Result<String, TargetError> = schema.process_with_json_typegen(OutputMode::Rust);
+        assert!(rust.is_ok());
+    }
+}
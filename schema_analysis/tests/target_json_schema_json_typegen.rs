@@ -3,7 +3,7 @@
 use json_typegen_shared::OutputMode;
 use serde_json::{json, Value};
 
-use schema_analysis::{InferredSchema, Schema};
+use schema_analysis::{Coalesce, Field, InferredSchema, Schema};
 
 mod shared;
 use shared::FormatTests;
@@ -196,10 +196,7 @@ impl FormatTests<Value> for JSchema {
                     // so it's simply not required instead of required and both "string" and "null".
                     "possibly_null": { "type": "string" }
                 },
-                // FIXME: "null_or_missing" is included because json_typegen collapses
-                // null/missing + no inference info into it, but checks only Shape::Optional
-                // for the "required" field.
-                "required": [ "hello", "null_or_missing" ],
+                "required": [ "hello" ],
             }
         }))
     }
@@ -231,16 +228,51 @@ impl FormatTests<Value> for JSchema {
                 "optional": {},
                 "sequence": {
                     "type": "array",
-
-                    // FIXME:
-                    // json_typegen seems to discard optional info when generating a
-                    // json schema for a sequence type with optional values.
-                    // "items": { "type": [ "string", "null" ] },
-                    "items": { "type": "string" },
+                    // json_typegen discards the optional info of the elements, it is added
+                    // back from the schema.
+                    "items": { "type": [ "string", "null" ] },
                 },
                 "world": { "type": "string" },
             },
+            // "optional" is only ever null, but always present.
             "required": [ "hello", "optional", "sequence", "world" ],
         }))
     }
 }
+
+#[test]
+fn nullable_items_are_nested_and_from_unions() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "matrix": [[1, null], [2]], "tags": ["a"] },
+            { "matrix": [null], "tags": [] }
+        ]"#,
+    )
+    .unwrap();
+    let mut union = Schema::Integer(Default::default());
+    union.coalesce(Schema::Null(Default::default()));
+    let union_items = Schema::Sequence {
+        field: Box::new(Field::with_schema(union)),
+        context: Default::default(),
+    };
+
+    let json_schema = |schema: &Schema| -> Value {
+        let output = schema
+            .process_with_json_typegen(OutputMode::JsonSchema)
+            .unwrap();
+        serde_json::from_str(&output).unwrap()
+    };
+
+    let items = &json_schema(&schema)["items"]["properties"];
+    assert_eq!(items["matrix"]["items"]["type"], json!(["array", "null"]));
+    assert_eq!(
+        items["matrix"]["items"]["items"]["type"],
+        json!(["number", "null"])
+    );
+    assert_eq!(items["tags"]["items"]["type"], json!("string"));
+
+    assert_eq!(
+        json_schema(&union_items)["items"]["type"],
+        json!(["number", "null"])
+    );
+}
@@ -2,7 +2,10 @@
 
 use serde_json::{json, Value};
 
-use schema_analysis::{InferredSchema, Schema};
+use schema_analysis::{
+    targets::schemars::{JsonSchemaOptions, JsonSchemaVersion},
+    Coalesce, Field, InferredSchema, Schema,
+};
 
 mod shared;
 use shared::FormatTests;
@@ -27,6 +30,10 @@ impl FormatTests<Value> for JSchema {
         let deserialized_json_schema: Value =
             serde_json::from_str(&serialized_json_schema).unwrap();
         assert_eq!(deserialized_json_schema, target_json_schema);
+
+        // The lightweight emitter should produce the same output.
+        let native_json_schema = tested_schema.to_json_schema_native(Default::default());
+        assert_eq!(native_json_schema, target_json_schema);
     }
 
     fn null() -> Option<Value> {
@@ -115,14 +122,10 @@ impl FormatTests<Value> for JSchema {
             "$schema": SCHEMA_TYPE,
             "type": "array",
             "items": {
+                // Note: order is important here because the representation is a vec
                 "anyOf": [
-                    // Note: order is important here because the representation is a vec
-                    {
-                        "anyOf": [
-                            { "type": "integer" },
-                            { "type": "string" },
-                        ]
-                    },
+                    { "type": "integer" },
+                    { "type": "string" },
                     { "type": "null" }
                 ]
             }
@@ -231,3 +234,275 @@ impl FormatTests<Value> for JSchema {
         }))
     }
 }
+
+#[test]
+fn deny_additional_properties() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "hello": 1, "nested": [ { "world": "!" } ] }"#).unwrap();
+
+    let options = JsonSchemaOptions {
+        deny_additional_properties: true,
+        ..Default::default()
+    };
+    let serialized_json_schema = schema
+        .to_json_schema_with_schemars_options(&JsonSchemaVersion::default(), &options)
+        .unwrap();
+    let deserialized_json_schema: Value = serde_json::from_str(&serialized_json_schema).unwrap();
+
+    assert_eq!(
+        deserialized_json_schema,
+        json!({
+            "$schema": SCHEMA_TYPE,
+            "type": "object",
+            "required": [ "hello", "nested" ],
+            "properties": {
+                "hello": { "type": "integer" },
+                "nested": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": [ "world" ],
+                        "properties": {
+                            "world": { "type": "string" },
+                        },
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "additionalProperties": false,
+        })
+    );
+}
+
+#[test]
+fn openapi_components() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{
+            "name": "a",
+            "home_address": { "street": "b" },
+            "work_address": { "street": "c" },
+            "pets": [ { "name": "d" } ]
+        }"#,
+    )
+    .unwrap();
+
+    let components = schema.to_openapi_components("Person").unwrap();
+
+    // Identical structs share a component.
+    let address = json!({
+        "type": "object",
+        "required": [ "street" ],
+        "properties": { "street": { "type": "string" } },
+    });
+    assert_eq!(
+        components,
+        json!({
+            "components": {
+                "schemas": {
+                    "Person": {
+                        "type": "object",
+                        "required": [ "home_address", "name", "pets", "work_address" ],
+                        "properties": {
+                            "home_address": { "$ref": "#/components/schemas/PersonHomeAddress" },
+                            "name": { "type": "string" },
+                            "pets": {
+                                "type": "array",
+                                "items": { "$ref": "#/components/schemas/PersonPets" },
+                            },
+                            "work_address": { "$ref": "#/components/schemas/PersonHomeAddress" },
+                        },
+                    },
+                    "PersonHomeAddress": address,
+                    "PersonPets": {
+                        "type": "object",
+                        "required": [ "name" ],
+                        "properties": { "name": { "type": "string" } },
+                    },
+                },
+            },
+            "$ref": "#/components/schemas/Person",
+        })
+    );
+}
+
+#[test]
+fn openapi_components_nullable() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "billing": { "street": "a" }, "shipping": { "street": "b" } },
+            { "billing": { "street": "c" }, "shipping": null }
+        ]"#,
+    )
+    .unwrap();
+
+    let components = schema.to_openapi_components("Order").unwrap();
+
+    // A nullable struct references the same component as a non-nullable one.
+    let schemas = &components["components"]["schemas"];
+    assert_eq!(
+        schemas["Order"]["properties"],
+        json!({
+            "billing": { "$ref": "#/components/schemas/OrderBilling" },
+            "shipping": {
+                "allOf": [ { "$ref": "#/components/schemas/OrderBilling" } ],
+                "nullable": true,
+            },
+        })
+    );
+    assert_eq!(
+        schemas["OrderBilling"],
+        json!({
+            "type": "object",
+            "required": [ "street" ],
+            "properties": { "street": { "type": "string" } },
+        })
+    );
+    // The other component is the root sequence.
+    assert_eq!(schemas.as_object().unwrap().len(), 3);
+}
+
+#[test]
+fn emit_constants() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "version": "v2", "kind": "a", "maybe": "x", "count": 1 },
+            { "version": "v2", "kind": "b", "maybe": null, "count": 1 }
+        ]"#,
+    )
+    .unwrap();
+
+    let options = JsonSchemaOptions {
+        emit_constants: true,
+        ..Default::default()
+    };
+    let serialized_json_schema = schema
+        .to_json_schema_with_schemars_options(&JsonSchemaVersion::default(), &options)
+        .unwrap();
+    let deserialized_json_schema: Value = serde_json::from_str(&serialized_json_schema).unwrap();
+
+    let properties = &deserialized_json_schema["items"]["properties"];
+    assert_eq!(
+        properties["version"],
+        json!({ "type": "string", "const": "v2" })
+    );
+    assert_eq!(
+        properties["count"],
+        json!({ "type": "integer", "const": 1 })
+    );
+    assert_eq!(properties["kind"], json!({ "type": "string" }));
+    // Nullable fields can't be constant.
+    assert_eq!(properties["maybe"], json!({ "type": [ "string", "null" ] }));
+}
+
+#[test]
+fn emit_formats() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "email": "ada@example.com", "id": "123e4567-e89b-12d3-a456-426614174000", "mixed": "ada@example.com", "day": "2001-12-31" },
+            { "email": "grace@example.org", "id": "00000000-0000-0000-0000-000000000000", "mixed": "not an email", "day": "1970-01-01" }
+        ]"#,
+    )
+    .unwrap();
+
+    let to_properties = |options: &JsonSchemaOptions| {
+        let serialized_json_schema = schema
+            .to_json_schema_with_schemars_options(&JsonSchemaVersion::default(), options)
+            .unwrap();
+        let deserialized_json_schema: Value =
+            serde_json::from_str(&serialized_json_schema).unwrap();
+        deserialized_json_schema["items"]["properties"].clone()
+    };
+
+    let properties = to_properties(&JsonSchemaOptions {
+        emit_formats: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        properties["email"],
+        json!({ "type": "string", "format": "email" })
+    );
+    assert_eq!(
+        properties["id"],
+        json!({ "type": "string", "format": "uuid" })
+    );
+    assert_eq!(
+        properties["day"],
+        json!({ "type": "string", "format": "date" })
+    );
+    assert_eq!(properties["mixed"], json!({ "type": "string" }));
+
+    // Off by default.
+    let properties = to_properties(&Default::default());
+    assert_eq!(properties["email"], json!({ "type": "string" }));
+}
+
+#[test]
+fn native_matches_schemars_for_every_version() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "a": 1, "b": [ "x", null ], "c": { "d": 1.5 }, "e": null, "f": true },
+            { "a": "1", "b": [], "c": null, "g": [ 1, "2", null ] }
+        ]"#,
+    )
+    .unwrap();
+
+    for version in [
+        JsonSchemaVersion::Draft07,
+        JsonSchemaVersion::Draft2019_09,
+        JsonSchemaVersion::OpenApi3,
+    ] {
+        let serialized_json_schema = schema
+            .to_json_schema_with_schemars_version(&version)
+            .unwrap();
+        let schemars_json_schema: Value = serde_json::from_str(&serialized_json_schema).unwrap();
+        assert_eq!(schema.to_json_schema_native(version), schemars_json_schema);
+    }
+}
+
+#[test]
+fn numeric_or_null_is_nullable() {
+    let nullable_integer = json!({ "type": ["integer", "null"] });
+    let check = |schema: &Schema, expected: &Value| {
+        let schemars: Value =
+            serde_json::from_str(&schema.to_json_schema_with_schemars().unwrap()).unwrap();
+        let native = schema.to_json_schema_native(Default::default());
+        for output in [schemars, native].iter() {
+            assert_eq!(output["properties"]["a"], *expected, "{}", output);
+        }
+    };
+
+    // The field itself may be null.
+    let InferredSchema { schema } = serde_json::from_str(r#"[{ "a": 1 }, { "a": null }]"#).unwrap();
+    let field_level = match schema {
+        Schema::Sequence { field, .. } => field.schema.unwrap(),
+        _ => unreachable!(),
+    };
+    check(&field_level, &nullable_integer);
+
+    // The field holds a union that contains null.
+    let holding = |variants: Vec<Schema>| {
+        let mut union = Schema::Null(Default::default());
+        for variant in variants {
+            union.coalesce(variant);
+        }
+        Schema::Struct {
+            fields: vec![("a".into(), Field::with_schema(union))]
+                .into_iter()
+                .collect(),
+            context: Default::default(),
+        }
+    };
+    check(
+        &holding(vec![Schema::Integer(Default::default())]),
+        &nullable_integer,
+    );
+
+    // With several other variants the null joins the same anyOf.
+    check(
+        &holding(vec![
+            Schema::Integer(Default::default()),
+            Schema::Float(Default::default()),
+        ]),
+        &json!({ "anyOf": [{ "type": "integer" }, { "type": "number" }, { "type": "null" }] }),
+    );
+}
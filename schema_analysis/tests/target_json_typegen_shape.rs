@@ -1,9 +1,11 @@
 #![cfg(feature = "json_typegen")]
 
-use json_typegen_shared::Shape;
+use json_typegen_shared::{OutputMode, Shape};
 
 use linked_hash_map::LinkedHashMap;
-use schema_analysis::{InferredSchema, Schema};
+use schema_analysis::{
+    targets::json_typegen::ShapeOptions, Coalesce, Field, InferredSchema, Schema,
+};
 
 mod shared;
 use shared::FormatTests;
@@ -107,7 +109,10 @@ impl FormatTests<Shape> for JsonTypegen {
         let mut fields = LinkedHashMap::new();
 
         fields.insert("hello".to_string(), Shape::Integer);
-        fields.insert("null_or_missing".to_string(), Shape::Null);
+        fields.insert(
+            "null_or_missing".to_string(),
+            Shape::Optional(Box::new(Shape::Bottom)),
+        );
         fields.insert(
             "possibly_missing".to_string(),
             Shape::Optional(Box::new(Shape::Floating)),
@@ -153,3 +158,304 @@ impl FormatTests<Shape> for JsonTypegen {
         Some(Shape::Struct { fields })
     }
 }
+
+#[test]
+fn field_order_is_preserved_into_the_output() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "zebra": 1, "apple": 2, "mango": 3 }"#).unwrap();
+
+    // The order of the Shape follows the Schema, which is currently sorted.
+    let keys: Vec<String> = match schema.to_json_typegen_shape() {
+        Shape::Struct { fields } => fields.keys().cloned().collect(),
+        _ => unreachable!(),
+    };
+    assert_eq!(keys, ["apple", "mango", "zebra"]);
+
+    // And json_typegen does not re-sort it.
+    let output = schema.process_with_json_typegen(OutputMode::Rust).unwrap();
+    let apple = output.find("apple").unwrap();
+    let mango = output.find("mango").unwrap();
+    let zebra = output.find("zebra").unwrap();
+    assert!(apple < mango && mango < zebra);
+}
+
+#[test]
+fn unsafe_integers_as_strings() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"{ "id": 9007199254740993, "count": 1 }"#).unwrap();
+
+    let options = ShapeOptions {
+        unsafe_integers_as_strings: true,
+        ..Default::default()
+    };
+    let fields = match schema.to_json_typegen_shape_with_options(&options) {
+        Shape::Struct { fields } => fields,
+        _ => unreachable!(),
+    };
+    assert_eq!(fields["id"], Shape::StringT);
+    assert_eq!(fields["count"], Shape::Integer);
+
+    // The default keeps them as numbers.
+    let fields = match schema.to_json_typegen_shape() {
+        Shape::Struct { fields } => fields,
+        _ => unreachable!(),
+    };
+    assert_eq!(fields["id"], Shape::Integer);
+
+    let mut typegen_options = json_typegen_shared::Options::default();
+    typegen_options.output_mode = OutputMode::Typescript;
+    let output = json_typegen_shared::codegen_from_shape(
+        "Root",
+        &schema.to_json_typegen_shape_with_options(&options),
+        typegen_options.clone(),
+    )
+    .unwrap();
+    assert!(output.contains("id: string;"), "{}", output);
+    assert!(output.contains("count: number;"), "{}", output);
+
+    // The options also reach the generated code.
+    let output = schema
+        .process_with_json_typegen_with_shape_options("Root", &typegen_options, &options)
+        .unwrap();
+    assert!(output.contains("id: string;"), "{}", output);
+    assert!(output.contains("count: number;"), "{}", output);
+    let output = schema
+        .process_with_json_typegen_options("Root", &typegen_options)
+        .unwrap();
+    assert!(output.contains("id: number;"), "{}", output);
+}
+
+#[test]
+fn every_output_mode_produces_output() {
+    let InferredSchema { schema } =
+        serde_json::from_str(r#"[{ "id": 1, "name": "a" }, { "id": 2, "tags": ["x"] }]"#).unwrap();
+
+    for mode in [
+        OutputMode::Rust,
+        OutputMode::Typescript,
+        OutputMode::TypescriptTypeAlias,
+        OutputMode::KotlinJackson,
+        OutputMode::KotlinKotlinx,
+        OutputMode::JsonSchema,
+        OutputMode::Shape,
+    ]
+    .iter()
+    {
+        let output = schema.process_with_json_typegen(mode.clone()).unwrap();
+        assert!(!output.trim().is_empty(), "{:?}", mode);
+    }
+}
+
+#[test]
+fn invalid_identifiers_are_renamed() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{ "first-name": "a", "type": "b", "2fa": true, "self": 1, "nested": { "y-z": 1 } }"#,
+    )
+    .unwrap();
+
+    let output = schema.process_with_json_typegen(OutputMode::Rust).unwrap();
+    for (rename, field) in [
+        ("first-name", "first_name: String"),
+        ("type", "type_field: String"),
+        ("2fa", "n2fa: bool"),
+        ("self", "self_field: i64"),
+        ("y-z", "y_z: i64"),
+    ]
+    .iter()
+    {
+        let attribute = format!("#[serde(rename = \"{}\")]\n    pub {},", rename, field);
+        assert!(output.contains(&attribute), "{}\n{}", attribute, output);
+    }
+    // Valid identifiers are left alone.
+    assert!(output.contains("    pub nested: Nested,"), "{}", output);
+    assert!(!output.contains("rename = \"nested\""), "{}", output);
+}
+
+#[test]
+fn max_depth() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{ "id": 1, "replies": [
+            { "id": 2, "replies": [{ "id": 3, "replies": [] }] },
+            { "id": 4, "replies": [] }
+        ] }"#,
+    )
+    .unwrap();
+
+    let options = ShapeOptions {
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let replies = |shape: &Shape| match shape {
+        Shape::Struct { fields } => match &fields["replies"] {
+            Shape::VecT { elem_type } => (**elem_type).clone(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+    let shape = schema.to_json_typegen_shape_with_options(&options);
+    let nested = replies(&shape);
+    assert!(matches!(nested, Shape::Struct { .. }));
+    assert_eq!(replies(&nested), Shape::Any);
+
+    // Without a limit every level gets a struct.
+    let nested = replies(&replies(&schema.to_json_typegen_shape()));
+    assert!(matches!(nested, Shape::Struct { .. }));
+}
+
+#[test]
+fn numeric_or_null_is_optional() {
+    let field_shape = |schema: Schema| match schema.to_json_typegen_shape() {
+        Shape::Struct { fields } => fields["a"].clone(),
+        shape => panic!("{:?}", shape),
+    };
+
+    // The field itself may be null.
+    let InferredSchema { schema } = serde_json::from_str(r#"[{ "a": 1 }, { "a": null }]"#).unwrap();
+    let field_level = match schema {
+        Schema::Sequence { field, .. } => field.schema.unwrap(),
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        field_shape(field_level),
+        Shape::Optional(Box::new(Shape::Integer))
+    );
+
+    // The field holds a union that contains null, which must not be wrapped twice.
+    let holding = |variants: Vec<Schema>, may_be_null: bool| {
+        let mut union = Schema::Null(Default::default());
+        for variant in variants {
+            union.coalesce(variant);
+        }
+        let mut field = Field::with_schema(union);
+        field.status.may_be_null = may_be_null;
+        Schema::Struct {
+            fields: vec![("a".to_string(), field)].into_iter().collect(),
+            context: Default::default(),
+        }
+    };
+    for &may_be_null in [false, true].iter() {
+        assert_eq!(
+            field_shape(holding(
+                vec![Schema::Integer(Default::default())],
+                may_be_null
+            )),
+            Shape::Optional(Box::new(Shape::Integer))
+        );
+        assert_eq!(
+            field_shape(holding(
+                vec![
+                    Schema::Integer(Default::default()),
+                    Schema::String(Default::default()),
+                ],
+                may_be_null
+            )),
+            Shape::Optional(Box::new(Shape::Any))
+        );
+    }
+}
+
+#[test]
+fn discriminated_union_is_a_tagged_enum() {
+    let infer = |json: &str| serde_json::from_str::<InferredSchema>(json).unwrap().schema;
+    let figure = Schema::Union {
+        variants: vec![
+            infer(r#"{ "kind": "circle", "radius": 1.5 }"#),
+            infer(r#"{ "kind": "square", "side": 2 }"#),
+        ],
+    };
+    let schema = Schema::Struct {
+        fields: vec![
+            ("id".to_string(), Field::with_schema(infer("1"))),
+            ("figure".to_string(), Field::with_schema(figure)),
+        ]
+        .into_iter()
+        .collect(),
+        context: Default::default(),
+    };
+
+    // json_typegen shapes have no enums.
+    match schema.to_json_typegen_shape() {
+        Shape::Struct { fields } => assert_eq!(fields["figure"], Shape::Any),
+        _ => unreachable!(),
+    }
+
+    let output = schema.process_with_json_typegen(OutputMode::Rust).unwrap();
+    let tagged_enum = r#"#[serde(tag = "kind")]
+pub enum Figure {
+    #[serde(rename = "circle")]
+    Circle(FigureCircle),
+    #[serde(rename = "square")]
+    Square(FigureSquare),
+}"#;
+    assert!(output.contains(tagged_enum), "{}", output);
+    assert!(output.contains("    pub figure: Figure,"), "{}", output);
+    assert!(output.contains("pub struct FigureCircle {"), "{}", output);
+    assert!(output.contains("    pub radius: f64,"), "{}", output);
+    assert!(output.contains("pub struct FigureSquare {"), "{}", output);
+    assert!(output.contains("    pub side: i64,"), "{}", output);
+    // The discriminator is the tag of the enum, not a field of the structs.
+    assert!(!output.contains("pub kind"), "{}", output);
+    // The enum can't derive Default.
+    let derive = output
+        .lines()
+        .find(|line| line.starts_with("#[derive("))
+        .unwrap();
+    assert!(output.contains(&format!(
+        "{}\n{}",
+        derive.replace("Default, ", ""),
+        tagged_enum
+    )));
+}
+
+#[test]
+fn tagged_enum_variants_share_nested_structs() {
+    let infer = |json: &str| serde_json::from_str::<InferredSchema>(json).unwrap().schema;
+    let schema = Schema::Union {
+        variants: vec![
+            infer(r#"{ "kind": "a", "meta": { "id": 1 }, "info": { "x": 1 } }"#),
+            infer(r#"{ "kind": "b", "meta": { "id": 2 }, "info": { "y": "z" } }"#),
+        ],
+    };
+
+    let output = schema.process_with_json_typegen(OutputMode::Rust).unwrap();
+    // The same object is generated once, and both variants refer to it.
+    assert_eq!(output.matches("pub struct Meta {").count(), 1, "{}", output);
+    assert_eq!(
+        output.matches("    pub meta: Meta,").count(),
+        2,
+        "{}",
+        output
+    );
+    // Different objects under the same key get different names.
+    assert!(output.contains("pub struct Info {"), "{}", output);
+    assert!(output.contains("pub struct Info2 {"), "{}", output);
+    assert!(output.contains("    pub info: Info,"), "{}", output);
+    assert!(output.contains("    pub info: Info2,"), "{}", output);
+}
+
+#[test]
+fn nested_structs_do_not_take_the_root_name() {
+    let infer = |json: &str| serde_json::from_str::<InferredSchema>(json).unwrap().schema;
+    let figure = Schema::Union {
+        variants: vec![
+            infer(r#"{ "kind": "circle", "radius": 1.5 }"#),
+            infer(r#"{ "kind": "square", "side": 2 }"#),
+        ],
+    };
+    let schema = Schema::Struct {
+        fields: vec![
+            ("root".into(), Field::with_schema(infer(r#"{ "id": 1 }"#))),
+            ("figure".into(), Field::with_schema(figure)),
+        ]
+        .into_iter()
+        .collect(),
+        context: Default::default(),
+    };
+
+    let output = schema.process_with_json_typegen(OutputMode::Rust).unwrap();
+    // The root keeps its name, the nested struct gets another one.
+    assert_eq!(output.matches("pub struct Root {").count(), 1, "{}", output);
+    assert!(output.contains("pub struct Root2 {"), "{}", output);
+    assert!(output.contains("    pub root: Root2,"), "{}", output);
+    assert!(output.contains("    pub figure: Figure,"), "{}", output);
+}
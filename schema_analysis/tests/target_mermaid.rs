@@ -0,0 +1,163 @@
+use schema_analysis::{InferredSchema, Schema};
+
+mod shared;
+use shared::FormatTests;
+
+struct Mermaid;
+
+test_format!(Mermaid);
+
+const HEADER: &str = "classDiagram\n";
+
+impl FormatTests<&str> for Mermaid {
+    fn convert_to_inferred_schema(_value: &str) -> InferredSchema {
+        // Not needed for testing the target.
+        unreachable!()
+    }
+
+    // Note: here we are actually switching the source and target.
+    // The target schema from the tests before is converted to a diagram and compared to the
+    // classes below.
+    fn compare(target_classes: &str, tested_schema: Schema) {
+        let output = tested_schema.to_mermaid("Root");
+        assert_eq!(output, format!("{}{}", HEADER, target_classes));
+    }
+
+    // Roots that are not structs have no class of their own.
+    fn null() -> Option<&'static str> {
+        Some("")
+    }
+    fn boolean() -> Option<&'static str> {
+        Some("")
+    }
+    fn integer() -> Option<&'static str> {
+        Some("")
+    }
+    fn float() -> Option<&'static str> {
+        Some("")
+    }
+    fn string() -> Option<&'static str> {
+        Some("")
+    }
+
+    fn empty_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn string_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn integer_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn mixed_sequence() -> Option<&'static str> {
+        Some("")
+    }
+    fn optional_mixed_sequence() -> Option<&'static str> {
+        Some("")
+    }
+
+    fn empty_map_struct() -> Option<&'static str> {
+        Some(
+            "    class Root {
+    }
+",
+        )
+    }
+    fn map_struct_single() -> Option<&'static str> {
+        Some(
+            "    class Root {
+        Integer hello
+    }
+",
+        )
+    }
+    fn map_struct_double() -> Option<&'static str> {
+        Some(
+            "    class Root {
+        Integer hello
+        String world
+    }
+",
+        )
+    }
+    fn sequence_map_struct_mixed() -> Option<&'static str> {
+        Some(
+            "    class Root {
+        Integer hello
+        Union mixed
+        String world
+    }
+",
+        )
+    }
+    fn sequence_map_struct_optional_or_missing() -> Option<&'static str> {
+        Some(
+            "    class Root {
+        Integer hello
+        Any? null_or_missing
+        Float? possibly_missing
+        String? possibly_null
+    }
+",
+        )
+    }
+    fn map_struct_mixed_sequence() -> Option<&'static str> {
+        Some(
+            "    class Root {
+        Integer hello
+        List~String~ sequence
+        String world
+    }
+",
+        )
+    }
+    fn map_struct_mixed_sequence_optional() -> Option<&'static str> {
+        Some(
+            "    class Root {
+        Integer hello
+        Any? optional
+        List~String?~ sequence
+        String world
+    }
+",
+        )
+    }
+}
+
+#[test]
+fn nested_classes_and_identifiers() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{
+            "root": true,
+            "2fa-enabled": false,
+            "user name": "a",
+            "items": [ { "id": 1, "child": { "id": 2 } } ],
+            "child": { "quote\"d": 1.5 }
+        }"#,
+    )
+    .unwrap();
+
+    let expected = r#"classDiagram
+    class Root {
+        Boolean 2fa-enabled
+        Child child
+        List~Items~ items
+        Boolean root
+        String user_name
+    }
+    Root *-- Child : child
+    Root *-- "*" Items : items
+    class Child {
+        Float quote_d
+    }
+    class Items {
+        Child2 child
+        Integer id
+    }
+    Items *-- Child2 : child
+    class Child2 {
+        Integer id
+    }
+"#;
+    assert_eq!(schema.to_mermaid("root"), expected);
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "schemars_integration")]
+
+use schema_analysis::{InferredSchema, Schema};
+use serde_json::{json, Value};
+
+/// Checks `value` against the subset of json schema produced by the schemars target.
+fn conforms(value: &Value, json_schema: &Value) -> bool {
+    let json_schema = match json_schema {
+        Value::Bool(accepts) => return *accepts,
+        Value::Object(json_schema) => json_schema,
+        _ => unreachable!(),
+    };
+
+    if let Some(types) = json_schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(name) => vec![name],
+            Value::Array(names) => names.iter().map(|name| name.as_str().unwrap()).collect(),
+            _ => unreachable!(),
+        };
+        let has_type = |name: &str| match name {
+            "null" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "string" => value.is_string(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => unreachable!(),
+        };
+        if !types.into_iter().any(has_type) {
+            return false;
+        }
+    }
+    if let Some(Value::Array(any_of)) = json_schema.get("anyOf") {
+        if !any_of
+            .iter()
+            .any(|json_schema| conforms(value, json_schema))
+        {
+            return false;
+        }
+    }
+    if let (Some(items), Value::Array(elements)) = (json_schema.get("items"), value) {
+        if !elements.iter().all(|element| conforms(element, items)) {
+            return false;
+        }
+    }
+    if let Value::Object(object) = value {
+        if let Some(Value::Array(required)) = json_schema.get("required") {
+            if !required
+                .iter()
+                .all(|key| object.contains_key(key.as_str().unwrap()))
+            {
+                return false;
+            }
+        }
+        if let Some(Value::Object(properties)) = json_schema.get("properties") {
+            for (key, property) in object {
+                match properties.get(key) {
+                    Some(json_schema) if !conforms(property, json_schema) => return false,
+                    _ => {}
+                }
+            }
+        }
+    }
+    true
+}
+
+fn assert_sample_conforms(schema: &Schema) -> Value {
+    let sample = schema.sample_document();
+    let json_schema: Value =
+        serde_json::from_str(&schema.to_json_schema_with_schemars().unwrap()).unwrap();
+    assert!(
+        conforms(&sample, &json_schema),
+        "{:#} does not conform to {:#}",
+        sample,
+        json_schema
+    );
+    sample
+}
+
+#[test]
+fn sample_conforms_to_json_schema() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            {
+                "id": 7,
+                "name": "b",
+                "score": 1.5,
+                "active": true,
+                "tags": ["x", "y"],
+                "empty": [],
+                "nulls": [null],
+                "mixed": [1, "a", null],
+                "maybe": null,
+                "nested": { "deep": [{ "value": 2 }] }
+            },
+            {
+                "id": 3,
+                "name": "a",
+                "score": 2,
+                "active": false,
+                "tags": [],
+                "empty": [],
+                "nulls": [],
+                "mixed": [],
+                "maybe": "c",
+                "nested": { "deep": [] },
+                "sometimes": true
+            }
+        ]"#,
+    )
+    .unwrap();
+
+    let sample = assert_sample_conforms(&schema);
+    assert_eq!(
+        sample,
+        json!([{
+            "id": 3,
+            "name": "a",
+            "score": 1.5,
+            "active": true,
+            "tags": ["x"],
+            "empty": [],
+            "nulls": [null],
+            "mixed": [1],
+            "maybe": "c",
+            "nested": { "deep": [{ "value": 2 }] },
+        }])
+    );
+}
+
+#[test]
+fn sample_scalars() {
+    for data in ["null", "true", "-4", "0.25", r#""text""#, "[]", "{}"].iter() {
+        let InferredSchema { schema } = serde_json::from_str(data).unwrap();
+        let sample = assert_sample_conforms(&schema);
+        assert_eq!(sample, serde_json::from_str::<Value>(data).unwrap());
+    }
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "serde_json")]
+
+use schema_analysis::InferredSchema;
+use serde_json::json;
+
+#[test]
+fn string_field_summary() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"[
+            { "name": "Ada", "tags": ["x"] },
+            { "name": "Grace Hopper", "tags": [] },
+            { "name": "Bo" },
+            { "name": "null" }
+        ]"#,
+    )
+    .unwrap();
+
+    let summary = schema.to_summary_json();
+    assert_eq!(summary["type"], "Sequence");
+    assert_eq!(summary["count"], 1);
+
+    let items = &summary["items"];
+    assert_eq!(items["type"], "Struct");
+    assert_eq!(items["count"], 4);
+
+    let name = &items["fields"]["name"];
+    assert_eq!(name["type"], "String");
+    assert_eq!(name["count"], 4);
+    assert_eq!(
+        name["samples"],
+        json!(["Ada", "Bo", "Grace Hopper", "null"])
+    );
+    assert_eq!(name["minLength"], 2);
+    assert_eq!(name["maxLength"], 12);
+    assert_eq!(name["suspicious"], json!(["null"]));
+    assert!(name.get("mayBeMissing").is_none());
+
+    let tags = &items["fields"]["tags"];
+    assert_eq!(tags["type"], "Sequence");
+    assert_eq!(tags["mayBeMissing"], true);
+    assert_eq!(tags["minLength"], 0);
+    assert_eq!(tags["maxLength"], 1);
+    assert_eq!(tags["items"]["type"], "String");
+}
+
+#[test]
+fn scalar_summaries() {
+    let InferredSchema { schema } = serde_json::from_str(
+        r#"{
+            "id": [1, 2, 3, 4, 5, 6, 7, 8],
+            "ratio": 0.5,
+            "flag": false,
+            "nothing": null,
+            "mixed": [1, "a"]
+        }"#,
+    )
+    .unwrap();
+
+    let fields = &schema.to_summary_json()["fields"];
+
+    let ids = &fields["id"]["items"];
+    assert_eq!(ids["type"], "Integer");
+    assert_eq!(ids["samples"].as_array().unwrap().len(), 5);
+    assert_eq!(ids["min"], 1);
+    assert_eq!(ids["max"], 8);
+
+    assert_eq!(fields["ratio"]["min"], 0.5);
+    assert_eq!(
+        fields["flag"],
+        json!({ "type": "Boolean", "count": 1, "trues": 0, "falses": 1 })
+    );
+    assert_eq!(
+        fields["nothing"],
+        json!({ "type": "Any", "mayBeNull": true })
+    );
+
+    let variants = fields["mixed"]["items"]["variants"].as_array().unwrap();
+    let types: Vec<_> = variants.iter().map(|v| v["type"].clone()).collect();
+    assert_eq!(types, vec![json!("Integer"), json!("String")]);
+}
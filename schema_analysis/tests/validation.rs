@@ -0,0 +1,141 @@
+use schema_analysis::{
+    analysis::{ValidationIssue, ValidationIssueKind},
+    Context, InferredSchema, KeyNormalizer, Schema,
+};
+
+fn known_schema() -> Schema {
+    let data = r#"[
+        { "id": 1, "name": "a", "tags": ["x"], "parent": null, "score": 1.5 },
+        { "id": 2, "name": "b", "tags": [], "parent": 1 },
+        { "id": 3, "name": 3, "tags": ["y", "z"], "parent": 2, "score": 0.5 }
+    ]"#;
+    serde_json::from_str::<InferredSchema>(data).unwrap().schema
+}
+
+fn validate(schema: &Schema, document: &str) -> Vec<ValidationIssue> {
+    let mut deserializer = serde_json::Deserializer::from_str(document);
+    schema.validate(&mut deserializer).unwrap().issues
+}
+
+fn issue(path: &[&str], kind: ValidationIssueKind) -> ValidationIssue {
+    ValidationIssue {
+        path: path.iter().map(|s| s.to_string()).collect(),
+        kind,
+    }
+}
+
+#[test]
+fn conforming_document() {
+    let schema = known_schema();
+    let before = schema.clone();
+
+    let document = r#"[
+        { "id": 4, "name": "c", "tags": ["w"], "parent": null },
+        { "id": 5, "name": 5, "tags": [], "parent": 4, "score": 2.0 }
+    ]"#;
+    let mut deserializer = serde_json::Deserializer::from_str(document);
+    let report = schema.validate(&mut deserializer).unwrap();
+
+    assert!(report.is_valid(), "{:?}", report);
+    assert_eq!(schema, before);
+}
+
+#[test]
+fn non_conforming_document() {
+    let schema = known_schema();
+
+    let document = r#"[
+        { "id": "6", "name": null, "tags": [1, "v"], "extra": { "a": 1 } }
+    ]"#;
+
+    assert_eq!(
+        validate(&schema, document),
+        vec![
+            issue(
+                &["id"],
+                ValidationIssueKind::TypeMismatch {
+                    expected: vec!["Integer"],
+                    found: "String",
+                },
+            ),
+            issue(&["name"], ValidationIssueKind::UnexpectedNull),
+            issue(
+                &["tags"],
+                ValidationIssueKind::TypeMismatch {
+                    expected: vec!["String"],
+                    found: "Integer",
+                },
+            ),
+            issue(&["extra"], ValidationIssueKind::UnexpectedField),
+            issue(&["parent"], ValidationIssueKind::MissingField),
+        ]
+    );
+}
+
+#[test]
+fn root_mismatch() {
+    let schema = known_schema();
+
+    assert_eq!(
+        validate(&schema, r#"{ "id": 1 }"#),
+        vec![issue(
+            &[],
+            ValidationIssueKind::TypeMismatch {
+                expected: vec!["Sequence"],
+                found: "Struct",
+            },
+        )]
+    );
+    assert_eq!(
+        validate(&schema, "null"),
+        vec![issue(
+            &[],
+            ValidationIssueKind::TypeMismatch {
+                expected: vec!["Sequence"],
+                found: "Null",
+            },
+        )]
+    );
+}
+
+#[test]
+fn validation_with_context() {
+    let mut context = Context::default();
+    context.settings.key_normalizer = Some(KeyNormalizer::Fold);
+    context.settings.ignore_path(&["payload"]);
+    let mut deserializer =
+        serde_json::Deserializer::from_str(r#"{ "user_id": 1, "payload": { "a": 1 } }"#);
+    let inferred = context.deserialize_schema(&mut deserializer).unwrap();
+
+    let validate_with_context = |document: &str| {
+        let mut deserializer = serde_json::Deserializer::from_str(document);
+        inferred
+            .schema
+            .validate_with_context(&inferred.context, &mut deserializer)
+            .unwrap()
+            .issues
+    };
+    assert_eq!(
+        validate_with_context(r#"{ "UserId": 2, "payload": [true] }"#),
+        vec![]
+    );
+    assert_eq!(
+        validate_with_context(r#"{ "userId": "2" }"#),
+        vec![issue(
+            &["userid"],
+            ValidationIssueKind::TypeMismatch {
+                expected: vec!["Integer"],
+                found: "String"
+            }
+        )]
+    );
+
+    // The default settings don't know about the normalized keys.
+    assert_eq!(
+        validate(&inferred.schema, r#"{ "UserId": 2 }"#),
+        vec![
+            issue(&["UserId"], ValidationIssueKind::UnexpectedField),
+            issue(&["userid"], ValidationIssueKind::MissingField),
+        ]
+    );
+}
@@ -24,8 +24,8 @@ pub enum DataType {
     Bson = 5,
     Xml = 6,
 }
-impl ToString for DataType {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             DataType::Json => "json",
             DataType::Yaml => "yaml",
@@ -34,7 +34,7 @@ impl ToString for DataType {
             DataType::Bson => "bson",
             DataType::Xml => "xml",
         };
-        s.into()
+        f.write_str(s)
     }
 }
 
@@ -70,7 +70,7 @@ mod infer {
 
     use serde::{de::DeserializeSeed, Deserializer};
 
-    use schema_analysis::InferredSchema;
+    use schema_analysis::{Coalesce, InferredSchema};
 
     pub fn from_json(v: &[u8]) -> Result<(), serde_json::Error> {
         process(
@@ -81,11 +81,23 @@ mod infer {
     }
 
     pub fn from_yaml(v: &[u8]) -> Result<(), serde_yaml::Error> {
-        process(
-            v,
-            serde_yaml::from_slice,
-            serde_yaml::Deserializer::from_slice(v),
-        )
+        use serde::de::Error;
+        let s = std::str::from_utf8(v).map_err(|e| serde_yaml::Error::custom(e.to_string()))?;
+
+        let mut schema = super::INFERRED_SCHEMA.lock().unwrap();
+
+        start();
+        // Yaml files may hold multiple documents, so they are analyzed separately and merged.
+        let inferred = schema_analysis::helpers::yaml::infer_multi_document(s)?;
+        match schema.as_mut() {
+            Some(existing) => existing.coalesce(inferred),
+            None => {
+                schema.replace(inferred);
+            }
+        };
+        end();
+
+        Ok(())
     }
 
     pub fn from_cbor(v: &[u8]) -> Result<(), serde_cbor::Error> {
@@ -140,16 +152,16 @@ mod infer {
         };
         end();
 
-        return Ok(());
+        Ok(())
+    }
 
-        const TIMER_LABEL: &str = "[WASM] Inference";
-        // See `log!` for why logging goes in nested functions.
-        fn start() {
-            web_sys::console::time_with_label(TIMER_LABEL);
-        }
-        fn end() {
-            web_sys::console::time_end_with_label(TIMER_LABEL);
-        }
+    const TIMER_LABEL: &str = "[WASM] Inference";
+    // See `log!` for why logging goes in nested functions.
+    fn start() {
+        web_sys::console::time_with_label(TIMER_LABEL);
+    }
+    fn end() {
+        web_sys::console::time_end_with_label(TIMER_LABEL);
     }
 }
 
@@ -183,6 +195,23 @@ pub fn get_typescript_type_alias_types() -> Result<wasm_bindgen::JsValue, wasm_b
     get_target(|s| Schema::process_with_json_typegen(s, OutputMode::TypescriptTypeAlias))
 }
 
+#[wasm_bindgen]
+pub fn get_json_typegen_json_schema() -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    get_target(|s| Schema::process_with_json_typegen(s, OutputMode::JsonSchema))
+}
+
+#[wasm_bindgen]
+pub fn get_json_typegen_shape() -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    get_target(|s| Schema::process_with_json_typegen(s, OutputMode::Shape))
+}
+
+/// The `Debug` output of the json_typegen `Shape`, useful to understand why the generated
+/// types came out the way they did.
+#[wasm_bindgen]
+pub fn get_shape_debug() -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    get_target(|s| Ok::<_, std::fmt::Error>(format!("{:#?}", s.to_json_typegen_shape())))
+}
+
 #[wasm_bindgen]
 pub fn get_raw() -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
     get_target(serde_json::to_string_pretty::<Schema>)
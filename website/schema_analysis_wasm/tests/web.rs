@@ -0,0 +1,39 @@
+//! Run with `wasm-pack test --node`.
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use schema_analysis_wasm::{
+    clear_schema, get_json_typegen_json_schema, get_json_typegen_shape, get_shape_debug, infer,
+    DataType,
+};
+
+fn infer_sample() {
+    clear_schema();
+    let data = br#"[{ "id": 1, "name": "a" }, { "id": 2, "tags": ["x"] }]"#;
+    infer(data.to_vec(), DataType::Json).unwrap();
+}
+
+fn assert_non_empty(value: wasm_bindgen::JsValue) {
+    let output = value.as_string().unwrap();
+    assert!(!output.trim().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn json_typegen_json_schema() {
+    infer_sample();
+    assert_non_empty(get_json_typegen_json_schema().unwrap());
+}
+
+#[wasm_bindgen_test]
+fn json_typegen_shape() {
+    infer_sample();
+    assert_non_empty(get_json_typegen_shape().unwrap());
+}
+
+#[wasm_bindgen_test]
+fn shape_debug() {
+    infer_sample();
+    let output = get_shape_debug().unwrap().as_string().unwrap();
+    assert!(output.contains("Struct"));
+}